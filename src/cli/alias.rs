@@ -0,0 +1,136 @@
+use std::ffi::OsString;
+use std::process::Command;
+use clap::CommandFactory;
+use crate::cli::args::Cli;
+use crate::core::config::{load_config, set_command_line_config, GIT_DIR};
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+/// 按 shell 的规则把别名拆成参数：支持单引号、双引号与反斜杠转义
+fn split_words(value: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => match chars.next() {
+                Some(next) => word.push(next),
+                None => return Err("结尾是未转义的反斜杠".into()),
+            },
+            (Some(_), c) => word.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err("引号没有闭合".into());
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// 跳过子命令前的 `-c 键=值`，返回子命令所在的位置，并提前记录这些覆盖以便查找别名
+fn command_position(args: &[OsString]) -> usize {
+    let mut overrides = Vec::new();
+    let mut pos = 1;
+    while let Some(arg) = args.get(pos).and_then(|a| a.to_str()) {
+        if arg == "-c" {
+            match args.get(pos + 1) {
+                Some(value) => overrides.push(value.to_string_lossy().into_owned()),
+                None => break,
+            }
+            pos += 2;
+        } else if let Some(value) = arg.strip_prefix("-c") {
+            overrides.push(value.strip_prefix('=').unwrap_or(value).to_string());
+            pos += 1;
+        } else {
+            break;
+        }
+    }
+    if let Err(e) = set_command_line_config(&overrides) {
+        eprintln!("error: {}", e);
+        std::process::exit(129);
+    }
+    pos
+}
+
+/// 内置子命令名（内置命令不能被别名覆盖）
+fn is_builtin(name: &str) -> bool {
+    name == "help" || Cli::command().get_subcommands().any(|c| c.get_name() == name)
+}
+
+/// 执行 `!` 开头的 shell 别名，额外的参数作为 `$@` 传入，以其退出状态退出
+fn run_shell_alias(name: &str, command: &str, args: &[OsString]) -> ! {
+    let script = if args.is_empty() { command.to_string() } else { format!("{} \"$@\"", command) };
+    match Command::new("sh").arg("-c").arg(script).arg(command).args(args).status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(128)),
+        Err(e) => fail(&format!("fatal: 无法执行别名 '{}': {}", name, e)),
+    }
+}
+
+/// 在 clap 解析之前展开命令行中的别名（配置中的 `alias.<名>`）
+///
+/// 展开结果的第一个词仍可以是别名，出现循环时报错退出。`!` 开头的别名交给 `sh` 执行。
+/// `help <别名>` 或别名后带 `-h`/`--help` 时先输出别名的展开结果。
+pub fn expand_aliases(mut args: Vec<OsString>) -> Vec<OsString> {
+    let pos = command_position(&args);
+    let config = load_config(&GIT_DIR).unwrap_or_default();
+    let alias_of = |name: &str| config.get(&format!("alias.{}", name)).map(str::to_string);
+
+    if args.get(pos).and_then(|a| a.to_str()) == Some("help") {
+        if let Some(name) = args.get(pos + 1).and_then(|a| a.to_str()).filter(|name| !is_builtin(name)) {
+            if let Some(value) = alias_of(name) {
+                println!("'{}' 是 '{}' 的别名", name, value);
+                std::process::exit(0);
+            }
+        }
+        return args;
+    }
+
+    let mut chain: Vec<String> = Vec::new();
+    while let Some(name) = args.get(pos).and_then(|a| a.to_str()).map(str::to_string) {
+        if is_builtin(&name) {
+            break;
+        }
+        let Some(value) = alias_of(&name) else {
+            break;
+        };
+        if chain.contains(&name) {
+            chain.push(name);
+            fail(&format!("fatal: 别名循环: {}", chain.join(" -> ")));
+        }
+        if args[pos + 1..].iter().any(|a| a == "-h" || a == "--help") {
+            println!("'{}' 是 '{}' 的别名", name, value);
+        }
+        if let Some(command) = value.strip_prefix('!') {
+            run_shell_alias(&name, command, &args[pos + 1..]);
+        }
+        let words = split_words(&value).unwrap_or_else(|e| fail(&format!("fatal: 无法解析别名 '{}': {}", name, e)));
+        if words.is_empty() {
+            fail(&format!("fatal: 别名 '{}' 为空", name));
+        }
+        args.splice(pos..=pos, words.into_iter().map(OsString::from));
+        chain.push(name);
+    }
+    args
+}
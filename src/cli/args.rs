@@ -1,14 +1,36 @@
 use clap::{Parser, Subcommand};
 use crate::commands::{
-    init::git_init, add::git_add,commit::git_commit,rm::git_rm,//checkout::git_checkout,
-    branch::git_branch,merge::git_merge,branch::git_branch_delete,
-    status::git_status
+    init::git_init, add::git_add,commit::{git_commit, CommitOptions},rm::git_rm,//checkout::git_checkout,
+    branch::{git_branch, git_branch_list, git_branch_move, BranchListOptions},merge::git_merge,branch::git_branch_delete,
+    status::git_status,log::git_log,diff::git_diff,
+    cherry_pick::{git_cherry_pick, git_revert, PickOptions, SequencerCommand},
+    rebase::{git_rebase, RebaseCommand},
+    remote::{git_remote_add, git_remote_list, git_remote_remove},
+    sparse_checkout::{git_sparse_checkout, SparseCheckoutAction},
+    worktree::{git_worktree_add, git_worktree_list, git_worktree_prune, git_worktree_remove, WorktreeAddOptions},
+    clone::git_clone, fetch::git_fetch, push::{git_push, PushOptions},
+    cat_file::{git_cat_file, git_cat_file_batch, CatFileMode}, hash_object::git_hash_object,
+    ls_tree::git_ls_tree, ls_files::git_ls_files, write_tree::git_write_tree, commit_tree::git_commit_tree,
+    update_ref::{git_update_ref, git_update_ref_stdin}, symbolic_ref::git_symbolic_ref,
+    show_ref::{git_show_ref, ShowRefOptions},
+    pack_refs::git_pack_refs, fsck::git_fsck, check_ref_format::git_check_ref_format, config::{git_config, ConfigAction, ConfigFile}, count_objects::git_count_objects, prune::git_prune, gc::git_gc, upload_pack::git_upload_pack, receive_pack::git_receive_pack, http_backend::git_http_backend
 };
+use crate::core::config::{set_command_line_config, IS_BARE};
+use crate::core::repository::InitOptions;
+use std::path::PathBuf;
+use crate::core::diff::RenameOptions;
+use crate::core::object::ObjectKind;
+use crate::core::reference::RefFormatOptions;
+use clap::Args;
 
 #[derive(Parser)]
 #[command(name = "rust-git")]
 #[command(about = "一个用Rust实现的简易Git工具", long_about = None)]
 pub struct Cli {
+    /// 以 `键=值` 的形式临时覆盖配置，可以多次给出
+    #[arg(short = 'c', value_name = "KEY=VALUE")]
+    pub config: Vec<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -16,6 +38,22 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     Init {
+        /// 创建没有工作区的裸仓库
+        #[arg(long)]
+        bare: bool,
+
+        /// 初始分支名（默认为 init.defaultBranch 或 master）
+        #[arg(short = 'b', long = "initial-branch")]
+        initial_branch: Option<String>,
+
+        /// 从模板目录复制 hooks、info 等文件
+        #[arg(long)]
+        template: Option<String>,
+
+        /// 把 git 目录放在指定位置，工作区中只留一个指向它的文件
+        #[arg(long = "separate-git-dir", conflicts_with = "bare")]
+        separate_git_dir: Option<String>,
+
         #[arg(default_value = ".")]
         path: String,
     },
@@ -23,16 +61,112 @@ pub enum Commands {
         file: String,
     },
     Commit {
+        /// 提交说明，可以多次给出，每个作为一段
         #[arg(short, long)]
-        message: String,
+        message: Vec<String>,
+
+        /// 从文件读取提交说明（`-` 表示标准输入）
+        #[arg(short = 'F', long = "file")]
+        file: Option<String>,
+
+        /// 使用指定的提交说明模板
+        #[arg(short = 't', long = "template")]
+        template: Option<String>,
+
+        /// 给出提交说明后仍然打开编辑器
+        #[arg(short = 'e', long = "edit")]
+        edit: bool,
+
+        /// 允许空的提交说明
+        #[arg(long = "allow-empty-message")]
+        allow_empty_message: bool,
+
+        /// 替换当前 HEAD 指向的提交
+        #[arg(long)]
+        amend: bool,
+
+        /// 修补提交时沿用原提交说明，不打开编辑器
+        #[arg(long = "no-edit")]
+        no_edit: bool,
+
+        /// 修补提交时把作者改为当前用户
+        #[arg(long = "reset-author")]
+        reset_author: bool,
+
+        /// 先暂存所有已跟踪文件的修改与删除
+        #[arg(short = 'a', long = "all")]
+        all: bool,
+
+        /// 允许创建与父提交内容相同的提交
+        #[arg(long = "allow-empty")]
+        allow_empty: bool,
+
+        /// 只提交这些路径的当前内容
+        paths: Vec<String>,
+
+        /// 跳过 pre-commit 与 commit-msg 钩子
+        #[arg(short = 'n', long = "no-verify")]
+        no_verify: bool,
     },
     Branch {
         /// 删除分支
         #[arg(short = 'd', long = "delete")]
         delete: bool,
 
-        /// 分支名
-        name: Option<String>,
+        /// 强制删除分支，即使尚未合并
+        #[arg(short = 'D')]
+        force_delete: bool,
+
+        /// 重命名分支（连同 reflog 与上游配置）
+        #[arg(short = 'm', long = "move")]
+        rename: bool,
+
+        /// 重命名分支，即使新名字已存在
+        #[arg(short = 'M')]
+        force_rename: bool,
+
+        /// 复制分支（连同 reflog 与上游配置）
+        #[arg(short = 'c', long = "copy")]
+        copy: bool,
+
+        /// 复制分支，即使新名字已存在
+        #[arg(short = 'C')]
+        force_copy: bool,
+
+        /// 显示提交与上游信息，重复两次（-vv）时显示上游分支名
+        #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+        verbose: u8,
+
+        /// 按通配模式列出分支
+        #[arg(short = 'l', long = "list")]
+        list: bool,
+
+        /// 同时列出远程跟踪分支
+        #[arg(short = 'a', long = "all")]
+        all: bool,
+
+        /// 只列出远程跟踪分支
+        #[arg(short = 'r', long = "remotes")]
+        remotes: bool,
+
+        /// 排序键：refname、committerdate、authordate、objectname，前加 `-` 倒序
+        #[arg(long = "sort")]
+        sort: Option<String>,
+
+        /// 只列出已合并到指定提交（默认 HEAD）的分支
+        #[arg(long = "merged", num_args = 0..=1, default_missing_value = "HEAD")]
+        merged: Option<String>,
+
+        /// 只列出未合并到指定提交（默认 HEAD）的分支
+        #[arg(long = "no-merged", num_args = 0..=1, default_missing_value = "HEAD")]
+        no_merged: Option<String>,
+
+        /// 只列出包含指定提交（默认 HEAD）的分支
+        #[arg(long = "contains", num_args = 0..=1, default_missing_value = "HEAD")]
+        contains: Option<String>,
+
+        /// 分支名与起点（列出时为通配模式）
+        names: Vec<String>,
     },
     Checkout {
         #[arg(short = 'b', long = "create", help = "创建新分支")]
@@ -43,6 +177,10 @@ pub enum Commands {
     },
     Merge {
         branch: String,
+
+        /// 跳过 pre-merge-commit 钩子
+        #[arg(long = "no-verify")]
+        no_verify: bool,
     },
     Rm {
         /// 是否递归删除目录
@@ -52,27 +190,670 @@ pub enum Commands {
         file: String,
     },
     Status,
+    Log {
+        /// 起始版本（默认 HEAD）
+        revision: Option<String>,
+
+        /// 只显示修改了该路径的提交
+        #[arg(last = true)]
+        path: Option<String>,
+
+        /// 跟随文件重命名继续追踪历史
+        #[arg(long = "follow")]
+        follow: bool,
+
+        /// 每个提交只显示一行
+        #[arg(long = "oneline")]
+        oneline: bool,
+
+        /// 最多显示的提交数
+        #[arg(short = 'n', long = "max-count")]
+        max_count: Option<usize>,
+    },
+    Diff {
+        /// 比较暂存区与 HEAD（或指定提交）
+        #[arg(long = "cached", visible_alias = "staged")]
+        cached: bool,
+
+        /// 检测重命名，可附带相似度阈值（百分比），如 -M70
+        #[arg(short = 'M', long = "find-renames", value_name = "n", num_args = 0..=1)]
+        find_renames: Option<Option<u32>>,
+
+        /// 检测复制，可附带相似度阈值（百分比），如 -C80
+        #[arg(short = 'C', long = "find-copies", value_name = "n", num_args = 0..=1)]
+        find_copies: Option<Option<u32>>,
+
+        /// 关闭重命名检测
+        #[arg(long = "no-renames")]
+        no_renames: bool,
+
+        /// 要比较的提交（0~2 个）
+        revisions: Vec<String>,
+    },
+    /// 把已有提交的改动应用到当前分支
+    CherryPick(SequencerArgs),
+    /// 生成撤销已有提交改动的新提交
+    Revert(SequencerArgs),
+    /// 把当前分支的提交重放到新的基点上
+    Rebase {
+        /// 上游分支或提交
+        #[arg(required_unless_present_any = ["continue_", "abort", "skip"])]
+        upstream: Option<String>,
+
+        /// 重放到指定的提交之上（默认为上游）
+        #[arg(long = "onto", value_name = "newbase")]
+        onto: Option<String>,
+
+        /// 先用 $GIT_SEQUENCE_EDITOR 编辑待办列表
+        #[arg(short = 'i', long = "interactive")]
+        interactive: bool,
+
+        /// 解决冲突后继续
+        #[arg(long = "continue", conflicts_with_all = ["upstream", "abort", "skip"])]
+        continue_: bool,
+
+        /// 放弃 rebase 并恢复到开始前的状态
+        #[arg(long = "abort", conflicts_with_all = ["upstream", "skip"])]
+        abort: bool,
+
+        /// 跳过当前提交
+        #[arg(long = "skip", conflicts_with = "upstream")]
+        skip: bool,
+    },
+    /// 管理远程仓库
+    Remote {
+        /// 列出时同时显示地址
+        #[arg(short = 'v', long = "verbose")]
+        verbose: bool,
+
+        #[command(subcommand)]
+        action: Option<RemoteAction>,
+    },
+    /// 管理关联到同一仓库的多个工作区
+    Worktree {
+        #[command(subcommand)]
+        action: WorktreeAction,
+    },
+    /// 稀疏检出：只在工作区中检出部分目录（cone 模式）
+    SparseCheckout {
+        #[command(subcommand)]
+        action: SparseAction,
+    },
+    /// 克隆仓库到新目录
+    Clone {
+        /// 源仓库路径，或 file://、http:// 地址
+        source: String,
+
+        /// 目标目录（默认为源仓库的目录名）
+        directory: Option<String>,
+    },
+    /// 从远程获取对象并更新远程跟踪分支
+    Fetch {
+        /// 远程名或仓库路径（默认为当前分支的上游远程或 origin）
+        remote: Option<String>,
+    },
+    /// 把本地分支推送到远程
+    Push {
+        /// 远程名或仓库路径（默认为当前分支的上游远程或 origin）
+        remote: Option<String>,
+
+        /// 推送规则，如 main、main:dev、+main、:old
+        refspecs: Vec<String>,
+
+        /// 允许非快进更新
+        #[arg(short = 'f', long = "force")]
+        force: bool,
+
+        /// 远程引用与预期一致时才允许强制更新
+        #[arg(long = "force-with-lease", value_name = "refname[:expect]", num_args = 0..=1, require_equals = true)]
+        force_with_lease: Option<Option<String>>,
+
+        /// 推送成功后把远程分支设为上游
+        #[arg(short = 'u', long = "set-upstream")]
+        set_upstream: bool,
+
+        /// 删除远程引用
+        #[arg(short = 'd', long = "delete")]
+        delete: bool,
+
+        /// 跳过 pre-push 钩子
+        #[arg(long = "no-verify")]
+        no_verify: bool,
+    },
+    /// 查看对象的类型、大小或内容
+    CatFile {
+        /// 输出对象类型
+        #[arg(short = 't', conflicts_with_all = ["size", "pretty", "exists"])]
+        show_type: bool,
+
+        /// 输出对象大小
+        #[arg(short = 's', conflicts_with_all = ["pretty", "exists"])]
+        size: bool,
+
+        /// 按类型美化输出对象内容
+        #[arg(short = 'p', conflicts_with = "exists")]
+        pretty: bool,
+
+        /// 只用退出状态表示对象是否存在
+        #[arg(short = 'e')]
+        exists: bool,
+
+        /// 从标准输入读取对象名，输出信息与内容
+        #[arg(long, conflicts_with = "batch_check")]
+        batch: bool,
+
+        /// 从标准输入读取对象名，只输出信息
+        #[arg(long = "batch-check")]
+        batch_check: bool,
+
+        /// `<类型> <对象>` 或 `<对象>`
+        #[arg(num_args = 0..=2)]
+        args: Vec<String>,
+    },
+    /// 计算内容的对象 hash，可选写入对象库
+    HashObject {
+        /// 写入对象库
+        #[arg(short = 'w')]
+        write: bool,
+
+        /// 对象类型
+        #[arg(short = 't', default_value = "blob")]
+        kind: String,
+
+        /// 从标准输入读取内容
+        #[arg(long)]
+        stdin: bool,
+
+        files: Vec<String>,
+    },
+    /// 列出 tree 的内容
+    LsTree {
+        /// 递归列出子目录
+        #[arg(short = 'r')]
+        recursive: bool,
+
+        /// 只输出路径
+        #[arg(long = "name-only")]
+        name_only: bool,
+
+        /// tree、提交或其他能解析为 tree 的名字
+        tree_ish: String,
+
+        paths: Vec<String>,
+    },
+    /// 列出 index 中的文件
+    LsFiles {
+        /// 同时输出模式、hash 与阶段
+        #[arg(short = 's', long = "stage")]
+        stage: bool,
+
+        /// 在路径前显示状态标记：`H` 为普通文件，`S` 为稀疏检出范围之外（skip-worktree）的文件
+        #[arg(short = 't')]
+        tags: bool,
+    },
+    /// 把 index 写成 tree 对象
+    WriteTree,
+    /// 用 tree 与父提交创建提交对象
+    CommitTree {
+        tree: String,
+
+        /// 父提交，可重复
+        #[arg(short = 'p')]
+        parents: Vec<String>,
+
+        /// 提交说明，可重复（按段落拼接）
+        #[arg(short = 'm')]
+        messages: Vec<String>,
+
+        /// 从文件读取提交说明（`-` 表示标准输入）
+        #[arg(short = 'F')]
+        file: Option<String>,
+    },
+    /// 安全地更新引用
+    UpdateRef {
+        /// 删除引用
+        #[arg(short = 'd')]
+        delete: bool,
+
+        /// 不跟随符号引用，直接更新它本身
+        #[arg(long = "no-deref")]
+        no_deref: bool,
+
+        /// 从标准输入读取命令，在一个事务中执行
+        #[arg(long)]
+        stdin: bool,
+
+        /// `<引用> <新值> [<旧值>]`，删除时为 `<引用> [<旧值>]`
+        #[arg(num_args = 0..=3)]
+        args: Vec<String>,
+    },
+    /// 读取或修改配置（系统、用户、仓库三层与命令行 -c）
+    Config {
+        /// 使用系统配置文件
+        #[arg(long, conflicts_with_all = ["global", "local", "file"])]
+        system: bool,
+
+        /// 使用用户配置文件（~/.config/rust-git/config）
+        #[arg(long, conflicts_with_all = ["local", "file"])]
+        global: bool,
+
+        /// 使用仓库配置文件
+        #[arg(long, conflicts_with = "file")]
+        local: bool,
+
+        /// 使用指定的配置文件
+        #[arg(short = 'f', long)]
+        file: Option<String>,
+
+        /// 显示每个值来自哪个文件
+        #[arg(long = "show-origin")]
+        show_origin: bool,
+
+        /// 读取键的值（多个值时取最后一个）
+        #[arg(long, conflicts_with_all = ["get_all", "set", "add", "unset", "unset_all", "list"])]
+        get: bool,
+
+        /// 读取键的所有值
+        #[arg(long = "get-all", conflicts_with_all = ["set", "add", "unset", "unset_all", "list"])]
+        get_all: bool,
+
+        /// 设置键的值
+        #[arg(long, conflicts_with_all = ["add", "unset", "unset_all", "list"])]
+        set: bool,
+
+        /// 为键追加一个值
+        #[arg(long, conflicts_with_all = ["unset", "unset_all", "list"])]
+        add: bool,
+
+        /// 删除键（只有一个值时）
+        #[arg(long, conflicts_with_all = ["unset_all", "list"])]
+        unset: bool,
+
+        /// 删除键的所有值
+        #[arg(long = "unset-all", conflicts_with = "list")]
+        unset_all: bool,
+
+        /// 列出所有配置
+        #[arg(short = 'l', long)]
+        list: bool,
+
+        /// `<键>` 或 `<键> <值>`
+        #[arg(num_args = 0..=2)]
+        args: Vec<String>,
+    },
+    /// 检查引用名是否符合 git 的规则
+    CheckRefFormat {
+        /// 把参数当作分支名检查，合法时输出它
+        #[arg(long)]
+        branch: bool,
+
+        /// 去掉开头的 '/' 并合并连续的 '/'，合法时输出结果
+        #[arg(long)]
+        normalize: bool,
+
+        /// 允许只有一级的名字
+        #[arg(long = "allow-onelevel")]
+        allow_onelevel: bool,
+
+        /// 允许一个 '*'（用于 refspec）
+        #[arg(long = "refspec-pattern")]
+        refspec_pattern: bool,
+
+        #[arg(allow_hyphen_values = true)]
+        name: String,
+    },
+    /// 读取、设置或删除符号引用
+    SymbolicRef {
+        /// 删除符号引用
+        #[arg(short = 'd', long = "delete")]
+        delete: bool,
+
+        /// 去掉 refs/heads/ 等前缀
+        #[arg(long)]
+        short: bool,
+
+        /// 不是符号引用时不输出错误
+        #[arg(short = 'q', long = "quiet")]
+        quiet: bool,
+
+        name: String,
+
+        target: Option<String>,
+    },
+    /// 列出引用
+    ShowRef {
+        /// 只列出分支
+        #[arg(long)]
+        heads: bool,
+
+        /// 只列出标签
+        #[arg(long)]
+        tags: bool,
+
+        /// 同时列出 HEAD
+        #[arg(long)]
+        head: bool,
+
+        /// 只输出 hash
+        #[arg(short = 's', long = "hash")]
+        hash: bool,
+
+        /// 要求完整的引用名
+        #[arg(long)]
+        verify: bool,
+
+        /// 不输出，只用退出状态表示结果
+        #[arg(short = 'q', long = "quiet")]
+        quiet: bool,
+
+        patterns: Vec<String>,
+    },
+    /// 把松散引用打包进 packed-refs 文件
+    PackRefs {
+        /// 打包所有引用，而不只是标签
+        #[arg(long)]
+        all: bool,
+
+        /// 保留已打包的松散引用文件
+        #[arg(long = "no-prune")]
+        no_prune: bool,
+    },
+    /// 校验对象库与引用的完整性，报告缺失、损坏与悬空的对象
+    Fsck {
+        /// 把悬空对象写入 lost-found 目录以便恢复
+        #[arg(long = "lost-found")]
+        lost_found: bool,
+    },
+    /// 统计松散对象的数量与占用的空间
+    CountObjects {
+        /// 输出详细报告
+        #[arg(short = 'v', long)]
+        verbose: bool,
+    },
+    /// 删除不可达的松散对象
+    Prune {
+        /// 只删除早于该时间的对象（如 2.weeks.ago、now、never），默认为 now
+        #[arg(long)]
+        expire: Option<String>,
+
+        /// 只列出将被删除的对象
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
+
+        /// 列出删除的对象
+        #[arg(short = 'v', long)]
+        verbose: bool,
+    },
+    /// 打包引用并删除超过宽限期的不可达对象
+    Gc {
+        /// 宽限期，默认取配置 gc.pruneExpire，否则为 2.weeks.ago
+        #[arg(long)]
+        prune: Option<String>,
+
+        /// 不输出统计报告
+        #[arg(short = 'q', long)]
+        quiet: bool,
+    },
+    /// 通过标准输入输出向 fetch / clone 提供对象（pack 协议 v2 服务端）
+    UploadPack {
+        /// 仓库路径
+        path: String,
+    },
+    /// 通过标准输入输出接收 push（pack 协议服务端）
+    ReceivePack {
+        /// 仓库路径
+        path: String,
+    },
+    /// 启动 HTTP 服务器，通过 smart HTTP 提供目录下的仓库
+    HttpBackend {
+        /// 提供服务的目录（其本身或其子目录是仓库）
+        #[arg(default_value = ".")]
+        directory: String,
+
+        /// 监听的地址
+        #[arg(long, default_value = "127.0.0.1")]
+        bind: String,
+
+        /// 监听的端口，0 表示由系统分配
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RemoteAction {
+    /// 添加远程
+    Add {
+        name: String,
+        url: String,
+    },
+    /// 删除远程
+    #[command(visible_alias = "rm")]
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorktreeAction {
+    /// 在 <path> 创建工作区并检出 <branch>
+    Add {
+        /// 创建新分支并检出
+        #[arg(short = 'b', value_name = "new-branch", conflicts_with = "detach")]
+        new_branch: Option<String>,
+
+        /// 以分离头指针检出
+        #[arg(long)]
+        detach: bool,
+
+        /// 即使分支已在其他工作区中检出也继续
+        #[arg(short = 'f', long)]
+        force: bool,
+
+        path: String,
+
+        /// 要检出的分支或提交
+        branch: Option<String>,
+    },
+    /// 列出所有工作区
+    List {
+        /// 输出便于脚本解析的格式
+        #[arg(long)]
+        porcelain: bool,
+    },
+    /// 删除工作区
+    Remove {
+        /// 即使工作区中有修改或未跟踪的文件也删除
+        #[arg(short = 'f', long)]
+        force: bool,
+
+        /// 工作区的路径或名字
+        worktree: String,
+    },
+    /// 清理目录已不存在的工作区信息
+    Prune {
+        /// 只列出将被清理的工作区
+        #[arg(short = 'n', long = "dry-run")]
+        dry_run: bool,
+
+        /// 列出清理的工作区
+        #[arg(short = 'v', long)]
+        verbose: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SparseAction {
+    /// 把检出范围设为给定的目录，未启用时同时启用稀疏检出
+    Set {
+        #[arg(required = true)]
+        dirs: Vec<String>,
+    },
+    /// 在检出范围中加入目录
+    Add {
+        #[arg(required = true)]
+        dirs: Vec<String>,
+    },
+    /// 列出检出的目录
+    List,
+    /// 关闭稀疏检出，重新检出所有文件
+    Disable,
+}
+
+/// cherry-pick 与 revert 共用的参数
+#[derive(Args)]
+pub struct SequencerArgs {
+    /// 要应用的提交
+    #[arg(required_unless_present_any = ["continue_", "abort", "skip"])]
+    commits: Vec<String>,
+
+    /// 只修改暂存区与工作区，不自动提交
+    #[arg(short = 'n', long = "no-commit")]
+    no_commit: bool,
+
+    /// 合并提交以第几个父提交为基准（从 1 开始）
+    #[arg(short = 'm', long = "mainline", value_name = "parent-number")]
+    mainline: Option<usize>,
+
+    /// 解决冲突后继续
+    #[arg(long = "continue", conflicts_with_all = ["commits", "abort", "skip"])]
+    continue_: bool,
+
+    /// 放弃操作并恢复到开始前的状态
+    #[arg(long = "abort", conflicts_with_all = ["commits", "skip"])]
+    abort: bool,
+
+    /// 跳过当前提交
+    #[arg(long = "skip", conflicts_with = "commits")]
+    skip: bool,
+}
+
+impl SequencerArgs {
+    fn split(&self) -> (SequencerCommand, PickOptions) {
+        let command = if self.continue_ {
+            SequencerCommand::Continue
+        } else if self.abort {
+            SequencerCommand::Abort
+        } else if self.skip {
+            SequencerCommand::Skip
+        } else {
+            SequencerCommand::Start(self.commits.clone())
+        };
+        (command, PickOptions { no_commit: self.no_commit, mainline: self.mainline })
+    }
+}
+
+impl Commands {
+    /// 是否需要工作区（裸仓库中拒绝执行）
+    fn needs_work_tree(&self) -> bool {
+        match self {
+            Commands::Add { .. }
+            | Commands::Commit { .. }
+            | Commands::Rm { .. }
+            | Commands::Checkout { .. }
+            | Commands::Status
+            | Commands::Merge { .. }
+            | Commands::CherryPick(_)
+            | Commands::Revert(_)
+            | Commands::Rebase { .. }
+            | Commands::LsFiles { .. }
+            | Commands::SparseCheckout { .. } => true,
+            // 两个提交之间的比较不涉及工作区与暂存区
+            Commands::Diff { cached, revisions, .. } => *cached || revisions.len() < 2,
+            _ => false,
+        }
+    }
 }
 
 impl Cli {
     pub fn execute(&self) {
+        if let Err(e) = set_command_line_config(&self.config) {
+            eprintln!("error: {}", e);
+            std::process::exit(129);
+        }
+        if *IS_BARE && self.command.needs_work_tree() {
+            eprintln!("fatal: 此操作必须在工作区中运行（当前是裸仓库）");
+            std::process::exit(128);
+        }
         match &self.command {
-            Commands::Init { path } => git_init(path),
+            Commands::Init { bare, initial_branch, template, separate_git_dir, path } => {
+                let opts = InitOptions {
+                    bare: *bare,
+                    initial_branch: initial_branch.clone(),
+                    template: template.as_ref().map(PathBuf::from),
+                    separate_git_dir: separate_git_dir.as_ref().map(PathBuf::from),
+                };
+                git_init(path, &opts)
+            }
             Commands::Add { file } => git_add(file),
-            Commands::Commit { message } => git_commit(message),
+            Commands::Commit {
+                message,
+                file,
+                template,
+                edit,
+                allow_empty_message,
+                no_verify,
+                amend,
+                no_edit,
+                reset_author,
+                all,
+                allow_empty,
+                paths,
+            } => {
+                let opts = CommitOptions {
+                    messages: message.clone(),
+                    file: file.clone(),
+                    template: template.clone(),
+                    edit: *edit,
+                    allow_empty_message: *allow_empty_message,
+                    no_verify: *no_verify,
+                    amend: *amend,
+                    no_edit: *no_edit,
+                    reset_author: *reset_author,
+                    all: *all,
+                    paths: paths.clone(),
+                    allow_empty: *allow_empty,
+                };
+                git_commit(opts)
+            }
             // Commands::Branch { name } => {
             //     let _ = git_branch(name.as_deref());
             // },
-            Commands::Branch { delete, name } => {
-                if *delete {
-                    if let Some(name) = name {
-                        git_branch_delete(name);
-                    } else {
+            Commands::Branch { delete, force_delete, rename, force_rename, copy, force_copy, verbose, list, all, remotes, sort, merged, no_merged, contains, names } => {
+                let listing = *verbose > 0 || *list || *all || *remotes || sort.is_some()
+                    || merged.is_some() || no_merged.is_some() || contains.is_some();
+                let name = names.first().map(String::as_str);
+                if *rename || *force_rename || *copy || *force_copy {
+                    let copying = *copy || *force_copy;
+                    let force = *force_rename || *force_copy;
+                    match names.as_slice() {
+                        [new] => git_branch_move(None, new, copying, force),
+                        [old, new] => git_branch_move(Some(old), new, copying, force),
+                        _ => {
+                            eprintln!("用法: branch -m|-c [<旧分支名>] <新分支名>");
+                            std::process::exit(1);
+                        }
+                    }
+                } else if !*delete && !*force_delete && (names.is_empty() || listing) {
+                    git_branch_list(&BranchListOptions {
+                        verbose: *verbose,
+                        patterns: names.clone(),
+                        all: *all,
+                        remotes: *remotes,
+                        sort: sort.clone(),
+                        merged: merged.clone(),
+                        no_merged: no_merged.clone(),
+                        contains: contains.clone(),
+                    });
+                } else if *delete || *force_delete {
+                    if names.is_empty() {
                         eprintln!("请指定要删除的分支名");
+                        std::process::exit(1);
                     }
+                    git_branch_delete(names, *force_delete);
                 } else {
                     // git_branch(name.as_deref());
-                    if let Err(e) = git_branch(name.as_deref()) {
+                    if let Err(e) = git_branch(name, names.get(1).map(String::as_str)) {
                         eprintln!("创建分支失败: {}", e);
                     }
                 }
@@ -80,9 +861,190 @@ impl Cli {
             Commands::Checkout { create, branch } => {
                 crate::commands::checkout::git_checkout(branch, *create);
             }
-            Commands::Merge { branch } => git_merge(branch),
+            Commands::Merge { branch, no_verify } => git_merge(branch, *no_verify),
             Commands::Rm { file, recursive } => git_rm(file, *recursive),
             Commands::Status => git_status(),
+            Commands::Log { revision, path, follow, oneline, max_count } => {
+                git_log(revision.as_deref(), path.as_deref(), *follow, *oneline, *max_count)
+            }
+            Commands::Diff { cached, find_renames, find_copies, no_renames, revisions } => {
+                let mut opts = RenameOptions::default();
+                if let Some(threshold) = find_renames {
+                    opts.renames = true;
+                    opts.threshold = threshold.unwrap_or(opts.threshold).min(100);
+                }
+                if let Some(threshold) = find_copies {
+                    opts.copies = true;
+                    opts.threshold = threshold.unwrap_or(opts.threshold).min(100);
+                }
+                if *no_renames {
+                    opts.renames = false;
+                }
+                git_diff(revisions, *cached, opts)
+            }
+            Commands::CherryPick(args) => {
+                let (command, opts) = args.split();
+                git_cherry_pick(command, opts)
+            }
+            Commands::Revert(args) => {
+                let (command, opts) = args.split();
+                git_revert(command, opts)
+            }
+            Commands::Rebase { upstream, onto, interactive, continue_, abort, skip } => {
+                let command = if *continue_ {
+                    RebaseCommand::Continue
+                } else if *abort {
+                    RebaseCommand::Abort
+                } else if *skip {
+                    RebaseCommand::Skip
+                } else {
+                    RebaseCommand::Start {
+                        upstream: upstream.clone().unwrap_or_default(),
+                        onto: onto.clone(),
+                        interactive: *interactive,
+                    }
+                };
+                git_rebase(command)
+            }
+            Commands::Remote { verbose, action } => match action {
+                None => git_remote_list(*verbose),
+                Some(RemoteAction::Add { name, url }) => git_remote_add(name, url),
+                Some(RemoteAction::Remove { name }) => git_remote_remove(name),
+            },
+            Commands::Worktree { action } => match action {
+                WorktreeAction::Add { new_branch, detach, force, path, branch } => {
+                    let opts = WorktreeAddOptions { new_branch: new_branch.clone(), detach: *detach, force: *force };
+                    git_worktree_add(path, branch.as_deref(), &opts)
+                }
+                WorktreeAction::List { porcelain } => git_worktree_list(*porcelain),
+                WorktreeAction::Remove { force, worktree } => git_worktree_remove(worktree, *force),
+                WorktreeAction::Prune { dry_run, verbose } => git_worktree_prune(*dry_run, *verbose),
+            },
+            Commands::SparseCheckout { action } => git_sparse_checkout(match action {
+                SparseAction::Set { dirs } => SparseCheckoutAction::Set(dirs.clone()),
+                SparseAction::Add { dirs } => SparseCheckoutAction::Add(dirs.clone()),
+                SparseAction::List => SparseCheckoutAction::List,
+                SparseAction::Disable => SparseCheckoutAction::Disable,
+            }),
+            Commands::Clone { source, directory } => git_clone(source, directory.as_deref()),
+            Commands::Fetch { remote } => git_fetch(remote.as_deref()),
+            Commands::Push { remote, refspecs, force, force_with_lease, set_upstream, delete, no_verify } => {
+                let opts = PushOptions {
+                    force: *force,
+                    force_with_lease: force_with_lease.clone(),
+                    set_upstream: *set_upstream,
+                    delete: *delete,
+                    no_verify: *no_verify,
+                };
+                git_push(remote.as_deref(), refspecs, opts)
+            }
+            Commands::CatFile { show_type, size, pretty, exists, batch, batch_check, args } => {
+                if *batch || *batch_check {
+                    git_cat_file_batch(*batch);
+                    return;
+                }
+                let mode = if *show_type {
+                    CatFileMode::Type
+                } else if *size {
+                    CatFileMode::Size
+                } else if *pretty {
+                    CatFileMode::Pretty
+                } else if *exists {
+                    CatFileMode::Exists
+                } else {
+                    match args.first().and_then(|kind| ObjectKind::from_name(kind)) {
+                        Some(kind) if args.len() == 2 => CatFileMode::Raw(kind),
+                        _ => {
+                            eprintln!("用法: cat-file (-t | -s | -e | -p | <类型>) <对象>");
+                            std::process::exit(129);
+                        }
+                    }
+                };
+                match args.last() {
+                    Some(object) => git_cat_file(mode, object),
+                    None => {
+                        eprintln!("用法: cat-file (-t | -s | -e | -p | <类型>) <对象>");
+                        std::process::exit(129);
+                    }
+                }
+            }
+            Commands::HashObject { write, kind, stdin, files } => {
+                let kind = ObjectKind::from_name(kind).unwrap_or_else(|| {
+                    eprintln!("fatal: 无效的对象类型 '{}'", kind);
+                    std::process::exit(128);
+                });
+                git_hash_object(files, *stdin, *write, kind)
+            }
+            Commands::LsTree { recursive, name_only, tree_ish, paths } => git_ls_tree(tree_ish, *recursive, *name_only, paths),
+            Commands::LsFiles { stage, tags } => git_ls_files(*stage, *tags),
+            Commands::WriteTree => git_write_tree(),
+            Commands::CommitTree { tree, parents, messages, file } => git_commit_tree(tree, parents, messages, file.as_deref()),
+            Commands::UpdateRef { delete, no_deref, stdin, args } => {
+                if *stdin {
+                    git_update_ref_stdin();
+                    return;
+                }
+                let usage = || -> ! {
+                    eprintln!("用法: update-ref [-d] <引用> [<新值>] [<旧值>]");
+                    std::process::exit(129);
+                };
+                match (args.as_slice(), *delete) {
+                    ([name], true) => git_update_ref(name, None, None, true, *no_deref),
+                    ([name, old], true) => git_update_ref(name, None, Some(old), true, *no_deref),
+                    ([name, new], false) => git_update_ref(name, Some(new), None, false, *no_deref),
+                    ([name, new, old], false) => git_update_ref(name, Some(new), Some(old), false, *no_deref),
+                    _ => usage(),
+                }
+            }
+            Commands::Config { system, global, local, file, show_origin, get, get_all, set, add, unset, unset_all, list, args } => {
+                let target = if *system {
+                    Some(ConfigFile::System)
+                } else if *global {
+                    Some(ConfigFile::Global)
+                } else if *local {
+                    Some(ConfigFile::Local)
+                } else {
+                    file.as_deref().map(ConfigFile::Path)
+                };
+                let key = args.first().cloned();
+                let value = args.get(1).cloned();
+                let action = match (key, value) {
+                    (None, None) if *list => Some(ConfigAction::List),
+                    (Some(key), None) if *get || !(*get_all || *set || *add || *unset || *unset_all || *list) => Some(ConfigAction::Get(key)),
+                    (Some(key), None) if *get_all => Some(ConfigAction::GetAll(key)),
+                    (Some(key), None) if *unset => Some(ConfigAction::Unset(key)),
+                    (Some(key), None) if *unset_all => Some(ConfigAction::UnsetAll(key)),
+                    (Some(key), Some(value)) if *add => Some(ConfigAction::Add(key, value)),
+                    (Some(key), Some(value)) if *set || !(*get || *get_all || *unset || *unset_all || *list) => Some(ConfigAction::Set(key, value)),
+                    _ => None,
+                };
+                match action {
+                    Some(action) => git_config(action, target, *show_origin),
+                    None => {
+                        eprintln!("用法: config [--system | --global | --local | -f <文件>] [--show-origin] (--get <键> | --get-all <键> | --set <键> <值> | --add <键> <值> | --unset <键> | --unset-all <键> | --list)");
+                        std::process::exit(129);
+                    }
+                }
+            }
+            Commands::CheckRefFormat { branch, normalize, allow_onelevel, refspec_pattern, name } => {
+                let opts = RefFormatOptions { allow_onelevel: *allow_onelevel, refspec_pattern: *refspec_pattern, normalize: *normalize };
+                git_check_ref_format(name, opts, *branch)
+            }
+            Commands::SymbolicRef { delete, short, quiet, name, target } => {
+                git_symbolic_ref(name, target.as_deref(), *delete, *short, *quiet)
+            }
+            Commands::ShowRef { heads, tags, head, hash, verify, quiet, patterns } => {
+                let opts = ShowRefOptions { heads: *heads, tags: *tags, head: *head, hash_only: *hash, verify: *verify, quiet: *quiet };
+                git_show_ref(patterns, &opts)
+            }
+            Commands::PackRefs { all, no_prune } => git_pack_refs(*all, !*no_prune),
+            Commands::Fsck { lost_found } => git_fsck(*lost_found),
+            Commands::CountObjects { verbose } => git_count_objects(*verbose),
+            Commands::Prune { expire, dry_run, verbose } => git_prune(expire.as_deref(), *dry_run, *verbose),
+            Commands::Gc { prune, quiet } => git_gc(prune.as_deref(), *quiet),
+            Commands::UploadPack { path } => git_upload_pack(path),
+            Commands::ReceivePack { path } => git_receive_pack(path),
+            Commands::HttpBackend { directory, bind, port } => git_http_backend(directory, bind, *port),
         }
     }
 }
@@ -14,6 +14,7 @@ pub fn git_add(file_path: &str) {
 
     if let Err(e) = add_to_index(&abs_path) {
         eprintln!("添加文件失败: {}", e);
+        std::process::exit(1);
     } else {
         if *IS_VERBOSE {
             println!("已添加 {}", file_path);
@@ -4,10 +4,188 @@ use std::io::{self};
 // use std::path::Path;
 use crate::core::reference::{read_head_commit_hash, validate_branch_name};
 use crate::core::config::{GIT_DIR,IS_VERBOSE};
-use crate::core::reference::{get_current_branch_name};
-pub fn git_branch(branch_name: Option<&str>) -> io::Result<()> {
+use crate::core::reference::{conflicting_ref, delete_ref, get_current_branch_name, list_refs, read_ref, ref_exists, resolve_revision, set_head, write_ref};
+use crate::core::refs::{RefStore, RefValue};
+use crate::core::commit::{ahead_behind, collect_ancestors, is_ancestor, read_commit, split_signature};
+use crate::core::remote::{shorten_ref, upstream_ref};
+use crate::core::config::load_config;
+use crate::core::repo_config::update_repo_config;
+use crate::core::repository::common_dir;
+use crate::core::worktree::{find_branch_checkout, list_worktrees};
+use crate::utils::fs::write_atomic;
+use crate::utils::glob::glob_match;
+
+/// 列出分支时的选项
+#[derive(Debug, Default)]
+pub struct BranchListOptions {
+    /// `-v` 的个数：1 显示提交与领先/落后，2 另外显示上游分支名
+    pub verbose: u8,
+    /// `--list` 的通配模式，任一匹配即列出
+    pub patterns: Vec<String>,
+    /// `-a`：本地与远程跟踪分支都列出
+    pub all: bool,
+    /// `-r`：只列出远程跟踪分支
+    pub remotes: bool,
+    /// `--sort=<键>`，键前加 `-` 表示倒序
+    pub sort: Option<String>,
+    pub merged: Option<String>,
+    pub no_merged: Option<String>,
+    pub contains: Option<String>,
+}
+
+/// 列表中的一项：显示名、完整引用名（游离 HEAD 为 None）与指向的提交
+struct BranchEntry {
+    display: String,
+    refname: Option<String>,
+    hash: String,
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn resolve_or_fail(rev: &str) -> String {
+    resolve_revision(rev, &GIT_DIR).unwrap_or_else(|| fail(&format!("error: 无法解析提交 '{}'", rev)))
+}
+
+/// 排序键对应的值；日期取签名中的时间戳，缺失时视为 0
+fn sort_key(entry: &BranchEntry, key: &str) -> (i64, String) {
+    let date = |author: bool| {
+        read_commit(&entry.hash, &GIT_DIR)
+            .ok()
+            .and_then(|c| split_signature(if author { &c.author } else { &c.committer }).1.map(|(ts, _)| ts))
+            .unwrap_or(0)
+    };
+    match key {
+        "committerdate" => (date(false), String::new()),
+        "authordate" => (date(true), String::new()),
+        "objectname" => (0, entry.hash.clone()),
+        _ => (0, entry.refname.clone().unwrap_or_default()),
+    }
+}
+
+/// `-v` 时显示的上游信息，如 `[origin/main: ahead 1, behind 2]`
+fn tracking_info(branch: &str, hash: &str, verbose: u8) -> Option<String> {
+    let config = load_config(&GIT_DIR).ok()?;
+    let upstream = upstream_ref(branch, &config)?;
+    let name = shorten_ref(&upstream);
+    let Some(target) = read_ref(&GIT_DIR, &upstream) else {
+        return Some(format!("[{}: gone]", name));
+    };
+    let (ahead, behind) = ahead_behind(hash, &target, &GIT_DIR);
+    let mut counts = Vec::new();
+    if ahead > 0 {
+        counts.push(format!("ahead {}", ahead));
+    }
+    if behind > 0 {
+        counts.push(format!("behind {}", behind));
+    }
+    match (verbose >= 2, counts.is_empty()) {
+        (true, true) => Some(format!("[{}]", name)),
+        (true, false) => Some(format!("[{}: {}]", name, counts.join(", "))),
+        (false, true) => None,
+        (false, false) => Some(format!("[{}]", counts.join(", "))),
+    }
+}
+
+/// 列出分支，当前分支前标 `*`
+pub fn git_branch_list(opts: &BranchListOptions) {
+    let repo_path = &*GIT_DIR;
+    let current = get_current_branch_name(repo_path);
+    let mut entries = Vec::new();
+
+    // 游离 HEAD 作为单独的一项排在最前面
+    if current.is_none() && !opts.remotes {
+        if let Ok(Some(hash)) = read_head_commit_hash(repo_path) {
+            let display = format!("(HEAD detached at {})", &hash[..7.min(hash.len())]);
+            entries.push(BranchEntry { display, refname: None, hash });
+        }
+    }
+    if !opts.remotes {
+        for (name, hash) in list_refs(repo_path, "refs/heads/") {
+            let display = shorten_ref(&name).to_string();
+            entries.push(BranchEntry { display, refname: Some(name), hash });
+        }
+    }
+    if opts.remotes || opts.all {
+        for (name, hash) in list_refs(repo_path, "refs/remotes/") {
+            let short = shorten_ref(&name);
+            let display = if opts.all { format!("remotes/{}", short) } else { short.to_string() };
+            entries.push(BranchEntry { display, refname: Some(name), hash });
+        }
+    }
+
+    if !opts.patterns.is_empty() {
+        entries.retain(|e| e.refname.is_some() && opts.patterns.iter().any(|p| glob_match(p, shorten_ref(e.refname.as_deref().unwrap_or("")))));
+    }
+    if let Some(rev) = &opts.merged {
+        let ancestors = collect_ancestors(&resolve_or_fail(rev), repo_path);
+        entries.retain(|e| ancestors.contains(&e.hash));
+    }
+    if let Some(rev) = &opts.no_merged {
+        let ancestors = collect_ancestors(&resolve_or_fail(rev), repo_path);
+        entries.retain(|e| !ancestors.contains(&e.hash));
+    }
+    if let Some(rev) = &opts.contains {
+        let commit = resolve_or_fail(rev);
+        entries.retain(|e| is_ancestor(&commit, &e.hash, repo_path));
+    }
+
+    if let Some(sort) = &opts.sort {
+        let (reverse, key) = match sort.strip_prefix('-') {
+            Some(key) => (true, key),
+            None => (false, sort.as_str()),
+        };
+        if !["refname", "committerdate", "authordate", "objectname"].contains(&key) {
+            fail(&format!("error: 不支持的排序键 '{}'", key));
+        }
+        // 游离 HEAD 始终在最前面，不参与排序
+        let start = entries.iter().take_while(|e| e.refname.is_none()).count();
+        entries[start..].sort_by_cached_key(|e| sort_key(e, key));
+        if reverse {
+            entries[start..].reverse();
+        }
+    }
+
+    let width = entries.iter().map(|e| e.display.chars().count()).max().unwrap_or(0);
+    for entry in &entries {
+        let is_current = match (&entry.refname, &current) {
+            (None, _) => true,
+            (Some(name), Some(branch)) => name.strip_prefix("refs/heads/") == Some(branch.as_str()),
+            _ => false,
+        };
+        // 在其他工作区中检出的分支用 `+` 标出
+        let elsewhere = || entry.refname.as_deref().is_some_and(|name| find_branch_checkout(repo_path, name, Some(repo_path)).is_some());
+        let marker = if is_current { '*' } else if elsewhere() { '+' } else { ' ' };
+        if opts.verbose == 0 {
+            println!("{} {}", marker, entry.display);
+            continue;
+        }
+        let subject = read_commit(&entry.hash, repo_path).map(|c| c.subject().to_string()).unwrap_or_default();
+        let tracking = entry
+            .refname
+            .as_deref()
+            .and_then(|name| name.strip_prefix("refs/heads/"))
+            .and_then(|branch| tracking_info(branch, &entry.hash, opts.verbose));
+        let subject = match tracking {
+            Some(info) => format!("{} {}", info, subject),
+            None => subject,
+        };
+        println!("{} {:<width$} {} {}", marker, entry.display, &entry.hash[..7.min(entry.hash.len())], subject, width = width);
+    }
+
+    // 远程默认分支的符号引用，如 `origin/HEAD -> origin/main`
+    if (opts.remotes || opts.all) && opts.patterns.is_empty() && opts.merged.is_none() && opts.no_merged.is_none() && opts.contains.is_none() {
+        for (name, target) in RefStore::new(repo_path).list_symbolic("refs/remotes/") {
+            let prefix = if opts.all { "remotes/" } else { "" };
+            println!("  {}{} -> {}", prefix, shorten_ref(&name), shorten_ref(&target));
+        }
+    }
+}
+/// 创建分支，起点默认为当前 HEAD
+pub fn git_branch(branch_name: Option<&str>, start_point: Option<&str>) -> io::Result<()> {
     let repo_path = &*GIT_DIR; // 使用配置中的仓库路径
-    let heads_dir = repo_path.join("refs/heads");
 
     if let Some(name) = branch_name {
         if let Err(reason) = validate_branch_name(name) {
@@ -21,50 +199,225 @@ pub fn git_branch(branch_name: Option<&str>) -> io::Result<()> {
         // })?;
 
         // let current_commit = fs::read_to_string(&head_ref)?.trim().to_string();
-        let current_commit = read_head_commit_hash(repo_path)?;
+        let current_commit = match start_point {
+            Some(rev) => resolve_or_fail(rev),
+            None => read_head_commit_hash(repo_path)?.unwrap_or_else(|| {
+                let current = get_current_branch_name(repo_path).unwrap_or_else(|| "HEAD".to_string());
+                fail(&format!("fatal: 当前分支 '{}' 还没有任何提交，无法基于它创建分支", current))
+            }),
+        };
 
-        let new_branch = heads_dir.join(name);
-        fs::write(new_branch, format!("{}\n", current_commit))?;
+        let refname = format!("refs/heads/{}", name);
+        if let Some(existing) = conflicting_ref(repo_path, &refname) {
+            fail(&format!("error: 无法创建分支 '{}'：与已有的引用 '{}' 冲突", name, existing));
+        }
+        write_ref(repo_path, &refname, &current_commit)?;
         if *IS_VERBOSE {
             println!("✅ 已创建分支 '{}'，基于提交 {}", name, current_commit);
         }
     } else {
-        // 列出所有分支
-        let entries = fs::read_dir(&heads_dir)?;
-        for entry in entries {
-            let path = entry?.path();
-            if let Some(name) = path.file_name() {
-                if *IS_VERBOSE {
-                    println!("{}", name.to_string_lossy());
-                }
-            }
-        }
+        git_branch_list(&BranchListOptions::default());
     }
 
     Ok(())
 }
 
-/// 删除分支
-pub fn git_branch_delete(branch_name: &str) {
+/// 分支是否已合并：提交可以从 HEAD 或该分支的上游到达
+fn is_merged(branch: &str, hash: &str) -> bool {
     let repo_path = &*GIT_DIR;
-    let heads_dir = repo_path.join("refs/heads");
-    let branch_path = heads_dir.join(branch_name);
+    let head = read_head_commit_hash(repo_path).ok().flatten();
+    if head.is_some_and(|head| is_ancestor(hash, &head, repo_path)) {
+        return true;
+    }
+    load_config(repo_path)
+        .ok()
+        .and_then(|config| upstream_ref(branch, &config))
+        .and_then(|upstream| read_ref(repo_path, &upstream))
+        .is_some_and(|upstream| is_ancestor(hash, &upstream, repo_path))
+}
 
-    // 检查是否为当前分支
+/// 删除分支，连同其 reflog 与上游配置
+///
+/// 未合并到 HEAD 或上游的分支只有 `force`（`-D`）时才删除；删除后打印原来的提交，以便恢复。
+pub fn git_branch_delete(names: &[String], force: bool) {
+    let repo_path = &*GIT_DIR;
     let current = get_current_branch_name(repo_path);
-    if let Some(current_name) = current {
-        if current_name == branch_name {
-            eprintln!("❌ 不能删除当前所在的分支 '{}'", branch_name);
-            return;
+    let mut failed = false;
+
+    for name in names {
+        let refname = format!("refs/heads/{}", name);
+        if current.as_deref() == Some(name.as_str()) {
+            eprintln!("error: 不能删除当前所在的分支 '{}'", name);
+            failed = true;
+            continue;
+        }
+        if let Some(worktree) = find_branch_checkout(repo_path, &refname, Some(repo_path)) {
+            eprintln!("error: 不能删除在工作区 '{}' 中检出的分支 '{}'", worktree.path.display(), name);
+            failed = true;
+            continue;
+        }
+        let Some(hash) = read_ref(repo_path, &refname) else {
+            eprintln!("error: 分支 '{}' 不存在", name);
+            failed = true;
+            continue;
+        };
+        let merged = is_merged(name, &hash);
+        if !merged && !force {
+            eprintln!("error: 分支 '{}' 尚未完全合并", name);
+            eprintln!("提示：如果确定要删除它，请运行 'branch -D {}'", name);
+            failed = true;
+            continue;
         }
+
+        if let Err(e) = delete_ref(repo_path, &refname) {
+            eprintln!("error: 删除分支 '{}' 失败: {}", name, e);
+            failed = true;
+            continue;
+        }
+        let _ = fs::remove_file(common_dir(repo_path).join("logs").join(&refname));
+        let section = format!("branch.{}", name);
+        if let Err(e) = update_repo_config(repo_path, |config| {
+            config.remove_section(&section);
+            Ok(())
+        }) {
+            eprintln!("warning: 无法删除分支 '{}' 的配置: {}", name, e);
+        }
+
+        println!("Deleted branch {} (was {}).", name, &hash[..7.min(hash.len())]);
+        if !merged {
+            println!("提示：该分支尚未合并，如需恢复，请运行 'branch {} {}'", name, hash);
+        }
+        if *IS_VERBOSE {
+            println!("✅ 已删除分支 '{}'", name);
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
     }
+}
 
-    if branch_path.exists() {
-        match fs::remove_file(&branch_path) {
-            Ok(_) => {if *IS_VERBOSE {println!("✅ 已删除分支 '{}'", branch_name)}},
-            Err(e) => eprintln!("删除失败: {}", e),
+/// 复制或移动分支的 reflog（`logs/refs/heads/<名>`），没有 reflog 时什么也不做
+///
+/// 新文件通过 [`write_atomic`] 写入，中途崩溃不会留下只写了一半的 reflog。
+fn transfer_reflog(old: &str, new: &str, copy: bool) -> io::Result<()> {
+    let logs = common_dir(&GIT_DIR).join("logs/refs/heads");
+    let (from, to) = (logs.join(old), logs.join(new));
+    if !from.is_file() {
+        return Ok(());
+    }
+    let content = fs::read(&from)?;
+    if copy {
+        return write_atomic(&to, &content);
+    }
+    // `a` 与 `a/b` 互相重命名时，旧文件或它所在的目录占着新路径，只能先删掉旧文件
+    let nested = to.starts_with(&from) || from.starts_with(&to);
+    if !nested {
+        write_atomic(&to, &content)?;
+    }
+    fs::remove_file(&from)?;
+    let mut dir = from.parent();
+    while let Some(d) = dir.filter(|d| *d != logs) {
+        if fs::remove_dir(d).is_err() {
+            break;
         }
-    } else {
-        eprintln!("❌ 分支 '{}' 不存在", branch_name);
+        dir = d.parent();
+    }
+    if nested {
+        write_atomic(&to, &content)?;
+    }
+    Ok(())
+}
+
+/// 重命名（`-m`）或复制（`-c`）分支，连同其 reflog 与上游配置
+///
+/// `old` 为 None 时作用于当前分支；`force` 为 true 时（`-M`/`-C`）允许覆盖已有的分支。
+pub fn git_branch_move(old: Option<&str>, new: &str, copy: bool, force: bool) {
+    let repo_path = &*GIT_DIR;
+    let current = get_current_branch_name(repo_path);
+    let old = match old {
+        Some(name) => name.to_string(),
+        None => current.clone().unwrap_or_else(|| fail("error: 当前不在任何分支上，请指定要操作的分支")),
+    };
+    let action = if copy { "复制" } else { "重命名" };
+    if let Err(reason) = validate_branch_name(new) {
+        fail(&format!("error: 无效的分支名 '{}': {}", new, reason));
+    }
+
+    let old_ref = format!("refs/heads/{}", old);
+    let new_ref = format!("refs/heads/{}", new);
+    let is_current = current.as_deref() == Some(old.as_str());
+    // 尚未提交的当前分支也可以重命名，此时只需修改 HEAD
+    let hash = match read_ref(repo_path, &old_ref) {
+        Some(hash) => Some(hash),
+        None if is_current && !copy => None,
+        None => fail(&format!("error: 分支 '{}' 不存在", old)),
+    };
+    if old != new {
+        if ref_exists(repo_path, &new_ref) && !force {
+            fail(&format!("fatal: 分支 '{}' 已存在", new));
+        }
+        if let Some(existing) = conflicting_ref(repo_path, &new_ref).filter(|r| *r != old_ref && *r != new_ref) {
+            fail(&format!("error: 无法{}分支为 '{}'：与已有的引用 '{}' 冲突", action, new, existing));
+        }
+        if copy && current.as_deref() == Some(new) {
+            fail(&format!("fatal: 不能覆盖当前所在的分支 '{}'", new));
+        }
+        if let Some(worktree) = find_branch_checkout(repo_path, &new_ref, Some(repo_path)) {
+            fail(&format!("fatal: 不能覆盖在工作区 '{}' 中检出的分支 '{}'", worktree.path.display(), new));
+        }
+    }
+
+    let result = (|| -> io::Result<()> {
+        if let Some(hash) = &hash {
+            if copy || old == new {
+                write_ref(repo_path, &new_ref, hash)?;
+            } else if new_ref.starts_with(&format!("{}/", old_ref)) || old_ref.starts_with(&format!("{}/", new_ref)) {
+                // `a` 与 `a/b` 互相重命名：旧引用的文件（或它所在的目录）占着新引用的位置，
+                // 无法放进同一个事务，只能先删除旧引用；新引用写入失败时恢复旧引用
+                delete_ref(repo_path, &old_ref)?;
+                if let Err(e) = write_ref(repo_path, &new_ref, hash) {
+                    let _ = write_ref(repo_path, &old_ref, hash);
+                    return Err(e);
+                }
+            } else {
+                // 删除与写入在同一个事务中，任何一步失败时旧分支都保持不变
+                let store = RefStore::new(repo_path);
+                let mut transaction = store.transaction();
+                transaction.delete_if(&old_ref, hash).update(&new_ref, hash);
+                transaction.commit()?;
+            }
+        }
+        // reflog 只在引用事务成功之后才移动
+        if old != new {
+            transfer_reflog(&old, new, copy)?;
+            update_repo_config(repo_path, |config| {
+                let (from, to) = (format!("branch.{}", old), format!("branch.{}", new));
+                if copy {
+                    config.copy_section(&from, &to);
+                } else {
+                    config.rename_section(&from, &to);
+                }
+                Ok(())
+            })?;
+        }
+        if is_current && !copy {
+            set_head(&new_ref, repo_path)?;
+        }
+        // 其他工作区中检出的分支被重命名时，它们的 HEAD 一并跟随
+        if !copy && old != new {
+            for worktree in list_worktrees(repo_path)? {
+                if worktree.head() == Some(RefValue::Symbolic(old_ref.clone())) {
+                    set_head(&new_ref, &worktree.git_dir)?;
+                }
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        fail(&format!("error: {}分支失败: {}", action, e));
+    }
+    if *IS_VERBOSE {
+        println!("✅ 已将分支 '{}' {}为 '{}'", old, action, new);
     }
 }
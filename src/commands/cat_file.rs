@@ -0,0 +1,96 @@
+use std::io::{self, BufRead, Write};
+use crate::core::config::GIT_DIR;
+use crate::core::object::{detect_kind, ObjectKind};
+use crate::core::reference::read_resolved_object;
+use crate::core::tree::{list_tree, parse_tree_entries};
+
+/// cat-file 的查询方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatFileMode {
+    /// `-t`：类型
+    Type,
+    /// `-s`：大小
+    Size,
+    /// `-e`：只用退出状态表示对象是否存在
+    Exists,
+    /// `-p`：按类型美化输出
+    Pretty,
+    /// `cat-file <类型> <对象>`：原样输出，类型必须一致
+    Raw(ObjectKind),
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+/// 按 `ls-tree` 的格式输出 tree 的直接内容，`content` 是 tree 对象的内容
+pub fn format_tree_entries(tree_hash: &str, content: &[u8], recursive: bool, name_only: bool) -> io::Result<String> {
+    let entries = parse_tree_entries(tree_hash, &String::from_utf8_lossy(content))?;
+    let mut out = String::new();
+    for (kind, hash, path) in list_tree(entries, recursive)? {
+        if name_only {
+            out.push_str(&format!("{}\n", path));
+        } else {
+            let mode = if kind == ObjectKind::Tree { "040000" } else { "100644" };
+            out.push_str(&format!("{} {} {}\t{}\n", mode, kind.name(), hash, path));
+        }
+    }
+    Ok(out)
+}
+
+/// 查看对象的类型、大小或内容
+pub fn git_cat_file(mode: CatFileMode, object: &str) {
+    let repo_path = &*GIT_DIR;
+    let found = read_resolved_object(object, repo_path);
+    let Some((hash, content)) = found else {
+        if mode == CatFileMode::Exists {
+            std::process::exit(1);
+        }
+        fail(&format!("fatal: 不是有效的对象名 '{}'", object));
+    };
+    let kind = detect_kind(&content);
+
+    let mut stdout = io::stdout().lock();
+    let result = match mode {
+        CatFileMode::Type => writeln!(stdout, "{}", kind.name()),
+        CatFileMode::Size => writeln!(stdout, "{}", content.len()),
+        CatFileMode::Exists => Ok(()),
+        CatFileMode::Pretty if kind == ObjectKind::Tree => {
+            format_tree_entries(&hash, &content, false, false).and_then(|text| stdout.write_all(text.as_bytes()))
+        }
+        CatFileMode::Pretty => stdout.write_all(&content),
+        CatFileMode::Raw(expected) if expected != kind => fail(&format!("fatal: {} 的类型是 {}，不是 {}", object, kind.name(), expected.name())),
+        CatFileMode::Raw(_) => stdout.write_all(&content),
+    };
+    if let Err(e) = result {
+        fail(&format!("fatal: 无法输出对象: {}", e));
+    }
+}
+
+/// `--batch` / `--batch-check`：从标准输入逐行读取对象名，输出 `<hash> <类型> <大小>`（及内容）
+pub fn git_cat_file_batch(with_content: bool) {
+    let repo_path = &*GIT_DIR;
+    let stdin = io::stdin();
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else { break };
+        let name = line.trim();
+        let found = read_resolved_object(name, repo_path);
+        let result = match found {
+            None => writeln!(stdout, "{} missing", name),
+            Some((hash, content)) => {
+                writeln!(stdout, "{} {} {}", hash, detect_kind(&content).name(), content.len()).and_then(|_| {
+                    if with_content {
+                        stdout.write_all(&content)?;
+                        writeln!(stdout)?;
+                    }
+                    Ok(())
+                })
+            }
+        };
+        if result.and_then(|_| stdout.flush()).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::core::reference::{check_ref_format, validate_branch_name, RefFormatOptions};
+
+/// 检查引用名是否合法：合法时以状态 0 退出，否则以状态 1 退出且不输出
+///
+/// `normalize` 时输出规范化后（去掉开头的 `/`、合并连续的 `/`）的名字；
+/// `branch` 时（`--branch`）把参数当作分支名检查，合法时输出它，否则报错并以状态 128 退出。
+pub fn git_check_ref_format(name: &str, opts: RefFormatOptions, branch: bool) {
+    if branch {
+        match validate_branch_name(name) {
+            Ok(()) => println!("{}", name),
+            Err(reason) => {
+                eprintln!("fatal: '{}' 不是有效的分支名: {}", name, reason);
+                std::process::exit(128);
+            }
+        }
+        return;
+    }
+    match check_ref_format(name, opts) {
+        Ok(normalized) if opts.normalize => println!("{}", normalized),
+        Ok(_) => {}
+        Err(_) => std::process::exit(1),
+    }
+}
@@ -1,16 +1,37 @@
-use std::fs;
 // use std::path::Path;
-use crate::core::reference::{set_head,validate_branch_name};
+use crate::core::reference::{get_head_ref,set_head,validate_branch_name,read_head_commit_hash,conflicting_ref,read_ref,ref_exists,write_ref};
+use crate::core::hooks::run_hook;
+use crate::core::protocol::ZERO_ID;
 use crate::core::commit::read_commit_tree;
-use crate::core::tree::{restore_tree, clean_working_directory};
+use crate::core::tree::{read_tree_entries, restore_tree, clean_working_directory};
 use crate::core::config::{GIT_DIR,IS_VERBOSE};
+use crate::core::worktree::find_branch_checkout;
 /// 判断是否是合法的 40 位 commit hash
 fn is_commit_hash(s: &str) -> bool {
     s.len() == 40 && s.chars().all(|c| c.is_ascii_hexdigit())
 }
 
-/// 检查分支名是否合法。如果非法，返回 `Err(原因)`，合法则返回 `Ok(())`
+/// 切换完成后运行 post-checkout 钩子，参数为原 HEAD、新 HEAD 与 `1`（表示切换分支）
+///
+/// 尚无提交的一侧用全零 id 表示。钩子不影响切换本身，但其失败会让 checkout 以失败状态退出。
+fn run_post_checkout(old_head: Option<&str>, new_head: Option<&str>) {
+    let old_head = old_head.unwrap_or(ZERO_ID);
+    let new_head = new_head.unwrap_or(ZERO_ID);
+    match run_hook(&GIT_DIR, "post-checkout", &[old_head, new_head, "1"], None) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => eprintln!("无法运行 post-checkout 钩子: {}", e),
+    }
+}
 
+/// 检出前确认目标提交的 tree 可以安全地写入工作区，否则不动 HEAD 与工作区，直接失败
+fn verify_target_tree(commit: &str) {
+    let checked = read_commit_tree(commit, &GIT_DIR).and_then(|tree| read_tree_entries(&tree, &GIT_DIR));
+    if let Err(e) = checked {
+        eprintln!("error: 无法检出 {}: {}", commit, e);
+        std::process::exit(1);
+    }
+}
 
 /// 主函数：执行 checkout 逻辑
 pub fn git_checkout(target: &str, create: bool) {
@@ -23,10 +44,12 @@ pub fn git_checkout(target: &str, create: bool) {
         }
         return;
     }
+    let old_head = read_head_commit_hash(repo_path).ok().flatten();
 
     // 🆕 detached HEAD 模式
     if !create && is_commit_hash(target) {
-        fs::write(repo_path.join("HEAD"), format!("{}\n", target)).unwrap();
+        verify_target_tree(target);
+        write_ref(repo_path, "HEAD", target).unwrap();
         if *IS_VERBOSE {
             println!("🔗 已切换到 commit {}（detached HEAD）", target);
         }
@@ -50,6 +73,7 @@ pub fn git_checkout(target: &str, create: bool) {
             }
         }
 
+        run_post_checkout(old_head.as_deref(), Some(target));
         return;
     }
 
@@ -61,44 +85,57 @@ pub fn git_checkout(target: &str, create: bool) {
         return;
     }
 
-    let ref_path = repo_path.join("refs/heads").join(target);
+    let refname = format!("refs/heads/{}", target);
 
     if create {
-        if ref_path.exists() {
+        if ref_exists(repo_path, &refname) {
             if *IS_VERBOSE {
                 eprintln!("❌ 分支 '{}' 已存在", target);
             }
             return;
         }
 
-        // 获取当前 HEAD 指向的 commit hash
-        let head_content = fs::read_to_string(repo_path.join("HEAD"))
-            .unwrap_or_default()
-            .trim()
-            .to_string();
+        if let Some(existing) = conflicting_ref(repo_path, &refname) {
+            eprintln!("❌ 无法创建分支 '{}'：与已有的引用 '{}' 冲突", target, existing);
+            std::process::exit(1);
+        }
 
-        let commit_hash = if head_content.starts_with("ref: ") {
-            let head_ref_path = repo_path.join(head_content.trim_start_matches("ref: ").trim());
-            fs::read_to_string(head_ref_path).unwrap_or_default().trim().to_string()
-        } else {
-            head_content
+        // 当前分支还没有提交时，新分支同样尚未诞生：只移动 HEAD，工作区与暂存区保持不变
+        let Some(commit_hash) = old_head.clone() else {
+            if let Err(e) = set_head(&refname, repo_path) {
+                eprintln!("❌ 无法设置 HEAD: {}", e);
+                std::process::exit(1);
+            }
+            if *IS_VERBOSE {
+                println!("✅ 已切换到新分支 '{}'（还没有任何提交）", target);
+            }
+            run_post_checkout(None, None);
+            return;
         };
-
-        fs::write(&ref_path, format!("{}\n", commit_hash)).unwrap();
+        write_ref(repo_path, &refname, &commit_hash).unwrap();
         if *IS_VERBOSE {
             println!("✅ 创建分支 '{}'", target);
         }
     }
 
     // 分支切换
-    if !ref_path.exists() {
-        if *IS_VERBOSE {
+    let Some(commit_hash) = read_ref(repo_path, &refname) else {
+        if old_head.is_none() && get_head_ref(repo_path).ok().as_deref() == Some(refname.as_str()) {
+            if *IS_VERBOSE {
+                println!("已经位于分支 '{}'（还没有任何提交）", target);
+            }
+        } else if *IS_VERBOSE {
             eprintln!("❌ 分支 '{}' 不存在", target);
         }
         return;
+    };
+    if let Some(worktree) = find_branch_checkout(repo_path, &refname, Some(repo_path)) {
+        eprintln!("fatal: 分支 '{}' 已经在工作区 '{}' 中检出", target, worktree.path.display());
+        std::process::exit(128);
     }
+    verify_target_tree(&commit_hash);
 
-    if let Err(e) = set_head(&format!("refs/heads/{}", target), repo_path) {
+    if let Err(e) = set_head(&refname, repo_path) {
         if *IS_VERBOSE {
             eprintln!("❌ 无法设置 HEAD: {}", e);
         }
@@ -112,18 +149,6 @@ pub fn git_checkout(target: &str, create: bool) {
         return;
     }
 
-    let commit_hash = fs::read_to_string(&ref_path)
-        .unwrap_or_default()
-        .trim()
-        .to_string();
-
-    if commit_hash.is_empty() {
-        if *IS_VERBOSE {
-            println!("提示：当前分支尚无提交，工作区为空（仅保留 git 仓库）");
-        }
-        return;
-    }
-
     match read_commit_tree(&commit_hash, repo_path) {
         Ok(tree_hash) => {
 
@@ -135,6 +160,7 @@ pub fn git_checkout(target: &str, create: bool) {
                 if *IS_VERBOSE {
                     println!("✅ 已切换到分支 '{}'", target);
                 }
+                run_post_checkout(old_head.as_deref(), Some(&commit_hash));
             }
 
         }
@@ -0,0 +1,312 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::core::commit::{default_signature, read_commit, update_head, write_commit};
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::index::{index_file, read_index_map};
+use crate::core::reference::{delete_ref, get_head_ref, read_head_commit_hash, ref_exists, resolve_revision};
+use crate::core::sequencer::{
+    apply_commit, first_unresolved, read_todo, reset_hard, write_todo, Action, ApplyOutcome, TodoItem,
+};
+use crate::core::tree::{read_tree_entries, write_tree_from_map};
+
+/// cherry-pick / revert 的子操作
+pub enum SequencerCommand {
+    /// 依次应用给定的提交
+    Start(Vec<String>),
+    /// 解决冲突后继续
+    Continue,
+    /// 放弃整个操作，回到开始前的状态
+    Abort,
+    /// 跳过当前冲突的提交
+    Skip,
+}
+
+/// 应用提交时的选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PickOptions {
+    /// 只更新 index 与工作区，不创建提交
+    pub no_commit: bool,
+    /// 合并提交以第几个父提交为基准（从 1 开始）
+    pub mainline: Option<usize>,
+}
+
+/// 把指定提交的改动应用到当前分支
+pub fn git_cherry_pick(command: SequencerCommand, opts: PickOptions) {
+    run(Action::Pick, command, opts);
+}
+
+/// 生成撤销指定提交改动的新提交
+pub fn git_revert(command: SequencerCommand, opts: PickOptions) {
+    run(Action::Revert, command, opts);
+}
+
+fn sequencer_dir() -> PathBuf {
+    GIT_DIR.join("sequencer")
+}
+
+/// 当前冲突提交的标记文件名
+fn pending_file(action: Action) -> &'static str {
+    match action {
+        Action::Revert => "REVERT_HEAD",
+        _ => "CHERRY_PICK_HEAD",
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn run(action: Action, command: SequencerCommand, opts: PickOptions) {
+    match command {
+        SequencerCommand::Start(revisions) => start(action, &revisions, opts),
+        SequencerCommand::Continue => resume(true),
+        SequencerCommand::Skip => resume(false),
+        SequencerCommand::Abort => abort(),
+    }
+}
+
+fn start(action: Action, revisions: &[String], opts: PickOptions) {
+    let repo_path = &*GIT_DIR;
+    if sequencer_dir().exists() {
+        fail("已有 cherry-pick 或 revert 正在进行，请先使用 --continue、--skip 或 --abort");
+    }
+    if revisions.is_empty() {
+        fail("请指定要应用的提交");
+    }
+
+    let mut todo = Vec::new();
+    for rev in revisions {
+        let hash = resolve_revision(rev, repo_path).unwrap_or_else(|| fail(&format!("无法解析版本 '{}'", rev)));
+        let commit = read_commit(&hash, repo_path).unwrap_or_else(|e| fail(&format!("无法读取提交 {}: {}", hash, e)));
+        todo.push(TodoItem { action, commit: hash, subject: commit.subject().to_string() });
+    }
+
+    let head = read_head_commit_hash(repo_path).ok().flatten();
+    if !opts.no_commit {
+        let head_tree = match &head {
+            None => Default::default(),
+            Some(head) => read_commit(head, repo_path)
+                .and_then(|c| read_tree_entries(&c.tree, repo_path))
+                .unwrap_or_default(),
+        };
+        if read_index_map(&index_file(repo_path)) != head_tree {
+            fail("暂存区有未提交的修改，请先提交后再执行");
+        }
+    }
+
+    let dir = sequencer_dir();
+    let saved = fs::create_dir_all(&dir)
+        .and_then(|_| fs::write(dir.join("head"), format!("{}\n", head.unwrap_or_default())))
+        .and_then(|_| fs::write(dir.join("opts"), encode_opts(opts)))
+        .and_then(|_| write_todo(&dir.join("todo"), &todo));
+    if let Err(e) = saved {
+        fail(&format!("无法保存操作状态: {}", e));
+    }
+
+    run_todo();
+}
+
+/// 依次执行待办列表，遇到冲突时保存状态并退出
+fn run_todo() {
+    let repo_path = &*GIT_DIR;
+    let dir = sequencer_dir();
+    let opts = decode_opts(&fs::read_to_string(dir.join("opts")).unwrap_or_default());
+    let mut todo = read_todo(&dir.join("todo")).unwrap_or_else(|e| fail(&format!("无法读取待办列表: {}", e)));
+
+    while !todo.is_empty() {
+        let item = todo[0].clone();
+        let revert = item.action == Action::Revert;
+        let short = &item.commit[..7.min(item.commit.len())];
+
+        match apply_commit(&item.commit, revert, opts.mainline, repo_path) {
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                fail(&e);
+            }
+            Ok(ApplyOutcome::Clean(tree)) => {
+                if !opts.no_commit {
+                    commit_result(&item, &tree);
+                }
+            }
+            Ok(ApplyOutcome::Conflicted(paths)) => {
+                let message = commit_message(&item);
+                let saved = fs::write(repo_path.join(pending_file(item.action)), format!("{}\n", item.commit))
+                    .and_then(|_| fs::write(repo_path.join("MERGE_MSG"), &message))
+                    .and_then(|_| fs::write(dir.join("conflicts"), paths.join("\n")))
+                    .and_then(|_| write_todo(&dir.join("todo"), &todo));
+                if let Err(e) = saved {
+                    fail(&format!("无法保存操作状态: {}", e));
+                }
+
+                for path in &paths {
+                    println!("Merge conflict in {}", path);
+                }
+                let name = if revert { "revert" } else { "cherry-pick" };
+                eprintln!("无法应用 {}... {}", short, item.subject);
+                eprintln!("提示：解决冲突后用 add 标记已解决，再执行 \"{} --continue\"；", name);
+                eprintln!("提示：也可以执行 \"{} --skip\" 跳过该提交，或 \"{} --abort\" 放弃整个操作", name, name);
+                std::process::exit(1);
+            }
+        }
+
+        todo.remove(0);
+        if let Err(e) = write_todo(&dir.join("todo"), &todo) {
+            fail(&format!("无法保存待办列表: {}", e));
+        }
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+/// 生成新提交的说明：cherry-pick 沿用原说明，revert 使用标准格式
+fn commit_message(item: &TodoItem) -> String {
+    match item.action {
+        Action::Revert => format!("Revert \"{}\"\n\nThis reverts commit {}.\n", item.subject, item.commit),
+        _ => read_commit(&item.commit, &GIT_DIR).map(|c| c.message).unwrap_or_default(),
+    }
+}
+
+/// 用给定的 tree 创建提交；结果与 HEAD 相同时跳过
+fn commit_result(item: &TodoItem, tree: &str) {
+    let repo_path = &*GIT_DIR;
+    let parents: Vec<String> = read_head_commit_hash(repo_path).ok().flatten().into_iter().collect();
+
+    if let Some(parent) = parents.first() {
+        if read_commit(parent, repo_path).map(|c| c.tree).ok().as_deref() == Some(tree) {
+            println!("提交 {} 的改动已存在，跳过空提交", &item.commit[..7.min(item.commit.len())]);
+            return;
+        }
+    }
+
+    let message = fs::read_to_string(repo_path.join("MERGE_MSG")).unwrap_or_else(|_| commit_message(item));
+    let committer = default_signature();
+    let author = match item.action {
+        Action::Revert => committer.clone(),
+        _ => read_commit(&item.commit, repo_path).map(|c| c.author).unwrap_or_else(|_| committer.clone()),
+    };
+
+    let result = write_commit(repo_path, tree, &parents, &author, &committer, &message)
+        .and_then(|hash| update_head(repo_path, &hash).map(|_| hash));
+    match result {
+        Ok(hash) => {
+            if *IS_VERBOSE {
+                let verb = if item.action == Action::Revert { "撤销" } else { "应用" };
+                println!("🍒 已{}提交 {} {}", verb, &item.commit[..7.min(item.commit.len())], item.subject);
+            }
+            println!("{}", hash);
+        }
+        Err(e) => fail(&format!("提交失败: {}", e)),
+    }
+}
+
+/// `--continue`（commit 为 true）或 `--skip`
+fn resume(commit: bool) {
+    let repo_path = &*GIT_DIR;
+    let dir = sequencer_dir();
+    if !dir.exists() {
+        fail("没有正在进行的 cherry-pick 或 revert");
+    }
+    let opts = decode_opts(&fs::read_to_string(dir.join("opts")).unwrap_or_default());
+    let mut todo = read_todo(&dir.join("todo")).unwrap_or_default();
+    let conflicts = read_conflicts(&dir);
+
+    if let Some(item) = todo.first().cloned() {
+        let pending = repo_path.join(pending_file(item.action));
+        if pending.exists() {
+            if commit {
+                check_resolved(&conflicts);
+                if !opts.no_commit {
+                    let entries = read_index_map(&index_file(repo_path));
+                    match write_tree_from_map(&entries, repo_path) {
+                        Ok(tree) => commit_result(&item, &tree),
+                        Err(e) => fail(&format!("无法写入 tree: {}", e)),
+                    }
+                }
+            } else {
+                let head = read_head_commit_hash(repo_path).ok().flatten();
+                if let Err(e) = reset_hard(head.as_deref(), &conflicts, repo_path) {
+                    fail(&format!("无法恢复工作区: {}", e));
+                }
+            }
+            let _ = fs::remove_file(pending);
+            let _ = fs::remove_file(repo_path.join("MERGE_MSG"));
+            let _ = fs::remove_file(dir.join("conflicts"));
+            todo.remove(0);
+            if let Err(e) = write_todo(&dir.join("todo"), &todo) {
+                fail(&format!("无法保存待办列表: {}", e));
+            }
+        }
+    }
+
+    run_todo();
+}
+
+/// `--abort`：回到操作开始前的提交
+fn abort() {
+    let repo_path = &*GIT_DIR;
+    let dir = sequencer_dir();
+    if !dir.exists() {
+        fail("没有正在进行的 cherry-pick 或 revert");
+    }
+
+    let head = fs::read_to_string(dir.join("head")).unwrap_or_default().trim().to_string();
+    let conflicts = read_conflicts(&dir);
+    // 开始时分支尚无提交：删除期间创建的分支引用，回到 unborn 状态
+    let restored = if head.is_empty() {
+        let branch = get_head_ref(repo_path).ok().filter(|name| ref_exists(repo_path, name));
+        branch.map_or(Ok(()), |name| delete_ref(repo_path, &name)).and_then(|_| reset_hard(None, &conflicts, repo_path))
+    } else {
+        update_head(repo_path, &head).and_then(|_| reset_hard(Some(&head), &conflicts, repo_path))
+    };
+    if let Err(e) = restored {
+        fail(&format!("无法恢复到原来的状态: {}", e));
+    }
+
+    for name in ["CHERRY_PICK_HEAD", "REVERT_HEAD", "MERGE_MSG"] {
+        let _ = fs::remove_file(repo_path.join(name));
+    }
+    let _ = fs::remove_dir_all(&dir);
+    if *IS_VERBOSE {
+        println!("↩️ 已放弃操作，恢复到 {}", if head.is_empty() { "尚无提交的分支" } else { &head });
+    }
+}
+
+fn read_conflicts(dir: &Path) -> Vec<String> {
+    fs::read_to_string(dir.join("conflicts"))
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 冲突文件必须去掉冲突标记并重新 add 之后才能继续
+fn check_resolved(conflicts: &[String]) {
+    if let Some(path) = first_unresolved(conflicts, &GIT_DIR) {
+        fail(&format!("文件 {} 仍有未解决的冲突，请修改后用 add 标记为已解决", path));
+    }
+}
+
+fn encode_opts(opts: PickOptions) -> String {
+    let mut content = String::new();
+    if opts.no_commit {
+        content.push_str("no-commit\n");
+    }
+    if let Some(m) = opts.mainline {
+        content.push_str(&format!("mainline {}\n", m));
+    }
+    content
+}
+
+fn decode_opts(content: &str) -> PickOptions {
+    let mut opts = PickOptions::default();
+    for line in content.lines() {
+        if line.trim() == "no-commit" {
+            opts.no_commit = true;
+        } else if let Some(m) = line.strip_prefix("mainline ") {
+            opts.mainline = m.trim().parse().ok();
+        }
+    }
+    opts
+}
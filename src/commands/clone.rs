@@ -0,0 +1,111 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::core::commit::read_commit;
+use crate::core::config::{DEFAULT_BRANCH, GIT_DIR_NAME, IS_VERBOSE};
+use crate::core::reference::{delete_ref, read_ref, set_head, write_ref};
+use crate::core::refs::{RefStore, RefValue};
+use crate::core::remote::{default_fetch_refspec, fetch, find_remote, open_remote};
+use crate::core::repo_config::{load_repo_config, update_repo_config};
+use crate::core::repository::{init_repository, InitOptions};
+use crate::core::transport::{is_c_git_repository, is_url};
+use crate::core::tree::restore_tree;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// 把源地址规范化：本地路径转为绝对路径，URL 保持不变
+fn normalize_source(source: &str) -> String {
+    if is_url(source) {
+        return source.to_string();
+    }
+    let path = fs::canonicalize(source).unwrap_or_else(|_| fail(&format!("仓库 '{}' 不存在", source)));
+    if !is_c_git_repository(&path) {
+        if let Err(e) = open_remote(&path.to_string_lossy()) {
+            fail(&e.to_string());
+        }
+    }
+    path.to_string_lossy().to_string()
+}
+
+/// 克隆仓库到新目录，并检出远程 HEAD 指向的分支
+///
+/// 源可以是本地路径，也可以是 `file://`（通过管道）或 `http://`（smart HTTP）地址。
+pub fn git_clone(source: &str, directory: Option<&str>) {
+    // 新仓库的 git 目录（切换到目标目录后的相对路径）
+    let git_dir = PathBuf::from(&*GIT_DIR_NAME);
+    let url = normalize_source(source);
+
+    let directory = match directory {
+        Some(d) => d.to_string(),
+        None => {
+            let base = url.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+            base.strip_suffix(".git").unwrap_or(base).to_string()
+        }
+    };
+    let target = Path::new(&directory);
+    if target.exists() && fs::read_dir(target).map(|mut d| d.next().is_some()).unwrap_or(true) {
+        fail(&format!("目标路径 '{}' 已存在且不是空目录", directory));
+    }
+    println!("正在克隆到 '{}'...", directory);
+
+    let prepared = fs::create_dir_all(target)
+        .and_then(|_| std::env::set_current_dir(target))
+        .and_then(|_| init_repository(Path::new("."), &InitOptions::default()));
+    if let Err(e) = prepared {
+        fail(&format!("无法创建仓库: {}", e));
+    }
+    let repo_path = git_dir.as_path();
+
+    let configured = update_repo_config(repo_path, |config| {
+        config.set("remote.origin.url", &url)?;
+        config.add("remote.origin.fetch", &default_fetch_refspec("origin"))
+    });
+    if let Err(e) = configured {
+        fail(&format!("无法写入配置: {}", e));
+    }
+
+    let config = load_repo_config(repo_path).unwrap_or_default();
+    let remote = find_remote("origin", &config).unwrap_or_else(|| fail("无法读取远程配置"));
+    if let Err(e) = fetch(&remote, repo_path) {
+        fail(&format!("获取失败: {}", e));
+    }
+
+    // 远程 HEAD 指向的分支（fetch 时已记录为 refs/remotes/origin/HEAD）
+    let branch = match RefStore::new(repo_path).read("refs/remotes/origin/HEAD") {
+        Some(RefValue::Symbolic(target)) => target.strip_prefix("refs/remotes/origin/").map(str::to_string),
+        _ => None,
+    }
+    .unwrap_or_else(|| DEFAULT_BRANCH.clone());
+    let head_commit = read_ref(repo_path, &format!("refs/remotes/origin/{}", branch));
+
+    let local_ref = format!("refs/heads/{}", branch);
+    if branch != *DEFAULT_BRANCH {
+        let _ = delete_ref(repo_path, &format!("refs/heads/{}", *DEFAULT_BRANCH));
+    }
+    if let Err(e) = set_head(&local_ref, repo_path) {
+        fail(&format!("无法设置 HEAD: {}", e));
+    }
+
+    let Some(commit) = head_commit else {
+        println!("警告：你克隆了一个空仓库");
+        return;
+    };
+
+    let checked_out = write_ref(repo_path, &local_ref, &commit)
+        .and_then(|_| {
+            update_repo_config(repo_path, |config| {
+                config.set(&format!("branch.{}.remote", branch), "origin")?;
+                config.set(&format!("branch.{}.merge", branch), &local_ref)
+            })
+        })
+        .and_then(|_| read_commit(&commit, repo_path))
+        .and_then(|info| restore_tree(&info.tree, repo_path));
+    if let Err(e) = checked_out {
+        fail(&format!("检出失败: {}", e));
+    }
+    if *IS_VERBOSE {
+        println!("✅ 已克隆 {}，检出分支 {}", url, branch);
+    }
+}
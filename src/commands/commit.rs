@@ -1,10 +1,367 @@
-use crate::core::commit::create_commit;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::core::blob::write_blob;
+use crate::core::commit::{default_signature, read_commit, read_commit_tree, update_head, write_commit, write_index_tree, CommitInfo};
+use crate::core::hooks::{run_hook, run_hook_with_index};
+use crate::core::index::{commit_index_map, index_file, read_index_map, read_skip_worktree, write_index_map};
+use crate::core::reference::{get_current_branch_name, read_head_commit_hash};
+use crate::core::config::load_config;
+use crate::core::tree::{hash_tree_from_map, read_tree_entries, write_tree_from_map};
 // use std::path::Path;
 use crate::core::config::{GIT_DIR};
-pub fn git_commit(message: &str) {
-    match create_commit(message, &*GIT_DIR) {
+use crate::utils::editor::{cleanup_whitespace, commit_editor, edit_text};
+use crate::utils::fs::LockFile;
+
+/// commit 的选项
+#[derive(Debug, Clone, Default)]
+pub struct CommitOptions {
+    /// `-m`：每个值是一段，段之间空一行
+    pub messages: Vec<String>,
+    /// `-F`：从文件读取提交说明，`-` 表示标准输入
+    pub file: Option<String>,
+    /// `-t`：提交说明模板，覆盖 `commit.template` 配置
+    pub template: Option<String>,
+    /// `-e`：已经给出提交说明时仍然打开编辑器
+    pub edit: bool,
+    /// 允许空的提交说明
+    pub allow_empty_message: bool,
+    /// `--amend`：替换当前 HEAD 指向的提交
+    pub amend: bool,
+    /// `--no-edit`：修补提交时直接沿用原提交说明
+    pub no_edit: bool,
+    /// `--reset-author`：修补提交时把作者改为当前用户
+    pub reset_author: bool,
+    /// `-a`：提交前先暂存已跟踪文件的修改与删除
+    pub all: bool,
+    /// 只提交这些路径在工作区中的内容
+    pub paths: Vec<String>,
+    /// 允许与父提交内容相同的提交
+    pub allow_empty: bool,
+    /// 跳过 pre-commit 与 commit-msg 钩子
+    pub no_verify: bool,
+}
+
+/// `-a` 与 `commit <paths>` 在内存中准备好的内容，提交成功后才写回 index
+struct Staged {
+    /// 本次提交的内容（路径 -> blob hash）
+    content: HashMap<String, String>,
+    /// 提交后的 index
+    next_index: HashMap<String, String>,
+}
+
+/// `-a` 与 `commit <paths>` 从读取 index 到提交后写回期间持有的 index 锁
+static INDEX_LOCK: Mutex<Option<LockFile>> = Mutex::new(None);
+
+fn fail(message: &str) -> ! {
+    // process::exit 不会运行析构函数，先释放 index 锁，以免留下 index.lock
+    if let Ok(mut held) = INDEX_LOCK.lock() {
+        held.take();
+    }
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// 运行会阻止提交的钩子，失败时退出
+///
+/// `staged` 为 `-a` 或 `commit <paths>` 在内存中准备好的提交内容时，把它写入临时的 index 文件供钩子读取，
+/// 返回钩子运行后这个文件中的内容（钩子可能修改了它）；真正的 index 保持不动。
+fn run_blocking_hook(name: &str, args: &[&str], staged: Option<&HashMap<String, String>>) -> Option<HashMap<String, String>> {
+    let result = match staged {
+        None => run_hook(&GIT_DIR, name, args, None).map(|ok| (ok, None)),
+        Some(staged) => {
+            let path = GIT_DIR.join(format!("next-index-{}", std::process::id()));
+            let result = write_index_map(&path, staged)
+                .and_then(|_| run_hook_with_index(&GIT_DIR, name, args, &path))
+                .map(|ok| (ok, Some(read_index_map(&path))));
+            let _ = fs::remove_file(&path);
+            result
+        }
+    };
+    match result {
+        Ok((true, staged)) => staged,
+        Ok((false, _)) => fail(&format!("提交失败: {} 钩子返回了非零状态", name)),
+        Err(e) => fail(&format!("提交失败: 无法运行 {} 钩子: {}", name, e)),
+    }
+}
+
+/// 展开路径开头的 `~/`
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// 读取提交说明模板：`-t` 优先，其次是 `commit.template` 配置
+fn read_template(opts: &CommitOptions) -> Option<String> {
+    let config = load_config(&GIT_DIR).unwrap_or_default();
+    let path = opts.template.clone().or_else(|| config.get("commit.template").map(str::to_string))?;
+    match fs::read_to_string(expand_home(&path)) {
+        Ok(content) => Some(content),
+        Err(e) => fail(&format!("无法读取提交说明模板 '{}': {}", path, e)),
+    }
+}
+
+/// HEAD 指向的提交，尚无提交时为 None
+fn head_commit() -> Option<String> {
+    read_head_commit_hash(&GIT_DIR).ok().flatten()
+}
+
+/// HEAD 的 tree（路径 -> blob hash），尚无提交时为空
+fn head_tree_map() -> HashMap<String, String> {
+    let repo_path = &*GIT_DIR;
+    head_commit()
+        .and_then(|h| read_commit_tree(&h, repo_path).ok())
+        .and_then(|tree| read_tree_entries(&tree, repo_path).ok())
+        .unwrap_or_default()
+}
+
+/// `-a`：在 index 的基础上放入已跟踪文件在工作区中的修改与删除，返回新的 index（稀疏检出范围之外的文件不算删除）
+///
+/// 只在内存中准备，提交成功后才写回 index。
+fn stage_tracked_changes() -> io::Result<HashMap<String, String>> {
+    let index_path = index_file(&GIT_DIR);
+    let mut index = read_index_map(&index_path);
+    let skipped = read_skip_worktree(&index_path);
+    let tracked: Vec<String> = index.keys().filter(|path| !skipped.contains(*path)).cloned().collect();
+    for path in tracked {
+        if Path::new(&path).is_file() {
+            index.insert(path.clone(), write_blob(Path::new(&path))?);
+        } else {
+            index.remove(&path);
+        }
+    }
+    Ok(index)
+}
+
+/// `commit <paths>`：以 HEAD 的 tree 为基础，只放入给定路径在工作区中的内容
+///
+/// 这些路径在提交后的 index 中同步更新，其他已暂存的修改保持暂存、不进入本次提交。
+fn stage_only_paths(paths: &[String]) -> Result<Staged, String> {
+    let repo_path = &*GIT_DIR;
+    let index_path = index_file(repo_path);
+    let mut index = read_index_map(&index_path);
+    let skipped = read_skip_worktree(&index_path);
+    let mut tree = head_tree_map();
+    let known: BTreeSet<String> = index.keys().chain(tree.keys()).filter(|path| !skipped.contains(*path)).cloned().collect();
+
+    for spec in paths {
+        let spec = spec.trim_start_matches("./").trim_end_matches('/');
+        let prefix = format!("{}/", spec);
+        let matched: Vec<&String> = known
+            .iter()
+            .filter(|p| spec == "." || spec.is_empty() || p.as_str() == spec || p.starts_with(&prefix))
+            .collect();
+        if matched.is_empty() {
+            return Err(format!("pathspec '{}' did not match any file(s) known to git", spec));
+        }
+        for path in matched {
+            if Path::new(path).is_file() {
+                let hash = write_blob(Path::new(path)).map_err(|e| format!("无法读取 '{}': {}", path, e))?;
+                tree.insert(path.clone(), hash.clone());
+                index.insert(path.clone(), hash);
+            } else {
+                tree.remove(path);
+                index.remove(path);
+            }
+        }
+    }
+
+    Ok(Staged { content: tree, next_index: index })
+}
+
+/// 编辑器中显示的状态摘要：当前分支与将要提交的变更（`index` 为本次提交的内容）
+fn status_summary(index: &HashMap<String, String>) -> String {
+    let repo_path = &*GIT_DIR;
+    let mut summary = String::from(
+        "# Please enter the commit message for your changes. Lines starting\n\
+         # with '#' will be ignored, and an empty message aborts the commit.\n#\n",
+    );
+    match get_current_branch_name(repo_path) {
+        Some(branch) => summary.push_str(&format!("# On branch {}\n", branch)),
+        None => summary.push_str("# HEAD detached\n"),
+    }
+
+    let head_tree = head_tree_map();
+
+    let mut changes: Vec<(String, &str)> = Vec::new();
+    for (path, hash) in index {
+        match head_tree.get(path) {
+            None => changes.push((path.clone(), "new file")),
+            Some(old) if old != hash => changes.push((path.clone(), "modified")),
+            _ => {}
+        }
+    }
+    for path in head_tree.keys().filter(|p| !index.contains_key(*p)) {
+        changes.push((path.clone(), "deleted"));
+    }
+    changes.sort();
+
+    if changes.is_empty() {
+        summary.push_str("#\n# No changes\n");
+    } else {
+        summary.push_str("#\n# Changes to be committed:\n");
+        for (path, kind) in changes {
+            summary.push_str(&format!("#\t{:<12}{}\n", format!("{}:", kind), path));
+        }
+    }
+    summary.push_str("#\n");
+    summary
+}
+
+/// 根据选项确定提交说明：`-m`、`-F`，或者用编辑器编辑（可以带模板）
+///
+/// 修补提交且没有给出新说明时，以原提交说明为初始内容（`--no-edit` 时直接沿用）。
+fn compose_message(opts: &CommitOptions, amended: Option<&CommitInfo>, index: &HashMap<String, String>) -> String {
+    if !opts.messages.is_empty() && opts.file.is_some() {
+        fail("选项 -m 与 -F 不能同时使用");
+    }
+
+    let given = match &opts.file {
+        Some(path) if path == "-" => {
+            let mut content = String::new();
+            if let Err(e) = io::stdin().read_to_string(&mut content) {
+                fail(&format!("无法从标准输入读取提交说明: {}", e));
+            }
+            Some(content)
+        }
+        Some(path) => Some(fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("无法读取文件 '{}': {}", path, e)))),
+        None if !opts.messages.is_empty() => Some(opts.messages.join("\n\n")),
+        None => None,
+    };
+
+    if given.is_some() && !opts.edit {
+        return cleanup_whitespace(&given.unwrap_or_default());
+    }
+    let given = given.or_else(|| amended.map(|c| c.message.clone()));
+    if opts.no_edit && given.is_some() {
+        return cleanup_whitespace(&given.unwrap_or_default());
+    }
+
+    let template = if given.is_none() { read_template(opts) } else { None };
+    let mut initial = given.or_else(|| template.clone()).unwrap_or_default();
+    if !initial.is_empty() && !initial.ends_with('\n') {
+        initial.push('\n');
+    }
+    initial.push('\n');
+    initial.push_str(&status_summary(index));
+
+    let path = GIT_DIR.join("COMMIT_EDITMSG");
+    let edited = edit_text(&commit_editor(), &path, &initial).unwrap_or_else(|e| fail(&format!("提交失败: {}", e)));
+    let message = cleanup_whitespace(&edited);
+
+    // 与 git 一样，模板原样未改时视为放弃提交
+    if let Some(template) = template {
+        let template = cleanup_whitespace(&crate::utils::editor::strip_comments(&template));
+        if !template.is_empty() && message == template {
+            fail("Aborting commit; you did not edit the message.");
+        }
+    }
+    message
+}
+
+pub fn git_commit(opts: CommitOptions) {
+    let repo_path = &*GIT_DIR;
+    if opts.all && !opts.paths.is_empty() {
+        fail("不能同时使用 -a 与路径参数");
+    }
+
+    let head = head_commit();
+    let amended = match (&head, opts.amend) {
+        (Some(head), true) => Some(read_commit(head, repo_path).unwrap_or_else(|e| fail(&format!("无法读取 HEAD: {}", e)))),
+        (None, true) => fail("You have nothing to amend."),
+        (_, false) => None,
+    };
+
+    // 读取之前就锁住 index，其间其他进程（如 add）对 index 的修改不会在写回时被覆盖
+    if opts.all || !opts.paths.is_empty() {
+        let lock = LockFile::acquire(&index_file(repo_path)).unwrap_or_else(|e| fail(&format!("提交失败: {}", e)));
+        *INDEX_LOCK.lock().unwrap_or_else(|e| e.into_inner()) = Some(lock);
+    }
+    let mut staged = if opts.all {
+        let index = stage_tracked_changes().unwrap_or_else(|e| fail(&format!("无法暂存修改: {}", e)));
+        Some(Staged { content: index.clone(), next_index: index })
+    } else if !opts.paths.is_empty() {
+        Some(stage_only_paths(&opts.paths).unwrap_or_else(|e| fail(&format!("提交失败: {}", e))))
+    } else {
+        None
+    };
+
+    if !opts.no_verify {
+        if let Some(changed) = run_blocking_hook("pre-commit", &[], staged.as_ref().map(|s| &s.content)) {
+            // -a 时钩子对临时 index 的修改同样进入提交后的 index
+            if let Some(staged) = staged.as_mut() {
+                if opts.all {
+                    staged.next_index = changed.clone();
+                }
+                staged.content = changed;
+            }
+        }
+    }
+
+    let (tree, content) = match &staged {
+        Some(staged) => (write_tree_from_map(&staged.content, repo_path), staged.content.clone()),
+        None => (write_index_tree(repo_path), read_index_map(&index_file(repo_path))),
+    };
+    let tree = tree.unwrap_or_else(|e| fail(&format!("提交失败: {}", e)));
+    let parents: Vec<String> = match &amended {
+        Some(commit) => commit.parents.clone(),
+        None => head.into_iter().collect(),
+    };
+
+    // 与父提交内容相同的提交没有意义，除非明确要求；根提交与空 tree 比较
+    if !opts.allow_empty {
+        let parent_tree = match parents.first() {
+            Some(parent) => read_commit_tree(parent, repo_path).ok(),
+            None => hash_tree_from_map(&HashMap::new()).ok(),
+        };
+        if parent_tree.as_deref() == Some(tree.as_str()) {
+            if opts.amend {
+                fail("You asked to amend the most recent commit, but doing so would make it empty.\n使用 --allow-empty 仍然提交");
+            }
+            fail("nothing to commit, working tree clean\n使用 --allow-empty 创建没有修改的提交");
+        }
+    }
+
+    let mut message = compose_message(&opts, amended.as_ref(), &content);
+
+    if !opts.no_verify {
+        // commit-msg 钩子通过 COMMIT_EDITMSG 文件读取并可以修改提交说明
+        let msg_path = GIT_DIR.join("COMMIT_EDITMSG");
+        if let Err(e) = fs::write(&msg_path, &message) {
+            fail(&format!("提交失败: 无法写入 {}: {}", msg_path.display(), e));
+        }
+        run_blocking_hook("commit-msg", &[&msg_path.to_string_lossy()], staged.as_ref().map(|s| &s.content));
+        message = fs::read_to_string(&msg_path).unwrap_or(message);
+    }
+
+    if message.trim().is_empty() && !opts.allow_empty_message {
+        fail("Aborting commit due to empty commit message.");
+    }
+
+    let committer = default_signature();
+    let author = match &amended {
+        Some(commit) if !opts.reset_author => commit.author.clone(),
+        _ => committer.clone(),
+    };
+    let created = write_commit(repo_path, &tree, &parents, &author, &committer, &message)
+        .and_then(|hash| update_head(repo_path, &hash).map(|_| hash));
+    match created {
         // Ok(hash) => println!("已创建提交: {}", hash),
         Ok(hash) => println!("{}", hash),
-        Err(e) => eprintln!("提交失败: {}", e),
+        Err(e) => fail(&format!("提交失败: {}", e)),
+    }
+    if let Some(staged) = staged {
+        let lock = INDEX_LOCK.lock().ok().and_then(|mut held| held.take());
+        let written = match lock {
+            Some(lock) => commit_index_map(lock, &index_file(repo_path), &staged.next_index),
+            None => write_index_map(&index_file(repo_path), &staged.next_index),
+        };
+        if let Err(e) = written {
+            fail(&format!("提交已创建，但无法更新 index: {}", e));
+        }
     }
 }
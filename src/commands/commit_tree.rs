@@ -0,0 +1,55 @@
+use std::fs;
+use std::io::{self, Read};
+use crate::core::commit::{default_signature, write_commit};
+use crate::core::config::GIT_DIR;
+use crate::core::object::{detect_kind, write_object, ObjectKind};
+use crate::core::reference::read_resolved_object;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+fn resolve_kind(spec: &str, kind: ObjectKind) -> String {
+    let repo_path = &*GIT_DIR;
+    let (hash, content) = read_resolved_object(spec, repo_path).unwrap_or_else(|| fail(&format!("fatal: 不是有效的对象名 '{}'", spec)));
+    let actual = if content.is_empty() { ObjectKind::Tree } else { detect_kind(&content) };
+    if actual != kind {
+        fail(&format!("fatal: {} 不是 {} 对象", spec, kind.name()));
+    }
+    // `<版本>:<子目录>` 的 tree 只在内存中生成，新提交引用它之前先写入对象库
+    write_object(&content, repo_path).unwrap_or_else(|e| fail(&format!("fatal: 无法写入对象 {}: {}", hash, e)))
+}
+
+/// 用给定的 tree 与父提交创建提交对象并输出其 hash（不移动任何引用）
+///
+/// 提交说明依次取自 `-m`（多个时按段落拼接）、`-F`，都没有时从标准输入读取。
+pub fn git_commit_tree(tree: &str, parents: &[String], messages: &[String], file: Option<&str>) {
+    let tree = resolve_kind(tree, ObjectKind::Tree);
+    let parents: Vec<String> = parents.iter().map(|p| resolve_kind(p, ObjectKind::Commit)).collect();
+
+    let mut message = if !messages.is_empty() {
+        messages.join("\n\n")
+    } else if let Some(file) = file {
+        let read = if file == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text).map(|_| text)
+        } else {
+            fs::read_to_string(file)
+        };
+        read.unwrap_or_else(|e| fail(&format!("fatal: 无法读取提交说明 '{}': {}", file, e)))
+    } else {
+        let mut text = String::new();
+        io::stdin().read_to_string(&mut text).unwrap_or_else(|e| fail(&format!("fatal: 无法读取提交说明: {}", e)));
+        text
+    };
+    if !message.ends_with('\n') {
+        message.push('\n');
+    }
+
+    let signature = default_signature();
+    match write_commit(&GIT_DIR, &tree, &parents, &signature, &signature, &message) {
+        Ok(hash) => println!("{}", hash),
+        Err(e) => fail(&format!("fatal: 无法写入提交: {}", e)),
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+use crate::core::config::{config_layers, global_config_path, load_layer, system_config_path, ConfigLayer, GIT_DIR};
+use crate::core::repo_config::{is_valid_key, repo_config_path, update_config_file, RepoConfig};
+
+/// config 命令要执行的操作
+pub enum ConfigAction {
+    Get(String),
+    GetAll(String),
+    Set(String, String),
+    Add(String, String),
+    Unset(String),
+    UnsetAll(String),
+    List,
+}
+
+/// `--system` / `--global` / `--local` / `-f <文件>` 指定的配置文件
+#[derive(Clone, Copy)]
+pub enum ConfigFile<'a> {
+    System,
+    Global,
+    Local,
+    Path(&'a str),
+}
+
+fn fail(message: &str, code: i32) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(code);
+}
+
+fn file_path(file: ConfigFile) -> PathBuf {
+    match file {
+        ConfigFile::System => system_config_path(),
+        ConfigFile::Global => global_config_path().unwrap_or_else(|| fail("fatal: 无法确定用户配置文件的位置（$HOME 未设置）", 128)),
+        ConfigFile::Local => {
+            if !GIT_DIR.is_dir() {
+                fail("fatal: 不在 rust-git 仓库中，不能使用 --local", 128);
+            }
+            repo_config_path(&GIT_DIR)
+        }
+        ConfigFile::Path(path) => PathBuf::from(path),
+    }
+}
+
+/// 要读取的各层配置：指定文件时只有这一个，否则为系统、用户、仓库（在仓库中时）与命令行四层
+fn read_layers(file: Option<ConfigFile>) -> Vec<ConfigLayer> {
+    let result = match file {
+        Some(file) => load_layer(file_path(file)).map(|layer| vec![layer]),
+        None => config_layers(GIT_DIR.is_dir().then_some(GIT_DIR.as_path())),
+    };
+    result.unwrap_or_else(|e| fail(&format!("fatal: 无法读取配置: {}", e), 128))
+}
+
+/// 输出一个值，`show_origin` 时在前面加上来源与制表符
+fn print_value(layer: &ConfigLayer, key: Option<&str>, value: &str, show_origin: bool) {
+    let origin = if show_origin { format!("{}\t", layer.origin()) } else { String::new() };
+    match key {
+        Some(key) => println!("{}{}={}", origin, key, value),
+        None => println!("{}{}", origin, value),
+    }
+}
+
+/// 读取或修改分层配置
+///
+/// 读取时依次合并系统（`/etc/rust-git/config`）、用户（`~/.config/rust-git/config`）、
+/// 仓库（`<git 目录>/config`）与命令行 `-c` 四层，后面的优先；`file` 指定时只读这一个文件。
+/// 修改默认写入仓库配置。与 git 一样，键不存在时以状态 1 退出，
+/// 要删除的键不存在或要覆盖多值键时以状态 5 退出。
+pub fn git_config(action: ConfigAction, file: Option<ConfigFile>, show_origin: bool) {
+    match action {
+        ConfigAction::Get(key) | ConfigAction::GetAll(key) if !is_valid_key(&key) => {
+            fail(&format!("error: 无效的配置键 '{}'", key), 1)
+        }
+        ConfigAction::Get(key) => {
+            let layers = read_layers(file);
+            let found = layers.iter().rev().find_map(|layer| layer.config.get(&key).map(|value| (layer, value)));
+            match found {
+                Some((layer, value)) => print_value(layer, None, value, show_origin),
+                None => std::process::exit(1),
+            }
+        }
+        ConfigAction::GetAll(key) => {
+            let layers = read_layers(file);
+            let mut found = false;
+            for layer in &layers {
+                for value in layer.config.get_all(&key) {
+                    print_value(layer, None, value, show_origin);
+                    found = true;
+                }
+            }
+            if !found {
+                std::process::exit(1);
+            }
+        }
+        ConfigAction::List => {
+            for layer in &read_layers(file) {
+                for (key, value) in layer.config.entries() {
+                    print_value(layer, Some(&key), value, show_origin);
+                }
+            }
+        }
+        ConfigAction::Set(key, value) => edit(file, &key, |config| {
+            if config.get_all(&key).len() > 1 {
+                return Err((format!("warning: {} 有多个值\nerror: 不能用一个值覆盖多个值，请使用 --add 或 --unset-all", key), 5));
+            }
+            config.set(&key, &value).map_err(invalid_key)
+        }),
+        ConfigAction::Add(key, value) => edit(file, &key, |config| config.add(&key, &value).map_err(invalid_key)),
+        ConfigAction::Unset(key) => edit(file, &key, |config| match config.get_all(&key).len() {
+            0 => Err((String::new(), 5)),
+            1 => {
+                config.unset_all(&key);
+                Ok(())
+            }
+            _ => Err((format!("warning: {} 有多个值", key), 5)),
+        }),
+        ConfigAction::UnsetAll(key) => edit(file, &key, |config| match config.unset_all(&key) {
+            0 => Err((String::new(), 5)),
+            _ => Ok(()),
+        }),
+    }
+}
+
+/// 修改被拒绝时的错误信息与退出状态
+type Refusal = (String, i32);
+
+fn invalid_key(e: std::io::Error) -> Refusal {
+    (format!("error: {}", e), 1)
+}
+
+/// 修改指定的配置文件（默认为仓库配置）
+///
+/// `change` 拒绝修改时先放弃锁文件再退出，不会留下 `config.lock`。
+fn edit<F>(file: Option<ConfigFile>, key: &str, change: F)
+where
+    F: FnOnce(&mut RepoConfig) -> Result<(), Refusal>,
+{
+    let path = file_path(file.unwrap_or(ConfigFile::Local));
+    let mut refusal = None;
+    let result = update_config_file(&path, |config| {
+        change(config).map_err(|r| {
+            refusal = Some(r);
+            std::io::Error::other(format!("拒绝修改 {}", key))
+        })
+    });
+    if let Some((message, code)) = refusal {
+        if !message.is_empty() {
+            eprintln!("{}", message);
+        }
+        std::process::exit(code);
+    }
+    if let Err(e) = result {
+        fail(&format!("error: 无法写入 {}: {}", path.display(), e), 4);
+    }
+}
@@ -0,0 +1,16 @@
+use crate::core::config::GIT_DIR;
+use crate::core::gc::count_objects;
+
+/// 统计松散对象的数量与占用的空间
+///
+/// 默认输出 `N objects, K kilobytes`，`verbose` 时（`-v`）输出包括无用文件在内的详细报告。
+pub fn git_count_objects(verbose: bool) {
+    match count_objects(&GIT_DIR) {
+        Ok(count) if verbose => print!("{}", count.verbose_report()),
+        Ok(count) => println!("{} objects, {} kilobytes", count.count, count.size),
+        Err(e) => {
+            eprintln!("fatal: 无法统计对象: {}", e);
+            std::process::exit(128);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::core::commit::read_commit_tree;
+use crate::core::config::GIT_DIR;
+use crate::core::diff::{diff_maps_with, RenameOptions};
+use crate::core::index::{index_file, read_index, read_skip_worktree};
+use crate::core::object::read_object;
+use crate::core::reference::resolve_revision;
+use crate::core::tree::read_tree_entries;
+use crate::utils::hash::sha1_hash;
+
+/// 读取某个版本的 tree 映射
+fn revision_map(rev: &str, repo_path: &Path) -> Result<HashMap<String, String>, String> {
+    let commit = resolve_revision(rev, repo_path).ok_or_else(|| format!("无法解析版本 '{}'", rev))?;
+    let tree = read_commit_tree(&commit, repo_path).map_err(|e| format!("无法读取提交 {}: {}", commit, e))?;
+    read_tree_entries(&tree, repo_path).map_err(|e| format!("无法读取 tree {}: {}", tree, e))
+}
+
+/// 读取 index 映射（路径 -> hash）
+fn index_map(repo_path: &Path) -> HashMap<String, String> {
+    read_index(&index_file(repo_path))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(hash, path)| (path, hash))
+        .collect()
+}
+
+/// 计算工作区中指定路径的当前 hash（文件不存在则忽略）
+///
+/// 稀疏检出范围之外（带 skip-worktree 标记）的文件按 index 中的内容计算，不显示为删除。
+fn worktree_map<'a>(paths: impl Iterator<Item = &'a String>, repo_path: &Path) -> HashMap<String, String> {
+    let index = index_map(repo_path);
+    let skipped = read_skip_worktree(&index_file(repo_path));
+    paths
+        .filter_map(|path| match index.get(path).filter(|_| skipped.contains(path)) {
+            Some(hash) => Some((path.clone(), hash.clone())),
+            None => fs::read(path).ok().map(|content| (path.clone(), sha1_hash(&content))),
+        })
+        .collect()
+}
+
+/// 以 `--name-status` 格式显示变更
+///
+/// - 无参数：index 与工作区比较
+/// - `--cached [<commit>]`：提交（默认 HEAD）与 index 比较
+/// - `<commit>`：提交与工作区比较
+/// - `<commit> <commit>`：两个提交比较
+pub fn git_diff(revisions: &[String], cached: bool, opts: RenameOptions) {
+    let repo_path = &*GIT_DIR;
+
+    let result = match (revisions, cached) {
+        ([], false) => {
+            let old = index_map(repo_path);
+            let new = worktree_map(old.keys(), repo_path);
+            Ok((old, new))
+        }
+        ([], true) => revision_map("HEAD", repo_path)
+            .or_else(|_| Ok(HashMap::new()))
+            .map(|old| (old, index_map(repo_path))),
+        ([rev], true) => revision_map(rev, repo_path).map(|old| (old, index_map(repo_path))),
+        ([rev], false) => revision_map(rev, repo_path).map(|old| {
+            let index = index_map(repo_path);
+            let new = worktree_map(old.keys().chain(index.keys()), repo_path);
+            (old, new)
+        }),
+        ([a, b], false) => revision_map(a, repo_path).and_then(|old| revision_map(b, repo_path).map(|new| (old, new))),
+        _ => Err("用法: diff [--cached] [<commit> [<commit>]]".to_string()),
+    };
+
+    let (old, new) = match result {
+        Ok(maps) => maps,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // 工作区里的新内容尚未写入对象库，找不到对象时回退到直接读文件
+    let changes = diff_maps_with(&old, &new, opts, |path, hash| {
+        read_object(hash, repo_path).ok().or_else(|| fs::read(path).ok())
+    });
+    for change in changes {
+        println!("{}", change.name_status());
+    }
+}
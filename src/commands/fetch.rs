@@ -0,0 +1,43 @@
+use crate::core::config::GIT_DIR;
+use crate::core::remote::{default_remote_name, fetch, find_remote, format_update, shorten_ref, RefStatus};
+use crate::core::config::load_config;
+
+/// 从远程获取对象并更新远程跟踪分支
+pub fn git_fetch(remote: Option<&str>) {
+    let repo_path = &*GIT_DIR;
+    let name = remote.map(str::to_string).unwrap_or_else(|| default_remote_name(repo_path));
+    let config = load_config(repo_path).unwrap_or_default();
+    let remote = match find_remote(&name, &config) {
+        Some(r) => r,
+        None => {
+            eprintln!("'{}' 不是远程名，也不是仓库路径", name);
+            std::process::exit(1);
+        }
+    };
+
+    let updates = match fetch(&remote, repo_path) {
+        Ok(u) => u,
+        Err(e) => {
+            eprintln!("获取失败: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let changed: Vec<_> = updates.iter().filter(|u| u.status != RefStatus::UpToDate).collect();
+    if !changed.is_empty() {
+        println!("From {}", remote.url);
+    }
+    let mut rejected = false;
+    for update in changed {
+        let line = format_update(update, shorten_ref(&update.src), shorten_ref(&update.dst));
+        if matches!(update.status, RefStatus::Rejected(_)) {
+            rejected = true;
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+    if rejected {
+        std::process::exit(1);
+    }
+}
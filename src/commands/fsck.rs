@@ -0,0 +1,59 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::core::config::GIT_DIR;
+use crate::core::fsck::{check_repository, Finding};
+use crate::core::object::{read_object, ObjectKind};
+
+/// 把悬空对象写入 `lost-found/`：提交写入 `commit/<hash>`（内容为 hash），其余对象写入 `other/<hash>`（内容为对象本身）
+fn write_lost_found(findings: &[Finding], repo_path: &Path) -> io::Result<()> {
+    for finding in findings {
+        let Finding::Dangling { kind, hash } = finding else { continue };
+        let (dir, content) = if *kind == ObjectKind::Commit {
+            ("commit", format!("{}\n", hash).into_bytes())
+        } else {
+            ("other", read_object(hash, repo_path)?)
+        };
+        let dir = repo_path.join("lost-found").join(dir);
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join(hash), content)?;
+    }
+    Ok(())
+}
+
+/// 校验对象库与引用的完整性
+///
+/// 重新计算所有对象的 hash，从所有引用、reflog 与 index 出发检查连通性，
+/// 报告缺失、损坏与悬空的对象；`lost_found` 时把悬空对象写入 `lost-found/` 以便恢复。
+/// 发现任何错误时以状态 1 退出。
+pub fn git_fsck(lost_found: bool) {
+    let repo_path = &*GIT_DIR;
+    let findings = match check_repository(repo_path) {
+        Ok(findings) => findings,
+        Err(e) => {
+            eprintln!("fatal: fsck 失败: {}", e);
+            std::process::exit(128);
+        }
+    };
+
+    for finding in &findings {
+        match finding {
+            Finding::HashMismatch { .. }
+            | Finding::Garbage(_)
+            | Finding::Corrupt { .. }
+            | Finding::BadRef { .. }
+            | Finding::BadIndexEntry { .. } => eprintln!("{}", finding),
+            _ => println!("{}", finding),
+        }
+    }
+    if lost_found {
+        if let Err(e) = write_lost_found(&findings, repo_path) {
+            eprintln!("fatal: 无法写入 lost-found: {}", e);
+            std::process::exit(128);
+        }
+    }
+
+    if findings.iter().any(Finding::is_error) {
+        std::process::exit(1);
+    }
+}
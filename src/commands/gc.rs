@@ -0,0 +1,39 @@
+use crate::commands::prune::expiry_or_fail;
+use crate::core::config::GIT_DIR;
+use crate::core::gc::{count_objects, prune_objects, ObjectCount, DEFAULT_PRUNE_EXPIRE};
+use crate::core::refs::RefStore;
+use crate::core::config::load_config;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+fn count_or_fail() -> ObjectCount {
+    count_objects(&GIT_DIR).unwrap_or_else(|e| fail(&format!("fatal: 无法统计对象: {}", e)))
+}
+
+/// 整理仓库：打包所有引用，并删除超过宽限期的不可达松散对象
+///
+/// 宽限期依次取 `prune`（`--prune=<date>`）、配置 `gc.pruneExpire`，默认为两周前。
+/// 除非 `quiet`，前后各输出一份 `count-objects -v` 格式的报告。
+pub fn git_gc(prune: Option<&str>, quiet: bool) {
+    let repo_path = &*GIT_DIR;
+    let configured = load_config(repo_path)
+        .ok()
+        .and_then(|config| config.get("gc.pruneExpire").map(str::to_string));
+    let expire = expiry_or_fail(prune.or(configured.as_deref()).unwrap_or(DEFAULT_PRUNE_EXPIRE));
+
+    let before = count_or_fail();
+    if let Err(e) = RefStore::new(repo_path).pack(true, true) {
+        fail(&format!("fatal: pack-refs 失败: {}", e));
+    }
+    let pruned = prune_objects(repo_path, expire, false).unwrap_or_else(|e| fail(&format!("fatal: prune 失败: {}", e)));
+    let after = count_or_fail();
+
+    if !quiet {
+        print!("清理前:\n{}", before.verbose_report());
+        print!("清理后:\n{}", after.verbose_report());
+        println!("已删除 {} 个不可达对象", pruned.len());
+    }
+}
@@ -0,0 +1,40 @@
+use std::fs;
+use std::io::{self, Read};
+use crate::core::config::GIT_DIR;
+use crate::core::object::{detect_kind, write_object, ObjectKind};
+use crate::utils::hash::sha1_hash;
+
+/// 计算文件（或标准输入）内容的对象 hash，`write` 时写入对象库
+///
+/// `kind` 不是 blob 时会检查内容的格式，防止写入无法解析的提交或 tree。
+pub fn git_hash_object(files: &[String], stdin: bool, write: bool, kind: ObjectKind) {
+    let mut inputs: Vec<(String, io::Result<Vec<u8>>)> = Vec::new();
+    if stdin {
+        let mut content = Vec::new();
+        let result = io::stdin().read_to_end(&mut content).map(|_| content);
+        inputs.push(("<stdin>".to_string(), result));
+    }
+    for file in files {
+        inputs.push((file.clone(), fs::read(file)));
+    }
+
+    for (name, content) in inputs {
+        let content = content.unwrap_or_else(|e| {
+            eprintln!("fatal: 无法读取 '{}': {}", name, e);
+            std::process::exit(128);
+        });
+        if kind != ObjectKind::Blob && detect_kind(&content) != kind {
+            eprintln!("fatal: '{}' 不是有效的 {} 对象", name, kind.name());
+            std::process::exit(128);
+        }
+        let hash = if write {
+            write_object(&content, &GIT_DIR).unwrap_or_else(|e| {
+                eprintln!("fatal: 无法写入对象: {}", e);
+                std::process::exit(128);
+            })
+        } else {
+            sha1_hash(&content)
+        };
+        println!("{}", hash);
+    }
+}
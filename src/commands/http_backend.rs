@@ -0,0 +1,55 @@
+use std::fs;
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use crate::core::config::IS_VERBOSE;
+use crate::core::http::{read_request, write_response, Response};
+use crate::core::serve::http_backend;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// 处理一个连接上的一个请求
+fn handle_connection(stream: TcpStream, base: &Path) {
+    let Ok(read_half) = stream.try_clone() else { return };
+    let mut reader = BufReader::new(read_half);
+    let mut writer = stream;
+    let response = match read_request(&mut reader) {
+        Ok(request) => {
+            let response = http_backend(base, &request);
+            if *IS_VERBOSE {
+                println!("🌐 {} {} -> {}", request.method, request.path, response.status);
+            }
+            response
+        }
+        Err(e) if e.kind() == io::ErrorKind::FileTooLarge => Response::error(413, &e.to_string()),
+        Err(e) => Response::error(400, &e.to_string()),
+    };
+    if let Err(e) = write_response(&mut writer, &response) {
+        eprintln!("写出响应失败: {}", e);
+    }
+}
+
+/// 启动内置的 HTTP 服务器，通过 smart HTTP 提供 `directory` 下的仓库
+///
+/// 仓库地址为 `http://<地址>:<端口>/<相对路径>`，`directory` 本身是仓库时路径为空。
+/// 端口为 0 时由系统分配，实际地址打印在第一行输出中。
+pub fn git_http_backend(directory: &str, bind: &str, port: u16) {
+    let base: PathBuf = fs::canonicalize(directory).unwrap_or_else(|_| fail(&format!("目录 '{}' 不存在", directory)));
+    let listener = TcpListener::bind((bind, port)).unwrap_or_else(|e| fail(&format!("无法监听 {}:{}: {}", bind, port, e)));
+    let address = listener.local_addr().unwrap_or_else(|e| fail(&e.to_string()));
+    println!("listening on http://{}", address);
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let base = base.clone();
+                thread::spawn(move || handle_connection(stream, &base));
+            }
+            Err(e) => eprintln!("接受连接失败: {}", e),
+        }
+    }
+}
@@ -1,10 +1,15 @@
-use crate::core::repository::init_repository;
+use crate::core::repository::{init_repository, InitOptions};
 use std::path::Path;
 use crate::core::config::IS_VERBOSE;
-pub fn git_init(target_path: &str) {
+
+/// 初始化仓库：`opts.bare` 时 `target_path` 本身就是 git 目录，否则在其中创建 `.mygit`
+pub fn git_init(target_path: &str, opts: &InitOptions) {
     let path = Path::new(target_path);
-    match init_repository(path) {
-        Ok(_) => {if *IS_VERBOSE { println!("已在 {} 初始化空的Git仓库", path.display())}},
-        Err(e) => eprintln!("初始化失败: {}", e),
+    match init_repository(path, opts) {
+        Ok(git_dir) => {if *IS_VERBOSE { println!("已在 {} 初始化空的Git仓库", git_dir.display())}},
+        Err(e) => {
+            eprintln!("初始化失败: {}", e);
+            std::process::exit(1);
+        }
     }
 }
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::core::commit::{read_commit, split_signature, CommitInfo};
+use crate::core::config::GIT_DIR;
+use crate::core::diff::{diff_maps, Change, RenameOptions};
+use crate::core::reference::{get_current_branch_name, is_unborn, resolve_revision};
+use crate::core::tree::read_tree_entries;
+use crate::utils::time::format_timestamp;
+
+/// 读取提交对应的 tree 映射（失败时视为空）
+fn tree_map(commit: &CommitInfo) -> HashMap<String, String> {
+    read_tree_entries(&commit.tree, &GIT_DIR).unwrap_or_default()
+}
+
+/// 显示提交历史
+///
+/// - `path`：只显示修改了该路径的提交
+/// - `follow`：配合 `path` 使用，遇到重命名时继续追踪旧文件名
+pub fn git_log(revision: Option<&str>, path: Option<&str>, follow: bool, oneline: bool, max_count: Option<usize>) {
+    let repo_path = &*GIT_DIR;
+
+    // 与 git 一样允许省略 `--`：无法解析为版本、但确实是文件时按路径处理
+    let (revision, path) = match (revision, path) {
+        (Some(rev), None) if resolve_revision(rev, repo_path).is_none() && std::path::Path::new(rev).exists() => {
+            (None, Some(rev))
+        }
+        other => other,
+    };
+
+    if follow && path.is_none() {
+        eprintln!("--follow 需要且只能指定一个路径");
+        std::process::exit(1);
+    }
+
+    let start = match resolve_revision(revision.unwrap_or("HEAD"), repo_path) {
+        Some(hash) => hash,
+        None => {
+            match revision {
+                None | Some("HEAD") if is_unborn(repo_path) => {
+                    let branch = get_current_branch_name(repo_path).unwrap_or_default();
+                    eprintln!("fatal: 当前分支 '{}' 还没有任何提交", branch);
+                }
+                rev => eprintln!("无法解析版本 '{}'", rev.unwrap_or("HEAD")),
+            }
+            std::process::exit(1);
+        }
+    };
+
+    let mut tracked = path.map(|p| p.trim_start_matches("./").to_string());
+    let mut queue = VecDeque::from([start]);
+    let mut visited = HashSet::new();
+    let mut shown = 0;
+
+    while let Some(hash) = queue.pop_front() {
+        if !visited.insert(hash.clone()) {
+            continue;
+        }
+        if max_count.is_some_and(|max| shown >= max) {
+            break;
+        }
+
+        let commit = match read_commit(&hash, repo_path) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("无法读取提交 {}: {}", hash, e);
+                break;
+            }
+        };
+        queue.extend(commit.parents.iter().cloned());
+
+        if let Some(current_path) = tracked.clone() {
+            let parent_map = commit
+                .parents
+                .first()
+                .and_then(|p| read_commit(p, repo_path).ok())
+                .map(|p| tree_map(&p))
+                .unwrap_or_default();
+            let opts = RenameOptions { renames: follow, ..RenameOptions::default() };
+            let changes = diff_maps(&parent_map, &tree_map(&commit), repo_path, opts);
+
+            let touched = changes.iter().find(|c| c.path() == current_path);
+            match touched {
+                None => continue,
+                Some(Change::Renamed { from, .. }) => tracked = Some(from.clone()),
+                Some(_) => {}
+            }
+        }
+
+        print_commit(&hash, &commit, oneline);
+        shown += 1;
+    }
+}
+
+fn print_commit(hash: &str, commit: &CommitInfo, oneline: bool) {
+    if oneline {
+        println!("{} {}", &hash[..7.min(hash.len())], commit.subject());
+        return;
+    }
+
+    println!("commit {}", hash);
+    if commit.parents.len() > 1 {
+        let short: Vec<_> = commit.parents.iter().map(|p| &p[..7.min(p.len())]).collect();
+        println!("Merge: {}", short.join(" "));
+    }
+    let (author, date) = split_signature(&commit.author);
+    println!("Author: {}", author);
+    if let Some((timestamp, tz)) = date {
+        println!("Date:   {}", format_timestamp(timestamp, tz));
+    }
+    println!();
+    for line in commit.message.lines() {
+        println!("    {}", line);
+    }
+    println!();
+}
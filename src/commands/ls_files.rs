@@ -0,0 +1,24 @@
+use crate::core::config::GIT_DIR;
+use crate::core::index::{index_file, read_index_map, read_skip_worktree};
+
+/// 列出 index 中的文件，`stage` 时按 `<模式> <hash> <阶段>\t<路径>` 输出
+///
+/// `tags` 时（`-t`）在每行前加上状态标记：`H` 表示普通文件，`S` 表示带 skip-worktree 标记的文件。
+pub fn git_ls_files(stage: bool, tags: bool) {
+    let index_path = index_file(&GIT_DIR);
+    let mut entries: Vec<(String, String)> = read_index_map(&index_path).into_iter().collect();
+    let skipped = if tags { read_skip_worktree(&index_path) } else { Default::default() };
+    entries.sort();
+    for (path, hash) in entries {
+        let tag = match (tags, skipped.contains(&path)) {
+            (false, _) => "",
+            (true, true) => "S ",
+            (true, false) => "H ",
+        };
+        if stage {
+            println!("{}100644 {} 0\t{}", tag, hash, path);
+        } else {
+            println!("{}{}", tag, path);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::core::commit::read_commit;
+use crate::core::config::GIT_DIR;
+use crate::core::object::{detect_kind, read_object, ObjectKind};
+use crate::core::reference::read_resolved_object;
+use crate::commands::cat_file::format_tree_entries;
+
+/// 列出 tree（或提交的 tree）的内容，`paths` 非空时只列出匹配的路径
+pub fn git_ls_tree(tree_ish: &str, recursive: bool, name_only: bool, paths: &[String]) {
+    let repo_path = &*GIT_DIR;
+    let (hash, content) = read_resolved_object(tree_ish, repo_path).unwrap_or_else(|| {
+        eprintln!("fatal: 不是有效的对象名 '{}'", tree_ish);
+        std::process::exit(128);
+    });
+    let tree = match detect_kind(&content) {
+        ObjectKind::Commit => read_commit(&hash, repo_path)
+            .ok()
+            .and_then(|c| read_object(&c.tree, repo_path).ok().map(|content| (c.tree, content))),
+        ObjectKind::Tree => Some((hash, content)),
+        // 空内容也是合法的空 tree
+        ObjectKind::Blob if content.is_empty() => Some((hash, content)),
+        _ => None,
+    };
+    let Some((tree, content)) = tree else {
+        eprintln!("fatal: '{}' 不是 tree 对象", tree_ish);
+        std::process::exit(128);
+    };
+
+    let listing = format_tree_entries(&tree, &content, recursive, name_only).unwrap_or_else(|e| {
+        eprintln!("fatal: 无法读取 tree: {}", e);
+        std::process::exit(128);
+    });
+    for line in listing.lines() {
+        let path = line.rsplit_once('\t').map_or(line, |(_, p)| p);
+        let matched = paths.is_empty()
+            || paths.iter().any(|p| {
+                let p = p.trim_end_matches('/');
+                path == p || path.starts_with(&format!("{}/", p))
+            });
+        if matched {
+            println!("{}", line);
+        }
+    }
+}
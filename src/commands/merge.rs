@@ -1,17 +1,16 @@
-use std::fs;
 use std::path::Path;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use crate::core::commit::{read_commit_tree, create_merge_commit,find_common_ancestor};
-use crate::core::reference::{get_head_ref, get_current_branch_name};
-use crate::core::tree::{
-    read_tree_entries, load_blob, merge_tree_simple, write_tree_from_map, restore_tree,
-};
+use crate::core::merge::{merge_trees, Conflict};
+use crate::core::reference::{get_head_ref, get_current_branch_name, read_ref, write_ref};
+use crate::core::tree::{read_tree_entries, write_tree_from_map, restore_tree};
 use crate::core::config::{GIT_DIR, IS_VERBOSE};
-pub fn git_merge(target_branch: &str) {
+use crate::core::hooks::run_hook;
+pub fn git_merge(target_branch: &str, no_verify: bool) {
     let repo_path = &*GIT_DIR;
 
     // 获取 HEAD 和当前分支
-    let head_ref_path = match get_head_ref(repo_path) {
+    let head_ref = match get_head_ref(repo_path) {
         Ok(p) => p,
         Err(e) => return eprintln!("无法获取 HEAD: {}", e),
     };
@@ -26,13 +25,25 @@ pub fn git_merge(target_branch: &str) {
     //     return;
     // }
 
-    let target_ref = repo_path.join("refs/heads").join(target_branch);
-    if !target_ref.exists() {
-        return eprintln!("目标分支 '{}' 不存在", target_branch);
-    }
+    let target_ref = format!("refs/heads/{}", target_branch);
 
-    let current_commit = fs::read_to_string(&head_ref_path).unwrap().trim().to_string();
-    let target_commit = fs::read_to_string(&target_ref).unwrap().trim().to_string();
+    let Some(target_commit) = read_ref(repo_path, &target_ref) else {
+        return eprintln!("目标分支 '{}' 不存在", target_branch);
+    };
+    // 当前分支还没有提交：直接指向目标提交并检出
+    let Some(current_commit) = read_ref(repo_path, &head_ref) else {
+        let checked_out = read_commit_tree(&target_commit, repo_path)
+            .and_then(|tree| write_ref(repo_path, &head_ref, &target_commit).map(|_| tree))
+            .and_then(|tree| restore_tree(&tree, repo_path));
+        if let Err(e) = checked_out {
+            eprintln!("合并失败: {}", e);
+            std::process::exit(1);
+        }
+        if *IS_VERBOSE {
+            println!("当前分支还没有提交，已直接指向 '{}'", target_branch);
+        }
+        return;
+    };
 
     if current_commit == target_commit {
         if *IS_VERBOSE {
@@ -40,11 +51,6 @@ pub fn git_merge(target_branch: &str) {
         }
         return;
     }
-    if current_commit.len() < 2 || target_commit.len() < 2 {
-        if *IS_VERBOSE {
-            return eprintln!("当前或目标分支为空，无法合并");
-        }
-    }
 
     // 读取三方 tree
     let current_tree_hash = read_commit_tree(&current_commit, repo_path).unwrap();
@@ -61,62 +67,31 @@ pub fn git_merge(target_branch: &str) {
         HashMap::new() // 无共同祖先，视为初次提交
     };
 
-    // 冲突检测（base-aware）
-    let all_files: HashSet<_> = current_tree
-        .keys()
-        .chain(target_tree.keys())
-        .collect();
-
-    let mut conflict_found = false;
-
-    for file in all_files {
-        let base = base_tree.get(file);
-        let cur = current_tree.get(file);
-        let tgt = target_tree.get(file);
-
-        // if base == cur || base == tgt {
-        //     continue; // 一边没改，自动合并
-        // }
-
-        if cur != tgt && base != cur && base != tgt {
-            let cur_lines = cur
-                .and_then(|h| load_blob(h, repo_path).ok())
-                .unwrap_or_default();
-            let tgt_lines = tgt
-                .and_then(|h| load_blob(h, repo_path).ok())
-                .unwrap_or_default();
-
-            let max_lines = cur_lines.len().max(tgt_lines.len());
-            let mut i = 0;
-            while i < max_lines {
-                let l1 = cur_lines.get(i);
-                let l2 = tgt_lines.get(i);
-                if l1 != l2 {
-                    let start = i + 1;
-                    let mut end = start;
-                    i += 1;
-                    while i < max_lines && cur_lines.get(i) != tgt_lines.get(i) {
-                        end = i + 1;
-                        i += 1;
-                    }
-                    let name = Path::new(file)
-                        .file_name()
-                        .unwrap_or_else(|| file.as_ref())
-                        .to_string_lossy();
+    // 冲突检测（base-aware，跟随重命名）
+    let outcome = merge_trees(&base_tree, &current_tree, &target_tree, repo_path);
+
+    for conflict in &outcome.conflicts {
+        match conflict {
+            Conflict::Content { path, ranges } => {
+                let name = Path::new(path)
+                    .file_name()
+                    .unwrap_or_else(|| path.as_ref())
+                    .to_string_lossy();
+                for (start, end) in ranges {
                     if start == end {
                         println!("Merge conflict in {}: {}", name, start);
                     } else {
                         println!("Merge conflict in {}: [{}-{}]", name, start, end);
                     }
-                    conflict_found = true;
-                } else {
-                    i += 1;
                 }
             }
+            Conflict::RenameRename { source, ours, theirs } => {
+                println!("Merge conflict in {}: renamed to {} and {}", source, ours, theirs);
+            }
         }
     }
 
-    if conflict_found {
+    if !outcome.conflicts.is_empty() {
         if *IS_VERBOSE {
             println!("❗ 冲突发生，请手动解决");
         }
@@ -125,9 +100,22 @@ pub fn git_merge(target_branch: &str) {
     if *IS_VERBOSE {
         println!("存在分叉但无冲突");
     }
-    // 合并 tree（默认以目标为主）
-    // let merged_tree = merge_tree_simple(&current_tree, &target_tree);
-    let merged_tree = merge_tree_simple(&base_tree, &current_tree, &target_tree);
+    let merged_tree = outcome.tree;
+
+    // pre-merge-commit 钩子失败时放弃合并，不留下任何修改
+    if !no_verify {
+        match run_hook(repo_path, "pre-merge-commit", &[], None) {
+            Ok(true) => {}
+            Ok(false) => {
+                eprintln!("合并失败: pre-merge-commit 钩子返回了非零状态");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("合并失败: 无法运行 pre-merge-commit 钩子: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
 
     let new_tree_hash = write_tree_from_map(&merged_tree, repo_path).unwrap();
 
@@ -141,7 +129,7 @@ pub fn git_merge(target_branch: &str) {
         .unwrap();
 
     // 更新 HEAD
-    fs::write(&head_ref_path, format!("{}\n", merge_commit_hash)).unwrap();
+    write_ref(repo_path, &head_ref, &merge_commit_hash).unwrap();
     restore_tree(&new_tree_hash, repo_path).unwrap();
     if *IS_VERBOSE {
         println!("已合并分支 '{}'（创建合并提交）", target_branch);
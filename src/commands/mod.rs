@@ -5,4 +5,33 @@ pub mod branch;
 pub mod checkout;
 pub mod merge;
 pub mod rm;
-pub mod status;
\ No newline at end of file
+pub mod status;
+pub mod log;
+pub mod diff;
+pub mod cherry_pick;
+pub mod rebase;
+pub mod remote;
+pub mod clone;
+pub mod fetch;
+pub mod push;
+pub mod upload_pack;
+pub mod receive_pack;
+pub mod http_backend;
+pub mod pack_refs;
+pub mod cat_file;
+pub mod hash_object;
+pub mod ls_tree;
+pub mod ls_files;
+pub mod write_tree;
+pub mod commit_tree;
+pub mod update_ref;
+pub mod symbolic_ref;
+pub mod show_ref;
+pub mod fsck;
+pub mod count_objects;
+pub mod prune;
+pub mod gc;
+pub mod check_ref_format;
+pub mod config;
+pub mod worktree;
+pub mod sparse_checkout;
\ No newline at end of file
@@ -0,0 +1,20 @@
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::refs::RefStore;
+
+/// 把松散引用打包进 `packed-refs`
+///
+/// 默认只打包标签（以及已经打包过的引用），`all` 时打包所有分支与远程跟踪分支；
+/// `prune` 为 false 时（`--no-prune`）保留松散引用文件。
+pub fn git_pack_refs(all: bool, prune: bool) {
+    match RefStore::new(&GIT_DIR).pack(all, prune) {
+        Ok(count) => {
+            if *IS_VERBOSE {
+                println!("📦 已打包 {} 个引用", count);
+            }
+        }
+        Err(e) => {
+            eprintln!("fatal: pack-refs 失败: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
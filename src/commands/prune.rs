@@ -0,0 +1,36 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::gc::prune_objects;
+use crate::utils::time::parse_expiry;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+/// 当前时间的 unix 时间戳
+pub fn now_secs() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// 解析 `--expire` / `--prune` 的时间，无法识别时退出
+pub fn expiry_or_fail(spec: &str) -> i64 {
+    parse_expiry(spec, now_secs()).unwrap_or_else(|| fail(&format!("fatal: 无法识别的过期时间 '{}'", spec)))
+}
+
+/// 删除从引用、reflog 与 index 都不可达的松散对象
+///
+/// `expire` 默认为 `now`，即不保留任何宽限期；`dry_run` 时（`-n`）只列出将被删除的对象，
+/// `verbose` 时（`-v`）列出实际删除的对象，格式均为 `<hash> <类型>`。
+pub fn git_prune(expire: Option<&str>, dry_run: bool, verbose: bool) {
+    let expire = expiry_or_fail(expire.unwrap_or("now"));
+    let pruned = prune_objects(&GIT_DIR, expire, dry_run).unwrap_or_else(|e| fail(&format!("fatal: prune 失败: {}", e)));
+    if dry_run || verbose {
+        for (kind, hash) in &pruned {
+            println!("{} {}", hash, kind.name());
+        }
+    }
+    if *IS_VERBOSE {
+        println!("🧹 {} 个不可达对象", pruned.len());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::reference::{check_ref_format, get_current_branch_name, ref_exists, resolve_revision, RefFormatOptions};
+use crate::core::remote::{default_remote_name, find_remote, format_update, push, shorten_ref, PushSpec, RefStatus};
+use crate::core::config::load_config;
+use crate::core::repo_config::update_repo_config;
+
+/// push 的选项
+#[derive(Debug, Default)]
+pub struct PushOptions {
+    pub force: bool,
+    /// `--force-with-lease[=<引用>[:<期望值>]]`
+    pub force_with_lease: Option<Option<String>>,
+    pub set_upstream: bool,
+    pub delete: bool,
+    /// 跳过 pre-push 钩子
+    pub no_verify: bool,
+}
+
+/// 补全为完整的引用名：已有的本地分支或标签按其类型，其余按分支处理
+fn qualify(name: &str) -> String {
+    if name.starts_with("refs/") {
+        return name.to_string();
+    }
+    let tag = format!("refs/tags/{}", name);
+    if ref_exists(&GIT_DIR, &tag) && !ref_exists(&GIT_DIR, &format!("refs/heads/{}", name)) {
+        return tag;
+    }
+    format!("refs/heads/{}", name)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+/// 把本地分支推送到远程
+pub fn git_push(remote: Option<&str>, refspecs: &[String], opts: PushOptions) {
+    let repo_path = &*GIT_DIR;
+    let config = load_config(repo_path).unwrap_or_default();
+    let name = remote.map(str::to_string).unwrap_or_else(|| default_remote_name(repo_path));
+    let remote = find_remote(&name, &config).unwrap_or_else(|| fail(&format!("'{}' 不是远程名，也不是仓库路径", name)));
+
+    // 不指定时推送当前分支到其上游（或同名分支）
+    let refspecs: Vec<String> = if refspecs.is_empty() {
+        let branch = get_current_branch_name(repo_path).unwrap_or_else(|| fail("当前不在任何分支上，请指定要推送的分支"));
+        let upstream = config
+            .get(&format!("branch.{}.merge", branch))
+            .filter(|_| config.get(&format!("branch.{}.remote", branch)) == Some(name.as_str()));
+        match upstream {
+            Some(merge) => vec![format!("refs/heads/{}:{}", branch, merge)],
+            None => vec![format!("refs/heads/{}", branch)],
+        }
+    } else {
+        refspecs.to_vec()
+    };
+
+    // --force-with-lease=<引用>[:<期望值>] 只作用于指定的引用
+    let (lease_ref, lease_expect) = match &opts.force_with_lease {
+        Some(Some(value)) => {
+            let (r, expect) = match value.split_once(':') {
+                Some((r, e)) => (r, Some(e)),
+                None => (value.as_str(), None),
+            };
+            // 完整的 hash 不要求本地有该对象
+            let expect = expect.map(|e| {
+                if e.len() == 40 && e.chars().all(|c| c.is_ascii_hexdigit()) {
+                    e.to_lowercase()
+                } else {
+                    resolve_revision(e, repo_path).unwrap_or_else(|| fail(&format!("无法解析版本 '{}'", e)))
+                }
+            });
+            (Some(qualify(r)), expect)
+        }
+        _ => (None, None),
+    };
+
+    let mut specs = Vec::new();
+    for spec in &refspecs {
+        let (force, spec) = match spec.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (opts.force, spec.as_str()),
+        };
+        let (src, dst) = if opts.delete {
+            (None, qualify(spec))
+        } else {
+            match spec.split_once(':') {
+                Some(("", dst)) => (None, qualify(dst)),
+                Some((src, dst)) => (Some(src.to_string()), qualify(dst)),
+                None => (Some(spec.to_string()), qualify(spec)),
+            }
+        };
+        if src.is_some() {
+            if let Err(reason) = check_ref_format(&dst, RefFormatOptions::default()) {
+                fail(&format!("fatal: 无效的目标引用 '{}': {}", dst, reason));
+            }
+        }
+        let src = src.map(|s| if ref_exists(&GIT_DIR, &format!("refs/heads/{}", s)) { format!("refs/heads/{}", s) } else { s });
+        let lease = match (&opts.force_with_lease, &lease_ref) {
+            (None, _) => None,
+            (Some(_), Some(r)) if *r != dst => None,
+            (Some(_), Some(_)) => Some(lease_expect.clone()),
+            (Some(_), None) => Some(None),
+        };
+        specs.push(PushSpec { src, dst, force, lease });
+    }
+
+    let updates = push(&remote, &specs, !opts.no_verify, repo_path).unwrap_or_else(|e| fail(&format!("推送失败: {}", e)));
+
+    if updates.iter().all(|u| u.status == RefStatus::UpToDate) {
+        println!("Everything up-to-date");
+    } else {
+        println!("To {}", remote.url);
+    }
+    let mut rejected = false;
+    let mut remote_rejected = false;
+    for update in &updates {
+        if update.status == RefStatus::UpToDate {
+            continue;
+        }
+        let line = format_update(update, shorten_ref(&update.src), shorten_ref(&update.dst));
+        if matches!(update.status, RefStatus::Rejected(_)) {
+            rejected = true;
+            eprintln!("{}", line);
+        } else if matches!(update.status, RefStatus::RemoteRejected(_)) {
+            remote_rejected = true;
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    if opts.set_upstream {
+        if let Some(remote_name) = &remote.name {
+            for update in updates.iter().filter(|u| !matches!(u.status, RefStatus::Rejected(_) | RefStatus::RemoteRejected(_) | RefStatus::Deleted)) {
+                let (Some(branch), Some(merge)) = (update.src.strip_prefix("refs/heads/"), update.dst.strip_prefix("refs/heads/")) else {
+                    continue;
+                };
+                let result = update_repo_config(repo_path, |config| {
+                    config.set(&format!("branch.{}.remote", branch), remote_name)?;
+                    config.set(&format!("branch.{}.merge", branch), &update.dst)
+                });
+                match result {
+                    Ok(_) => println!("branch '{}' set up to track '{}/{}'.", branch, remote_name, merge),
+                    Err(e) => eprintln!("无法设置上游分支: {}", e),
+                }
+            }
+        }
+    }
+
+    if rejected || remote_rejected {
+        eprintln!("error: 部分引用推送失败");
+        if rejected {
+            eprintln!("提示：远程包含本地没有的提交，请先 fetch 并合并后再推送，或使用 --force-with-lease");
+        }
+        std::process::exit(1);
+    }
+    if *IS_VERBOSE {
+        println!("🚀 推送完成");
+    }
+}
@@ -0,0 +1,490 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use crate::core::commit::{
+    collect_ancestors, default_signature, is_ancestor, read_commit, rev_list, update_head, write_commit,
+};
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::index::{index_file, read_index_map};
+use crate::core::reference::{get_head_ref, read_head_commit_hash, resolve_revision, set_head, write_ref};
+use crate::core::refs::RefStore;
+use crate::core::sequencer::{
+    apply_commit, first_unresolved, format_todo, read_todo, reset_hard, write_todo, Action, ApplyOutcome, TodoItem,
+};
+use crate::core::tree::{read_tree_entries, write_tree_from_map};
+use crate::utils::editor::{cleanup_whitespace, commit_editor, edit_text, launch_editor, sequence_editor};
+use crate::utils::fs::write_atomic;
+use crate::utils::hash::sha1_hash;
+
+/// rebase 的子操作
+pub enum RebaseCommand {
+    /// 把当前分支独有的提交重放到 `onto`（默认为 `upstream`）之上
+    Start { upstream: String, onto: Option<String>, interactive: bool },
+    /// 解决冲突或修改完提交后继续
+    Continue,
+    /// 放弃 rebase，回到开始前的状态
+    Abort,
+    /// 跳过当前提交
+    Skip,
+}
+
+const TODO_HELP: &str = "
+# 命令：
+# p, pick <commit> = 使用该提交
+# r, reword <commit> = 使用该提交，但修改提交说明
+# e, edit <commit> = 使用该提交，但停下来以便修改
+# s, squash <commit> = 使用该提交，并与前一个提交合并（编辑合并后的说明）
+# f, fixup <commit> = 与 squash 相同，但丢弃该提交的说明
+# x, exec <command> = 用 shell 执行命令，失败时停下
+# d, drop <commit> = 丢弃该提交
+#
+# 各行按从上到下的顺序执行，可以调整顺序。
+# 删除某一行会丢弃对应的提交；删除所有行则取消 rebase。
+";
+
+pub fn git_rebase(command: RebaseCommand) {
+    match command {
+        RebaseCommand::Start { upstream, onto, interactive } => start(&upstream, onto.as_deref(), interactive),
+        RebaseCommand::Continue => resume(true),
+        RebaseCommand::Skip => resume(false),
+        RebaseCommand::Abort => abort(),
+    }
+}
+
+fn state_dir() -> PathBuf {
+    GIT_DIR.join("rebase-merge")
+}
+
+fn state_file(name: &str) -> PathBuf {
+    state_dir().join(name)
+}
+
+fn read_state(name: &str) -> String {
+    fs::read_to_string(state_file(name)).unwrap_or_default().trim().to_string()
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..7.min(hash.len())]
+}
+
+/// HEAD 指向的提交；分支还没有提交时无法 rebase
+fn head_commit() -> String {
+    read_head_commit_hash(&GIT_DIR).ok().flatten().unwrap_or_else(|| fail("当前分支还没有任何提交"))
+}
+
+fn tree_of(commit: &str) -> Option<String> {
+    read_commit(commit, &GIT_DIR).ok().map(|c| c.tree)
+}
+
+/// index 与工作区中已跟踪的文件都必须与 HEAD 一致
+fn ensure_clean(head: &str) {
+    let repo_path = &*GIT_DIR;
+    let head_tree = tree_of(head)
+        .and_then(|t| read_tree_entries(&t, repo_path).ok())
+        .unwrap_or_default();
+    let index = read_index_map(&index_file(repo_path));
+    if index != head_tree {
+        fail("暂存区有未提交的修改，请先提交后再执行 rebase");
+    }
+    for (path, hash) in &index {
+        if fs::read(path).map(|c| sha1_hash(&c)).ok().as_ref() != Some(hash) {
+            fail(&format!("工作区中的 {} 有未提交的修改，请先提交后再执行 rebase", path));
+        }
+    }
+}
+
+fn start(upstream: &str, onto: Option<&str>, interactive: bool) {
+    let repo_path = &*GIT_DIR;
+    if state_dir().exists() {
+        fail("已有 rebase 正在进行，请先使用 --continue、--skip 或 --abort");
+    }
+    if repo_path.join("sequencer").exists() {
+        fail("有 cherry-pick 或 revert 正在进行，请先完成或放弃");
+    }
+
+    let resolve = |rev: &str| resolve_revision(rev, repo_path).unwrap_or_else(|| fail(&format!("无法解析版本 '{}'", rev)));
+    let upstream_hash = resolve(upstream);
+    let onto_hash = onto.map(resolve).unwrap_or_else(|| upstream_hash.clone());
+    let head = head_commit();
+    ensure_clean(&head);
+
+    let head_name = get_head_ref(repo_path).unwrap_or_else(|_| "detached HEAD".to_string());
+
+    // 只重放当前分支独有的非合并提交
+    let upstream_ancestors = collect_ancestors(&upstream_hash, repo_path);
+    let commits: Vec<String> = rev_list(&head, &upstream_ancestors, repo_path)
+        .into_iter()
+        .filter(|c| read_commit(c, repo_path).map(|info| info.parents.len() <= 1).unwrap_or(false))
+        .collect();
+
+    if !interactive {
+        let onto_ancestors = collect_ancestors(&onto_hash, repo_path);
+        let already_based = is_ancestor(&onto_hash, &head, repo_path)
+            && rev_list(&head, &onto_ancestors, repo_path).len() == commits.len();
+        if already_based {
+            println!("Current branch {} is up to date.", head_name.trim_start_matches("refs/heads/"));
+            return;
+        }
+    }
+
+    let mut todo: Vec<TodoItem> = commits
+        .iter()
+        .map(|c| TodoItem {
+            action: Action::Pick,
+            commit: short(c).to_string(),
+            subject: read_commit(c, repo_path).map(|i| i.subject().to_string()).unwrap_or_default(),
+        })
+        .collect();
+
+    let dir = state_dir();
+    if let Err(e) = fs::create_dir_all(&dir) {
+        fail(&format!("无法创建 rebase 状态目录: {}", e));
+    }
+
+    if interactive {
+        let todo_path = state_file("git-rebase-todo");
+        let help = format!(
+            "\n# Rebase {}..{} onto {} ({} 条命令)\n{}",
+            short(&upstream_hash),
+            short(&head),
+            short(&onto_hash),
+            todo.len(),
+            TODO_HELP
+        );
+        let edited = fs::write(&todo_path, format_todo(&todo) + &help)
+            .and_then(|_| launch_editor(&sequence_editor(), &todo_path))
+            .and_then(|_| read_todo(&todo_path));
+        todo = match edited {
+            Ok(items) => items,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                fail(&format!("无法读取待办列表: {}", e));
+            }
+        };
+        if let Err(e) = validate_todo(&todo) {
+            let _ = fs::remove_dir_all(&dir);
+            fail(&e);
+        }
+        if todo.is_empty() {
+            let _ = fs::remove_dir_all(&dir);
+            println!("Nothing to do");
+            return;
+        }
+    }
+
+    let saved = fs::write(state_file("head-name"), format!("{}\n", head_name))
+        .and_then(|_| fs::write(state_file("orig-head"), format!("{}\n", head)))
+        .and_then(|_| fs::write(state_file("onto"), format!("{}\n", onto_hash)))
+        .and_then(|_| fs::write(state_file("done"), ""))
+        .and_then(|_| write_todo(&state_file("git-rebase-todo"), &todo));
+    if let Err(e) = saved {
+        fail(&format!("无法保存 rebase 状态: {}", e));
+    }
+
+    // 分离 HEAD 到新的基点，再逐个重放
+    let moved = write_ref(repo_path, "HEAD", &onto_hash)
+        .and_then(|_| reset_hard(Some(&onto_hash), &[], repo_path));
+    if let Err(e) = moved {
+        fail(&format!("无法切换到 {}: {}", short(&onto_hash), e));
+    }
+    if *IS_VERBOSE {
+        println!("🔁 开始 rebase：{} 个提交 -> {}", todo.len(), short(&onto_hash));
+    }
+
+    run_todo();
+}
+
+/// 检查待办列表中的提交都能解析，且 squash/fixup 前面有可合并的提交
+fn validate_todo(todo: &[TodoItem]) -> Result<(), String> {
+    let mut has_previous = false;
+    for item in todo {
+        match item.action {
+            Action::Exec => continue,
+            Action::Revert => return Err("rebase 不支持 revert 操作".to_string()),
+            _ => {}
+        }
+        if resolve_revision(&item.commit, &GIT_DIR).is_none() {
+            return Err(format!("无法解析待办列表中的提交 '{}'", item.commit));
+        }
+        match item.action {
+            Action::Squash | Action::Fixup if !has_previous => {
+                return Err(format!("无法在没有前一个提交的情况下执行 '{}'", item.action.as_str()));
+            }
+            Action::Drop => {}
+            _ => has_previous = true,
+        }
+    }
+    Ok(())
+}
+
+/// 依次执行待办列表，遇到冲突或 edit 时停下
+fn run_todo() {
+    let repo_path = &*GIT_DIR;
+    loop {
+        let mut todo = read_todo(&state_file("git-rebase-todo")).unwrap_or_else(|e| fail(&format!("无法读取待办列表: {}", e)));
+        if todo.is_empty() {
+            return finish();
+        }
+
+        let item = todo.remove(0);
+        let mut done = fs::read_to_string(state_file("done")).unwrap_or_default();
+        done.push_str(&format_todo(std::slice::from_ref(&item)));
+        let saved = write_todo(&state_file("git-rebase-todo"), &todo).and_then(|_| write_atomic(&state_file("done"), done.as_bytes()));
+        if let Err(e) = saved {
+            fail(&format!("无法保存 rebase 状态: {}", e));
+        }
+
+        match item.action {
+            Action::Drop => continue,
+            Action::Exec => {
+                if *IS_VERBOSE {
+                    println!("▶ 执行: {}", item.subject);
+                }
+                let ok = Command::new("sh").arg("-c").arg(&item.subject).status().is_ok_and(|s| s.success());
+                if !ok {
+                    eprintln!("执行失败: {}", item.subject);
+                    eprintln!("提示：修复问题后执行 \"rebase --continue\"");
+                    std::process::exit(1);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let hash = resolve_revision(&item.commit, repo_path)
+            .unwrap_or_else(|| fail(&format!("无法解析待办列表中的提交 '{}'", item.commit)));
+        let commit = read_commit(&hash, repo_path).unwrap_or_else(|e| fail(&format!("无法读取提交 {}: {}", hash, e)));
+        let head = head_commit();
+
+        // 父提交就是当前 HEAD 时直接快进，保留原提交
+        let replays_in_place = matches!(item.action, Action::Pick | Action::Reword | Action::Edit)
+            && commit.parents.len() == 1
+            && commit.parents[0] == head;
+        if replays_in_place {
+            let moved = update_head(repo_path, &hash).and_then(|_| reset_hard(Some(&hash), &[], repo_path));
+            if let Err(e) = moved {
+                fail(&format!("无法快进到 {}: {}", short(&hash), e));
+            }
+            if item.action == Action::Reword {
+                commit_step(&item, &hash, &commit.tree, true);
+            }
+            if item.action == Action::Edit {
+                return stop_for_edit(&item);
+            }
+            continue;
+        }
+
+        match apply_commit(&hash, false, None, repo_path) {
+            Err(e) => fail(&format!("{}\n提示：可以执行 \"rebase --skip\" 跳过该提交，或 \"rebase --abort\" 放弃 rebase", e)),
+            Ok(ApplyOutcome::Conflicted(paths)) => {
+                let saved = fs::write(repo_path.join("REBASE_HEAD"), format!("{}\n", hash))
+                    .and_then(|_| fs::write(state_file("conflicts"), paths.join("\n")));
+                if let Err(e) = saved {
+                    fail(&format!("无法保存 rebase 状态: {}", e));
+                }
+                for path in &paths {
+                    println!("Merge conflict in {}", path);
+                }
+                eprintln!("无法应用 {}... {}", short(&hash), commit.subject());
+                eprintln!("提示：解决冲突后用 add 标记已解决，再执行 \"rebase --continue\"；");
+                eprintln!("提示：也可以执行 \"rebase --skip\" 跳过该提交，或 \"rebase --abort\" 放弃 rebase");
+                std::process::exit(1);
+            }
+            Ok(ApplyOutcome::Clean(tree)) => {
+                commit_step(&item, &hash, &tree, false);
+                if item.action == Action::Edit {
+                    return stop_for_edit(&item);
+                }
+            }
+        }
+    }
+}
+
+/// 根据操作类型把 `tree` 提交为新的 HEAD
+///
+/// - pick/edit/reword：以当前 HEAD 为父提交，保留原作者（`in_place` 时 HEAD 已是原提交，只改说明）
+/// - squash/fixup：与当前 HEAD 合并为一个提交
+fn commit_step(item: &TodoItem, hash: &str, tree: &str, in_place: bool) {
+    let repo_path = &*GIT_DIR;
+    let original = read_commit(hash, repo_path).unwrap_or_else(|e| fail(&format!("无法读取提交 {}: {}", hash, e)));
+    let head = head_commit();
+    let head_info = read_commit(&head, repo_path).ok();
+    let committer = default_signature();
+
+    let (parents, author, message) = match item.action {
+        Action::Squash | Action::Fixup => {
+            let previous = head_info.clone().unwrap_or_else(|| fail("没有可以合并的前一个提交"));
+            let message = if item.action == Action::Fixup {
+                previous.message.clone()
+            } else {
+                let combined = format!(
+                    "# 前一个提交的说明：\n\n{}\n\n# 提交 {} 的说明：\n\n{}\n",
+                    previous.message.trim_end(),
+                    short(hash),
+                    original.message.trim_end()
+                );
+                edit_message(&combined)
+            };
+            (previous.parents, previous.author, message)
+        }
+        _ => {
+            let message = if item.action == Action::Reword {
+                edit_message(&original.message)
+            } else {
+                original.message.clone()
+            };
+            let parents = if in_place { original.parents.clone() } else { vec![head.clone()] };
+            if !in_place && head_info.as_ref().map(|h| h.tree.as_str()) == Some(tree) {
+                println!("提交 {} 的改动已存在，跳过空提交", short(hash));
+                return;
+            }
+            (parents, original.author.clone(), message)
+        }
+    };
+
+    let result = write_commit(repo_path, tree, &parents, &author, &committer, &message)
+        .and_then(|new| update_head(repo_path, &new).map(|_| new));
+    match result {
+        Ok(new) => {
+            if *IS_VERBOSE {
+                println!("🔁 {} {} -> {} {}", item.action.as_str(), short(hash), short(&new), original.subject());
+            }
+        }
+        Err(e) => fail(&format!("提交失败: {}", e)),
+    }
+}
+
+/// 用编辑器修改提交说明，说明为空时中止
+///
+/// 与 commit 一样，去掉注释后合并连续的空行并去掉首尾空行。
+fn edit_message(initial: &str) -> String {
+    let path = state_file("message");
+    let initial = format!("{}\n\n# 请输入提交说明，以 '#' 开头的行会被忽略，说明为空会中止操作。\n", initial.trim_end());
+    match edit_text(&commit_editor(), &path, &initial).map(|message| cleanup_whitespace(&message)) {
+        Ok(message) if !message.is_empty() => message,
+        Ok(_) => fail("提交说明为空，已中止；修改后可执行 \"rebase --continue\""),
+        Err(e) => fail(&format!("无法编辑提交说明: {}", e)),
+    }
+}
+
+/// edit 操作：记录停下时的提交，等待用户修改
+fn stop_for_edit(item: &TodoItem) {
+    let head = head_commit();
+    if let Err(e) = fs::write(state_file("amend"), format!("{}\n", head)) {
+        fail(&format!("无法保存 rebase 状态: {}", e));
+    }
+    println!("Stopped at {}... {}", short(&head), item.subject);
+    println!("现在可以修改文件并 add，然后执行 \"rebase --continue\"");
+}
+
+/// `--continue`（commit 为 true）或 `--skip`
+fn resume(commit: bool) {
+    let repo_path = &*GIT_DIR;
+    if !state_dir().exists() {
+        fail("没有正在进行的 rebase");
+    }
+    let conflicts: Vec<String> = read_state("conflicts").lines().map(str::to_string).filter(|l| !l.is_empty()).collect();
+    let rebase_head = repo_path.join("REBASE_HEAD");
+
+    if rebase_head.exists() {
+        let hash = fs::read_to_string(&rebase_head).unwrap_or_default().trim().to_string();
+        let done = read_todo(&state_file("done")).unwrap_or_default();
+        let item = done.last().cloned().unwrap_or_else(|| fail("rebase 状态已损坏，请执行 --abort"));
+
+        if commit {
+            if let Some(path) = first_unresolved(&conflicts, repo_path) {
+                fail(&format!("文件 {} 仍有未解决的冲突，请修改后用 add 标记为已解决", path));
+            }
+            let entries = read_index_map(&index_file(repo_path));
+            let tree = write_tree_from_map(&entries, repo_path).unwrap_or_else(|e| fail(&format!("无法写入 tree: {}", e)));
+            commit_step(&item, &hash, &tree, false);
+        } else {
+            let head = head_commit();
+            if let Err(e) = reset_hard(Some(&head), &conflicts, repo_path) {
+                fail(&format!("无法恢复工作区: {}", e));
+            }
+        }
+        let _ = fs::remove_file(&rebase_head);
+        let _ = fs::remove_file(state_file("conflicts"));
+
+        if commit && item.action == Action::Edit {
+            return stop_for_edit(&item);
+        }
+    } else if state_file("amend").exists() {
+        // edit 停下后暂存的修改并入该提交
+        let amend = read_state("amend");
+        let head = head_commit();
+        let entries = read_index_map(&index_file(repo_path));
+        let head_tree = tree_of(&head).and_then(|t| read_tree_entries(&t, repo_path).ok()).unwrap_or_default();
+        if commit && head == amend && entries != head_tree {
+            let info = read_commit(&head, repo_path).unwrap_or_else(|e| fail(&format!("无法读取提交 {}: {}", head, e)));
+            let result = write_tree_from_map(&entries, repo_path)
+                .and_then(|tree| write_commit(repo_path, &tree, &info.parents, &info.author, &default_signature(), &info.message))
+                .and_then(|new| update_head(repo_path, &new));
+            if let Err(e) = result {
+                fail(&format!("无法修改提交: {}", e));
+            }
+        } else if !commit {
+            if let Err(e) = reset_hard(Some(&head), &[], repo_path) {
+                fail(&format!("无法恢复工作区: {}", e));
+            }
+        }
+        let _ = fs::remove_file(state_file("amend"));
+    }
+
+    run_todo();
+}
+
+/// 完成 rebase：把原分支移动到新的提交上
+fn finish() {
+    let repo_path = &*GIT_DIR;
+    let head_name = read_state("head-name");
+    let new_head = head_commit();
+    let _ = write_ref(repo_path, "ORIG_HEAD", &read_state("orig-head"));
+
+    if head_name.starts_with("refs/") {
+        // 分支与 HEAD 在同一个事务中更新
+        let store = RefStore::new(repo_path);
+        let mut transaction = store.transaction();
+        transaction.update(&head_name, &new_head).set_symbolic("HEAD", &head_name);
+        if let Err(e) = transaction.commit() {
+            fail(&format!("无法更新 {}: {}", head_name, e));
+        }
+    }
+    let _ = fs::remove_dir_all(state_dir());
+    println!("Successfully rebased and updated {}.", head_name);
+}
+
+/// `--abort`：回到 rebase 开始前的分支与提交
+fn abort() {
+    let repo_path = &*GIT_DIR;
+    if !state_dir().exists() {
+        fail("没有正在进行的 rebase");
+    }
+    let head_name = read_state("head-name");
+    let orig_head = read_state("orig-head");
+    let conflicts: Vec<String> = read_state("conflicts").lines().map(str::to_string).collect();
+
+    let head_restored = if head_name.starts_with("refs/") {
+        set_head(&head_name, repo_path)
+    } else {
+        write_ref(repo_path, "HEAD", &orig_head)
+    };
+    let restored = head_restored
+        .and_then(|_| update_head(repo_path, &orig_head))
+        .and_then(|_| reset_hard(Some(&orig_head), &conflicts, repo_path));
+    if let Err(e) = restored {
+        fail(&format!("无法恢复到原来的状态: {}", e));
+    }
+
+    let _ = fs::remove_file(repo_path.join("REBASE_HEAD"));
+    let _ = fs::remove_dir_all(state_dir());
+    if *IS_VERBOSE {
+        println!("↩️ 已放弃 rebase，恢复到 {}", short(&orig_head));
+    }
+}
@@ -0,0 +1,20 @@
+use std::io::{self, BufWriter};
+use crate::core::remote::open_remote;
+use crate::core::serve::serve_receive_pack;
+
+/// 在标准输入输出上提供 receive-pack 服务，接收另一端 push 过来的对象与引用更新
+pub fn git_receive_pack(path: &str) {
+    let repo = match open_remote(path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("fatal: {}", e);
+            std::process::exit(128);
+        }
+    };
+    let mut reader = io::stdin().lock();
+    let mut writer = BufWriter::new(io::stdout().lock());
+    if let Err(e) = serve_receive_pack(&repo, &mut reader, &mut writer) {
+        eprintln!("fatal: receive-pack: {}", e);
+        std::process::exit(128);
+    }
+}
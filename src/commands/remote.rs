@@ -0,0 +1,82 @@
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::reference::{check_ref_format, RefFormatOptions};
+use crate::core::refs::RefStore;
+use crate::core::remote::default_fetch_refspec;
+use crate::core::repo_config::{load_repo_config, update_repo_config};
+
+/// 列出所有远程，`verbose` 时同时显示地址
+pub fn git_remote_list(verbose: bool) {
+    let config = match load_repo_config(&GIT_DIR) {
+        Ok(c) => c,
+        Err(e) => return eprintln!("读取配置失败: {}", e),
+    };
+    for name in config.subsections("remote") {
+        if verbose {
+            let url = config.get(&format!("remote.{}.url", name)).unwrap_or_default();
+            println!("{}\t{} (fetch)", name, url);
+            println!("{}\t{} (push)", name, url);
+        } else {
+            println!("{}", name);
+        }
+    }
+}
+
+/// 添加远程
+pub fn git_remote_add(name: &str, url: &str) {
+    // 与 git 一样，要求远程跟踪分支 `refs/remotes/<名>/<分支>` 是合法的引用名
+    if let Err(e) = check_ref_format(&format!("refs/remotes/{}/test", name), RefFormatOptions::default()) {
+        eprintln!("无效的远程名 '{}': {}", name, e);
+        std::process::exit(1);
+    }
+    let result = update_repo_config(&GIT_DIR, |config| {
+        if config.get(&format!("remote.{}.url", name)).is_some() {
+            return Err(std::io::Error::new(std::io::ErrorKind::AlreadyExists, format!("远程 {} 已存在", name)));
+        }
+        config.set(&format!("remote.{}.url", name), url)?;
+        config.add(&format!("remote.{}.fetch", name), &default_fetch_refspec(name))
+    });
+    match result {
+        Ok(_) => {
+            if *IS_VERBOSE {
+                println!("🔗 已添加远程 {} -> {}", name, url);
+            }
+        }
+        Err(e) => {
+            eprintln!("添加远程失败: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 删除远程，同时删除它的远程跟踪分支以及分支上指向它的上游配置
+pub fn git_remote_remove(name: &str) {
+    let result = update_repo_config(&GIT_DIR, |config| {
+        if !config.remove_section(&format!("remote.{}", name)) {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("没有名为 {} 的远程", name)));
+        }
+        for branch in config.subsections("branch") {
+            if config.get(&format!("branch.{}.remote", branch)) == Some(name) {
+                config.unset_all(&format!("branch.{}.remote", branch));
+                config.unset_all(&format!("branch.{}.merge", branch));
+            }
+        }
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("删除远程失败: {}", e);
+        std::process::exit(1);
+    }
+
+    let prefix = format!("refs/remotes/{}/", name);
+    let store = RefStore::new(&GIT_DIR);
+    let mut transaction = store.transaction();
+    for (tracking, _) in store.list(&prefix).into_iter().chain(store.list_symbolic(&prefix)) {
+        transaction.delete(&tracking);
+    }
+    if let Err(e) = transaction.commit() {
+        eprintln!("无法删除远程跟踪分支: {}", e);
+    }
+    if *IS_VERBOSE {
+        println!("🗑️ 已删除远程 {}", name);
+    }
+}
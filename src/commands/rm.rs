@@ -37,13 +37,18 @@ pub fn git_rm(file: &str, recursive: bool) {
     }
 
     // 从 index 中移除（无论是文件还是目录内的所有文件）
-    if recursive {
-        remove_directory_entries_from_index(path);
+    let result = if recursive {
+        remove_directory_entries_from_index(path)
     } else {
         match remove_from_index(path) {
-            Ok(Some(_)) => {if *IS_VERBOSE{ println!("从暂存区移除: {}", file)}},
-            Ok(None) => {if *IS_VERBOSE{println!("文件 {} 不在暂存区中，但将从提交中排除（若存在）", file)}},
-            Err(e) => {if *IS_VERBOSE{eprintln!("更新 index 失败: {}", e)}},
+            Ok(Some(_)) => {if *IS_VERBOSE{ println!("从暂存区移除: {}", file)}; Ok(())},
+            Ok(None) => {if *IS_VERBOSE{println!("文件 {} 不在暂存区中，但将从提交中排除（若存在）", file)}; Ok(())},
+            Err(e) => Err(e),
         }
+    };
+    // index 被其他进程锁定等错误必须报告，否则用户会以为文件已从暂存区移除
+    if let Err(e) = result {
+        eprintln!("fatal: 更新 index 失败: {}", e);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,75 @@
+use crate::core::config::GIT_DIR;
+use crate::core::reference::{read_head_commit_hash, read_ref};
+use crate::core::refs::RefStore;
+
+/// show-ref 的选项
+#[derive(Debug, Default)]
+pub struct ShowRefOptions {
+    pub heads: bool,
+    pub tags: bool,
+    /// 同时列出 HEAD
+    pub head: bool,
+    /// 只输出 hash
+    pub hash_only: bool,
+    /// 模式必须是完整的引用名
+    pub verify: bool,
+    /// 不输出，只用退出状态表示是否找到
+    pub quiet: bool,
+}
+
+/// 列出引用及其 hash；`patterns` 与引用名末尾的完整路径段匹配（如 `main` 匹配 `refs/heads/main`）
+///
+/// 没有任何引用匹配时以状态 1 退出。
+pub fn git_show_ref(patterns: &[String], opts: &ShowRefOptions) {
+    let repo_path = &*GIT_DIR;
+    let mut refs: Vec<(String, String)> = Vec::new();
+
+    if opts.verify {
+        for pattern in patterns {
+            let hash = if pattern == "HEAD" {
+                read_head_commit_hash(repo_path).ok().flatten()
+            } else if pattern.starts_with("refs/") {
+                read_ref(repo_path, pattern)
+            } else {
+                None
+            };
+            match hash {
+                Some(hash) => refs.push((pattern.clone(), hash)),
+                None if opts.quiet => std::process::exit(1),
+                None => {
+                    eprintln!("fatal: '{}' - not a valid ref", pattern);
+                    std::process::exit(128);
+                }
+            }
+        }
+    } else {
+        if opts.head {
+            if let Ok(Some(hash)) = read_head_commit_hash(repo_path) {
+                refs.push(("HEAD".to_string(), hash));
+            }
+        }
+        for (name, hash) in RefStore::new(repo_path).list("refs/") {
+            let wanted_kind = (!opts.heads && !opts.tags)
+                || (opts.heads && name.starts_with("refs/heads/"))
+                || (opts.tags && name.starts_with("refs/tags/"));
+            let matched = patterns.is_empty() || patterns.iter().any(|p| name == *p || name.ends_with(&format!("/{}", p)));
+            if wanted_kind && matched {
+                refs.push((name, hash));
+            }
+        }
+    }
+
+    if refs.is_empty() {
+        std::process::exit(1);
+    }
+    if opts.quiet {
+        return;
+    }
+    for (name, hash) in refs {
+        if opts.hash_only {
+            println!("{}", hash);
+        } else {
+            println!("{} {}", hash, name);
+        }
+    }
+}
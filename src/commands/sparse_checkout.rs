@@ -0,0 +1,79 @@
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::sparse::{load_sparse, reapply_sparse, remove_sparse, save_sparse, SparseCone};
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+/// sparse-checkout 的子命令
+#[derive(Debug)]
+pub enum SparseCheckoutAction {
+    /// 把检出范围设为给定的目录（同时启用稀疏检出）
+    Set(Vec<String>),
+    /// 在当前范围中加入目录
+    Add(Vec<String>),
+    /// 列出检出的目录
+    List,
+    /// 关闭稀疏检出，重新检出所有文件
+    Disable,
+}
+
+/// 读取当前的检出范围，未启用稀疏检出时退出
+fn current_cone() -> SparseCone {
+    match load_sparse(&GIT_DIR) {
+        Ok(Some(cone)) => cone,
+        Ok(None) => fail("fatal: 当前工作区没有启用稀疏检出"),
+        Err(e) => fail(&format!("fatal: 无法读取稀疏检出模式: {}", e)),
+    }
+}
+
+/// 按新的范围更新工作区，报告因有修改而保留下来的文件
+fn reapply() {
+    match reapply_sparse(&GIT_DIR) {
+        Ok(kept) if !kept.is_empty() => {
+            eprintln!("warning: 以下路径有未提交的修改，虽在稀疏检出范围之外仍保留在工作区中:");
+            for path in kept {
+                eprintln!("\t{}", path);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => fail(&format!("fatal: 无法更新工作区: {}", e)),
+    }
+    if *IS_VERBOSE {
+        println!("✅ 稀疏检出已更新");
+    }
+}
+
+/// cone 模式的稀疏检出：只在工作区中检出顶层文件与指定目录，index 仍然记录完整的 tree
+pub fn git_sparse_checkout(action: SparseCheckoutAction) {
+    match action {
+        SparseCheckoutAction::Set(dirs) => {
+            let cone = SparseCone::new(&dirs).unwrap_or_else(|e| fail(&format!("fatal: {}", e)));
+            if let Err(e) = save_sparse(&GIT_DIR, &cone) {
+                fail(&format!("fatal: 无法写入稀疏检出模式: {}", e));
+            }
+            reapply();
+        }
+        SparseCheckoutAction::Add(dirs) => {
+            let current = current_cone();
+            let cone = SparseCone::new(current.dirs().chain(dirs.iter().map(String::as_str)))
+                .unwrap_or_else(|e| fail(&format!("fatal: {}", e)));
+            if let Err(e) = save_sparse(&GIT_DIR, &cone) {
+                fail(&format!("fatal: 无法写入稀疏检出模式: {}", e));
+            }
+            reapply();
+        }
+        SparseCheckoutAction::List => {
+            for dir in current_cone().dirs() {
+                println!("{}", dir);
+            }
+        }
+        SparseCheckoutAction::Disable => {
+            if let Err(e) = remove_sparse(&GIT_DIR) {
+                fail(&format!("fatal: 无法删除稀疏检出模式: {}", e));
+            }
+            reapply();
+        }
+    }
+}
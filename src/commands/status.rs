@@ -1,45 +1,26 @@
 use std::{fs, io};
 use std::path::{Path, PathBuf};
 use std::collections::{HashMap, HashSet};
-use crate::core::index::read_index;
+use crate::core::index::{index_file, read_index, read_skip_worktree};
+use crate::core::sparse::load_sparse;
 use crate::utils::hash::sha1_hash;
 use crate::core::tree::read_tree_entries;
-use crate::core::config::{GIT_DIR};
-/// 读取 HEAD 所在的 commit 的 tree（路径 -> blob hash 映射）
-fn read_head_tree_map(repo_path: &Path) -> io::Result<HashMap<String, String>> {
-    let head_path = repo_path.join("HEAD");
-    let head_content = fs::read_to_string(&head_path)?;
-    let head_ref = head_content
-        .trim()
-        .strip_prefix("ref: ")
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "HEAD 格式错误"))?;
-
-    let ref_path = repo_path.join(head_ref);
-    if !ref_path.exists() {
-        return Ok(HashMap::new()); // 首次 commit 前为空
-    }
-
-    let commit_hash = fs::read_to_string(&ref_path)?.trim().to_string();
-    if commit_hash.len() < 2 {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("无效的 commit hash: '{}'", commit_hash)));
-    }
-    let (dir, file) = commit_hash.split_at(2);
-
-    let commit_path = repo_path.join("objects").join(dir).join(file);
-    let commit_content = fs::read_to_string(commit_path)?;
-
-    let tree_hash = commit_content
-        .lines()
-        .find(|line| line.starts_with("tree "))
-        .map(|line| line[5..].trim())
-        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "无法从 commit 中读取 tree"))?;
-
-    read_tree_entries(tree_hash, repo_path)
+use crate::core::diff::{diff_maps, Change, RenameOptions};
+use crate::core::config::{GIT_DIR, GIT_DIR_NAME};
+use crate::core::reference::read_head_commit_hash;
+use crate::core::commit::read_commit_tree;
+/// 读取 HEAD 所在的 commit 的 tree（路径 -> blob hash 映射），分支尚无提交时为 None
+fn read_head_tree_map(repo_path: &Path) -> io::Result<Option<HashMap<String, String>>> {
+    let Some(commit_hash) = read_head_commit_hash(repo_path)? else {
+        return Ok(None);
+    };
+    let tree_hash = read_commit_tree(&commit_hash, repo_path)?;
+    read_tree_entries(&tree_hash, repo_path).map(Some)
 }
 
 pub fn git_status() {
     let repo_path = &*GIT_DIR;
-    let index_path = repo_path.join("index");
+    let index_path = index_file(repo_path);
 
     // 读取 index
     let index_entries = read_index(&index_path).unwrap_or_default();
@@ -48,27 +29,36 @@ pub fn git_status() {
         .map(|(hash, path)| (path.clone(), hash.clone()))
         .collect();
 
-    // 读取 HEAD 的 tree
-    let head_map = read_head_tree_map(repo_path).unwrap_or_default();
+    // 读取 HEAD 的 tree；首次提交前所有暂存的文件都是新增的
+    let head_map = match read_head_tree_map(repo_path) {
+        Ok(Some(map)) => map,
+        Ok(None) => {
+            println!("No commits yet");
+            HashMap::new()
+        }
+        Err(_) => HashMap::new(),
+    };
+    // 稀疏检出范围之外的文件不在工作区中，不算删除
+    let skipped = read_skip_worktree(&index_path);
+    if load_sparse(repo_path).ok().flatten().is_some() {
+        let present = (index_map.len() - skipped.len()) * 100 / index_map.len().max(1);
+        println!("You are in a sparse checkout with {}% of tracked files present.", present);
+    }
     let mut seen: HashSet<String> = HashSet::new();
 
-    // ✅ 1. 对比 HEAD 与 index：找出 staged 文件
-    for (path, index_hash) in &index_map {
-        match head_map.get(path) {
-            Some(tree_hash) => {
-                if tree_hash != index_hash {
-                    println!("staged: {}", path); // 文件内容变更
-                }
-            }
-            None => {
-                println!("staged: {}", path); // 新增文件
-            }
+    // ✅ 1. 对比 HEAD 与 index：找出 staged 文件（含重命名）
+    for change in diff_maps(&head_map, &index_map, repo_path, RenameOptions::default()) {
+        match change {
+            Change::Added(path) | Change::Modified(path) => println!("staged: {}", path),
+            Change::Renamed { from, to, .. } => println!("renamed: {} -> {}", from, to),
+            Change::Copied { from, to, .. } => println!("copied: {} -> {}", from, to),
+            Change::Deleted(_) => {}
         }
-        seen.insert(path.clone());
     }
+    seen.extend(index_map.keys().cloned());
 
     // ✅ 2. 对比 index 与工作区：找出 modified 或 deleted 文件
-    for (path, index_hash) in &index_map {
+    for (path, index_hash) in index_map.iter().filter(|(path, _)| !skipped.contains(*path)) {
         let path_buf = PathBuf::from(path);
         if path_buf.exists() {
             if let Ok(content) = fs::read(&path_buf) {
@@ -90,14 +80,8 @@ pub fn git_status() {
             .to_string_lossy()
             .replace('\\', "/");
 
-        // 获取配置中设置的 git 目录名（例如 ".mygit"）
-        let git_dir_str = GIT_DIR
-            .file_name()
-            .and_then(|os_str| os_str.to_str())
-            .unwrap_or(".mygit");
-
-        // 忽略 git_dir 开头的路径
-        if rel.starts_with(git_dir_str) {
+        // 忽略 git 目录（例如 ".mygit"）开头的路径
+        if rel.starts_with(GIT_DIR_NAME.as_str()) {
             continue;
         }
 
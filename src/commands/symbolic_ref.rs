@@ -0,0 +1,50 @@
+use crate::core::config::GIT_DIR;
+use crate::core::reference::{delete_ref, validate_ref_name};
+use crate::core::refs::{RefStore, RefValue};
+use crate::core::remote::shorten_ref;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+/// 读取、设置或删除符号引用
+///
+/// 只给名字时输出其指向的引用（`short` 时去掉 `refs/heads/` 等前缀）；不是符号引用时失败，`quiet` 时不输出错误。
+pub fn git_symbolic_ref(name: &str, target: Option<&str>, delete: bool, short: bool, quiet: bool) {
+    let repo_path = &*GIT_DIR;
+    let store = RefStore::new(repo_path);
+
+    if delete {
+        if !matches!(store.read(name), Some(RefValue::Symbolic(_))) {
+            fail(&format!("fatal: {} 不是符号引用", name));
+        }
+        if let Err(e) = delete_ref(repo_path, name) {
+            fail(&format!("fatal: 无法删除 {}: {}", name, e));
+        }
+        return;
+    }
+
+    if let Some(target) = target {
+        if !target.starts_with("refs/") {
+            fail(&format!("fatal: 拒绝让 {} 指向 refs/ 之外的 {}", name, target));
+        }
+        for refname in [name, target] {
+            if let Err(reason) = validate_ref_name(refname) {
+                fail(&format!("fatal: 无效的引用名 '{}': {}", refname, reason));
+            }
+        }
+        let mut transaction = store.transaction();
+        transaction.set_symbolic(name, target);
+        if let Err(e) = transaction.commit() {
+            fail(&format!("fatal: 无法更新 {}: {}", name, e));
+        }
+        return;
+    }
+
+    match store.read(name) {
+        Some(RefValue::Symbolic(target)) => println!("{}", if short { shorten_ref(&target) } else { &target }),
+        _ if quiet => std::process::exit(1),
+        _ => fail(&format!("fatal: ref {} is not a symbolic ref", name)),
+    }
+}
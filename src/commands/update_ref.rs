@@ -0,0 +1,110 @@
+use std::io::{self, BufRead};
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::protocol::ZERO_ID;
+use crate::core::object::write_object;
+use crate::core::reference::{read_resolved_object, validate_ref_name};
+use crate::core::refs::RefStore;
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+/// 把对象名解析为 hash；全零表示“不存在”
+///
+/// `<版本>:<子目录>` 的 tree 只在内存中生成，引用指向它之前先写入对象库。
+fn resolve_value(value: &str) -> Option<String> {
+    if value == ZERO_ID {
+        return None;
+    }
+    let (hash, content) = read_resolved_object(value, &GIT_DIR).unwrap_or_else(|| fail(&format!("fatal: {}: 不是有效的对象名", value)));
+    Some(write_object(&content, &GIT_DIR).unwrap_or_else(|e| fail(&format!("fatal: 无法写入对象 {}: {}", hash, e))))
+}
+
+/// 写入前检查引用名，不合法时退出
+fn check_name(name: &str) {
+    if let Err(reason) = validate_ref_name(name) {
+        fail(&format!("fatal: 无效的引用名 '{}': {}", name, reason));
+    }
+}
+
+/// 更新或删除一个引用；给出 `old` 时只有引用当前值与之一致才执行
+///
+/// 与 git 一样，`no_deref` 为 false 时更新符号引用（如 HEAD）所指向的引用。
+pub fn git_update_ref(name: &str, new: Option<&str>, old: Option<&str>, delete: bool, no_deref: bool) {
+    let store = RefStore::new(&GIT_DIR);
+    let target = if no_deref { name.to_string() } else { store.target_name(name) };
+    if !delete {
+        check_name(&target);
+    }
+    let old = old.map(resolve_value);
+
+    let mut transaction = store.transaction();
+    if delete {
+        match old.as_ref() {
+            Some(Some(old)) => transaction.delete_if(&target, old),
+            _ => transaction.delete(&target),
+        };
+    } else {
+        let new = new
+            .and_then(resolve_value)
+            .unwrap_or_else(|| fail("fatal: update-ref 需要新的值"));
+        match &old {
+            Some(old) => transaction.update_if(&target, &new, old.as_deref()),
+            None => transaction.update(&target, &new),
+        };
+    }
+    if let Err(e) = transaction.commit() {
+        fail(&format!("fatal: update_ref failed for ref '{}': {}", target, e));
+    }
+    if *IS_VERBOSE {
+        println!("🔗 已更新引用 {}", target);
+    }
+}
+
+/// `--stdin`：从标准输入读取 `update/create/delete/verify` 命令，在一个事务中全部执行
+pub fn git_update_ref_stdin() {
+    let store = RefStore::new(&GIT_DIR);
+    let mut transaction = store.transaction();
+    for line in io::stdin().lock().lines() {
+        let line = line.unwrap_or_else(|e| fail(&format!("fatal: 无法读取标准输入: {}", e)));
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => continue,
+            ["update", name, new] => {
+                check_name(name);
+                let new = resolve_value(new).unwrap_or_else(|| fail(&format!("fatal: update {}: 缺少新的值", name)));
+                transaction.update(name, &new);
+            }
+            ["update", name, new, old] => {
+                check_name(name);
+                let new = resolve_value(new).unwrap_or_else(|| fail(&format!("fatal: update {}: 缺少新的值", name)));
+                transaction.update_if(name, &new, resolve_value(old).as_deref());
+            }
+            ["create", name, new] => {
+                check_name(name);
+                let new = resolve_value(new).unwrap_or_else(|| fail(&format!("fatal: create {}: 缺少新的值", name)));
+                transaction.update_if(name, &new, None);
+            }
+            ["delete", name] => {
+                transaction.delete(name);
+            }
+            ["delete", name, old] => {
+                match resolve_value(old) {
+                    Some(old) => transaction.delete_if(name, &old),
+                    None => transaction.delete(name),
+                };
+            }
+            ["verify", name] | ["verify", name, _] => {
+                let expected = words.get(2).and_then(|old| resolve_value(old));
+                if store.resolve(name) != expected {
+                    fail(&format!("fatal: cannot lock ref '{}': 引用的当前值与预期不一致", name));
+                }
+            }
+            _ => fail(&format!("fatal: 无法识别的命令: {}", line)),
+        }
+    }
+    if let Err(e) = transaction.commit() {
+        fail(&format!("fatal: {}", e));
+    }
+}
@@ -0,0 +1,22 @@
+use std::io::{self, BufWriter};
+use crate::core::remote::open_remote;
+use crate::core::serve::serve_upload_pack;
+
+/// 在标准输入输出上提供 upload-pack 服务，供另一端的 fetch / clone 读取仓库
+///
+/// 只支持协议 v2；标准输出只能写协议数据，错误信息写到标准错误。
+pub fn git_upload_pack(path: &str) {
+    let repo = match open_remote(path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("fatal: {}", e);
+            std::process::exit(128);
+        }
+    };
+    let mut reader = io::stdin().lock();
+    let mut writer = BufWriter::new(io::stdout().lock());
+    if let Err(e) = serve_upload_pack(&repo, &mut reader, &mut writer) {
+        eprintln!("fatal: upload-pack: {}", e);
+        std::process::exit(128);
+    }
+}
@@ -0,0 +1,196 @@
+use std::fs;
+use std::path::Path;
+use crate::core::commit::read_commit;
+use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::reference::{delete_ref, read_head_commit_hash, read_ref, resolve_revision, validate_branch_name, write_ref};
+use crate::core::protocol::ZERO_ID;
+use crate::core::refs::RefValue;
+use crate::core::worktree::{add_worktree, find_branch_checkout, list_worktrees, prune_worktrees, remove_worktree, Worktree};
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(128);
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..7.min(hash.len())]
+}
+
+/// `worktree add` 的选项
+#[derive(Debug, Default)]
+pub struct WorktreeAddOptions {
+    /// 创建新分支并在新工作区中检出（`-b`）
+    pub new_branch: Option<String>,
+    /// 以分离头指针检出（`--detach`）
+    pub detach: bool,
+    /// 即使分支已在其他工作区中检出也继续（`-f`）
+    pub force: bool,
+}
+
+/// 创建关联工作区
+///
+/// 没有给出 `<branch>` 时与 git 一样使用以目录名命名的分支：分支已存在时检出它，否则基于 HEAD 新建。
+/// `<branch>` 不是分支名时按提交解析并以分离头指针检出。同一个分支不能同时在两个工作区中检出。
+pub fn git_worktree_add(path: &str, branch: Option<&str>, opts: &WorktreeAddOptions) {
+    let repo_path = &*GIT_DIR;
+    let resolve = |rev: &str| resolve_revision(rev, repo_path).unwrap_or_else(|| fail(&format!("fatal: 无效的引用: {}", rev)));
+    let head_commit = || {
+        read_head_commit_hash(repo_path)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| fail("fatal: 当前分支还没有任何提交，无法创建工作区"))
+    };
+
+    // (新工作区的 HEAD, 检出的提交, 需要新建的分支)
+    let (head, commit, create) = if let Some(name) = &opts.new_branch {
+        let commit = branch.map(resolve).unwrap_or_else(head_commit);
+        (format!("refs/heads/{}", name), commit, Some(name.clone()))
+    } else if opts.detach {
+        let commit = branch.map(resolve).unwrap_or_else(head_commit);
+        (commit.clone(), commit, None)
+    } else if let Some(branch) = branch {
+        let refname = format!("refs/heads/{}", branch);
+        match read_ref(repo_path, &refname) {
+            Some(commit) => (refname, commit, None),
+            None => {
+                let commit = resolve(branch);
+                (commit.clone(), commit, None)
+            }
+        }
+    } else {
+        let name = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| fail(&format!("fatal: 无法从 '{}' 推断分支名", path)));
+        let refname = format!("refs/heads/{}", name);
+        match read_ref(repo_path, &refname) {
+            Some(commit) => (refname, commit, None),
+            None => (refname, head_commit(), Some(name)),
+        }
+    };
+
+    if let Some(name) = &create {
+        if let Err(reason) = validate_branch_name(name) {
+            fail(&format!("fatal: '{}' 不是有效的分支名: {}", name, reason));
+        }
+        if read_ref(repo_path, &head).is_some() {
+            fail(&format!("fatal: 分支 '{}' 已存在", name));
+        }
+    } else if let Some(worktree) = find_branch_checkout(repo_path, &head, None).filter(|_| !opts.force) {
+        fail(&format!(
+            "fatal: 分支 '{}' 已经在工作区 '{}' 中检出",
+            head.trim_start_matches("refs/heads/"),
+            worktree.path.display()
+        ));
+    }
+
+    let description = match &create {
+        Some(name) => format!("新分支 '{}'", name),
+        None if head.starts_with("refs/heads/") => format!("检出 '{}'", head.trim_start_matches("refs/heads/")),
+        None => format!("分离头指针 {}", short(&commit)),
+    };
+    println!("准备工作区（{}）", description);
+
+    if create.is_some() {
+        if let Err(e) = write_ref(repo_path, &head, &commit) {
+            fail(&format!("fatal: 无法创建分支: {}", e));
+        }
+    }
+    let value = if head.starts_with("refs/") { RefValue::Symbolic(head.clone()) } else { RefValue::Direct(commit.clone()) };
+    if let Err(e) = add_worktree(repo_path, Path::new(path), &value, &commit) {
+        if create.is_some() {
+            let _ = delete_ref(repo_path, &head);
+        }
+        fail(&format!("fatal: 无法创建工作区 '{}': {}", path, e));
+    }
+
+    let subject = read_commit(&commit, repo_path)
+        .map(|c| c.message.lines().next().unwrap_or_default().to_string())
+        .unwrap_or_default();
+    println!("HEAD 现在位于 {} {}", short(&commit), subject);
+}
+
+/// 列出所有工作区：路径、HEAD 指向的提交与检出的分支
+///
+/// `porcelain` 时输出每个工作区一段、以空行分隔的 `worktree` / `HEAD` / `branch` 或 `detached` 行。
+pub fn git_worktree_list(porcelain: bool) {
+    let worktrees = list_worktrees(&GIT_DIR).unwrap_or_else(|e| fail(&format!("fatal: 无法读取工作区: {}", e)));
+    let width = worktrees.iter().map(|wt| wt.path.display().to_string().chars().count()).max().unwrap_or(0);
+    for worktree in &worktrees {
+        let head = worktree.head_commit();
+        if porcelain {
+            println!("worktree {}", worktree.path.display());
+            if worktree.bare {
+                println!("bare");
+            } else {
+                println!("HEAD {}", head.as_deref().unwrap_or(ZERO_ID));
+                match worktree.head() {
+                    Some(RefValue::Symbolic(target)) => println!("branch {}", target),
+                    _ => println!("detached"),
+                }
+            }
+            if let Some(reason) = worktree.prunable() {
+                println!("prunable {}", reason);
+            }
+            println!();
+            continue;
+        }
+        let mut line = format!("{:<width$} ", worktree.path.display().to_string(), width = width);
+        if worktree.bare {
+            line.push_str("(bare)");
+        } else {
+            line.push_str(short(head.as_deref().unwrap_or(ZERO_ID)));
+            match worktree.head() {
+                Some(RefValue::Symbolic(target)) => line.push_str(&format!(" [{}]", target.trim_start_matches("refs/heads/"))),
+                _ => line.push_str(" (detached HEAD)"),
+            }
+        }
+        if worktree.prunable().is_some() {
+            line.push_str(" prunable");
+        }
+        println!("{}", line);
+    }
+}
+
+/// 按路径或名字查找关联工作区
+fn find_worktree(spec: &str) -> Worktree {
+    let worktrees = list_worktrees(&GIT_DIR).unwrap_or_else(|e| fail(&format!("fatal: 无法读取工作区: {}", e)));
+    let path = fs::canonicalize(spec).ok();
+    worktrees
+        .into_iter()
+        .find(|wt| Some(&wt.path) == path.as_ref() || wt.name.as_deref() == Some(spec) || wt.path == Path::new(spec))
+        .unwrap_or_else(|| fail(&format!("fatal: '{}' 不是工作区", spec)))
+}
+
+/// 删除关联工作区；工作区中有未提交的修改或未跟踪的文件时，除非 `force` 否则拒绝
+pub fn git_worktree_remove(spec: &str, force: bool) {
+    let worktree = find_worktree(spec);
+    if worktree.name.is_none() {
+        fail("fatal: 不能删除主工作区");
+    }
+    if !force && worktree.path.is_dir() {
+        match worktree.has_changes() {
+            Ok(false) => {}
+            Ok(true) => fail(&format!("fatal: '{}' 中有修改或未跟踪的文件，使用 --force 强制删除", spec)),
+            Err(e) => fail(&format!("fatal: 无法检查 '{}' 的状态: {}，使用 --force 强制删除", spec, e)),
+        }
+    }
+    if let Err(e) = remove_worktree(&worktree) {
+        fail(&format!("fatal: 无法删除工作区 '{}': {}", spec, e));
+    }
+    if *IS_VERBOSE {
+        println!("🧹 已删除工作区 {}", worktree.path.display());
+    }
+}
+
+/// 清理目录已被删除的关联工作区留下的管理信息
+///
+/// `dry_run` 时（`-n`）只列出，`verbose` 时（`-v`）列出实际清理的工作区。
+pub fn git_worktree_prune(dry_run: bool, verbose: bool) {
+    let pruned = prune_worktrees(&GIT_DIR, dry_run).unwrap_or_else(|e| fail(&format!("fatal: 无法清理工作区: {}", e)));
+    if dry_run || verbose {
+        for (name, reason) in &pruned {
+            println!("Removing worktrees/{}: {}", name, reason);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::core::config::GIT_DIR;
+use crate::core::index::{index_file, read_index_map};
+use crate::core::tree::write_tree_from_map;
+
+/// 把 index 原样写成 tree 对象并输出其 hash
+pub fn git_write_tree() {
+    let repo_path = &*GIT_DIR;
+    let entries = read_index_map(&index_file(repo_path));
+    match write_tree_from_map(&entries, repo_path) {
+        Ok(hash) => println!("{}", hash),
+        Err(e) => {
+            eprintln!("fatal: 无法写入 tree: {}", e);
+            std::process::exit(128);
+        }
+    }
+}
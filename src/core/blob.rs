@@ -1,14 +1,14 @@
 use std::fs;
-use std::io::{self, Write};
+use std::io;
 use std::path::Path;
-use crate::utils::hash::sha1_hash;
 use crate::core::config::GIT_DIR;
+use crate::core::object::write_object;
 /// 将指定路径的文件内容写入 Git 风格的对象存储中，并返回该内容的 SHA-1 哈希值。
 ///
 /// 该函数会执行以下步骤：
 /// 1. 读取目标文件内容；
 /// 2. 计算其 SHA-1 哈希；
-/// 3. 将内容写入 `.mygit/objects/xx/yyyy...` 路径中；
+/// 3. 将内容原子地写入 `.mygit/objects/xx/yyyy...` 路径中；
 /// 4. 如果该对象已存在则不会重复写入。
 ///
 /// # 参数
@@ -38,21 +38,7 @@ use crate::core::config::GIT_DIR;
 ///
 pub fn write_blob(path: &Path) -> io::Result<String> {
     let content = fs::read(path)?;
-    let hash = sha1_hash(&content);
-    let (dir, file) = hash.split_at(2);
-
-    let object_dir = &*GIT_DIR.join("objects").join(dir);
-    if !object_dir.exists() {
-        fs::create_dir_all(&object_dir)?;
-    }
-
-    let object_path = object_dir.join(file);
-    if !object_path.exists() {
-        let mut file = fs::File::create(&object_path)?;
-        file.write_all(&content)?;
-    }
-
-    Ok(hash)
+    write_object(&content, &GIT_DIR)
 }
 
 
@@ -1,82 +1,150 @@
 use std::fs::{self};
 use std::io::{self};
 use std::path::{Path};
-use crate::core::{index::read_index, tree::create_tree};
-use crate::core::reference::read_head_commit_hash;
-use crate::utils::hash::sha1_hash;
-use std::collections::HashSet;
-use crate::core::config::IS_VERBOSE;
-
-pub fn create_commit(message: &str, repo_path: &Path) -> io::Result<String> {
-    let entries = read_index(&repo_path.join("index"))?;
+use crate::core::{index::{read_index, read_skip_worktree}, tree::create_tree};
+use crate::core::object::{object_path, write_object};
+use std::collections::{HashSet, VecDeque};
+use crate::core::config::{load_config, GIT_DIR, IS_VERBOSE};
+use crate::core::reference::{get_head_ref, write_ref};
+use crate::core::index::index_file;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 用 index 生成 tree 对象，返回其 hash
+pub fn write_index_tree(repo_path: &Path) -> io::Result<String> {
+    let entries = read_index(&index_file(repo_path))?;
     // println!("📦 准备生成 tree，当前 index 中的条目:");
     // for (hash, path) in &entries {
     //     println!("    {} {}", hash, path);
     // }
 
-    let tree_hash = create_tree(&entries, repo_path)?;
+    create_tree(&entries, &read_skip_worktree(&index_file(repo_path)), repo_path)
+}
 
-    // 获取 HEAD 内容（可能是 symbolic ref，也可能是 commit hash）
-    let head_path = repo_path.join("HEAD");
-    let head_content = fs::read_to_string(&head_path)?.trim().to_string();
-    // println!("📌 当前 HEAD 内容: {}", head_content);
-    // 获取 parent commit（如果存在）
-    let parent = match read_head_commit_hash(repo_path) {
-        Ok(commit) if !commit.is_empty() => Some(commit),
-        _ => None,
-    };
+/// 当前用户的签名（`名字 <邮箱> 时间戳 时区`）
+///
+/// 名字与邮箱取自配置 `user.name` / `user.email`，没有配置时使用占位值。
+pub fn default_signature() -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let config = load_config(&GIT_DIR).unwrap_or_default();
+    let name = config.get("user.name").filter(|n| !n.is_empty()).unwrap_or("Your Name");
+    let email = config.get("user.email").filter(|e| !e.is_empty()).unwrap_or("you@example.com");
+    format!("{} <{}> {} +0000", name, email, timestamp)
+}
 
+/// 写入提交对象，返回提交哈希（不移动任何引用）
+pub fn write_commit(
+    repo_path: &Path,
+    tree_hash: &str,
+    parents: &[String],
+    author: &str,
+    committer: &str,
+    message: &str,
+) -> io::Result<String> {
+    let mut content = format!("tree {}\n", tree_hash);
+    for parent in parents {
+        content.push_str(&format!("parent {}\n", parent));
+    }
+    content.push_str(&format!("author {}\ncommitter {}\n\n{}", author, committer, message));
+    write_object(content.as_bytes(), repo_path)
+}
 
-    let author = "Your Name <you@example.com>";
-    let content = format!(
-        "tree {}\n{}author {}\ncommitter {}\n\n{}",
-        tree_hash,
-        if let Some(p) = &parent { format!("parent {}\n", p) } else { "".into() },
-        author,
-        author,
-        message
-    );
-
-    let hash = sha1_hash(content.as_bytes());
-    let (dir, file) = hash.split_at(2);
-    let obj_dir = repo_path.join("objects").join(dir);
-    fs::create_dir_all(&obj_dir)?;
-    let path = obj_dir.join(file);
-    fs::write(path, content)?;
-
-    // 更新 HEAD 或分支引用
-    if head_content.starts_with("ref: ") {
-        let ref_path = repo_path.join(head_content.trim_start_matches("ref: ").trim());
-        if *IS_VERBOSE {
-            println!("🔗 更新分支 {} -> {}", ref_path.display(), hash);
+/// 让 HEAD 指向新的提交：HEAD 是分支时更新分支引用，detached 时直接改写 HEAD
+pub fn update_head(repo_path: &Path, hash: &str) -> io::Result<()> {
+    match get_head_ref(repo_path) {
+        Ok(ref_name) => {
+            if *IS_VERBOSE {
+                println!("🔗 更新分支 {} -> {}", ref_name, hash);
+            }
+            write_ref(repo_path, &ref_name, hash)
         }
-        fs::write(ref_path, format!("{}\n", hash))?;
-    } else {
-        // detached HEAD
-        if *IS_VERBOSE {
-            println!("🔗 更新 HEAD -> {}", hash);
+        Err(_) => {
+            // detached HEAD
+            if *IS_VERBOSE {
+                println!("🔗 更新 HEAD -> {}", hash);
+            }
+            write_ref(repo_path, "HEAD", hash)
         }
-        fs::write(head_path, format!("{}\n", hash))?;
     }
-
-    Ok(hash)
 }
 
-
-
 /// 从提交对象中读取 tree 哈希
 pub fn read_commit_tree(commit_hash: &str, repo_path: &Path) -> io::Result<String> {
-    let (dir, file) = commit_hash.split_at(2);
-    let path = repo_path.join("objects").join(dir).join(file);
+    let path = object_path(commit_hash, repo_path)?;
     let content = fs::read_to_string(path)?;
     for line in content.lines() {
-        if line.starts_with("tree ") {
-            return Ok(line[5..].trim().to_string());
+        if let Some(tree) = line.strip_prefix("tree ") {
+            return Ok(tree.trim().to_string());
         }
     }
     Err(io::Error::new(io::ErrorKind::InvalidData, "找不到 tree"))
 }
 
+/// 解析后的提交对象
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub tree: String,
+    pub parents: Vec<String>,
+    pub author: String,
+    pub committer: String,
+    pub message: String,
+}
+
+impl CommitInfo {
+    /// 提交说明的第一行
+    pub fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or("")
+    }
+}
+
+/// 拆分签名为 (`名字 <邮箱>`, 时间戳与时区)，旧格式的签名没有时间部分
+pub fn split_signature(signature: &str) -> (&str, Option<(i64, &str)>) {
+    if let Some(end) = signature.rfind('>') {
+        let (ident, rest) = signature.split_at(end + 1);
+        let mut parts = rest.split_whitespace();
+        if let (Some(ts), Some(tz)) = (parts.next(), parts.next()) {
+            if let Ok(ts) = ts.parse() {
+                return (ident, Some((ts, tz)));
+            }
+        }
+        return (ident, None);
+    }
+    (signature, None)
+}
+
+/// 读取并解析提交对象
+pub fn read_commit(commit_hash: &str, repo_path: &Path) -> io::Result<CommitInfo> {
+    let path = object_path(commit_hash, repo_path)?;
+    let content = fs::read_to_string(path)?;
+    let (header, message) = content.split_once("\n\n").unwrap_or((content.as_str(), ""));
+
+    let mut info = CommitInfo {
+        tree: String::new(),
+        parents: Vec::new(),
+        author: String::new(),
+        committer: String::new(),
+        message: message.to_string(),
+    };
+    for line in header.lines() {
+        if let Some(tree) = line.strip_prefix("tree ") {
+            info.tree = tree.trim().to_string();
+        } else if let Some(parent) = line.strip_prefix("parent ") {
+            info.parents.push(parent.trim().to_string());
+        } else if let Some(author) = line.strip_prefix("author ") {
+            info.author = author.to_string();
+        } else if let Some(committer) = line.strip_prefix("committer ") {
+            info.committer = committer.to_string();
+        }
+    }
+
+    if info.tree.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "找不到 tree"));
+    }
+    Ok(info)
+}
+
 pub fn create_merge_commit(
     repo_path: &Path,
     tree_hash: &str,
@@ -84,79 +152,83 @@ pub fn create_merge_commit(
     parent2: &str,
     message: &str,
 ) -> io::Result<String> {
-    let author = "Your Name <you@example.com>";
-    let content = format!(
-        "tree {}\nparent {}\nparent {}\nauthor {}\ncommitter {}\n\n{}",
-        tree_hash,
-        parent1,
-        parent2,
-        author,
-        author,
-        message
-    );
-
-    let hash = sha1_hash(content.as_bytes());
-    let (dir, file) = hash.split_at(2);
-    let obj_dir = repo_path.join("objects").join(dir);
-    fs::create_dir_all(&obj_dir)?;
-    let path = obj_dir.join(file);
-    fs::write(path, content)?;
-
-    Ok(hash)
+    let signature = default_signature();
+    let parents = [parent1.to_string(), parent2.to_string()];
+    write_commit(repo_path, tree_hash, &parents, &signature, &signature, message)
 }
 
-/// 向上追溯所有祖先
-fn collect_ancestors(mut commit: String, repo: &Path) -> HashSet<String> {
+/// 向上追溯所有祖先（包含自身，沿所有父提交）
+pub fn collect_ancestors(commit: &str, repo: &Path) -> HashSet<String> {
     let mut ancestors = HashSet::new();
+    let mut queue = VecDeque::from([commit.to_string()]);
 
-    while commit.len() >= 2 {
-        ancestors.insert(commit.clone());
-
-        let (dir, file) = commit.split_at(2);
-        let path = repo.join("objects").join(dir).join(file);
-        if !path.exists() {
-            break;
+    while let Some(hash) = queue.pop_front() {
+        if hash.len() < 2 || !ancestors.insert(hash.clone()) {
+            continue;
         }
-
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        if let Some(parent_line) = content.lines().find(|line| line.starts_with("parent ")) {
-            commit = parent_line[7..].to_string(); // skip "parent "
-        } else {
-            break; // no parent = root
+        if let Ok(info) = read_commit(&hash, repo) {
+            queue.extend(info.parents);
         }
     }
 
     ancestors
 }
 
-/// 查找共同祖先（第一个相交的）
+/// 判断 `ancestor` 是否为 `commit` 本身或其祖先
+pub fn is_ancestor(ancestor: &str, commit: &str, repo: &Path) -> bool {
+    collect_ancestors(commit, repo).contains(ancestor)
+}
+
+/// `commit` 相对 `base` 领先与落后的提交数
+pub fn ahead_behind(commit: &str, base: &str, repo: &Path) -> (usize, usize) {
+    let ours = collect_ancestors(commit, repo);
+    let theirs = collect_ancestors(base, repo);
+    (ours.difference(&theirs).count(), theirs.difference(&ours).count())
+}
+
+/// 查找共同祖先（从 target 出发按广度优先找到的第一个 current 的祖先）
 pub fn find_common_ancestor(
     current: &str,
     target: &str,
     repo: &Path,
 ) -> Option<String> {
-    let current_ancestors = collect_ancestors(current.to_string(), repo);
-    let mut t = target.to_string();
+    let current_ancestors = collect_ancestors(current, repo);
+    let mut queue = VecDeque::from([target.to_string()]);
+    let mut visited = HashSet::new();
 
-    while t.len() >= 2 {
-        if current_ancestors.contains(&t) {
-            return Some(t);
+    while let Some(hash) = queue.pop_front() {
+        if hash.len() < 2 || !visited.insert(hash.clone()) {
+            continue;
         }
-
-        let (dir, file) = t.split_at(2);
-        let path = repo.join("objects").join(dir).join(file);
-        if !path.exists() {
-            break;
+        if current_ancestors.contains(&hash) {
+            return Some(hash);
         }
-
-        let content = fs::read_to_string(&path).unwrap_or_default();
-        if let Some(parent_line) = content.lines().find(|line| line.starts_with("parent ")) {
-            t = parent_line[7..].to_string();
-        } else {
-            break;
+        if let Ok(info) = read_commit(&hash, repo) {
+            queue.extend(info.parents);
         }
     }
 
     None
 }
 
+/// 列出从 `tip` 可达、但不在 `exclude` 中的提交，父提交总在子提交之前
+pub fn rev_list(tip: &str, exclude: &HashSet<String>, repo: &Path) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![(tip.to_string(), false)];
+
+    while let Some((hash, expanded)) = stack.pop() {
+        if expanded {
+            result.push(hash);
+            continue;
+        }
+        if hash.len() < 2 || exclude.contains(&hash) || !visited.insert(hash.clone()) {
+            continue;
+        }
+        let parents = read_commit(&hash, repo).map(|c| c.parents).unwrap_or_default();
+        stack.push((hash, true));
+        stack.extend(parents.into_iter().rev().map(|p| (p, false)));
+    }
+
+    result
+}
@@ -1,48 +1,184 @@
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::Deserialize;
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::env;
+use crate::core::repo_config::{parse_bool, repo_config_path, RepoConfig};
+use crate::core::repository::find_git_dir;
+
 #[derive(Debug, Deserialize)]
 pub struct CoreConfig {
     pub git_dir: Option<String>,
     pub default_branch: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DiffConfig {
+    pub renames: Option<bool>,
+    pub rename_threshold: Option<u32>,
+}
+
+/// 旧式的 `config.toml`（当前目录下），只作为分层配置中没有对应键时的后备
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub core: Option<CoreConfig>,
+    pub diff: Option<DiffConfig>,
 }
 
-fn load_config() -> Config {
+fn load_toml_config() -> Config {
     let config_content = fs::read_to_string("config.toml").unwrap_or_default();
-    toml::from_str(&config_content).unwrap_or(Config { core: None })
+    toml::from_str(&config_content).unwrap_or(Config { core: None, diff: None })
 }
 
 // 👇 全局配置变量
-pub static CONFIG: Lazy<Config> = Lazy::new(load_config);
+pub static CONFIG: Lazy<Config> = Lazy::new(load_toml_config);
 
-// 👇 全局 git 目录（默认为 ".mygit"）
-pub static GIT_DIR: Lazy<PathBuf> = Lazy::new(|| {
-    let dir = CONFIG
+/// 分层配置中的一层
+#[derive(Debug)]
+pub struct ConfigLayer {
+    /// 配置文件路径，命令行覆盖（`-c`）时为 None
+    pub path: Option<PathBuf>,
+    pub config: RepoConfig,
+}
+
+impl ConfigLayer {
+    /// `--show-origin` 显示的来源，如 `file:.mygit/config` 或 `command line:`
+    pub fn origin(&self) -> String {
+        match &self.path {
+            Some(path) => format!("file:{}", path.display()),
+            None => "command line:".to_string(),
+        }
+    }
+}
+
+/// 命令行上 `-c 键=值` 给出的覆盖，在执行命令前设置
+static COMMAND_LINE_CONFIG: OnceCell<RepoConfig> = OnceCell::new();
+
+/// 解析并记录 `-c 键=值` 覆盖；只有键名时值为 true
+pub fn set_command_line_config(pairs: &[String]) -> Result<(), String> {
+    let mut config = RepoConfig::default();
+    for pair in pairs {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, "true"));
+        config.add(key, value).map_err(|_| format!("无效的配置键 '{}'", key))?;
+    }
+    let _ = COMMAND_LINE_CONFIG.set(config);
+    Ok(())
+}
+
+/// 系统级配置文件：`RUST_GIT_CONFIG_SYSTEM` 优先，否则为 `/etc/rust-git/config`
+pub fn system_config_path() -> PathBuf {
+    env::var_os("RUST_GIT_CONFIG_SYSTEM")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/etc/rust-git/config"))
+}
+
+/// 用户级配置文件：`RUST_GIT_CONFIG_GLOBAL` 优先，否则为 `$XDG_CONFIG_HOME/rust-git/config`
+/// 或 `~/.config/rust-git/config`
+pub fn global_config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("RUST_GIT_CONFIG_GLOBAL") {
+        return Some(PathBuf::from(path));
+    }
+    let base = match env::var_os("XDG_CONFIG_HOME").filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("rust-git").join("config"))
+}
+
+/// 读取一个配置文件作为一层，文件不存在时为空
+pub fn load_layer(path: PathBuf) -> io::Result<ConfigLayer> {
+    let config = RepoConfig::load(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    Ok(ConfigLayer { path: Some(path), config })
+}
+
+/// 按优先级从低到高读取各层配置：系统、用户、仓库（`repo_path` 不为 None 时）与命令行
+pub fn config_layers(repo_path: Option<&Path>) -> io::Result<Vec<ConfigLayer>> {
+    let mut layers = vec![load_layer(system_config_path())?];
+    if let Some(path) = global_config_path() {
+        layers.push(load_layer(path)?);
+    }
+    if let Some(repo_path) = repo_path {
+        layers.push(load_layer(repo_config_path(repo_path))?);
+    }
+    if let Some(config) = COMMAND_LINE_CONFIG.get() {
+        layers.push(ConfigLayer { path: None, config: config.clone() });
+    }
+    Ok(layers)
+}
+
+/// 读取合并后的配置：同一个键以优先级最高的一层为准，多值键按层的顺序保留所有值
+pub fn load_config(repo_path: &Path) -> io::Result<RepoConfig> {
+    let mut merged = RepoConfig::default();
+    for layer in config_layers(Some(repo_path))? {
+        merged.extend(layer.config);
+    }
+    Ok(merged)
+}
+
+/// 不在仓库中也能读取的配置（系统、用户与命令行）
+fn load_config_outside_repo() -> RepoConfig {
+    let mut merged = RepoConfig::default();
+    for layer in config_layers(None).unwrap_or_default() {
+        merged.extend(layer.config);
+    }
+    merged
+}
+
+// 👇 git 目录名（默认为 ".mygit"），工作区中这个名字的目录或 `gitdir:` 文件不属于工作区内容
+pub static GIT_DIR_NAME: Lazy<String> = Lazy::new(|| {
+    CONFIG
         .core
         .as_ref()
         .and_then(|c| c.git_dir.as_ref())
         .cloned()
-        .unwrap_or_else(|| ".mygit".to_string());
-    PathBuf::from(dir)
+        .unwrap_or_else(|| ".mygit".to_string())
 });
 
-// 👇 全局默认分支名（默认为 "master"）
-pub static DEFAULT_BRANCH: Lazy<String> = Lazy::new(|| {
-    CONFIG
-        .core
+// 👇 当前目录对应的仓库：(git 目录, 是否为裸仓库)
+static REPOSITORY: Lazy<Option<(PathBuf, bool)>> = Lazy::new(|| find_git_dir(Path::new(".")));
+
+// 👇 全局 git 目录：`.mygit`、`.mygit` 中 `gitdir:` 指向的目录，或当前目录本身（裸仓库）
+pub static GIT_DIR: Lazy<PathBuf> = Lazy::new(|| {
+    REPOSITORY
         .as_ref()
-        .and_then(|c| c.default_branch.as_ref())
-        .cloned()
+        .map(|(dir, _)| dir.clone())
+        .unwrap_or_else(|| PathBuf::from(&*GIT_DIR_NAME))
+});
+
+// 👇 当前仓库是否为裸仓库（`core.bare = true` 或直接位于 git 目录中），裸仓库没有工作区
+pub static IS_BARE: Lazy<bool> = Lazy::new(|| REPOSITORY.as_ref().is_some_and(|(_, bare)| *bare));
+
+// 👇 全局默认分支名（`init.defaultBranch`，默认为 "master"）
+pub static DEFAULT_BRANCH: Lazy<String> = Lazy::new(|| {
+    load_config_outside_repo()
+        .get("init.defaultbranch")
+        .filter(|b| !b.is_empty())
+        .map(str::to_string)
+        .or_else(|| CONFIG.core.as_ref().and_then(|c| c.default_branch.clone()))
         .unwrap_or_else(|| "master".to_string())
 });
 
+// 👇 是否默认启用重命名检测（`diff.renames`，默认为 true）
+pub static DETECT_RENAMES: Lazy<bool> = Lazy::new(|| {
+    load_config(&GIT_DIR)
+        .ok()
+        .and_then(|c| c.get("diff.renames").and_then(parse_bool))
+        .or_else(|| CONFIG.diff.as_ref().and_then(|d| d.renames))
+        .unwrap_or(true)
+});
+
+// 👇 重命名 / 复制检测的相似度阈值（`diff.renameThreshold`，百分比，可带 `%`，默认为 50）
+pub static RENAME_THRESHOLD: Lazy<u32> = Lazy::new(|| {
+    load_config(&GIT_DIR)
+        .ok()
+        .and_then(|c| c.get("diff.renamethreshold").and_then(|v| v.trim().trim_end_matches('%').parse().ok()))
+        .or_else(|| CONFIG.diff.as_ref().and_then(|d| d.rename_threshold))
+        .unwrap_or(50)
+        .min(100)
+});
+
 /// 是否启用详细输出模式（由环境变量控制）
 pub static IS_VERBOSE: Lazy<bool> = Lazy::new(|| {
     env::var("RUST_GIT_VERBOSE")
@@ -0,0 +1,269 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use crate::core::config::{DETECT_RENAMES, RENAME_THRESHOLD};
+use crate::core::object::read_object;
+
+/// 候选组合数超过 `RENAME_LIMIT²` 时跳过内容相似度打分，只保留精确匹配
+const RENAME_LIMIT: usize = 1000;
+
+/// 两个文件快照（路径 -> blob hash）之间的单个变更
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added(String),
+    Deleted(String),
+    Modified(String),
+    Renamed { from: String, to: String, score: u32 },
+    Copied { from: String, to: String, score: u32 },
+}
+
+impl Change {
+    /// 变更后的路径（删除时为原路径），用于排序和过滤
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added(p) | Change::Deleted(p) | Change::Modified(p) => p,
+            Change::Renamed { to, .. } | Change::Copied { to, .. } => to,
+        }
+    }
+
+    /// 输出 `git diff --name-status` 风格的一行
+    pub fn name_status(&self) -> String {
+        match self {
+            Change::Added(p) => format!("A\t{}", p),
+            Change::Deleted(p) => format!("D\t{}", p),
+            Change::Modified(p) => format!("M\t{}", p),
+            Change::Renamed { from, to, score } => format!("R{:03}\t{}\t{}", score, from, to),
+            Change::Copied { from, to, score } => format!("C{:03}\t{}\t{}", score, from, to),
+        }
+    }
+}
+
+/// 重命名 / 复制检测选项
+#[derive(Debug, Clone, Copy)]
+pub struct RenameOptions {
+    pub renames: bool,
+    pub copies: bool,
+    /// 相似度阈值（百分比），低于该值的文件对不视为重命名
+    pub threshold: u32,
+}
+
+impl Default for RenameOptions {
+    fn default() -> Self {
+        RenameOptions {
+            renames: *DETECT_RENAMES,
+            copies: false,
+            threshold: *RENAME_THRESHOLD,
+        }
+    }
+}
+
+/// 计算两段内容的相似度（0~100）
+///
+/// 按行统计 `dst` 中能在 `src` 里找到的字节数，再除以较大一方的长度，
+/// 与 git 的 "copied bytes / max size" 打分思路一致。
+pub fn similarity(src: &[u8], dst: &[u8]) -> u32 {
+    if src == dst {
+        return 100;
+    }
+    let max = src.len().max(dst.len());
+    if max == 0 {
+        return 100;
+    }
+
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    for line in src.split_inclusive(|&b| b == b'\n') {
+        *counts.entry(line).or_default() += 1;
+    }
+
+    let mut common = 0;
+    for line in dst.split_inclusive(|&b| b == b'\n') {
+        if let Some(count) = counts.get_mut(line) {
+            if *count > 0 {
+                *count -= 1;
+                common += line.len();
+            }
+        }
+    }
+
+    (common * 100 / max) as u32
+}
+
+/// 比较两个快照，文件内容从对象库中读取
+pub fn diff_maps(
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+    repo_path: &Path,
+    opts: RenameOptions,
+) -> Vec<Change> {
+    diff_maps_with(old, new, opts, |_, hash| read_object(hash, repo_path).ok())
+}
+
+/// 比较两个快照，`load(path, hash)` 负责提供文件内容（用于相似度打分）
+pub fn diff_maps_with<F>(
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+    opts: RenameOptions,
+    load: F,
+) -> Vec<Change>
+where
+    F: Fn(&str, &str) -> Option<Vec<u8>>,
+{
+    let mut changes = Vec::new();
+    let mut added: Vec<&String> = Vec::new();
+    let mut deleted: Vec<&String> = Vec::new();
+
+    for (path, hash) in new {
+        match old.get(path) {
+            None => added.push(path),
+            Some(old_hash) if old_hash != hash => changes.push(Change::Modified(path.clone())),
+            _ => {}
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            deleted.push(path);
+        }
+    }
+    added.sort();
+    deleted.sort();
+
+    let mut matched: HashSet<&String> = HashSet::new();
+
+    if opts.renames && !added.is_empty() && !deleted.is_empty() {
+        let mut used: HashSet<&String> = HashSet::new();
+
+        // 1. 精确匹配：hash 相同即为 100% 重命名，同名文件优先
+        for dst in &added {
+            let dst_hash = &new[*dst];
+            let candidates: Vec<&&String> = deleted
+                .iter()
+                .filter(|src| !used.contains(**src) && &old[**src] == dst_hash)
+                .collect();
+            let best = candidates
+                .iter()
+                .find(|src| file_name(src) == file_name(dst))
+                .or_else(|| candidates.first())
+                .copied();
+            if let Some(src) = best {
+                used.insert(*src);
+                matched.insert(*dst);
+                changes.push(Change::Renamed { from: (*src).clone(), to: (*dst).clone(), score: 100 });
+            }
+        }
+
+        // 2. 相似度匹配：按分数从高到低贪心配对
+        let rest_added: Vec<&String> = added.iter().copied().filter(|p| !matched.contains(p)).collect();
+        let rest_deleted: Vec<&String> = deleted.iter().copied().filter(|p| !used.contains(p)).collect();
+        if rest_added.len() * rest_deleted.len() <= RENAME_LIMIT * RENAME_LIMIT {
+            let mut scored = score_pairs(&rest_deleted, old, &rest_added, new, opts.threshold, &load);
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.cmp(b.2)).then_with(|| a.1.cmp(b.1)));
+            for (score, src, dst) in scored {
+                if used.contains(src) || matched.contains(dst) {
+                    continue;
+                }
+                used.insert(src);
+                matched.insert(dst);
+                changes.push(Change::Renamed { from: src.clone(), to: dst.clone(), score });
+            }
+        }
+
+        deleted.retain(|p| !used.contains(p));
+    }
+
+    if opts.copies {
+        // 复制检测的来源：变更后依然存在的旧文件
+        let mut sources: Vec<&String> = old.keys().filter(|p| new.contains_key(*p)).collect();
+        sources.sort();
+        let rest_added: Vec<&String> = added.iter().copied().filter(|p| !matched.contains(p)).collect();
+
+        for dst in &rest_added {
+            if let Some(src) = sources.iter().find(|src| old[**src] == new[*dst]) {
+                matched.insert(*dst);
+                changes.push(Change::Copied { from: (*src).clone(), to: (*dst).clone(), score: 100 });
+            }
+        }
+
+        let rest_added: Vec<&String> = rest_added.into_iter().filter(|p| !matched.contains(p)).collect();
+        if rest_added.len() * sources.len() <= RENAME_LIMIT * RENAME_LIMIT {
+            let mut scored = score_pairs(&sources, old, &rest_added, new, opts.threshold, &load);
+            scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.cmp(b.2)).then_with(|| a.1.cmp(b.1)));
+            for (score, src, dst) in scored {
+                if matched.contains(dst) {
+                    continue;
+                }
+                matched.insert(dst);
+                changes.push(Change::Copied { from: src.clone(), to: dst.clone(), score });
+            }
+        }
+    }
+
+    for path in added {
+        if !matched.contains(path) {
+            changes.push(Change::Added(path.clone()));
+        }
+    }
+    for path in deleted {
+        changes.push(Change::Deleted(path.clone()));
+    }
+
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
+
+/// 为所有 (src, dst) 组合打分，只保留达到阈值的组合
+fn score_pairs<'a, F>(
+    sources: &[&'a String],
+    old: &HashMap<String, String>,
+    targets: &[&'a String],
+    new: &HashMap<String, String>,
+    threshold: u32,
+    load: &F,
+) -> Vec<(u32, &'a String, &'a String)>
+where
+    F: Fn(&str, &str) -> Option<Vec<u8>>,
+{
+    let src_contents: Vec<Option<Vec<u8>>> = sources.iter().map(|p| load(p, &old[*p])).collect();
+    let mut scored = Vec::new();
+
+    for dst in targets {
+        let Some(dst_content) = load(dst, &new[*dst]) else { continue };
+        if dst_content.is_empty() {
+            continue;
+        }
+        for (src, src_content) in sources.iter().zip(&src_contents) {
+            let Some(src_content) = src_content else { continue };
+            if src_content.is_empty() {
+                continue;
+            }
+            // 大小相差过大时不可能达到阈值，直接跳过
+            let (small, large) = if src_content.len() < dst_content.len() {
+                (src_content.len(), dst_content.len())
+            } else {
+                (dst_content.len(), src_content.len())
+            };
+            if (small * 100 / large) < threshold as usize {
+                continue;
+            }
+            let score = similarity(src_content, &dst_content);
+            if score >= threshold {
+                scored.push((score, *src, *dst));
+            }
+        }
+    }
+
+    scored
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// 从变更列表中提取重命名对（原路径 -> 新路径）
+pub fn renames_of(changes: &[Change]) -> Vec<(String, String)> {
+    changes
+        .iter()
+        .filter_map(|c| match c {
+            Change::Renamed { from, to, .. } => Some((from.clone(), to.clone())),
+            _ => None,
+        })
+        .collect()
+}
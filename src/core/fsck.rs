@@ -0,0 +1,469 @@
+//! 对象库与引用的完整性检查（fsck）
+//!
+//! 检查分三步：逐个重新计算松散对象的 hash；从所有引用、reflog、index 以及进行中的
+//! rebase / cherry-pick / revert 的状态出发遍历，
+//! 按对象被引用的位置（提交、tree、blob）严格解析，记录缺失的对象与断开的链接；
+//! 最后把没有被遍历到、也没有被其他对象引用的对象报告为悬空（dangling）对象。
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::commit::split_signature;
+use crate::core::index::{index_file, read_index};
+use crate::core::object::{detect_kind, is_object_id, object_path, objects_dir, read_object, ObjectKind};
+use crate::core::repository::common_dir;
+use crate::core::reference::resolve_revision;
+use crate::core::refs::{RefStore, RefValue};
+use crate::core::sequencer::read_todo;
+use crate::core::tree::verify_tree_path;
+use crate::core::worktree::list_worktrees;
+use crate::utils::hash::sha1_hash;
+
+const ZERO_HASH: &str = "0000000000000000000000000000000000000000";
+
+/// 一条检查结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Finding {
+    /// 对象文件的内容与文件名中的 hash 不符
+    HashMismatch { hash: String, actual: String },
+    /// objects 目录下不是对象的文件
+    Garbage(PathBuf),
+    /// 对象存在，但不能按其类型解析
+    Corrupt { kind: ObjectKind, hash: String, reason: String },
+    /// 对象 `from` 引用了不存在的对象 `to`
+    BrokenLink { from_kind: ObjectKind, from: String, to_kind: ObjectKind, to: String },
+    /// 被引用、但对象库中不存在的对象
+    Missing { kind: ObjectKind, hash: String },
+    /// 引用、packed-refs 或 reflog 本身有问题
+    BadRef { name: String, reason: String },
+    /// index 中的条目指向不存在的 blob
+    BadIndexEntry { path: String, hash: String },
+    /// 不影响完整性的提示，如 HEAD 指向尚未提交的分支
+    Notice(String),
+    /// 不可达、且没有被其他对象引用的对象
+    Dangling { kind: ObjectKind, hash: String },
+}
+
+impl Finding {
+    /// 是否属于错误（悬空对象与提示不算）
+    pub fn is_error(&self) -> bool {
+        !matches!(self, Finding::Notice(_) | Finding::Dangling { .. })
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Finding::HashMismatch { hash, actual } => {
+                write!(f, "error: 对象 {} 的 hash 不符（实际为 {}）", hash, actual)
+            }
+            Finding::Garbage(path) => write!(f, "warning: 无用的文件: {}", path.display()),
+            Finding::Corrupt { kind, hash, reason } => {
+                write!(f, "error: 对象 {}: 无效的 {}: {}", hash, kind.name(), reason)
+            }
+            Finding::BrokenLink { from_kind, from, to_kind, to } => write!(
+                f,
+                "broken link from {:>6} {}\n              to {:>6} {}",
+                from_kind.name(),
+                from,
+                to_kind.name(),
+                to
+            ),
+            Finding::Missing { kind, hash } => write!(f, "missing {} {}", kind.name(), hash),
+            Finding::BadRef { name, reason } => write!(f, "error: {}: {}", name, reason),
+            Finding::BadIndexEntry { path, hash } => {
+                write!(f, "error: index 中的 '{}' 指向不存在的 blob {}", path, hash)
+            }
+            Finding::Notice(message) => write!(f, "notice: {}", message),
+            Finding::Dangling { kind, hash } => write!(f, "dangling {} {}", kind.name(), hash),
+        }
+    }
+}
+
+/// 严格解析提交对象，返回它引用的 tree 与父提交
+pub fn parse_commit(content: &[u8]) -> Result<(String, Vec<String>), String> {
+    let text = std::str::from_utf8(content).map_err(|_| "不是 UTF-8 文本".to_string())?;
+    let (header, _) = text.split_once("\n\n").ok_or("缺少提交说明前的空行")?;
+    let mut lines = header.lines().peekable();
+
+    let tree = lines.next().and_then(|l| l.strip_prefix("tree ")).ok_or("缺少 tree 行")?;
+    if !is_object_id(tree) {
+        return Err(format!("无效的 tree hash '{}'", tree));
+    }
+    let mut parents = Vec::new();
+    while let Some(parent) = lines.peek().and_then(|l| l.strip_prefix("parent ")) {
+        if !is_object_id(parent) {
+            return Err(format!("无效的 parent hash '{}'", parent));
+        }
+        parents.push(parent.to_string());
+        lines.next();
+    }
+    for field in ["author", "committer"] {
+        let signature = lines
+            .next()
+            .and_then(|l| l.strip_prefix(field).and_then(|rest| rest.strip_prefix(' ')))
+            .ok_or_else(|| format!("缺少 {} 行", field))?;
+        let (ident, _) = split_signature(signature);
+        if !ident.contains('<') || !ident.ends_with('>') {
+            return Err(format!("{} 行的签名格式错误", field));
+        }
+    }
+    Ok((tree.to_string(), parents))
+}
+
+/// 严格解析 tree 对象（`blob <hash> <路径>` 行），返回它引用的 blob
+pub fn parse_tree(content: &[u8]) -> Result<Vec<String>, String> {
+    let text = std::str::from_utf8(content).map_err(|_| "不是 UTF-8 文本".to_string())?;
+    if !text.is_empty() && !text.ends_with('\n') {
+        return Err("最后一行缺少换行".to_string());
+    }
+    let mut paths = HashSet::new();
+    let mut blobs = Vec::new();
+    for line in text.lines() {
+        let (hash, path) = line
+            .strip_prefix("blob ")
+            .and_then(|rest| rest.split_once(' '))
+            .ok_or_else(|| format!("无法解析的条目 '{}'", line))?;
+        if !is_object_id(hash) {
+            return Err(format!("无效的 blob hash '{}'", hash));
+        }
+        verify_tree_path(path).map_err(|reason| format!("不安全的路径 '{}': {}", path, reason))?;
+        if !paths.insert(path) {
+            return Err(format!("重复的路径 '{}'", path));
+        }
+        blobs.push(hash.to_string());
+    }
+    Ok(blobs)
+}
+
+/// 列出对象库中的所有松散对象，以及 objects 目录下不是对象的文件
+pub fn list_objects(repo_path: &Path) -> io::Result<(Vec<String>, Vec<PathBuf>)> {
+    let mut objects = Vec::new();
+    let mut garbage = Vec::new();
+    let objects_dir = objects_dir(repo_path);
+    let Ok(entries) = fs::read_dir(&objects_dir) else {
+        return Ok((objects, garbage));
+    };
+    for entry in entries {
+        let entry = entry?;
+        let dir = entry.file_name().to_string_lossy().to_string();
+        if dir == "info" || dir == "pack" {
+            continue;
+        }
+        if dir.len() != 2 || !dir.bytes().all(|b| b.is_ascii_hexdigit()) || !entry.path().is_dir() {
+            garbage.push(entry.path());
+            continue;
+        }
+        for file in fs::read_dir(entry.path())? {
+            let file = file?;
+            let hash = format!("{}{}", dir, file.file_name().to_string_lossy());
+            if is_object_id(&hash) && file.path().is_file() {
+                objects.push(hash);
+            } else {
+                garbage.push(file.path());
+            }
+        }
+    }
+    objects.sort();
+    garbage.sort();
+    Ok((objects, garbage))
+}
+
+/// 工作区专属的伪引用，其中记录的提交不能被当作不可达
+const PSEUDO_REFS: [&str; 4] = ["ORIG_HEAD", "REBASE_HEAD", "CHERRY_PICK_HEAD", "REVERT_HEAD"];
+
+/// 遍历的起点：所有引用、reflog 与 index 指向的对象
+struct Roots {
+    /// (来源, 期望的类型, hash)，来源用于报告问题
+    objects: Vec<(String, Option<ObjectKind>, String)>,
+    findings: Vec<Finding>,
+}
+
+/// 引用指向的对象应当是什么类型（分支与 HEAD 必须是提交，其余不限）
+fn expected_kind(name: &str) -> Option<ObjectKind> {
+    if name == "HEAD" || name.starts_with("refs/heads/") || name.starts_with("refs/remotes/") {
+        Some(ObjectKind::Commit)
+    } else {
+        None
+    }
+}
+
+fn collect_roots(repo_path: &Path) -> Roots {
+    let store = RefStore::new(repo_path);
+    let mut roots = Roots { objects: Vec::new(), findings: Vec::new() };
+    let bad = |name: &str, reason: String| Finding::BadRef { name: name.to_string(), reason };
+
+    match store.read("HEAD") {
+        None => roots.findings.push(bad("HEAD", "HEAD 不存在".to_string())),
+        Some(RefValue::Symbolic(target)) => {
+            if !target.starts_with("refs/") {
+                roots.findings.push(bad("HEAD", format!("指向无效的引用 '{}'", target)));
+            } else if store.resolve("HEAD").is_none() {
+                let branch = target.strip_prefix("refs/heads/").unwrap_or(&target);
+                roots.findings.push(Finding::Notice(format!("HEAD 指向尚未提交的分支 ({})", branch)));
+            }
+        }
+        Some(RefValue::Direct(hash)) if is_object_id(&hash) => {
+            roots.objects.push(("HEAD".to_string(), Some(ObjectKind::Commit), hash));
+        }
+        Some(RefValue::Direct(hash)) => roots.findings.push(bad("HEAD", format!("无效的 sha1 指针 '{}'", hash))),
+    }
+
+    let mut loose = HashSet::new();
+    for (name, value) in store.loose_refs("refs/") {
+        loose.insert(name.clone());
+        match value {
+            RefValue::Direct(hash) if is_object_id(&hash) => roots.objects.push((name.clone(), expected_kind(&name), hash)),
+            RefValue::Direct(hash) => roots.findings.push(bad(&name, format!("无效的 sha1 指针 '{}'", hash))),
+            RefValue::Symbolic(target) => {
+                if store.resolve(&target).is_none() {
+                    roots.findings.push(bad(&name, format!("符号引用指向不存在的引用 '{}'", target)));
+                }
+            }
+        }
+    }
+    match store.packed_refs() {
+        Ok(packed) => {
+            for (name, entry) in packed.into_iter().filter(|(name, _)| !loose.contains(name)) {
+                roots.objects.push((name.clone(), expected_kind(&name), entry.hash));
+                if let Some(peeled) = entry.peeled {
+                    roots.objects.push((name, None, peeled));
+                }
+            }
+        }
+        Err(e) => roots.findings.push(bad("packed-refs", e.to_string())),
+    }
+
+    for (name, path) in reflog_files(repo_path) {
+        let Ok(content) = fs::read_to_string(&path) else {
+            roots.findings.push(bad(&name, "无法读取 reflog".to_string()));
+            continue;
+        };
+        for (number, line) in content.lines().enumerate() {
+            let mut fields = line.split_whitespace();
+            let (Some(old), Some(new)) = (fields.next(), fields.next()) else {
+                roots.findings.push(bad(&name, format!("reflog 第 {} 行格式错误", number + 1)));
+                continue;
+            };
+            for hash in [old, new] {
+                if hash == ZERO_HASH {
+                    continue;
+                }
+                if is_object_id(hash) {
+                    roots.objects.push((name.clone(), Some(ObjectKind::Commit), hash.to_string()));
+                } else {
+                    roots.findings.push(bad(&name, format!("reflog 第 {} 行含有无效的 hash '{}'", number + 1, hash)));
+                }
+            }
+        }
+    }
+
+    for (hash, path) in read_index(&index_file(repo_path)).unwrap_or_default() {
+        if is_object_id(&hash) && read_object(&hash, repo_path).is_ok() {
+            roots.objects.push((format!("index:{}", path), Some(ObjectKind::Blob), hash));
+        } else {
+            roots.findings.push(Finding::BadIndexEntry { path, hash });
+        }
+    }
+
+    roots.objects.extend(state_roots(repo_path, ""));
+
+    // 其他工作区分离的 HEAD 与 index 也会引用对象，不能当作不可达
+    let current = fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.to_path_buf());
+    for worktree in list_worktrees(repo_path).unwrap_or_default().into_iter().filter(|wt| !wt.bare && wt.git_dir != current) {
+        let label = worktree.path.display().to_string();
+        roots.objects.extend(state_roots(&worktree.git_dir, &format!("{}:", label)));
+        if let Some(RefValue::Direct(hash)) = worktree.head().filter(|head| matches!(head, RefValue::Direct(h) if is_object_id(h))) {
+            roots.objects.push((format!("{}:HEAD", label), Some(ObjectKind::Commit), hash));
+        }
+        for (hash, path) in read_index(&index_file(&worktree.git_dir)).unwrap_or_default() {
+            if is_object_id(&hash) {
+                roots.objects.push((format!("{}:index:{}", label, path), Some(ObjectKind::Blob), hash));
+            }
+        }
+    }
+    roots
+}
+
+/// 工作区（`git_dir`）中进行中的操作引用的提交：`ORIG_HEAD` 等伪引用，
+/// 以及中断的 rebase / cherry-pick / revert 保存的起点与待办列表中的提交，`--continue` 与 `--abort` 还要用到它们
+fn state_roots(git_dir: &Path, label: &str) -> Vec<(String, Option<ObjectKind>, String)> {
+    let mut found = Vec::new();
+    let mut push = |name: String, hash: &str| {
+        // 待办列表可能被手工编辑成简写的 hash
+        let hash = if is_object_id(hash) { Some(hash.to_string()) } else { resolve_revision(hash, git_dir) };
+        if let Some(hash) = hash.filter(|h| is_object_id(h)) {
+            found.push((format!("{}{}", label, name), Some(ObjectKind::Commit), hash));
+        }
+    };
+
+    for name in PSEUDO_REFS.iter().chain(&["rebase-merge/orig-head", "rebase-merge/onto", "rebase-merge/amend", "sequencer/head"]) {
+        if let Ok(content) = fs::read_to_string(git_dir.join(name)) {
+            let hash = content.trim();
+            if !hash.is_empty() {
+                push(name.to_string(), hash);
+            }
+        }
+    }
+    for name in ["rebase-merge/git-rebase-todo", "rebase-merge/done", "sequencer/todo"] {
+        for item in read_todo(&git_dir.join(name)).unwrap_or_default() {
+            if !item.commit.is_empty() {
+                push(name.to_string(), &item.commit);
+            }
+        }
+    }
+    found
+}
+
+/// 列出 `logs/` 下的所有 reflog 文件及其引用名
+fn reflog_files(repo_path: &Path) -> Vec<(String, PathBuf)> {
+    let logs = common_dir(repo_path).join("logs");
+    let mut files = Vec::new();
+    let mut stack = vec![logs.clone()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if let Ok(name) = path.strip_prefix(&logs) {
+                files.push((name.to_string_lossy().replace('\\', "/"), path));
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// 按类型解析对象，返回它引用的对象
+fn parse_links(kind: ObjectKind, content: &[u8]) -> Result<Vec<(ObjectKind, String)>, String> {
+    match kind {
+        ObjectKind::Commit => {
+            let (tree, parents) = parse_commit(content)?;
+            let mut links = vec![(ObjectKind::Tree, tree)];
+            links.extend(parents.into_iter().map(|p| (ObjectKind::Commit, p)));
+            Ok(links)
+        }
+        ObjectKind::Tree => Ok(parse_tree(content)?.into_iter().map(|b| (ObjectKind::Blob, b)).collect()),
+        ObjectKind::Blob => Ok(Vec::new()),
+    }
+}
+
+/// 从所有引用、reflog 与 index 可达的对象，供 prune 判断哪些对象仍然需要
+///
+/// `refs/stash` 及其 reflog 与其他引用一样作为起点。引用本身有问题、或可达的对象无法解析时返回错误：
+/// 此时无法确定哪些对象仍然被需要，调用方不应删除任何对象。缺失的对象直接跳过。
+pub fn reachable_set(repo_path: &Path) -> io::Result<HashSet<String>> {
+    let roots = collect_roots(repo_path);
+    if let Some(problem) = roots.findings.iter().find(|f| matches!(f, Finding::BadRef { .. })) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{}（请先运行 fsck 修复）", problem)));
+    }
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<(Option<ObjectKind>, String)> = roots.objects.into_iter().map(|(_, kind, hash)| (kind, hash)).collect();
+    while let Some((expected, hash)) = stack.pop() {
+        if reachable.contains(&hash) {
+            continue;
+        }
+        let Ok(content) = read_object(&hash, repo_path) else { continue };
+        let kind = expected.unwrap_or_else(|| detect_kind(&content));
+        let links = parse_links(kind, &content).map_err(|reason| {
+            let problem = Finding::Corrupt { kind, hash: hash.clone(), reason };
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}（请先运行 fsck 修复）", problem))
+        })?;
+        reachable.insert(hash);
+        stack.extend(links.into_iter().map(|(kind, hash)| (Some(kind), hash)));
+    }
+    Ok(reachable)
+}
+
+/// 遍历中等待检查的对象
+struct Pending {
+    /// 根据引用位置期望的类型，None 表示按内容推断
+    expected: Option<ObjectKind>,
+    hash: String,
+    /// 引用它的对象，起点为 None
+    from: Option<(ObjectKind, String)>,
+    /// 遍历起点的来源（引用名、reflog 或 index 路径）
+    source: String,
+}
+
+/// 检查整个仓库，返回所有问题与悬空对象
+pub fn check_repository(repo_path: &Path) -> io::Result<Vec<Finding>> {
+    let (objects, garbage) = list_objects(repo_path)?;
+    let mut findings: Vec<Finding> = garbage.into_iter().map(Finding::Garbage).collect();
+
+    // 第一步：重新计算每个对象的 hash
+    let mut contents: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    for hash in objects {
+        let content = fs::read(object_path(&hash, repo_path)?)?;
+        let actual = sha1_hash(&content);
+        if actual != hash {
+            findings.push(Finding::HashMismatch { hash: hash.clone(), actual });
+        }
+        contents.insert(hash, content);
+    }
+
+    // 第二步：从引用、reflog 与 index 出发按类型遍历
+    let roots = collect_roots(repo_path);
+    findings.extend(roots.findings);
+    let mut reachable: HashMap<String, ObjectKind> = HashMap::new();
+    let mut missing = HashSet::new();
+    let mut stack: Vec<Pending> = roots
+        .objects
+        .into_iter()
+        .map(|(source, expected, hash)| Pending { expected, hash, from: None, source })
+        .collect();
+    stack.reverse();
+    while let Some(Pending { expected, hash, from, source }) = stack.pop() {
+        let Some(content) = contents.get(&hash) else {
+            let kind = expected.unwrap_or(ObjectKind::Commit);
+            match from {
+                Some((from_kind, from)) => {
+                    findings.push(Finding::BrokenLink { from_kind, from, to_kind: kind, to: hash.clone() });
+                    if missing.insert(hash.clone()) {
+                        findings.push(Finding::Missing { kind, hash });
+                    }
+                }
+                None => findings.push(Finding::BadRef { name: source, reason: format!("无效的 sha1 指针 {}", hash) }),
+            }
+            continue;
+        };
+        let detected = detect_kind(content);
+        let kind = expected.unwrap_or(detected);
+        if reachable.contains_key(&hash) {
+            continue;
+        }
+        if from.is_none() && expected == Some(ObjectKind::Commit) && detected != ObjectKind::Commit {
+            findings.push(Finding::BadRef { name: source, reason: format!("指向的对象 {} 不是提交", hash) });
+            continue;
+        }
+        reachable.insert(hash.clone(), kind);
+        match parse_links(kind, content) {
+            Ok(links) => {
+                for (link_kind, link) in links.into_iter().rev() {
+                    stack.push(Pending { expected: Some(link_kind), hash: link, from: Some((kind, hash.clone())), source: source.clone() });
+                }
+            }
+            Err(reason) => findings.push(Finding::Corrupt { kind, hash, reason }),
+        }
+    }
+
+    // 第三步：不可达的对象中，没有被其他不可达对象引用的即为悬空对象
+    let mut referenced = HashSet::new();
+    let mut unreachable = Vec::new();
+    for (hash, content) in contents.iter().filter(|(hash, _)| !reachable.contains_key(*hash)) {
+        let kind = detect_kind(content);
+        match parse_links(kind, content) {
+            Ok(links) => referenced.extend(links.into_iter().map(|(_, link)| link)),
+            Err(reason) => findings.push(Finding::Corrupt { kind, hash: hash.clone(), reason }),
+        }
+        unreachable.push((kind, hash));
+    }
+    for (kind, hash) in unreachable {
+        if !referenced.contains(hash) {
+            findings.push(Finding::Dangling { kind, hash: hash.clone() });
+        }
+    }
+    Ok(findings)
+}
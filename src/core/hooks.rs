@@ -0,0 +1,80 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use crate::core::config::load_config;
+use crate::core::repository::common_dir;
+use crate::core::worktree::list_worktrees;
+
+/// 钩子所在目录：`core.hooksPath` 配置优先，否则为共享 git 目录下的 `hooks`
+pub fn hooks_dir(repo_path: &Path) -> PathBuf {
+    let config = load_config(repo_path).unwrap_or_default();
+    match config.get("core.hookspath") {
+        Some(path) if !path.is_empty() => PathBuf::from(path),
+        _ => common_dir(repo_path).join("hooks"),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().map(|m| m.permissions().mode() & 0o111 != 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// 运行钩子的目录：git 目录所属工作区的根目录，裸仓库为 git 目录本身
+fn hook_work_dir(repo_path: &Path) -> PathBuf {
+    let git_dir = fs::canonicalize(repo_path).unwrap_or_else(|_| repo_path.to_path_buf());
+    list_worktrees(repo_path)
+        .ok()
+        .and_then(|worktrees| worktrees.into_iter().find(|wt| wt.git_dir == git_dir))
+        .map(|wt| wt.path)
+        .unwrap_or(git_dir)
+}
+
+/// 运行钩子，返回其是否成功；钩子不存在时视为成功
+///
+/// 与 git 一样在工作区根目录运行，钩子的标准输出转到标准错误，
+/// `input` 不为 None 时写入钩子的标准输入。存在但不可执行的钩子会被忽略并给出提示。
+pub fn run_hook(repo_path: &Path, name: &str, args: &[&str], input: Option<&str>) -> io::Result<bool> {
+    spawn_hook(repo_path, name, args, input, None)
+}
+
+/// 同 [`run_hook`]，并通过 `RUST_GIT_INDEX_FILE` 让钩子读取 `index` 而不是仓库的 index
+///
+/// 提交时用它把将要提交的 index 提供给 pre-commit 与 commit-msg 钩子。
+pub fn run_hook_with_index(repo_path: &Path, name: &str, args: &[&str], index: &Path) -> io::Result<bool> {
+    spawn_hook(repo_path, name, args, None, Some(index))
+}
+
+fn spawn_hook(repo_path: &Path, name: &str, args: &[&str], input: Option<&str>, index: Option<&Path>) -> io::Result<bool> {
+    let path = hooks_dir(repo_path).join(name);
+    if !path.is_file() {
+        return Ok(true);
+    }
+    if !is_executable(&path) {
+        eprintln!("hint: The '{}' hook was ignored because it's not set as executable.", name);
+        return Ok(true);
+    }
+
+    let path = fs::canonicalize(&path)?;
+    let mut command = Command::new(&path);
+    command
+        .args(args)
+        .current_dir(hook_work_dir(repo_path))
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::from(io::stderr()));
+    if let Some(index) = index {
+        command.env("RUST_GIT_INDEX_FILE", fs::canonicalize(index)?);
+    }
+    let mut child = command.spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // 钩子可能不读取标准输入就退出，写入失败不算错误
+        let _ = stdin.write_all(input.as_bytes());
+    }
+    Ok(child.wait()?.success())
+}
@@ -0,0 +1,214 @@
+//! 供 smart HTTP 使用的最小 HTTP/1.1 实现
+//!
+//! 每个连接只处理一个请求（`Connection: close`），请求体支持 `Content-Length` 与 chunked 编码。
+//! 请求体的长度来自客户端，读取时不按声明的长度预先分配内存，超过 [`MAX_REQUEST_BODY`] 的请求被拒绝。
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+
+/// 服务端接受的最大请求体（256 MiB）
+pub const MAX_REQUEST_BODY: usize = 256 * 1024 * 1024;
+
+/// 一个 HTTP 请求
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    /// 路径部分（不含查询串）
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// 一个 HTTP 响应
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// 按名字（不区分大小写）查找头部
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// 查询串中的参数
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query.split('&').find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+    }
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Response {
+        Response { status, headers: vec![("Content-Type".to_string(), content_type.to_string())], body }
+    }
+
+    /// 纯文本的错误响应
+    pub fn error(status: u16, message: &str) -> Response {
+        Response::new(status, "text/plain; charset=utf-8", format!("{}\n", message).into_bytes())
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Content Too Large",
+        415 => "Unsupported Media Type",
+        _ => "Internal Server Error",
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 读取一行（去掉 `\r\n`）
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "连接意外关闭"));
+    }
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// 读取头部直到空行
+fn read_headers<R: BufRead>(reader: &mut R) -> io::Result<Vec<(String, String)>> {
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            return Ok(headers);
+        }
+        let (name, value) = line.split_once(':').ok_or_else(|| invalid(format!("无效的 HTTP 头部: '{}'", line)))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+}
+
+/// 消息体超过上限时的错误（服务端以 413 响应）
+fn too_large(limit: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::FileTooLarge, format!("消息体超过 {} 字节的上限", limit))
+}
+
+/// 从 `reader` 读取恰好 `length` 字节追加到 `body`，总长度不能超过 `limit`
+fn read_exact_into<R: BufRead>(reader: &mut R, body: &mut Vec<u8>, length: usize, limit: usize) -> io::Result<()> {
+    if length > limit - body.len() {
+        return Err(too_large(limit));
+    }
+    if (reader.by_ref().take(length as u64).read_to_end(body)?) < length {
+        return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "消息体不完整"));
+    }
+    Ok(())
+}
+
+/// 按头部读取消息体，长度不超过 `limit`；`until_close` 为 true 时没有长度信息就读到连接关闭
+fn read_body<R: BufRead>(reader: &mut R, headers: &[(String, String)], until_close: bool, limit: usize) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    if find_header(headers, "Transfer-Encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+        loop {
+            let size_line = read_line(reader)?;
+            let size_hex = size_line.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size_hex, 16).map_err(|_| invalid(format!("无效的分块长度: '{}'", size_line)))?;
+            if size == 0 {
+                // 跳过分块尾部的头部
+                read_headers(reader)?;
+                return Ok(body);
+            }
+            read_exact_into(reader, &mut body, size, limit)?;
+            read_line(reader)?;
+        }
+    }
+    match find_header(headers, "Content-Length") {
+        Some(length) => {
+            let length: usize = length.parse().map_err(|_| invalid(format!("无效的 Content-Length: '{}'", length)))?;
+            read_exact_into(reader, &mut body, length, limit)?;
+        }
+        None if until_close => {
+            let read = reader.by_ref().take((limit as u64).saturating_add(1)).read_to_end(&mut body)?;
+            if read > limit {
+                return Err(too_large(limit));
+            }
+        }
+        None => {}
+    }
+    Ok(body)
+}
+
+/// 服务端：读取一个请求，请求体超过 [`MAX_REQUEST_BODY`] 时返回 `FileTooLarge` 错误
+pub fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Request> {
+    let line = read_line(reader)?;
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(invalid(format!("无效的请求行: '{}'", line)));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let headers = read_headers(reader)?;
+    let body = read_body(reader, &headers, false, MAX_REQUEST_BODY)?;
+    Ok(Request { method: method.to_string(), path: path.to_string(), query: query.to_string(), headers, body })
+}
+
+/// 服务端：写出一个响应
+pub fn write_response<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+    write!(writer, "HTTP/1.1 {} {}\r\n", response.status, reason_phrase(response.status))?;
+    for (name, value) in &response.headers {
+        write!(writer, "{}: {}\r\n", name, value)?;
+    }
+    write!(writer, "Content-Length: {}\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n", response.body.len())?;
+    writer.write_all(&response.body)?;
+    writer.flush()
+}
+
+/// 拆分 `http://host[:port]/path` 形式的地址，返回 (host:port, path)
+pub fn split_url(url: &str) -> io::Result<(String, String)> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, format!("不支持的地址 '{}'（只支持 http://）", url)))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let authority = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+    Ok((authority, path.to_string()))
+}
+
+/// 客户端：发送请求并读取响应
+pub fn send_request(url: &str, method: &str, headers: &[(&str, &str)], body: &[u8]) -> io::Result<Response> {
+    let (authority, path) = split_url(url)?;
+    let mut stream = TcpStream::connect(&authority)?;
+
+    let mut request = format!("{} {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: {}\r\nConnection: close\r\n", method, path, authority, crate::core::protocol::AGENT);
+    for (name, value) in headers {
+        request.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    if method == "POST" {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    let mut reader = BufReader::new(stream);
+    let status_line = read_line(&mut reader)?;
+    let status = status_line
+        .split(' ')
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| invalid(format!("无效的状态行: '{}'", status_line)))?;
+    let headers = read_headers(&mut reader)?;
+    let body = read_body(&mut reader, &headers, true, usize::MAX)?;
+    Ok(Response { status, headers, body })
+}
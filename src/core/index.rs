@@ -1,9 +1,22 @@
-use std::fs::{self, File};
-use std::io::{self, Write};
+use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use crate::core::blob::write_blob;
-use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::config::{GIT_DIR, GIT_DIR_NAME, IS_VERBOSE};
+use crate::core::sparse::{load_sparse, SparseCone};
+use crate::utils::fs::LockFile;
+
+/// 仓库的 index 文件
+///
+/// 与 git 的 `GIT_INDEX_FILE` 一样，当前仓库可以用 `RUST_GIT_INDEX_FILE` 环境变量改用其他文件：
+/// commit 借此让钩子看到将要提交的 index，而不必先改写真正的 index。
+pub fn index_file(repo_path: &Path) -> PathBuf {
+    match std::env::var_os("RUST_GIT_INDEX_FILE") {
+        Some(file) if !file.is_empty() && repo_path == GIT_DIR.as_path() => PathBuf::from(file),
+        _ => repo_path.join("index"),
+    }
+}
 /// 将路径标准化为统一格式（相对路径 + / 分隔符）
 /// 将路径标准化为统一格式（相对路径 + / 分隔符）
 pub fn normalize_path(path: &Path) -> io::Result<String> {
@@ -13,13 +26,25 @@ pub fn normalize_path(path: &Path) -> io::Result<String> {
     Ok(rel.to_string_lossy().replace('\\', "/"))
 }
 
+/// 解析 index 中的一行，返回 (hash, 路径, 是否带 skip-worktree 标记)
+///
+/// 每行为 `<hash> <路径>`，稀疏检出范围之外的条目以 `S ` 开头。
+fn parse_index_line(line: &str) -> Option<(&str, &str, bool)> {
+    let (skip, line) = match line.strip_prefix("S ") {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (hash, path) = line.split_once(' ')?;
+    Some((hash, path, skip))
+}
+
 /// 读取 index 内容为 map（path -> hash）
 fn load_index(index_path: &Path) -> BTreeMap<String, String> {
     let mut map = BTreeMap::new();
 
     if let Ok(content) = fs::read_to_string(index_path) {
         for line in content.lines() {
-            if let Some((hash, path)) = line.trim().split_once(' ') {
+            if let Some((hash, path, _)) = parse_index_line(line.trim()) {
                 map.insert(path.to_string(), hash.to_string());
             }
         }
@@ -28,13 +53,48 @@ fn load_index(index_path: &Path) -> BTreeMap<String, String> {
     map
 }
 
-/// 保存 index（path -> hash）为 index 文件
-fn save_index(index_path: &Path, map: &BTreeMap<String, String>) -> io::Result<()> {
-    let mut file = File::create(index_path)?;
+/// 把 index（path -> hash）写入已持有的锁文件并提交
+///
+/// 调用方应在读取 index 之前加锁，使“读取—修改—写回”整个过程不被其他进程打断。
+/// 启用稀疏检出时，范围之外且工作区中不存在的条目写入 skip-worktree 标记。
+fn save_index(mut lock: LockFile, index_path: &Path, map: &BTreeMap<String, String>) -> io::Result<()> {
+    let cone: Option<SparseCone> = index_path.parent().map(load_sparse).transpose()?.flatten();
+    let mut content = String::new();
     for (path, hash) in map {
-        writeln!(file, "{} {}", hash, path)?;
+        let skip = cone.as_ref().is_some_and(|cone| !cone.includes(path) && fs::symlink_metadata(path).is_err());
+        let flag = if skip { "S " } else { "" };
+        content.push_str(&format!("{}{} {}\n", flag, hash, path));
     }
-    Ok(())
+    lock.write_all(content.as_bytes())?;
+    lock.commit()
+}
+
+/// 读取 index 为映射（路径 -> hash），index 不存在时为空
+pub fn read_index_map(index_path: &Path) -> HashMap<String, String> {
+    load_index(index_path).into_iter().collect()
+}
+
+/// index 中带 skip-worktree 标记的路径（稀疏检出范围之外、不在工作区中的文件）
+pub fn read_skip_worktree(index_path: &Path) -> HashSet<String> {
+    fs::read_to_string(index_path)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| parse_index_line(line.trim()))
+        .filter(|(_, _, skip)| *skip)
+        .map(|(_, path, _)| path.to_string())
+        .collect()
+}
+
+/// 用给定的映射（路径 -> hash）整体替换 index
+pub fn write_index_map(index_path: &Path, entries: &HashMap<String, String>) -> io::Result<()> {
+    let lock = LockFile::acquire(index_path)?;
+    commit_index_map(lock, index_path, entries)
+}
+
+/// 用给定的映射整体替换 index，`lock` 是调用方在读取 index 之前取得的锁
+pub fn commit_index_map(lock: LockFile, index_path: &Path, entries: &HashMap<String, String>) -> io::Result<()> {
+    let map: BTreeMap<String, String> = entries.iter().map(|(p, h)| (p.clone(), h.clone())).collect();
+    save_index(lock, index_path, &map)
 }
 
 /// 添加单个文件（更新 blob、替换 index 条目）
@@ -48,13 +108,13 @@ fn add_single_file(path: &Path, index: &mut BTreeMap<String, String>) -> io::Res
     Ok(())
 }
 
-/// 遍历目录递归添加
-fn add_dir_recursive(dir: &Path, index: &mut BTreeMap<String, String>, exe: &Option<PathBuf>) -> io::Result<()> {
+/// 遍历目录递归添加，跳过稀疏检出范围之外的文件
+fn add_dir_recursive(dir: &Path, index: &mut BTreeMap<String, String>, exe: &Option<PathBuf>, cone: Option<&SparseCone>) -> io::Result<()> {
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
         let path = entry.path();
 
-        if path.is_dir() && path.file_name().map_or(false, |n| n == *GIT_DIR) {
+        if path.file_name().is_some_and(|n| n == GIT_DIR_NAME.as_str()) {
             continue;
         }
 
@@ -65,9 +125,11 @@ fn add_dir_recursive(dir: &Path, index: &mut BTreeMap<String, String>, exe: &Opt
         }
 
         if path.is_file() {
-            add_single_file(&path, index)?;
+            if cone.is_none_or(|cone| cone.includes(&normalize_path(&path).unwrap_or_default())) {
+                add_single_file(&path, index)?;
+            }
         } else if path.is_dir() {
-            add_dir_recursive(&path, index, exe)?;
+            add_dir_recursive(&path, index, exe, cone)?;
         }
     }
     Ok(())
@@ -76,10 +138,12 @@ fn add_dir_recursive(dir: &Path, index: &mut BTreeMap<String, String>, exe: &Opt
 
 // 公共接口：添加路径（文件或目录）到 index
 pub fn add_to_index(path: &Path) -> io::Result<()> {
-    let index_path = &*GIT_DIR.join("index");
-    let mut index = load_index(&index_path);
+    let index_path = &index_file(&GIT_DIR);
+    let lock = LockFile::acquire(index_path)?;
+    let mut index = load_index(index_path);
 
     let exe = std::env::current_exe().ok();
+    let cone = load_sparse(&GIT_DIR)?;
 
     if path.is_file() {
         if let Some(ref exe_path) = exe {
@@ -87,14 +151,18 @@ pub fn add_to_index(path: &Path) -> io::Result<()> {
                 return Ok(()); // 跳过可执行文件
             }
         }
+        let rel_path = normalize_path(path)?;
+        if cone.as_ref().is_some_and(|cone| !cone.includes(&rel_path)) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' 在稀疏检出范围之外", rel_path)));
+        }
         add_single_file(path, &mut index)?;
     } else if path.is_dir() {
-        add_dir_recursive(path, &mut index, &exe)?;
+        add_dir_recursive(path, &mut index, &exe, cone.as_ref())?;
     } else {
         return Err(io::Error::new(io::ErrorKind::InvalidInput, "路径不存在"));
     }
 
-    save_index(&index_path, &index)
+    save_index(lock, index_path, &index)
 }
 
 
@@ -104,7 +172,7 @@ pub fn read_index(index_path: &Path) -> io::Result<Vec<(String, String)>> {
     let mut entries = Vec::new();
 
     for line in content.lines() {
-        if let Some((hash, path)) = line.split_once(' ') {
+        if let Some((hash, path, _)) = parse_index_line(line) {
             entries.push((hash.to_string(), path.to_string()));
         }
     }
@@ -120,7 +188,7 @@ pub fn read_index(index_path: &Path) -> io::Result<Vec<(String, String)>> {
 pub fn remove_from_index(path: &Path) -> io::Result<Option<String>> {
     // println!("🔥 remove_from_index 正在运行");
 
-    let index_path = &*GIT_DIR.join("index");
+    let index_path = &index_file(&GIT_DIR);
 
     if !index_path.exists() {
         if *IS_VERBOSE {
@@ -129,7 +197,8 @@ pub fn remove_from_index(path: &Path) -> io::Result<Option<String>> {
         return Ok(None);
     }
 
-    let content = fs::read_to_string(&index_path)?;
+    let mut lock = LockFile::acquire(index_path)?;
+    let content = fs::read_to_string(index_path)?;
     // println!("📄 index 原始内容:\n{}", content);
 
     let mut new_lines = Vec::new();
@@ -139,7 +208,7 @@ pub fn remove_from_index(path: &Path) -> io::Result<Option<String>> {
     // println!("🎯 标准化目标路径: {}", target_path);
 
     for line in content.lines() {
-        if let Some((hash, entry_path)) = line.split_once(' ') {
+        if let Some((hash, entry_path, _)) = parse_index_line(line) {
             if entry_path == target_path {
                 if *IS_VERBOSE {
                     println!("✅ 从 index 中移除: {}", entry_path);
@@ -158,38 +227,35 @@ pub fn remove_from_index(path: &Path) -> io::Result<Option<String>> {
         }
         new_lines.push(line.to_string());
     }
-    if let Some(parent) = index_path.parent() {
-        fs::create_dir_all(parent)?;
-    }
     // println!("📄 最终写入 index 内容:\n{}", new_lines.join("\n"));
 
-    fs::write(&index_path, new_lines.join("\n"))?;
+    lock.write_all(new_lines.join("\n").as_bytes())?;
+    lock.commit()?;
 
-    if removed_hash.is_none() {
-        if *IS_VERBOSE {
-            println!("⚠️ 未能匹配并移除 index 条目: {}", target_path);
-        }
+    if removed_hash.is_none() && *IS_VERBOSE {
+        println!("⚠️ 未能匹配并移除 index 条目: {}", target_path);
     }
 
     Ok(removed_hash)
 }
 
-pub fn remove_directory_entries_from_index(dir_path: &Path) {
-    let index_path = &*GIT_DIR.join("index");
+pub fn remove_directory_entries_from_index(dir_path: &Path) -> io::Result<()> {
+    let index_path = &index_file(&GIT_DIR);
 
     if !index_path.exists() {
         if *IS_VERBOSE {
             println!("⚠️ index 文件不存在");
         }
-        return;
+        return Ok(());
     }
 
-    let content = fs::read_to_string(&index_path).unwrap_or_default();
+    let mut lock = LockFile::acquire(index_path)?;
+    let content = fs::read_to_string(index_path).unwrap_or_default();
     let target_dir = normalize_path(dir_path).unwrap_or_default();
 
     let mut new_lines = Vec::new();
     for line in content.lines() {
-        if let Some((_, entry_path)) = line.split_once(' ') {
+        if let Some((_, entry_path, _)) = parse_index_line(line) {
             if !entry_path.starts_with(&target_dir) {
                 new_lines.push(line.to_string());
             } else {
@@ -200,5 +266,6 @@ pub fn remove_directory_entries_from_index(dir_path: &Path) {
         }
     }
 
-    fs::write(&index_path, new_lines.join("\n")).unwrap();
+    lock.write_all(new_lines.join("\n").as_bytes())?;
+    lock.commit()
 }
\ No newline at end of file
@@ -0,0 +1,149 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use crate::core::config::IS_VERBOSE;
+use crate::core::diff::{diff_maps, renames_of, RenameOptions};
+use crate::core::tree::{load_blob, merge_tree_simple};
+
+/// 合并冲突
+#[derive(Debug, Clone)]
+pub enum Conflict {
+    /// 双方都修改了同一文件，附带冲突的行区间（从 1 开始，闭区间）
+    Content { path: String, ranges: Vec<(usize, usize)> },
+    /// 双方把同一文件重命名成了不同的名字
+    RenameRename { source: String, ours: String, theirs: String },
+}
+
+/// 三方合并的结果
+#[derive(Debug)]
+pub struct MergeOutcome {
+    pub tree: HashMap<String, String>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// 基于共同祖先的三方 tree 合并（支持重命名跟踪）
+///
+/// 一方重命名了文件而另一方在原路径上修改时，修改会跟随到新路径上，
+/// 而不是变成 "删除 + 新增" 两个互不相干的变更。
+pub fn merge_trees(
+    base: &HashMap<String, String>,
+    ours: &HashMap<String, String>,
+    theirs: &HashMap<String, String>,
+    repo_path: &Path,
+) -> MergeOutcome {
+    let (base, ours, theirs, mut conflicts) = align_renames(base, ours, theirs, repo_path);
+
+    let all_files: HashSet<_> = ours.keys().chain(theirs.keys()).collect();
+    let mut files: Vec<_> = all_files.into_iter().collect();
+    files.sort();
+
+    for file in files {
+        let base_hash = base.get(file);
+        let cur = ours.get(file);
+        let tgt = theirs.get(file);
+
+        if cur != tgt && base_hash != cur && base_hash != tgt {
+            let cur_lines = cur
+                .and_then(|h| load_blob(h, repo_path).ok())
+                .unwrap_or_default();
+            let tgt_lines = tgt
+                .and_then(|h| load_blob(h, repo_path).ok())
+                .unwrap_or_default();
+
+            let ranges = conflict_ranges(&cur_lines, &tgt_lines);
+            if !ranges.is_empty() {
+                conflicts.push(Conflict::Content { path: file.clone(), ranges });
+            }
+        }
+    }
+
+    let tree = merge_tree_simple(&base, &ours, &theirs);
+    MergeOutcome { tree, conflicts }
+}
+
+/// 逐行比较两个版本，返回连续不同的行区间
+pub fn conflict_ranges(cur_lines: &[String], tgt_lines: &[String]) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let max_lines = cur_lines.len().max(tgt_lines.len());
+    let mut i = 0;
+    while i < max_lines {
+        if cur_lines.get(i) != tgt_lines.get(i) {
+            let start = i + 1;
+            let mut end = start;
+            i += 1;
+            while i < max_lines && cur_lines.get(i) != tgt_lines.get(i) {
+                end = i + 1;
+                i += 1;
+            }
+            ranges.push((start, end));
+        } else {
+            i += 1;
+        }
+    }
+    ranges
+}
+
+type TreeMap = HashMap<String, String>;
+
+/// 把一方的重命名"搬运"到另一方和 base 上，使三方使用同一套路径
+fn align_renames(
+    base: &TreeMap,
+    ours: &TreeMap,
+    theirs: &TreeMap,
+    repo_path: &Path,
+) -> (TreeMap, TreeMap, TreeMap, Vec<Conflict>) {
+    let mut base = base.clone();
+    let mut ours = ours.clone();
+    let mut theirs = theirs.clone();
+    let mut conflicts = Vec::new();
+
+    let opts = RenameOptions { copies: false, ..RenameOptions::default() };
+    if !opts.renames {
+        return (base, ours, theirs, conflicts);
+    }
+
+    let ours_renames = renames_of(&diff_maps(&base, &ours, repo_path, opts));
+    let theirs_renames = renames_of(&diff_maps(&base, &theirs, repo_path, opts));
+    let ours_by_src: HashMap<_, _> = ours_renames.iter().cloned().collect();
+    let theirs_by_src: HashMap<_, _> = theirs_renames.iter().cloned().collect();
+
+    for (from, to) in &ours_renames {
+        match theirs_by_src.get(from) {
+            Some(their_to) if their_to == to => {
+                // 双方做了同样的重命名，base 也跟着改名，内容按常规三方合并
+                if let Some(hash) = base.remove(from) {
+                    base.insert(to.clone(), hash);
+                }
+            }
+            Some(their_to) => conflicts.push(Conflict::RenameRename {
+                source: from.clone(),
+                ours: to.clone(),
+                theirs: their_to.clone(),
+            }),
+            None => move_path(&mut base, &mut theirs, from, to),
+        }
+    }
+
+    for (from, to) in &theirs_renames {
+        if !ours_by_src.contains_key(from) {
+            move_path(&mut base, &mut ours, from, to);
+        }
+    }
+
+    (base, ours, theirs, conflicts)
+}
+
+/// 在 base 与另一方中把 `from` 改名为 `to`（另一方已占用 `to` 时保持原样，交给常规冲突检测）
+fn move_path(base: &mut TreeMap, other: &mut TreeMap, from: &str, to: &str) {
+    if !other.contains_key(from) || other.contains_key(to) {
+        return;
+    }
+    if let Some(hash) = base.remove(from) {
+        base.insert(to.to_string(), hash);
+    }
+    if let Some(hash) = other.remove(from) {
+        other.insert(to.to_string(), hash);
+    }
+    if *IS_VERBOSE {
+        println!("🔀 跟随重命名 {} -> {}", from, to);
+    }
+}
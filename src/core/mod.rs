@@ -4,4 +4,23 @@ pub mod blob;
 pub mod commit;
 pub mod tree;
 pub mod reference;
-pub mod config;
\ No newline at end of file
+pub mod config;
+pub mod diff;
+pub mod merge;
+pub mod sequencer;
+pub mod object;
+pub mod object_map;
+pub mod repo_config;
+pub mod remote;
+pub mod pktline;
+pub mod pack;
+pub mod protocol;
+pub mod serve;
+pub mod transport;
+pub mod http;
+pub mod hooks;
+pub mod refs;
+pub mod fsck;
+pub mod gc;
+pub mod worktree;
+pub mod sparse;
\ No newline at end of file
@@ -0,0 +1,167 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::commit::{collect_ancestors, read_commit};
+use crate::core::repository::common_dir;
+use crate::core::tree::read_tree_entries;
+use crate::utils::fs::write_atomic;
+use crate::utils::hash::sha1_hash;
+
+/// 对象库目录，所有工作区共享
+pub fn objects_dir(repo_path: &Path) -> PathBuf {
+    common_dir(repo_path).join("objects")
+}
+
+/// 是否是合法的对象 id（40 位小写十六进制）
+pub fn is_object_id(s: &str) -> bool {
+    s.len() == 40 && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// 对象在对象库中的路径（`objects/xx/yyyy...`）
+///
+/// 只接受合法的对象 id：tree 等对象中的 hash 来自外部，不能让它把路径带出对象库。
+pub fn object_path(hash: &str, repo_path: &Path) -> io::Result<PathBuf> {
+    if !is_object_id(hash) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("无效的对象 hash: '{}'", hash)));
+    }
+    let (dir, file) = hash.split_at(2);
+    Ok(objects_dir(repo_path).join(dir).join(file))
+}
+
+/// 对象是否存在
+pub fn object_exists(hash: &str, repo_path: &Path) -> bool {
+    object_path(hash, repo_path).is_ok_and(|path| path.is_file())
+}
+
+/// 读取对象的原始内容
+pub fn read_object(hash: &str, repo_path: &Path) -> io::Result<Vec<u8>> {
+    fs::read(object_path(hash, repo_path)?)
+}
+
+/// 写入对象，返回其 hash（已存在时不重复写入）
+///
+/// 先写入临时文件再改名，中途崩溃也不会留下只写了一半的对象。
+pub fn write_object(content: &[u8], repo_path: &Path) -> io::Result<String> {
+    let hash = sha1_hash(content);
+    let path = object_path(&hash, repo_path)?;
+    if !path.exists() {
+        write_atomic(&path, content)?;
+    }
+    Ok(hash)
+}
+
+/// 对象类型（对象文件本身不记录类型，由引用它的位置决定）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    Commit,
+    Tree,
+    Blob,
+}
+
+impl ObjectKind {
+    /// git 中的类型名
+    pub fn name(self) -> &'static str {
+        match self {
+            ObjectKind::Commit => "commit",
+            ObjectKind::Tree => "tree",
+            ObjectKind::Blob => "blob",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ObjectKind> {
+        match name {
+            "commit" => Some(ObjectKind::Commit),
+            "tree" => Some(ObjectKind::Tree),
+            "blob" => Some(ObjectKind::Blob),
+            _ => None,
+        }
+    }
+}
+
+/// 根据内容推断对象类型
+///
+/// 以 `tree <hash>` 开头、头部含有 `committer` 的是提交；每一行都是 `blob <hash> <路径>` 的是 tree；其余都是 blob。
+/// 空内容既可能是空 tree 也可能是空 blob，按 blob 处理。
+pub fn detect_kind(content: &[u8]) -> ObjectKind {
+    let Ok(text) = std::str::from_utf8(content) else {
+        return ObjectKind::Blob;
+    };
+    let header = text.split_once("\n\n").map_or(text, |(h, _)| h);
+    let is_commit = header.lines().next().and_then(|l| l.strip_prefix("tree ")).is_some_and(is_object_id)
+        && header.lines().any(|l| l.starts_with("committer "));
+    if is_commit {
+        return ObjectKind::Commit;
+    }
+    let is_tree = !text.is_empty()
+        && text.ends_with('\n')
+        && text.lines().all(|line| {
+            line.strip_prefix("blob ")
+                .and_then(|rest| rest.split_once(' '))
+                .is_some_and(|(hash, path)| is_object_id(hash) && !path.is_empty())
+        });
+    if is_tree {
+        ObjectKind::Tree
+    } else {
+        ObjectKind::Blob
+    }
+}
+
+/// 列出从 `tips` 可达的所有对象（提交、tree、blob）及其类型
+///
+/// `known` 返回 true 的提交视为对方已拥有，不再继续向下遍历其历史。
+pub fn reachable_objects<F>(tips: &[String], known: F, repo_path: &Path) -> io::Result<Vec<(ObjectKind, String)>>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut result = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack: Vec<String> = tips.to_vec();
+
+    while let Some(hash) = stack.pop() {
+        if hash.is_empty() || known(&hash) || !seen.insert(hash.clone()) {
+            continue;
+        }
+        let commit = read_commit(&hash, repo_path)?;
+        result.push((ObjectKind::Commit, hash));
+        if seen.insert(commit.tree.clone()) {
+            for blob in read_tree_entries(&commit.tree, repo_path)?.into_values() {
+                if seen.insert(blob.clone()) {
+                    result.push((ObjectKind::Blob, blob));
+                }
+            }
+            result.push((ObjectKind::Tree, commit.tree));
+        }
+        stack.extend(commit.parents);
+    }
+
+    Ok(result)
+}
+
+/// 计算对方缺少的对象：`tips` 可达、但不在 `haves`（对方已有的提交）历史中的对象
+///
+/// 只有本仓库中存在的 `haves` 才会被采信。
+pub fn objects_to_send(tips: &[String], haves: &[String], repo_path: &Path) -> io::Result<Vec<(ObjectKind, String)>> {
+    let mut common = HashSet::new();
+    for have in haves.iter().filter(|h| object_exists(h, repo_path)) {
+        if !common.contains(have) {
+            common.extend(collect_ancestors(have, repo_path));
+        }
+    }
+    reachable_objects(tips, |hash| common.contains(hash), repo_path)
+}
+
+/// 把 `tips` 可达、而目标仓库中还没有的对象复制过去，返回复制的对象数
+pub fn copy_missing_objects(tips: &[String], from: &Path, to: &Path) -> io::Result<usize> {
+    let objects = reachable_objects(tips, |hash| object_exists(hash, to), from)?;
+    let mut copied = 0;
+    // 遍历时已经读取并检查过每个 tree，含有不安全路径的 tree 在这之前就会报错
+    // 倒序写入：先写 blob、tree 与父提交，中途失败也不会留下缺少内容的提交
+    for (_, hash) in objects.into_iter().rev() {
+        if !object_exists(&hash, to) {
+            write_object(&read_object(&hash, from)?, to)?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
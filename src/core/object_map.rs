@@ -0,0 +1,391 @@
+//! rust-git 对象与 git 对象之间的转换
+//!
+//! rust-git 的对象 id 是内容本身的 SHA-1，tree 是扁平的 `blob <hash> <路径>` 列表；git 的对象 id 还包含
+//! `<类型> <长度>\0` 头，tree 按目录分层并以二进制编码。与其他仓库交换对象（pack 协议、packfile）时
+//! 一律使用 git 的格式与 id，在这里与本地对象互相转换：
+//!
+//! - blob 的内容相同
+//! - 本地 tree 中的文件都是普通文件（模式 `100644`），目录拆为子 tree（模式 `40000`）
+//! - 提交只替换 `tree` 与 `parent` 行中的 id，其余内容原样保留
+//!
+//! 导入的 git 对象转为本地对象后必须能原样转回，否则两边的 id 对不上，这样的对象拒绝导入
+//! （例如含有可执行文件、符号链接或子模块的 tree）。已知的对应关系缓存在 `objects/info/git-map` 中。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::commit::read_commit;
+use crate::core::fsck::parse_commit;
+use crate::core::object::{is_object_id, object_exists, objects_dir, objects_to_send, read_object, write_object, ObjectKind};
+use crate::core::pack::PackObjects;
+use crate::core::tree::{hash_tree_from_map, read_tree_entries, write_tree_from_map};
+use crate::utils::fs::LockFile;
+use crate::utils::hash::sha1_hash;
+
+/// git 对象的 id：`<类型> <长度>\0` 头加上内容的 SHA-1
+pub fn git_object_id(kind: &str, content: &[u8]) -> String {
+    let mut data = format!("{} {}\0", kind, content.len()).into_bytes();
+    data.extend_from_slice(content);
+    sha1_hash(&data)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn hex_to_bytes(hex: &str) -> Vec<u8> {
+    (0..hex.len()).step_by(2).filter_map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+}
+
+fn bytes_to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 替换提交头部 `tree` 与 `parent` 行中的 id，其余内容原样保留
+fn rewrite_commit<F>(content: &[u8], mut map: F) -> io::Result<Vec<u8>>
+where
+    F: FnMut(&str, &str) -> io::Result<String>,
+{
+    let end = content.windows(2).position(|w| w == b"\n\n").ok_or_else(|| invalid("提交缺少说明前的空行".to_string()))?;
+    let header = std::str::from_utf8(&content[..end]).map_err(|_| invalid("提交头部不是 UTF-8 文本".to_string()))?;
+    let mut result = Vec::new();
+    for (i, line) in header.split('\n').enumerate() {
+        if i > 0 {
+            result.push(b'\n');
+        }
+        match line.split_once(' ') {
+            Some((field @ ("tree" | "parent"), id)) => result.extend_from_slice(format!("{} {}", field, map(field, id)?).as_bytes()),
+            _ => result.extend_from_slice(line.as_bytes()),
+        }
+    }
+    result.extend_from_slice(&content[end..]);
+    Ok(result)
+}
+
+/// git 格式的 tree：(git id, 内容)
+type GitTree = (String, Vec<u8>);
+
+/// 本地对象与 git 对象的对应关系
+pub struct ObjectMap {
+    repo_path: PathBuf,
+    path: PathBuf,
+    /// (类型, 本地 id) -> git id；tree 只记录整个 tree（根目录）
+    git_ids: HashMap<(ObjectKind, String), String>,
+    /// git id -> (类型, 本地 id, 目录)：git 的子目录 tree 对应本地 tree 中某个目录下的部分
+    local_ids: HashMap<String, (ObjectKind, String, String)>,
+    /// 还没有写入缓存文件的条目
+    added: Vec<String>,
+}
+
+impl ObjectMap {
+    /// 读取仓库中缓存的对应关系
+    pub fn load(repo_path: &Path) -> io::Result<ObjectMap> {
+        let mut map = ObjectMap {
+            repo_path: repo_path.to_path_buf(),
+            path: objects_dir(repo_path).join("info").join("git-map"),
+            git_ids: HashMap::new(),
+            local_ids: HashMap::new(),
+            added: Vec::new(),
+        };
+        let content = match fs::read_to_string(&map.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        // 每行 `<类型> <本地 id> <git id>[ <目录>]`；无法解析的行忽略，需要时重新计算
+        for line in content.lines() {
+            let mut parts = line.splitn(4, ' ');
+            let (Some(kind), Some(local), Some(git)) = (parts.next().and_then(ObjectKind::from_name), parts.next(), parts.next()) else {
+                continue;
+            };
+            if is_object_id(local) && is_object_id(git) {
+                map.insert(kind, local, git, parts.next().unwrap_or(""));
+            }
+        }
+        Ok(map)
+    }
+
+    fn insert(&mut self, kind: ObjectKind, local: &str, git: &str, dir: &str) -> bool {
+        let mut added = false;
+        if dir.is_empty() {
+            added |= self.git_ids.insert((kind, local.to_string()), git.to_string()).is_none();
+        }
+        if !self.local_ids.contains_key(git) {
+            self.local_ids.insert(git.to_string(), (kind, local.to_string(), dir.to_string()));
+            added = true;
+        }
+        added
+    }
+
+    fn record(&mut self, kind: ObjectKind, local: &str, git: &str, dir: &str) {
+        if self.insert(kind, local, git, dir) {
+            let line = if dir.is_empty() {
+                format!("{} {} {}", kind.name(), local, git)
+            } else {
+                format!("{} {} {} {}", kind.name(), local, git, dir)
+            };
+            self.added.push(line);
+        }
+    }
+
+    /// 把新的对应关系追加到缓存文件
+    pub fn save(&mut self) -> io::Result<()> {
+        if self.added.is_empty() {
+            return Ok(());
+        }
+        let mut lock = LockFile::acquire(&self.path)?;
+        // 在锁内重新读取，保留其他进程在此期间写入的条目
+        let mut content = match fs::read(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        for line in self.added.drain(..) {
+            content.extend_from_slice(line.as_bytes());
+            content.push(b'\n');
+        }
+        lock.write_all(&content)?;
+        lock.commit()
+    }
+
+    /// git 提交 id 对应的本地提交，本地还没有时为 None
+    pub fn local_commit(&self, git: &str) -> Option<String> {
+        match self.local_ids.get(git) {
+            Some((ObjectKind::Commit, local, _)) if object_exists(local, &self.repo_path) => Some(local.clone()),
+            _ => None,
+        }
+    }
+
+    fn cached(&self, kind: ObjectKind, local: &str) -> Option<String> {
+        self.git_ids.get(&(kind, local.to_string())).cloned()
+    }
+
+    fn git_blob(&mut self, local: &str) -> io::Result<String> {
+        if let Some(git) = self.cached(ObjectKind::Blob, local) {
+            return Ok(git);
+        }
+        let git = git_object_id("blob", &read_object(local, &self.repo_path)?);
+        self.record(ObjectKind::Blob, local, &git, "");
+        Ok(git)
+    }
+
+    /// 把本地 tree 转为 git 的多层 tree，返回根目录的 git id 与所有 tree 的 (git id, 内容)
+    fn git_trees(&mut self, local: &str) -> io::Result<(String, Vec<GitTree>)> {
+        let entries = read_tree_entries(local, &self.repo_path)?.into_iter().collect();
+        self.build_trees(local, entries)
+    }
+
+    fn build_trees(&mut self, local: &str, entries: Vec<(String, String)>) -> io::Result<(String, Vec<GitTree>)> {
+        let mut trees = Vec::new();
+        let root = self.build_tree(local, "", entries, &mut trees)?;
+        self.record(ObjectKind::Tree, local, &root, "");
+        Ok((root, trees))
+    }
+
+    fn build_tree(&mut self, local: &str, dir: &str, entries: Vec<(String, String)>, trees: &mut Vec<GitTree>) -> io::Result<String> {
+        // (排序键, 模式, 名字, git id)：git 排序时把目录名看作以 `/` 结尾
+        let mut items = Vec::new();
+        let mut dirs: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        for (path, blob) in entries {
+            match path.split_once('/') {
+                Some((name, rest)) => dirs.entry(name.to_string()).or_default().push((rest.to_string(), blob)),
+                None => items.push((path.clone(), "100644", path, self.git_blob(&blob)?)),
+            }
+        }
+        for (name, sub) in dirs {
+            let path = if dir.is_empty() { name.clone() } else { format!("{}/{}", dir, name) };
+            let git = self.build_tree(local, &path, sub, trees)?;
+            items.push((format!("{}/", name), "40000", name, git));
+        }
+        items.sort();
+
+        let mut content = Vec::new();
+        for (_, mode, name, git) in items {
+            content.extend_from_slice(format!("{} {}\0", mode, name).as_bytes());
+            content.extend_from_slice(&hex_to_bytes(&git));
+        }
+        let git = git_object_id("tree", &content);
+        if !dir.is_empty() {
+            self.record(ObjectKind::Tree, local, &git, dir);
+        }
+        trees.push((git.clone(), content));
+        Ok(git)
+    }
+
+    fn git_tree(&mut self, local: &str) -> io::Result<String> {
+        match self.cached(ObjectKind::Tree, local) {
+            Some(git) => Ok(git),
+            None => Ok(self.git_trees(local)?.0),
+        }
+    }
+
+    /// 本地提交内容对应的 git 提交内容；父提交必须已经转换过
+    fn git_commit_content(&mut self, content: &[u8]) -> io::Result<Vec<u8>> {
+        rewrite_commit(content, |field, id| match field {
+            "tree" => self.git_tree(id),
+            _ => self.cached(ObjectKind::Commit, id).ok_or_else(|| invalid(format!("父提交 {} 还没有转换", id))),
+        })
+    }
+
+    /// 本地提交对应的 git 提交 id；需要时连同它的历史一起转换
+    pub fn git_commit(&mut self, local: &str) -> io::Result<String> {
+        let mut stack = vec![local.to_string()];
+        while let Some(hash) = stack.last().cloned() {
+            if self.cached(ObjectKind::Commit, &hash).is_some() {
+                stack.pop();
+                continue;
+            }
+            // git 提交的内容中含有父提交的 git id，先转换父提交
+            let parents = read_commit(&hash, &self.repo_path)?.parents;
+            let pending: Vec<String> = parents.into_iter().filter(|p| self.cached(ObjectKind::Commit, p).is_none()).collect();
+            if !pending.is_empty() {
+                stack.extend(pending);
+                continue;
+            }
+            let content = self.git_commit_content(&read_object(&hash, &self.repo_path)?)?;
+            self.record(ObjectKind::Commit, &hash, &git_object_id("commit", &content), "");
+            stack.pop();
+        }
+        self.cached(ObjectKind::Commit, local).ok_or_else(|| invalid(format!("无法转换提交 {}", local)))
+    }
+
+    /// 对方缺少的对象（见 [`objects_to_send`]），转为 git 格式的 (类型, 内容)
+    pub fn git_objects(&mut self, tips: &[String], haves: &[String]) -> io::Result<Vec<(ObjectKind, Vec<u8>)>> {
+        let mut seen = HashSet::new();
+        let mut result = Vec::new();
+        for (kind, hash) in objects_to_send(tips, haves, &self.repo_path)? {
+            match kind {
+                ObjectKind::Commit => {
+                    let git = self.git_commit(&hash)?;
+                    if seen.insert(git) {
+                        result.push((kind, self.git_commit_content(&read_object(&hash, &self.repo_path)?)?));
+                    }
+                }
+                ObjectKind::Tree => {
+                    for (git, content) in self.git_trees(&hash)?.1 {
+                        if seen.insert(git) {
+                            result.push((kind, content));
+                        }
+                    }
+                }
+                ObjectKind::Blob => {
+                    if seen.insert(self.git_blob(&hash)?) {
+                        result.push((kind, read_object(&hash, &self.repo_path)?));
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// 把 packfile 中的 git 对象转为本地对象写入对象库
+    ///
+    /// 先写 blob，再按父提交在前的顺序写入提交及其 tree，中途失败也不会留下缺少内容的提交。
+    /// 对象引用的、packfile 中没有的对象必须是本地已有并且已知对应关系的（对方据 `have` 省去的部分）。
+    pub fn import(&mut self, objects: &PackObjects) -> io::Result<()> {
+        for (git, (kind, content)) in objects {
+            if *kind == ObjectKind::Blob {
+                let local = write_object(content, &self.repo_path)?;
+                self.record(ObjectKind::Blob, &local, git, "");
+            }
+        }
+        let mut commits: Vec<&String> = objects.iter().filter(|(_, (kind, _))| *kind == ObjectKind::Commit).map(|(git, _)| git).collect();
+        commits.sort();
+        for git in commits {
+            self.import_commit(git, objects)?;
+        }
+        Ok(())
+    }
+
+    fn import_commit(&mut self, git: &str, objects: &PackObjects) -> io::Result<()> {
+        let mut stack = vec![git.to_string()];
+        while let Some(id) = stack.last().cloned() {
+            if self.local_commit(&id).is_some() {
+                stack.pop();
+                continue;
+            }
+            let Some((ObjectKind::Commit, content)) = objects.get(&id) else {
+                return Err(invalid(format!("缺少提交 {}", id)));
+            };
+            let (_, parents) = parse_commit(content).map_err(|reason| invalid(format!("提交 {} 无效: {}", id, reason)))?;
+            let pending: Vec<String> = parents.into_iter().filter(|p| self.local_commit(p).is_none()).collect();
+            if !pending.is_empty() {
+                stack.extend(pending);
+                continue;
+            }
+
+            let local_content = rewrite_commit(content, |field, id| match field {
+                "tree" => self.import_tree(id, objects),
+                _ => self.local_commit(id).ok_or_else(|| invalid(format!("缺少提交 {}", id))),
+            })?;
+            if self.git_commit_content(&local_content)? != *content {
+                return Err(invalid(format!("提交 {} 无法在 rust-git 中原样保存", id)));
+            }
+            let local = write_object(&local_content, &self.repo_path)?;
+            self.record(ObjectKind::Commit, &local, &id, "");
+            stack.pop();
+        }
+        Ok(())
+    }
+
+    /// 把 git 的根目录 tree 转为本地 tree，返回本地 id
+    fn import_tree(&mut self, git: &str, objects: &PackObjects) -> io::Result<String> {
+        if let Some((ObjectKind::Tree, local, dir)) = self.local_ids.get(git) {
+            if dir.is_empty() && object_exists(local, &self.repo_path) {
+                return Ok(local.clone());
+            }
+        }
+        let mut entries = HashMap::new();
+        self.flatten_tree(git, "", objects, &mut entries)?;
+        let local = hash_tree_from_map(&entries)?;
+        let (root, _) = self.build_trees(&local, entries.clone().into_iter().collect())?;
+        if root != git {
+            return Err(invalid(format!("tree {} 无法在 rust-git 中原样保存（目录为空或条目顺序不规范）", git)));
+        }
+        write_tree_from_map(&entries, &self.repo_path)
+    }
+
+    /// 把 git tree 展开为扁平的 (路径 -> 本地 blob)，`dir` 是它所在的目录
+    fn flatten_tree(&mut self, git: &str, dir: &str, objects: &PackObjects, entries: &mut HashMap<String, String>) -> io::Result<()> {
+        let join = |name: &str| if dir.is_empty() { name.to_string() } else { format!("{}/{}", dir, name) };
+        let Some((ObjectKind::Tree, content)) = objects.get(git) else {
+            // packfile 中没有的 tree 取自本地 tree 中对应的目录
+            let Some((ObjectKind::Tree, local, local_dir)) = self.local_ids.get(git).cloned() else {
+                return Err(invalid(format!("缺少 tree {}", git)));
+            };
+            for (path, blob) in read_tree_entries(&local, &self.repo_path)? {
+                let rest = if local_dir.is_empty() { Some(path.as_str()) } else { path.strip_prefix(&format!("{}/", local_dir)) };
+                if let Some(rest) = rest {
+                    entries.insert(join(rest), blob);
+                }
+            }
+            return Ok(());
+        };
+
+        // 每个条目：`<模式> <名字>\0<20 字节 id>`
+        let mut rest = content.as_slice();
+        while !rest.is_empty() {
+            let bad_entry = || invalid(format!("tree {} 中有无法解析的条目", git));
+            let space = rest.iter().position(|&b| b == b' ').ok_or_else(bad_entry)?;
+            let nul = rest.iter().position(|&b| b == 0).filter(|&nul| nul > space).ok_or_else(bad_entry)?;
+            let id = rest.get(nul + 1..nul + 21).map(bytes_to_hex).ok_or_else(bad_entry)?;
+            let mode = std::str::from_utf8(&rest[..space]).map_err(|_| bad_entry())?;
+            let name = std::str::from_utf8(&rest[space + 1..nul]).map_err(|_| invalid(format!("tree {} 中的文件名不是 UTF-8", git)))?;
+            let path = join(name);
+            match mode {
+                "100644" => {
+                    let blob = match self.local_ids.get(&id) {
+                        Some((ObjectKind::Blob, local, _)) if object_exists(local, &self.repo_path) => local.clone(),
+                        _ => return Err(invalid(format!("缺少 '{}' 的 blob {}", path, id))),
+                    };
+                    entries.insert(path, blob);
+                }
+                "40000" => self.flatten_tree(&id, &path, objects, entries)?,
+                _ => return Err(invalid(format!("不支持 '{}' 的文件模式 {}，rust-git 只能保存普通文件", path, mode))),
+            }
+            rest = &rest[nul + 21..];
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,303 @@
+//! packfile 的读写
+//!
+//! 格式与 git 的 packfile v2 相同：`PACK`、版本号、对象数，随后是每个对象的类型与长度头
+//! 加 zlib 压缩的内容，最后是前面所有字节的 SHA-1。对象都是 git 格式的，与本地对象的转换见
+//! [`crate::core::object_map`]。
+//!
+//! 写出时不使用 delta；读取时支持 `REF_DELTA` 与 `OFS_DELTA`，但基础对象必须在同一个 packfile 中
+//! （不接受 thin pack）。
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Read, Write};
+use flate2::bufread::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use sha1::{Digest, Sha1};
+use crate::core::object::ObjectKind;
+use crate::core::object_map::git_object_id;
+
+/// packfile 中的对象：git id -> (类型, 内容)；标签对象不在其中
+pub type PackObjects = HashMap<String, (ObjectKind, Vec<u8>)>;
+
+fn type_code(kind: ObjectKind) -> u8 {
+    match kind {
+        ObjectKind::Commit => 1,
+        ObjectKind::Tree => 2,
+        ObjectKind::Blob => 3,
+    }
+}
+
+fn type_name(code: u8) -> &'static str {
+    match code {
+        1 => "commit",
+        2 => "tree",
+        3 => "blob",
+        _ => "tag",
+    }
+}
+
+/// 把 git 格式的对象打包为 packfile
+pub fn write_pack(objects: &[(ObjectKind, Vec<u8>)]) -> io::Result<Vec<u8>> {
+    let mut pack = Vec::new();
+    pack.extend_from_slice(b"PACK");
+    pack.extend_from_slice(&2u32.to_be_bytes());
+    pack.extend_from_slice(&(objects.len() as u32).to_be_bytes());
+
+    for (kind, content) in objects {
+        // 类型与长度头：首字节含 3 位类型与长度低 4 位，之后每字节 7 位，最高位表示后面还有
+        let mut size = content.len();
+        let mut byte = (type_code(*kind) << 4) | (size & 0x0f) as u8;
+        size >>= 4;
+        while size > 0 {
+            pack.push(byte | 0x80);
+            byte = (size & 0x7f) as u8;
+            size >>= 7;
+        }
+        pack.push(byte);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content)?;
+        pack.extend_from_slice(&encoder.finish()?);
+    }
+
+    let checksum = Sha1::digest(&pack);
+    pack.extend_from_slice(&checksum);
+    Ok(pack)
+}
+
+/// 在读取时顺便计算已读数据的 SHA-1，并记录已读的字节数
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha1,
+    offset: u64,
+}
+
+impl<R: BufRead> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = {
+            let available = self.fill_buf()?;
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            n
+        };
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: BufRead> BufRead for HashingReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Ok(buffer) = self.inner.fill_buf() {
+            self.hasher.update(&buffer[..amount.min(buffer.len())]);
+        }
+        self.offset += amount as u64;
+        self.inner.consume(amount);
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_byte<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut byte = [0u8; 1];
+    reader.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// delta 对象的基础对象：同一 packfile 中的偏移（`OFS_DELTA`）或对象 id（`REF_DELTA`）
+enum DeltaBase {
+    Offset(u64),
+    Id(String),
+}
+
+/// 从流中读取一个 packfile，还原其中的 delta 对象，返回所有提交、tree 与 blob
+///
+/// 只读取 packfile 本身的字节，流中后续的数据不受影响。
+pub fn read_pack<R: BufRead>(reader: R) -> io::Result<PackObjects> {
+    let mut reader = HashingReader { inner: reader, hasher: Sha1::new(), offset: 0 };
+
+    let mut header = [0u8; 12];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != b"PACK" {
+        return Err(invalid("不是有效的 packfile"));
+    }
+    let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+    if version != 2 && version != 3 {
+        return Err(invalid(&format!("不支持的 packfile 版本 {}", version)));
+    }
+    let count = u32::from_be_bytes([header[8], header[9], header[10], header[11]]) as usize;
+
+    // 对象的起始偏移 -> (类型码, git id, 内容)
+    let mut resolved: HashMap<u64, (u8, String, Vec<u8>)> = HashMap::new();
+    let mut deltas = Vec::new();
+    for _ in 0..count {
+        let offset = reader.offset;
+        let mut byte = read_byte(&mut reader)?;
+        let kind = (byte >> 4) & 0x07;
+        let mut size = (byte & 0x0f) as usize;
+        let mut shift = 4;
+        while byte & 0x80 != 0 {
+            if shift > usize::BITS - 7 {
+                return Err(invalid("packfile 中的对象长度过大"));
+            }
+            byte = read_byte(&mut reader)?;
+            size |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+        }
+        let base = match kind {
+            1..=4 => None,
+            6 => {
+                // 到基础对象的距离：每字节 7 位，高位在前，后续每字节隐含加一
+                let mut byte = read_byte(&mut reader)?;
+                let mut distance = (byte & 0x7f) as u64;
+                while byte & 0x80 != 0 {
+                    if distance >= 1 << 56 {
+                        return Err(invalid("packfile 中的 delta 偏移过大"));
+                    }
+                    byte = read_byte(&mut reader)?;
+                    distance = ((distance + 1) << 7) | (byte & 0x7f) as u64;
+                }
+                let base = offset.checked_sub(distance).filter(|_| distance > 0).ok_or_else(|| invalid("packfile 中的 delta 偏移无效"))?;
+                Some(DeltaBase::Offset(base))
+            }
+            7 => {
+                let mut id = [0u8; 20];
+                reader.read_exact(&mut id)?;
+                Some(DeltaBase::Id(id.iter().map(|b| format!("{:02x}", b)).collect()))
+            }
+            _ => return Err(invalid(&format!("未知的对象类型 {}", kind))),
+        };
+
+        // 长度来自对方，不按它预先分配内存；解压时最多多读一个字节，用来发现长度不符
+        let mut content = Vec::new();
+        ZlibDecoder::new(&mut reader).take(size as u64 + 1).read_to_end(&mut content)?;
+        if content.len() != size {
+            return Err(invalid("packfile 中的对象长度不符"));
+        }
+        match base {
+            None => {
+                let id = git_object_id(type_name(kind), &content);
+                resolved.insert(offset, (kind, id, content));
+            }
+            Some(base) => deltas.push((offset, base, content)),
+        }
+    }
+
+    let expected = reader.hasher.finalize_reset();
+    let mut checksum = [0u8; 20];
+    reader.inner.read_exact(&mut checksum)?;
+    if expected.as_slice() != checksum {
+        return Err(invalid("packfile 校验和不符"));
+    }
+
+    // 基础对象本身也可能是 delta，反复还原直到没有进展
+    let mut offsets: HashMap<String, u64> = resolved.iter().map(|(offset, (_, id, _))| (id.clone(), *offset)).collect();
+    while !deltas.is_empty() {
+        let before = deltas.len();
+        let mut pending = Vec::new();
+        for (offset, base, delta) in deltas {
+            let base_offset = match &base {
+                DeltaBase::Offset(base) => Some(*base),
+                DeltaBase::Id(id) => offsets.get(id).copied(),
+            };
+            let Some((kind, _, base_content)) = base_offset.and_then(|base| resolved.get(&base)) else {
+                pending.push((offset, base, delta));
+                continue;
+            };
+            let kind = *kind;
+            let content = apply_delta(base_content, &delta)?;
+            let id = git_object_id(type_name(kind), &content);
+            offsets.insert(id.clone(), offset);
+            resolved.insert(offset, (kind, id, content));
+        }
+        if pending.len() == before {
+            return Err(invalid("packfile 中缺少 delta 的基础对象"));
+        }
+        deltas = pending;
+    }
+
+    let objects = resolved.into_values().filter_map(|(code, id, content)| {
+        let kind = match code {
+            1 => ObjectKind::Commit,
+            2 => ObjectKind::Tree,
+            3 => ObjectKind::Blob,
+            // rust-git 没有标签对象
+            _ => return None,
+        };
+        Some((id, (kind, content)))
+    });
+    Ok(objects.collect())
+}
+
+/// 读取 delta 头部的变长整数（每字节 7 位，低位在前）
+fn read_varint(data: &[u8], pos: &mut usize) -> io::Result<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos).ok_or_else(|| invalid("delta 数据不完整"))?;
+        *pos += 1;
+        if shift > usize::BITS - 7 {
+            return Err(invalid("delta 中的长度过大"));
+        }
+        value |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+}
+
+/// 按 delta 中的指令，从基础对象还原出目标对象
+///
+/// delta 以基础对象与目标对象的长度开头，之后每条指令要么从基础对象复制一段，要么插入随后的字节。
+fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    let mut pos = 0;
+    if read_varint(delta, &mut pos)? != base.len() {
+        return Err(invalid("delta 的基础对象长度不符"));
+    }
+    let size = read_varint(delta, &mut pos)?;
+    let mut result = Vec::new();
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+        if op & 0x80 != 0 {
+            // 复制：低 4 位标记偏移的哪些字节出现，接下来 3 位标记长度的哪些字节出现
+            let mut offset = 0usize;
+            let mut len = 0usize;
+            for i in 0..7 {
+                if op & (1 << i) != 0 {
+                    let byte = *delta.get(pos).ok_or_else(|| invalid("delta 数据不完整"))? as usize;
+                    pos += 1;
+                    if i < 4 {
+                        offset |= byte << (8 * i);
+                    } else {
+                        len |= byte << (8 * (i - 4));
+                    }
+                }
+            }
+            if len == 0 {
+                len = 0x10000;
+            }
+            let chunk = offset.checked_add(len).and_then(|end| base.get(offset..end)).ok_or_else(|| invalid("delta 的复制范围超出基础对象"))?;
+            result.extend_from_slice(chunk);
+        } else if op != 0 {
+            let chunk = delta.get(pos..pos + op as usize).ok_or_else(|| invalid("delta 数据不完整"))?;
+            result.extend_from_slice(chunk);
+            pos += op as usize;
+        } else {
+            return Err(invalid("delta 中有无效的指令"));
+        }
+        if result.len() > size {
+            return Err(invalid("delta 还原后的长度不符"));
+        }
+    }
+    if result.len() != size {
+        return Err(invalid("delta 还原后的长度不符"));
+    }
+    Ok(result)
+}
@@ -0,0 +1,117 @@
+use std::io::{self, Read, Write};
+
+/// pkt-line 数据包的最大数据长度（65520 减去 4 字节长度头）
+pub const MAX_PACKET_DATA: usize = 65516;
+
+/// 一个 pkt-line 数据包
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Packet {
+    Data(Vec<u8>),
+    /// `0000`：一段消息结束
+    Flush,
+    /// `0001`：协议 v2 中分隔能力与参数
+    Delim,
+    /// `0002`：协议 v2 中无状态连接的响应结束
+    ResponseEnd,
+}
+
+impl Packet {
+    /// 数据包的文本内容（去掉结尾的换行），特殊包返回 None
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Packet::Data(data) => {
+                let text = String::from_utf8_lossy(data);
+                Some(text.strip_suffix('\n').unwrap_or(&text).to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 读取一个数据包，连接已关闭时返回 None
+pub fn read_packet<R: Read>(reader: &mut R) -> io::Result<Option<Packet>> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..])? {
+            0 if filled == 0 => return Ok(None),
+            0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "pkt-line 长度头不完整")),
+            n => filled += n,
+        }
+    }
+    let length = std::str::from_utf8(&header)
+        .ok()
+        .and_then(|h| usize::from_str_radix(h, 16).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("无效的 pkt-line 长度头: {:?}", header)))?;
+    match length {
+        0 => Ok(Some(Packet::Flush)),
+        1 => Ok(Some(Packet::Delim)),
+        2 => Ok(Some(Packet::ResponseEnd)),
+        3 => Err(io::Error::new(io::ErrorKind::InvalidData, "无效的 pkt-line 长度 3")),
+        _ => {
+            let mut data = vec![0u8; length - 4];
+            reader.read_exact(&mut data)?;
+            Ok(Some(Packet::Data(data)))
+        }
+    }
+}
+
+/// 读取一个数据包，连接关闭时报错
+pub fn expect_packet<R: Read>(reader: &mut R) -> io::Result<Packet> {
+    read_packet(reader)?.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "对方意外关闭了连接"))
+}
+
+/// 读取文本行直到遇到 flush 或 delim，返回这些行以及结束它们的数据包
+pub fn read_lines<R: Read>(reader: &mut R) -> io::Result<(Vec<String>, Packet)> {
+    let mut lines = Vec::new();
+    loop {
+        let packet = expect_packet(reader)?;
+        match packet.text() {
+            Some(line) => {
+                if let Some(message) = line.strip_prefix("ERR ") {
+                    return Err(io::Error::other(format!("远程错误: {}", message)));
+                }
+                lines.push(line);
+            }
+            None => return Ok((lines, packet)),
+        }
+    }
+}
+
+/// 写入一个数据包
+pub fn write_packet<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    if data.len() > MAX_PACKET_DATA {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "pkt-line 数据过长"));
+    }
+    write!(writer, "{:04x}", data.len() + 4)?;
+    writer.write_all(data)
+}
+
+/// 写入一行文本（自动补上换行）
+pub fn write_line<W: Write>(writer: &mut W, line: &str) -> io::Result<()> {
+    if line.ends_with('\n') {
+        write_packet(writer, line.as_bytes())
+    } else {
+        write_packet(writer, format!("{}\n", line).as_bytes())
+    }
+}
+
+pub fn write_flush<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(b"0000")?;
+    writer.flush()
+}
+
+pub fn write_delim<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(b"0001")
+}
+
+/// 按 side-band 格式分块写出数据：每个数据包第一个字节是通道号（1 数据、2 进度、3 错误）
+pub fn write_sideband<W: Write>(writer: &mut W, band: u8, data: &[u8]) -> io::Result<()> {
+    for chunk in data.chunks(MAX_PACKET_DATA - 1) {
+        let mut packet = Vec::with_capacity(chunk.len() + 1);
+        packet.push(band);
+        packet.extend_from_slice(chunk);
+        write_packet(writer, &packet)?;
+    }
+    Ok(())
+}
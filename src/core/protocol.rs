@@ -0,0 +1,170 @@
+//! git pack 协议的客户端部分：请求的编码与响应的解析
+//!
+//! 只负责读写数据流，不关心底层是管道还是 HTTP，见 [`crate::core::transport`]。
+
+use std::io::{self, Read, Write};
+use crate::core::pack::{read_pack, PackObjects};
+use crate::core::pktline::{expect_packet, read_lines, write_delim, write_flush, write_line, write_packet, Packet};
+use crate::core::transport::{AdvertisedRefs, RefCommand, RefResult};
+
+/// 在协议中标识自己的 agent 字符串
+pub const AGENT: &str = concat!("rust-git/", env!("CARGO_PKG_VERSION"));
+
+/// 全零的对象 id，表示引用不存在
+pub const ZERO_ID: &str = "0000000000000000000000000000000000000000";
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// 读取协议 v2 的能力公布，返回能力列表（不含 `version 2`）
+pub fn read_capabilities<R: Read>(reader: &mut R) -> io::Result<Vec<String>> {
+    let (mut lines, _) = read_lines(reader)?;
+    if lines.first().map(String::as_str) != Some("version 2") {
+        return Err(invalid(format!("远程不支持协议 v2: {:?}", lines.first())));
+    }
+    lines.remove(0);
+    Ok(lines)
+}
+
+/// 写出一条协议 v2 命令：命令名与能力、分隔包、参数、flush
+fn write_command<W: Write>(writer: &mut W, command: &str, args: &[String]) -> io::Result<()> {
+    write_line(writer, &format!("command={}", command))?;
+    write_line(writer, &format!("agent={}", AGENT))?;
+    write_line(writer, "object-format=sha1")?;
+    write_delim(writer)?;
+    for arg in args {
+        write_line(writer, arg)?;
+    }
+    write_flush(writer)
+}
+
+/// 请求列出分支、标签与 HEAD
+pub fn write_ls_refs_request<W: Write>(writer: &mut W) -> io::Result<()> {
+    let args = ["symrefs", "peel", "unborn", "ref-prefix HEAD", "ref-prefix refs/heads/", "ref-prefix refs/tags/"];
+    write_command(writer, "ls-refs", &args.map(String::from))
+}
+
+/// 解析 ls-refs 的响应：每行 `<hash> <引用名>[ 属性...]`，HEAD 可能带 `symref-target:`
+///
+/// rust-git 没有标签对象，附注标签（带 `peeled:` 属性）取它指向的提交。
+pub fn read_ls_refs_response<R: Read>(reader: &mut R) -> io::Result<AdvertisedRefs> {
+    let (lines, _) = read_lines(reader)?;
+    let mut advertised = AdvertisedRefs::default();
+    for line in lines {
+        let mut parts = line.split(' ');
+        let (Some(hash), Some(name)) = (parts.next(), parts.next()) else {
+            return Err(invalid(format!("无法解析的引用公布: '{}'", line)));
+        };
+        let attrs: Vec<&str> = parts.collect();
+        if name == "HEAD" {
+            advertised.head = attrs.iter().find_map(|attr| attr.strip_prefix("symref-target:")).map(str::to_string);
+        } else if hash != "unborn" {
+            let hash = attrs.iter().find_map(|attr| attr.strip_prefix("peeled:")).unwrap_or(hash);
+            advertised.refs.push((name.to_string(), hash.to_string()));
+        }
+    }
+    Ok(advertised)
+}
+
+/// 请求对象：列出想要的提交与本地已有的提交，并以 `done` 结束协商
+///
+/// 不请求 `thin-pack`：delta 只能以同一个 packfile 中的对象为基础。
+pub fn write_fetch_request<W: Write>(writer: &mut W, wants: &[String], haves: &[String]) -> io::Result<()> {
+    let mut args = vec!["ofs-delta".to_string(), "no-progress".to_string()];
+    args.extend(wants.iter().map(|w| format!("want {}", w)));
+    args.extend(haves.iter().map(|h| format!("have {}", h)));
+    args.push("done".to_string());
+    write_command(writer, "fetch", &args)
+}
+
+/// 读取 fetch 的响应，返回 packfile 中的对象
+pub fn read_fetch_response<R: Read>(reader: &mut R) -> io::Result<PackObjects> {
+    // 跳过 packfile 之前的各节（如 acknowledgments），各节之间以 delim 分隔
+    loop {
+        let header = expect_packet(reader)?.text();
+        match header.as_deref() {
+            Some("packfile") => break,
+            Some(line) if line.starts_with("ERR ") => return Err(io::Error::other(format!("远程错误: {}", &line[4..]))),
+            Some(_) => {
+                read_lines(reader)?;
+            }
+            None => return Err(invalid("fetch 响应中没有 packfile".to_string())),
+        }
+    }
+
+    let mut pack = Vec::new();
+    loop {
+        match expect_packet(reader)? {
+            Packet::Data(data) => match data.split_first() {
+                Some((1, chunk)) => pack.extend_from_slice(chunk),
+                Some((2, progress)) => eprint!("remote: {}", String::from_utf8_lossy(progress)),
+                Some((3, message)) => {
+                    return Err(io::Error::other(format!("远程错误: {}", String::from_utf8_lossy(message).trim_end())))
+                }
+                _ => return Err(invalid("无效的 side-band 数据".to_string())),
+            },
+            Packet::Flush => break,
+            _ => return Err(invalid("packfile 节中出现了意外的数据包".to_string())),
+        }
+    }
+    read_pack(pack.as_slice())
+}
+
+/// 读取 receive-pack 的引用公布（协议 v0）
+pub fn read_receive_advertisement<R: Read>(reader: &mut R) -> io::Result<AdvertisedRefs> {
+    let (lines, _) = read_lines(reader)?;
+    let mut advertised = AdvertisedRefs::default();
+    for line in lines.iter().filter(|l| l.as_str() != "version 1") {
+        // 第一行在 NUL 之后附带能力列表
+        let line = line.split('\0').next().unwrap_or_default();
+        let Some((hash, name)) = line.split_once(' ') else {
+            return Err(invalid(format!("无法解析的引用公布: '{}'", line)));
+        };
+        // `.have` 是对方备用对象库中的提交，`^{}` 是附注标签指向的对象，都不是引用
+        if name != "capabilities^{}" && name != ".have" && !name.ends_with("^{}") {
+            advertised.refs.push((name.to_string(), hash.to_string()));
+        }
+    }
+    Ok(advertised)
+}
+
+/// 发送引用更新命令，以及（有需要时）packfile
+pub fn write_push_request<W: Write>(writer: &mut W, commands: &[RefCommand], pack: Option<&[u8]>) -> io::Result<()> {
+    for (i, command) in commands.iter().enumerate() {
+        let old = command.old.as_deref().unwrap_or(ZERO_ID);
+        let new = command.new.as_deref().unwrap_or(ZERO_ID);
+        let mut line = format!("{} {} {}", old, new, command.name);
+        if i == 0 {
+            line.push_str(&format!("\0report-status delete-refs agent={}", AGENT));
+        }
+        line.push('\n');
+        write_packet(writer, line.as_bytes())?;
+    }
+    write_flush(writer)?;
+    if let Some(pack) = pack {
+        writer.write_all(pack)?;
+    }
+    Ok(())
+}
+
+/// 读取 report-status：`unpack ok` 之后是每条引用的 `ok <引用>` 或 `ng <引用> <原因>`
+pub fn read_push_report<R: Read>(reader: &mut R) -> io::Result<Vec<RefResult>> {
+    let (lines, _) = read_lines(reader)?;
+    let mut lines = lines.into_iter();
+    match lines.next() {
+        Some(line) if line == "unpack ok" => {}
+        Some(line) => return Err(io::Error::other(format!("远程解包失败: {}", line.trim_start_matches("unpack ")))),
+        None => return Err(invalid("缺少 report-status".to_string())),
+    }
+    let mut results = Vec::new();
+    for line in lines {
+        if let Some(name) = line.strip_prefix("ok ") {
+            results.push((name.to_string(), Ok(())));
+        } else if let Some(rest) = line.strip_prefix("ng ") {
+            let (name, reason) = rest.split_once(' ').unwrap_or((rest, "failed"));
+            results.push((name.to_string(), Err(reason.to_string())));
+        }
+    }
+    Ok(results)
+}
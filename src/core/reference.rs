@@ -1,77 +1,320 @@
 use std::fs;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::Path;
+use std::collections::HashMap;
+use crate::core::commit::read_commit;
+use crate::core::object::{objects_dir, read_object};
+use crate::core::tree::{hash_tree_from_map, read_tree_entries, tree_content_from_map};
+use crate::core::refs::{RefStore, RefValue};
+use crate::utils::hash::sha1_hash;
 
 /// 设置 HEAD 指向新的分支
 pub fn set_head(ref_path: &str, repo_path: &Path) -> io::Result<()> {
-    fs::write(repo_path.join("HEAD"), format!("ref: {}\n", ref_path))
+    let store = RefStore::new(repo_path);
+    let mut transaction = store.transaction();
+    transaction.set_symbolic("HEAD", ref_path);
+    transaction.commit()
 }
 
-/// 获取 HEAD 当前指向的引用路径（如 refs/heads/main）
-pub fn get_head_ref(repo_path: &Path) -> io::Result<PathBuf> {
-    let head_path = repo_path.join("HEAD");
-    let content = fs::read_to_string(&head_path)?;
-    if content.starts_with("ref: ") {
-        let rel_ref = content[5..].trim(); // e.g. "refs/heads/main"
-        Ok(repo_path.join(rel_ref))
-    } else {
-        Err(io::Error::new(io::ErrorKind::Other, "HEAD 非符号引用"))
+/// 获取 HEAD 当前指向的引用名（如 refs/heads/main）
+pub fn get_head_ref(repo_path: &Path) -> io::Result<String> {
+    match RefStore::new(repo_path).read("HEAD") {
+        Some(RefValue::Symbolic(target)) => Ok(target),
+        Some(RefValue::Direct(_)) => Err(io::Error::other("HEAD 非符号引用")),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, "找不到 HEAD")),
     }
 }
 
 /// 获取当前 HEAD 指向的分支名，如 "main"
 pub fn get_current_branch_name(repo_path: &Path) -> Option<String> {
-    let head_path = repo_path.join("HEAD");
-    let content = fs::read_to_string(head_path).ok()?;
-    if content.starts_with("ref: ") {
-        let rel = content.trim().strip_prefix("ref: refs/heads/")?;
-        Some(rel.to_string())
+    let target = get_head_ref(repo_path).ok()?;
+    target.strip_prefix("refs/heads/").map(str::to_string)
+}
+
+/// 引用名检查的选项（对应 `check-ref-format` 的参数）
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RefFormatOptions {
+    /// 允许只有一级的名字（如 `HEAD`、`master`）
+    pub allow_onelevel: bool,
+    /// 允许一个 `*`，用于 refspec 中的通配
+    pub refspec_pattern: bool,
+    /// 检查前去掉开头的 `/` 并合并连续的 `/`
+    pub normalize: bool,
+}
+
+/// 按 git 的 check-ref-format 规则检查引用名，返回（规范化后的）名字或不合法的原因
+///
+/// 规则：每一级都不能为空、不能以 `.` 开头、不能以 `.lock` 结尾；不能含有 `..`、`@{`、
+/// 控制字符与 DEL、空格以及 `~ ^ : ? * [ \`；不能以 `/` 或 `.` 结尾；不能是单独的 `@`；
+/// 除非 `allow_onelevel`，至少要有两级。
+pub fn check_ref_format(name: &str, opts: RefFormatOptions) -> Result<String, String> {
+    let name = if opts.normalize {
+        // 只去掉开头的 `/` 并合并连续的 `/`，结尾的 `/` 保留下来由后面的检查拒绝
+        let mut normalized = String::with_capacity(name.len());
+        for c in name.trim_start_matches('/').chars() {
+            if !(c == '/' && normalized.ends_with('/')) {
+                normalized.push(c);
+            }
+        }
+        normalized
     } else {
-        None
+        name.to_string()
+    };
+
+    if name.is_empty() {
+        return Err("名字为空".into());
+    }
+    if name == "@" {
+        return Err("不能是单独的 '@'".into());
+    }
+    if let Some(c) = name.chars().find(|c| c.is_ascii_control()) {
+        return Err(format!("不能含有控制字符 {:?}", c));
+    }
+    if let Some(c) = name.chars().find(|c| matches!(c, ' ' | '~' | '^' | ':' | '?' | '[' | '\\')) {
+        return Err(format!("不能含有 '{}'", c));
+    }
+    let stars = name.matches('*').count();
+    if stars > usize::from(opts.refspec_pattern) {
+        return Err("不能含有 '*'".into());
+    }
+    if name.contains("..") {
+        return Err("不能含有 '..'".into());
+    }
+    if name.contains("@{") {
+        return Err("不能含有 '@{'".into());
+    }
+    if name.ends_with('.') {
+        return Err("不能以 '.' 结尾".into());
+    }
+    if name.starts_with('/') || name.ends_with('/') || name.contains("//") {
+        return Err("不能以 '/' 开头或结尾，也不能含有连续的 '/'".into());
     }
+    for part in name.split('/') {
+        if part.starts_with('.') {
+            return Err(format!("'{}' 不能以 '.' 开头", part));
+        }
+        if part.ends_with(".lock") {
+            return Err(format!("'{}' 不能以 '.lock' 结尾", part));
+        }
+    }
+    if !opts.allow_onelevel && !name.contains('/') {
+        return Err("至少要有两级，如 'refs/heads/x'".into());
+    }
+    Ok(name)
+}
+
+/// 检查命令行中给出的完整引用名：`HEAD`、`ORIG_HEAD` 这类全大写的伪引用，或以 `refs/` 开头的合法引用名
+pub fn validate_ref_name(name: &str) -> Result<(), String> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_uppercase() || c == '_') {
+        return Ok(());
+    }
+    if !name.starts_with("refs/") {
+        return Err("引用名必须以 'refs/' 开头".into());
+    }
+    check_ref_format(name, RefFormatOptions::default()).map(|_| ())
 }
 
+/// 检查分支名：`refs/heads/<名>` 必须是合法的引用名，且名字不能是 `HEAD` 或以 `-` 开头
 pub fn validate_branch_name(name: &str) -> Result<(), String> {
     if name.is_empty() {
         return Err("分支名不能为空".into());
     }
-
-    if name == "." || name == ".." {
-        return Err("分支名不能为 '.' 或 '..'".into());
+    if name == "HEAD" || name == "@" {
+        return Err(format!("分支名不能为 '{}'", name));
+    }
+    if name.starts_with('-') {
+        return Err("分支名不能以 '-' 开头".into());
     }
+    if name.len() > 255 {
+        return Err("分支名太长".into());
+    }
+    check_ref_format(&format!("refs/heads/{}", name), RefFormatOptions::default()).map(|_| ())
+}
 
-    if name.starts_with('/') || name.ends_with('/') {
-        return Err("分支名不能以 '/' 开头或结尾".into());
+/// 从 HEAD 读取当前指向的 commit hash，不论是否为分支
+///
+/// HEAD 指向的分支尚未提交（unborn）时返回 None。
+pub fn read_head_commit_hash(repo_path: &Path) -> io::Result<Option<String>> {
+    let store = RefStore::new(repo_path);
+    if store.read("HEAD").is_none() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, "找不到 HEAD"));
     }
+    Ok(store.resolve("HEAD"))
+}
+
+/// HEAD 指向的分支是否尚无提交（unborn）：分支引用在第一次提交前并不存在
+pub fn is_unborn(repo_path: &Path) -> bool {
+    matches!(read_head_commit_hash(repo_path), Ok(None))
+}
 
-    if name.contains("//") {
-        return Err("分支名不能包含连续的 '/'".into());
+/// 读取引用（如 `refs/heads/main`）指向的 hash，会跟随符号引用，已打包的引用同样可以读取
+///
+/// 引用不存在或为空（尚未提交的分支）时返回 None。
+pub fn read_ref(repo_path: &Path, name: &str) -> Option<String> {
+    RefStore::new(repo_path).resolve(name)
+}
+
+/// 引用是否存在
+pub fn ref_exists(repo_path: &Path, name: &str) -> bool {
+    RefStore::new(repo_path).exists(name)
+}
+
+/// 写入引用
+pub fn write_ref(repo_path: &Path, name: &str, hash: &str) -> io::Result<()> {
+    let store = RefStore::new(repo_path);
+    let mut transaction = store.transaction();
+    transaction.update(name, hash);
+    transaction.commit()
+}
+
+/// 删除引用（包括 `packed-refs` 中的条目），并清理 `refs/` 下因此变空的目录
+pub fn delete_ref(repo_path: &Path, name: &str) -> io::Result<()> {
+    let store = RefStore::new(repo_path);
+    if !store.exists(name) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("引用 '{}' 不存在", name)));
     }
+    let mut transaction = store.transaction();
+    transaction.delete(name);
+    transaction.commit()
+}
 
-    if name.contains("..") {
-        return Err("分支名不能包含 '..'".into());
+/// 检查引用名是否与已有引用在目录层级上冲突，返回冲突的引用名
+///
+/// 引用以文件保存，因此 `refs/heads/a` 与 `refs/heads/a/b` 不能同时存在。
+pub fn conflicting_ref(repo_path: &Path, name: &str) -> Option<String> {
+    RefStore::new(repo_path).conflicting(name)
+}
+
+/// 列出 `prefix`（如 `refs/heads/`）下的所有引用及其 hash，按名字排序
+///
+/// 符号引用（如 `refs/remotes/origin/HEAD`）与尚未提交的空引用不会列出。
+pub fn list_refs(repo_path: &Path, prefix: &str) -> Vec<(String, String)> {
+    RefStore::new(repo_path).list(prefix)
+}
+
+/// 把 `HEAD`、分支名、标签名、完整或缩写的 hash 解析为 commit hash
+///
+/// 支持 `~N`（沿第一父提交回溯 N 次）与 `^N`（第 N 个父提交）后缀，可以串联使用，如 `HEAD~2^2`。
+pub fn resolve_revision(rev: &str, repo_path: &Path) -> Option<String> {
+    let split = rev.find(['~', '^']).unwrap_or(rev.len());
+    let (name, mut suffix) = rev.split_at(split);
+    let mut hash = resolve_name(name, repo_path)?;
+
+    while let Some(op) = suffix.chars().next() {
+        suffix = &suffix[1..];
+        let digits_len = suffix.bytes().take_while(u8::is_ascii_digit).count();
+        let (digits, rest) = suffix.split_at(digits_len);
+        suffix = rest;
+        let n: usize = if digits.is_empty() { 1 } else { digits.parse().ok()? };
+
+        if op == '~' {
+            for _ in 0..n {
+                hash = read_commit(&hash, repo_path).ok()?.parents.first()?.clone();
+            }
+        } else if n > 0 {
+            hash = read_commit(&hash, repo_path).ok()?.parents.get(n - 1)?.clone();
+        }
     }
 
-    if name.contains(['~', '^', ':', '?', '*', '[', '\\', ' ']) {
-        return Err("分支名不能包含特殊字符：~, ^, :, ?, *, [, \\, 空格等".into());
+    Some(hash)
+}
+
+/// `<版本>:<路径>` 指向的对象
+enum PathObject {
+    /// 对象库中已有的对象（文件或整个 tree）
+    Stored(String),
+    /// 子目录：扁平的 tree 中没有对应的对象，只有其中的条目
+    Subtree(HashMap<String, String>),
+}
+
+/// 解析 `<版本>:<路径>`
+fn resolve_path(rev: &str, path: &str, repo_path: &Path) -> Option<PathObject> {
+    let commit = resolve_revision(if rev.is_empty() { "HEAD" } else { rev }, repo_path)?;
+    let tree = read_commit(&commit, repo_path).ok()?.tree;
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        return Some(PathObject::Stored(tree));
     }
+    let entries = read_tree_entries(&tree, repo_path).ok()?;
+    if let Some(hash) = entries.get(path) {
+        return Some(PathObject::Stored(hash.clone()));
+    }
+    let prefix = format!("{}/", path);
+    let sub: HashMap<String, String> = entries
+        .into_iter()
+        .filter_map(|(p, hash)| p.strip_prefix(&prefix).map(|rest| (rest.to_string(), hash)))
+        .collect();
+    (!sub.is_empty()).then_some(PathObject::Subtree(sub))
+}
 
-    if name.len() > 255 {
-        return Err("分支名太长".into());
+/// 把任意对象名解析为 hash：除 [`resolve_revision`] 支持的写法外，
+/// 还支持 `<版本>^{tree}`（提交的 tree）与 `<版本>:<路径>`（提交中的文件或子目录）
+///
+/// 子目录的 hash 只按内容计算，对应的 tree 不会写入对象库；需要读取内容时用 [`read_resolved_object`]。
+pub fn resolve_object(spec: &str, repo_path: &Path) -> Option<String> {
+    if let Some((rev, path)) = spec.split_once(':') {
+        return match resolve_path(rev, path, repo_path)? {
+            PathObject::Stored(hash) => Some(hash),
+            PathObject::Subtree(sub) => hash_tree_from_map(&sub).ok(),
+        };
+    }
+    if let Some(rev) = spec.strip_suffix("^{tree}") {
+        return read_commit(&resolve_revision(rev, repo_path)?, repo_path).ok().map(|c| c.tree);
+    }
+    if let Some(rev) = spec.strip_suffix("^{commit}").or_else(|| spec.strip_suffix("^{}")) {
+        return resolve_revision(rev, repo_path);
     }
+    resolve_revision(spec, repo_path)
+}
 
-    Ok(())
+/// 解析对象名并读取对象内容，返回 (hash, 内容)
+///
+/// `<版本>:<子目录>` 的 tree 内容在内存中生成，不写入对象库。
+pub fn read_resolved_object(spec: &str, repo_path: &Path) -> Option<(String, Vec<u8>)> {
+    if let Some((rev, path)) = spec.split_once(':') {
+        if let PathObject::Subtree(sub) = resolve_path(rev, path, repo_path)? {
+            let content = tree_content_from_map(&sub).ok()?.into_bytes();
+            return Some((sha1_hash(&content), content));
+        }
+    }
+    let hash = resolve_object(spec, repo_path)?;
+    let content = read_object(&hash, repo_path).ok()?;
+    Some((hash, content))
 }
 
-/// 从 HEAD 读取当前指向的 commit hash，不论是否为分支
-pub fn read_head_commit_hash(repo_path: &Path) -> io::Result<String> {
-    let head_path = repo_path.join("HEAD");
-    let head_content = fs::read_to_string(&head_path)?.trim().to_string();
+/// 解析不带后缀的名字
+fn resolve_name(name: &str, repo_path: &Path) -> Option<String> {
+    if name.is_empty() || name == "HEAD" {
+        return read_head_commit_hash(repo_path).ok().flatten();
+    }
 
-    if head_content.starts_with("ref: ") {
-        let ref_path = repo_path.join(head_content.trim_start_matches("ref: ").trim());
-        fs::read_to_string(&ref_path).map(|s| s.trim().to_string())
-    } else {
-        Ok(head_content)
+    let candidates = [
+        name.to_string(),
+        format!("refs/heads/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/remotes/{}", name),
+        format!("refs/remotes/{}/HEAD", name),
+    ];
+    for candidate in candidates.iter().filter(|c| c.starts_with("refs/")) {
+        if let Some(hash) = read_ref(repo_path, candidate) {
+            return Some(hash);
+        }
+    }
+
+    if name.len() < 4 || name.len() > 40 || !name.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let name = name.to_lowercase();
+    let (dir, rest) = name.split_at(2);
+    let mut matches = fs::read_dir(objects_dir(repo_path).join(dir))
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|f| f.starts_with(rest));
+    let found = matches.next()?;
+    // 缩写 hash 必须唯一
+    if matches.next().is_some() {
+        return None;
     }
+    Some(format!("{}{}", dir, found))
 }
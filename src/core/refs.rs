@@ -0,0 +1,440 @@
+//! 引用存储：松散引用（`refs/...` 下的单个文件）加上 `packed-refs` 文件
+//!
+//! 读取时松散引用优先，其次是 `packed-refs`。所有写入都经过 [`RefTransaction`]：
+//! 先为每个引用创建 `<引用>.lock` 并检查旧值，全部成功后才逐个改名生效，
+//! 任何一步失败都会删除已创建的锁文件，引用保持原样。
+//!
+//! 有多个工作区时，`refs/` 下的引用与 `packed-refs` 保存在共享的 git 目录中，
+//! HEAD、ORIG_HEAD 这类伪引用则属于各自的工作区。
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::reference::validate_ref_name;
+use crate::core::repository::common_dir;
+use crate::utils::fs::LockFile;
+
+const PACKED_REFS: &str = "packed-refs";
+const PACKED_HEADER: &str = "# pack-refs with: peeled fully-peeled sorted \n";
+
+/// 引用的值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefValue {
+    /// 直接指向一个对象（空字符串表示尚未提交的分支）
+    Direct(String),
+    /// 符号引用，如 HEAD 的 `ref: refs/heads/main`
+    Symbolic(String),
+}
+
+/// `packed-refs` 中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedRef {
+    pub hash: String,
+    /// 附注标签剥离后指向的对象（`^` 行）
+    pub peeled: Option<String>,
+}
+
+/// 一个仓库的引用存储
+#[derive(Debug, Clone)]
+pub struct RefStore {
+    git_dir: PathBuf,
+    common_dir: PathBuf,
+}
+
+fn is_hash(s: &str) -> bool {
+    s.len() == 40 && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+impl RefStore {
+    pub fn new(git_dir: &Path) -> RefStore {
+        RefStore { git_dir: git_dir.to_path_buf(), common_dir: common_dir(git_dir) }
+    }
+
+    fn loose_path(&self, name: &str) -> PathBuf {
+        if name == "refs" || name.starts_with("refs/") {
+            self.common_dir.join(name)
+        } else {
+            self.git_dir.join(name)
+        }
+    }
+
+    /// 读取松散引用文件；不存在或是目录时返回 None
+    fn read_loose(&self, name: &str) -> Option<RefValue> {
+        let path = self.loose_path(name);
+        if !path.is_file() {
+            return None;
+        }
+        let content = fs::read_to_string(path).ok()?;
+        let content = content.trim();
+        // 旧版本为尚未提交的分支写入的空文件视为不存在
+        if content.is_empty() {
+            return None;
+        }
+        Some(match content.strip_prefix("ref: ") {
+            Some(target) => RefValue::Symbolic(target.trim().to_string()),
+            None => RefValue::Direct(content.to_string()),
+        })
+    }
+
+    /// 读取 `packed-refs`，文件不存在时为空
+    pub fn packed_refs(&self) -> io::Result<BTreeMap<String, PackedRef>> {
+        let content = match fs::read_to_string(self.common_dir.join(PACKED_REFS)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => return Err(e),
+        };
+        let mut refs = BTreeMap::new();
+        let mut last: Option<String> = None;
+        for line in content.lines().filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("packed-refs 格式错误: '{}'", line));
+            if let Some(peeled) = line.strip_prefix('^') {
+                let entry: &mut PackedRef = last.as_ref().and_then(|name| refs.get_mut(name)).ok_or_else(invalid)?;
+                entry.peeled = Some(peeled.to_string());
+                continue;
+            }
+            let (hash, name) = line.split_once(' ').ok_or_else(invalid)?;
+            if !is_hash(hash) {
+                return Err(invalid());
+            }
+            refs.insert(name.to_string(), PackedRef { hash: hash.to_string(), peeled: None });
+            last = Some(name.to_string());
+        }
+        Ok(refs)
+    }
+
+    /// 把 `packed-refs` 的内容写入锁文件（调用方负责提交）
+    fn write_packed(lock: &mut LockFile, refs: &BTreeMap<String, PackedRef>) -> io::Result<()> {
+        let mut content = String::from(PACKED_HEADER);
+        for (name, entry) in refs {
+            content.push_str(&format!("{} {}\n", entry.hash, name));
+            if let Some(peeled) = &entry.peeled {
+                content.push_str(&format!("^{}\n", peeled));
+            }
+        }
+        lock.write_all(content.as_bytes())
+    }
+
+    /// 读取引用本身的值（不跟随符号引用），松散引用优先
+    pub fn read(&self, name: &str) -> Option<RefValue> {
+        if let Some(value) = self.read_loose(name) {
+            return Some(value);
+        }
+        let packed = self.packed_refs().ok()?;
+        packed.get(name).map(|entry| RefValue::Direct(entry.hash.clone()))
+    }
+
+    /// 读取引用指向的 hash，跟随符号引用；不存在或尚未提交时返回 None
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        let mut name = name.to_string();
+        // 符号引用最多跟随几层，防止循环
+        for _ in 0..5 {
+            match self.read(&name)? {
+                RefValue::Symbolic(target) => name = target,
+                RefValue::Direct(hash) => return Some(hash),
+            }
+        }
+        None
+    }
+
+    /// 跟随符号引用，返回最终的引用名（如 `HEAD` -> `refs/heads/main`），目标不必存在
+    pub fn target_name(&self, name: &str) -> String {
+        let mut name = name.to_string();
+        for _ in 0..5 {
+            match self.read(&name) {
+                Some(RefValue::Symbolic(target)) => name = target,
+                _ => break,
+            }
+        }
+        name
+    }
+
+    /// 引用是否存在（松散或已打包，含尚未提交的空引用与符号引用）
+    pub fn exists(&self, name: &str) -> bool {
+        self.read(name).is_some()
+    }
+
+    /// 列出 `prefix`（如 `refs/heads/`）下的所有松散引用名，包括符号引用
+    fn loose_names(&self, prefix: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut stack = vec![prefix.trim_end_matches('/').to_string()];
+        while let Some(name) = stack.pop() {
+            let path = self.loose_path(&name);
+            if path.is_dir() {
+                if let Ok(entries) = fs::read_dir(&path) {
+                    for entry in entries.flatten() {
+                        stack.push(format!("{}/{}", name, entry.file_name().to_string_lossy()));
+                    }
+                }
+            } else if path.is_file() && !name.ends_with(".lock") && name.starts_with(prefix) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// 列出 `prefix` 下的所有松散引用及其原始值（不跟随符号引用，也不校验内容），按名字排序
+    pub fn loose_refs(&self, prefix: &str) -> Vec<(String, RefValue)> {
+        let mut refs: Vec<(String, RefValue)> = self
+            .loose_names(prefix)
+            .into_iter()
+            .filter_map(|name| self.read_loose(&name).map(|value| (name, value)))
+            .collect();
+        refs.sort_by(|a, b| a.0.cmp(&b.0));
+        refs
+    }
+
+    /// 列出 `prefix` 下的所有引用及其 hash，按名字排序
+    ///
+    /// 符号引用与尚未提交的空引用不会列出。
+    pub fn list(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut refs: BTreeMap<String, String> = self
+            .packed_refs()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .map(|(name, entry)| (name, entry.hash))
+            .collect();
+        for name in self.loose_names(prefix) {
+            match self.read_loose(&name) {
+                Some(RefValue::Direct(hash)) => {
+                    refs.insert(name, hash);
+                }
+                _ => {
+                    refs.remove(&name);
+                }
+            }
+        }
+        refs.into_iter().collect()
+    }
+
+    /// 列出 `prefix` 下的符号引用及其目标（只会是松散引用）
+    pub fn list_symbolic(&self, prefix: &str) -> Vec<(String, String)> {
+        let mut refs: Vec<(String, String)> = self
+            .loose_names(prefix)
+            .into_iter()
+            .filter_map(|name| match self.read_loose(&name) {
+                Some(RefValue::Symbolic(target)) => Some((name, target)),
+                _ => None,
+            })
+            .collect();
+        refs.sort();
+        refs
+    }
+
+    /// 检查引用名是否与已有引用在目录层级上冲突，返回冲突的引用名
+    ///
+    /// 引用以文件保存，因此 `refs/heads/a` 与 `refs/heads/a/b` 不能同时存在；已打包的引用同样计入。
+    pub fn conflicting(&self, name: &str) -> Option<String> {
+        let mut prefix = String::new();
+        for component in name.split('/') {
+            if !prefix.is_empty() {
+                if prefix.matches('/').count() >= 2 && self.exists(&prefix) {
+                    return Some(prefix);
+                }
+                prefix.push('/');
+            }
+            prefix.push_str(component);
+        }
+        let children = format!("{}/", name);
+        if let Some(child) = self.loose_names(&children).into_iter().next() {
+            return Some(child);
+        }
+        self.packed_refs().ok()?.into_keys().find(|r| r.starts_with(&children))
+    }
+
+    /// 开始一个引用事务
+    pub fn transaction(&self) -> RefTransaction<'_> {
+        RefTransaction { store: self, updates: Vec::new() }
+    }
+
+    /// 把引用打包进 `packed-refs` 并删除对应的松散引用文件，返回打包的引用数
+    ///
+    /// `all` 为 false 时与 git 一样只打包标签（以及已经打包过的引用）；符号引用从不打包。
+    /// `prune` 为 false 时保留松散引用文件。
+    pub fn pack(&self, all: bool, prune: bool) -> io::Result<usize> {
+        let packed_path = self.common_dir.join(PACKED_REFS);
+        let mut packed_lock = LockFile::acquire(&packed_path)?;
+        let mut packed = self.packed_refs()?;
+        let mut moved = Vec::new();
+        for name in self.loose_names("refs/") {
+            if !all && !name.starts_with("refs/tags/") && !packed.contains_key(&name) {
+                continue;
+            }
+            if let Some(RefValue::Direct(hash)) = self.read_loose(&name) {
+                let peeled = packed.get(&name).filter(|e| e.hash == hash).and_then(|e| e.peeled.clone());
+                packed.insert(name.clone(), PackedRef { hash: hash.clone(), peeled });
+                moved.push((name, hash));
+            }
+        }
+        Self::write_packed(&mut packed_lock, &packed)?;
+        packed_lock.commit()?;
+
+        if prune {
+            for (name, hash) in &moved {
+                // 逐个加锁，确认打包期间没有被修改后再删除
+                let path = self.loose_path(name);
+                let Ok(lock) = LockFile::acquire(&path) else { continue };
+                if self.read_loose(name) == Some(RefValue::Direct(hash.clone())) {
+                    let _ = fs::remove_file(&path);
+                }
+                drop(lock);
+                self.remove_empty_dirs(&path);
+            }
+        }
+        Ok(moved.len())
+    }
+
+    /// 清理 `refs/` 下因删除引用而变空的目录（保留 `refs/heads` 这类顶层目录）
+    fn remove_empty_dirs(&self, path: &Path) {
+        let refs_root = self.common_dir.join("refs");
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if d == refs_root || d.parent() == Some(refs_root.as_path()) || !d.starts_with(&refs_root) || fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
+}
+
+/// 事务中的一项更新
+#[derive(Debug)]
+struct RefEdit {
+    name: String,
+    /// None 表示删除
+    new: Option<RefValue>,
+    /// 要求的当前值：None 不检查，Some(None) 要求不存在，Some(Some(h)) 要求指向 h
+    expected: Option<Option<String>>,
+}
+
+/// 引用事务：多个引用的更新要么全部生效，要么全部不生效
+pub struct RefTransaction<'a> {
+    store: &'a RefStore,
+    updates: Vec<RefEdit>,
+}
+
+impl RefTransaction<'_> {
+    /// 把引用指向 `hash`
+    pub fn update(&mut self, name: &str, hash: &str) -> &mut Self {
+        self.push(name, Some(RefValue::Direct(hash.to_string())), None)
+    }
+
+    /// 仅当引用当前指向 `old`（None 表示不存在）时才更新
+    pub fn update_if(&mut self, name: &str, hash: &str, old: Option<&str>) -> &mut Self {
+        self.push(name, Some(RefValue::Direct(hash.to_string())), Some(old.map(str::to_string)))
+    }
+
+    /// 把引用设为指向 `target` 的符号引用
+    pub fn set_symbolic(&mut self, name: &str, target: &str) -> &mut Self {
+        self.push(name, Some(RefValue::Symbolic(target.to_string())), None)
+    }
+
+    /// 删除引用（松散文件与 `packed-refs` 中的条目）
+    pub fn delete(&mut self, name: &str) -> &mut Self {
+        self.push(name, None, None)
+    }
+
+    /// 仅当引用当前指向 `old` 时才删除
+    pub fn delete_if(&mut self, name: &str, old: &str) -> &mut Self {
+        self.push(name, None, Some(Some(old.to_string())))
+    }
+
+    fn push(&mut self, name: &str, new: Option<RefValue>, expected: Option<Option<String>>) -> &mut Self {
+        self.updates.push(RefEdit { name: name.to_string(), new, expected });
+        self
+    }
+
+    /// 提交事务
+    pub fn commit(self) -> io::Result<()> {
+        let store = self.store;
+        let mut locks: Vec<LockFile> = Vec::new();
+        let result = Self::prepare(store, &self.updates, &mut locks).and_then(|packed| {
+            // 先让所有写入生效，再处理删除：写入失败时被删除的引用（如重命名前的分支）仍然完好
+            let (writes, deletes): (Vec<_>, Vec<_>) = self.updates.iter().zip(locks.drain(..)).partition(|(edit, _)| edit.new.is_some());
+            for (_, lock) in writes {
+                lock.commit()?;
+            }
+            // 先移除 packed-refs 中被删除的条目，再删除松散引用，避免旧值从 packed-refs 中重新出现
+            if let Some(packed_lock) = packed {
+                packed_lock.commit()?;
+            }
+            for (edit, lock) in deletes {
+                let path = store.loose_path(&edit.name);
+                if path.is_file() {
+                    fs::remove_file(&path)?;
+                }
+                drop(lock);
+                store.remove_empty_dirs(&path);
+            }
+            Ok(())
+        });
+        if result.is_err() {
+            // 丢弃剩余的锁（自动删除锁文件），再清理为锁文件创建的空目录
+            locks.clear();
+            for edit in &self.updates {
+                store.remove_empty_dirs(&store.loose_path(&edit.name));
+            }
+        }
+        result
+    }
+
+    /// 为每个引用加锁、检查旧值并把新值写入锁文件；有删除时还会锁定 `packed-refs` 并写入删除后的内容
+    ///
+    /// 引用的锁按顺序放入 `locks`；出错时已创建的锁随 [`LockFile`] 一起丢弃。
+    fn prepare(store: &RefStore, updates: &[RefEdit], locks: &mut Vec<LockFile>) -> io::Result<Option<LockFile>> {
+        for edit in updates {
+            if updates.iter().filter(|e| e.name == edit.name).count() > 1 {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("同一事务中多次更新引用 '{}'", edit.name)));
+            }
+            // 只检查写入：已经存在的不合法引用仍然可以删除
+            let invalid = |name: &str, reason: String| io::Error::new(io::ErrorKind::InvalidInput, format!("无效的引用名 '{}': {}", name, reason));
+            match &edit.new {
+                Some(RefValue::Symbolic(target)) => {
+                    validate_ref_name(&edit.name).map_err(|r| invalid(&edit.name, r))?;
+                    validate_ref_name(target).map_err(|r| invalid(target, r))?;
+                }
+                Some(RefValue::Direct(hash)) => {
+                    validate_ref_name(&edit.name).map_err(|r| invalid(&edit.name, r))?;
+                    if hash.is_empty() {
+                        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("不能把引用 '{}' 设为空值", edit.name)));
+                    }
+                }
+                None => {}
+            }
+            let mut lock = LockFile::acquire(&store.loose_path(&edit.name))?;
+
+            if let Some(expected) = &edit.expected {
+                let current = match store.read(&edit.name) {
+                    Some(RefValue::Direct(hash)) => Some(hash),
+                    _ => None,
+                };
+                if current != *expected {
+                    return Err(io::Error::other(format!("引用 '{}' 已被修改，与预期的值不一致", edit.name)));
+                }
+            }
+            match &edit.new {
+                Some(RefValue::Direct(hash)) => lock.write_all(format!("{}\n", hash).as_bytes())?,
+                Some(RefValue::Symbolic(target)) => lock.write_all(format!("ref: {}\n", target).as_bytes())?,
+                None => {}
+            }
+            locks.push(lock);
+        }
+
+        let deleted: Vec<&str> = updates.iter().filter(|e| e.new.is_none()).map(|e| e.name.as_str()).collect();
+        if deleted.is_empty() {
+            return Ok(None);
+        }
+        let mut packed = store.packed_refs()?;
+        if !deleted.iter().any(|name| packed.contains_key(*name)) {
+            return Ok(None);
+        }
+        let mut packed_lock = LockFile::acquire(&store.common_dir.join(PACKED_REFS))?;
+        // 重新读取，防止在加锁前被其他进程修改
+        packed = store.packed_refs()?;
+        for name in deleted {
+            packed.remove(name);
+        }
+        RefStore::write_packed(&mut packed_lock, &packed)?;
+        Ok(Some(packed_lock))
+    }
+}
@@ -0,0 +1,389 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::commit::is_ancestor;
+use crate::core::repository::find_git_dir;
+use crate::core::hooks::run_hook;
+use crate::core::object::object_exists;
+use crate::core::protocol::ZERO_ID;
+use crate::core::reference::{check_ref_format, delete_ref, list_refs, read_ref, write_ref, RefFormatOptions};
+use crate::core::refs::RefStore;
+use crate::core::config::load_config;
+use crate::core::repo_config::RepoConfig;
+use crate::core::transport::{is_url, open_transport, RefCommand, Service};
+
+/// 引用映射规则，如 `+refs/heads/*:refs/remotes/origin/*`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refspec {
+    /// 是否允许非快进更新（以 `+` 开头）
+    pub force: bool,
+    pub src: String,
+    pub dst: String,
+}
+
+impl Refspec {
+    pub fn parse(spec: &str) -> Option<Refspec> {
+        let (force, spec) = match spec.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, spec),
+        };
+        let (src, dst) = spec.split_once(':').unwrap_or((spec, spec));
+        // 两边的通配符必须同时出现，且最多一个
+        if src.matches('*').count() != dst.matches('*').count() || src.matches('*').count() > 1 {
+            return None;
+        }
+        let opts = RefFormatOptions { allow_onelevel: true, refspec_pattern: true, normalize: false };
+        if [src, dst].iter().any(|side| !side.is_empty() && check_ref_format(side, opts).is_err()) {
+            return None;
+        }
+        Some(Refspec { force, src: src.to_string(), dst: dst.to_string() })
+    }
+
+    /// 把源引用名映射为目标引用名，不匹配时返回 None
+    pub fn map(&self, name: &str) -> Option<String> {
+        match self.src.split_once('*') {
+            Some((prefix, suffix)) => {
+                let middle = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                Some(self.dst.replacen('*', middle, 1))
+            }
+            None => (name == self.src).then(|| self.dst.clone()),
+        }
+    }
+}
+
+/// 新建远程时默认的 fetch 规则
+pub fn default_fetch_refspec(name: &str) -> String {
+    format!("+refs/heads/*:refs/remotes/{}/*", name)
+}
+
+/// 一个远程仓库
+#[derive(Debug, Clone)]
+pub struct Remote {
+    /// 配置中的远程名；直接使用路径时为 None
+    pub name: Option<String>,
+    pub url: String,
+    pub fetch: Vec<Refspec>,
+}
+
+/// 按名字查找配置中的远程；找不到但参数是一个存在的路径或 URL 时，直接把它当作地址
+pub fn find_remote(name_or_url: &str, config: &RepoConfig) -> Option<Remote> {
+    if let Some(url) = config.get(&format!("remote.{}.url", name_or_url)) {
+        let fetch = config
+            .get_all(&format!("remote.{}.fetch", name_or_url))
+            .into_iter()
+            .filter_map(Refspec::parse)
+            .collect();
+        return Some(Remote { name: Some(name_or_url.to_string()), url: url.to_string(), fetch });
+    }
+    if Path::new(name_or_url).exists() || is_url(name_or_url) {
+        return Some(Remote { name: None, url: name_or_url.to_string(), fetch: Vec::new() });
+    }
+    None
+}
+
+/// 当前分支对应的远程名（`branch.<名>.remote`），没有配置时为 `origin`
+pub fn default_remote_name(repo_path: &Path) -> String {
+    let config = load_config(repo_path).unwrap_or_default();
+    crate::core::reference::get_current_branch_name(repo_path)
+        .and_then(|branch| config.get(&format!("branch.{}.remote", branch)).map(str::to_string))
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+/// 分支的上游引用（由 `branch.<名>.remote` 与 `branch.<名>.merge` 决定）
+///
+/// 远程为 `.` 时上游是本地分支，否则是对应的远程跟踪分支，如 `refs/remotes/origin/main`。
+pub fn upstream_ref(branch: &str, config: &RepoConfig) -> Option<String> {
+    let remote = config.get(&format!("branch.{}.remote", branch))?;
+    let merge = config.get(&format!("branch.{}.merge", branch))?;
+    if remote == "." {
+        Some(merge.to_string())
+    } else {
+        tracking_ref(remote, merge)
+    }
+}
+
+/// 远程仓库在本机上的 git 目录及其是否为裸仓库
+pub struct RemoteRepo {
+    pub git_dir: PathBuf,
+    pub bare: bool,
+}
+
+/// 打开本地路径形式的远程仓库：可以是带工作区的仓库，也可以直接是 git 目录（裸仓库）
+pub fn open_remote(url: &str) -> io::Result<RemoteRepo> {
+    let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+    match find_git_dir(path) {
+        Some((git_dir, bare)) => Ok(RemoteRepo { git_dir, bare }),
+        None => Err(io::Error::new(io::ErrorKind::NotFound, format!("'{}' 不是一个仓库", url))),
+    }
+}
+
+/// 引用更新的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefStatus {
+    New,
+    FastForward,
+    Forced,
+    Deleted,
+    UpToDate,
+    Rejected(String),
+    /// 远程端拒绝了更新
+    RemoteRejected(String),
+}
+
+/// 一条引用更新
+#[derive(Debug, Clone)]
+pub struct RefUpdate {
+    /// 来源引用（fetch 时为远程引用，push 时为本地引用）
+    pub src: String,
+    /// 被更新的引用
+    pub dst: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    pub status: RefStatus,
+}
+
+/// 判断一次更新的类型：新建、快进、强制或被拒绝
+fn classify(old: Option<&str>, new: &str, force: bool, repo_path: &Path) -> RefStatus {
+    match old {
+        None => RefStatus::New,
+        Some(old) if old == new => RefStatus::UpToDate,
+        Some(old) if object_exists(old, repo_path) && is_ancestor(old, new, repo_path) => RefStatus::FastForward,
+        Some(_) if force => RefStatus::Forced,
+        Some(old) if !object_exists(old, repo_path) => RefStatus::Rejected("fetch first".to_string()),
+        Some(_) => RefStatus::Rejected("non-fast-forward".to_string()),
+    }
+}
+
+/// 从远程获取对象，并按 fetch 规则更新远程跟踪分支，同时写入 `FETCH_HEAD`
+pub fn fetch(remote: &Remote, repo_path: &Path) -> io::Result<Vec<RefUpdate>> {
+    let mut transport = open_transport(&remote.url, Service::UploadPack)?;
+    let advertised = transport.list_refs(repo_path)?;
+    let (mut heads, mut tags): (Vec<_>, Vec<_>) = advertised
+        .refs
+        .iter()
+        .filter(|(name, _)| name.starts_with("refs/heads/") || name.starts_with("refs/tags/"))
+        .filter(|(name, _)| match check_ref_format(name, RefFormatOptions::default()) {
+            Ok(_) => true,
+            Err(reason) => {
+                eprintln!("warning: 忽略远程的无效引用 '{}': {}", name, reason);
+                false
+            }
+        })
+        .cloned()
+        .partition(|(name, _)| name.starts_with("refs/heads/"));
+
+    let mut wants: Vec<String> = Vec::new();
+    for (_, hash) in heads.iter().chain(tags.iter()) {
+        if !object_exists(hash, repo_path) && !wants.contains(hash) {
+            wants.push(hash.clone());
+        }
+    }
+    if !wants.is_empty() {
+        let haves: Vec<String> = list_refs(repo_path, "refs/").into_iter().map(|(_, hash)| hash).collect();
+        let fetched = transport.fetch_objects(&wants, &haves, repo_path)?;
+        // 换成取回后的本地 id；没能取回的引用不更新
+        let localize = |refs: Vec<(String, String)>| -> Vec<(String, String)> {
+            refs.into_iter()
+                .filter_map(|(name, hash)| {
+                    if !wants.contains(&hash) {
+                        return Some((name, hash));
+                    }
+                    let local = fetched.get(&hash).cloned();
+                    if local.is_none() {
+                        eprintln!("warning: 忽略远程引用 '{}'：无法取回对象 {}", name, hash);
+                    }
+                    local.map(|local| (name, local))
+                })
+                .collect()
+        };
+        heads = localize(heads);
+        tags = localize(tags);
+    }
+
+    // 所有引用在同一个事务中更新
+    let store = RefStore::new(repo_path);
+    let mut transaction = store.transaction();
+    let mut updates = Vec::new();
+    for (name, hash) in &heads {
+        for spec in &remote.fetch {
+            let Some(dst) = spec.map(name) else { continue };
+            let old = read_ref(repo_path, &dst);
+            let status = classify(old.as_deref(), hash, spec.force, repo_path);
+            if !matches!(status, RefStatus::Rejected(_) | RefStatus::UpToDate) {
+                transaction.update_if(&dst, hash, old.as_deref());
+            }
+            updates.push(RefUpdate { src: name.clone(), dst, old, new: Some(hash.clone()), status });
+        }
+    }
+
+    // 本地没有的标签直接取回，已有的标签不覆盖
+    for (name, hash) in &tags {
+        if read_ref(repo_path, name).is_none() {
+            transaction.update_if(name, hash, None);
+            updates.push(RefUpdate { src: name.clone(), dst: name.clone(), old: None, new: Some(hash.clone()), status: RefStatus::New });
+        }
+    }
+
+    // 远程 HEAD 指向的分支
+    if let Some(name) = &remote.name {
+        if let Some(branch) = advertised.head.as_deref().and_then(|h| h.strip_prefix("refs/heads/")) {
+            let tracking = format!("refs/remotes/{}/{}", name, branch);
+            let symref = format!("refs/remotes/{}/HEAD", name);
+            let tracked = read_ref(repo_path, &tracking).is_some()
+                || updates.iter().any(|u: &RefUpdate| u.dst == tracking && !matches!(u.status, RefStatus::Rejected(_)));
+            if tracked && !store.exists(&symref) {
+                transaction.set_symbolic(&symref, &tracking);
+            }
+        }
+    }
+
+    transaction.commit()?;
+
+    let mut fetch_head = String::new();
+    for (name, hash) in &heads {
+        let branch = name.trim_start_matches("refs/heads/");
+        fetch_head.push_str(&format!("{}\t\tbranch '{}' of {}\n", hash, branch, remote.url));
+    }
+    fs::write(repo_path.join("FETCH_HEAD"), fetch_head)?;
+
+    Ok(updates)
+}
+
+/// 一条 push 规则
+#[derive(Debug, Clone)]
+pub struct PushSpec {
+    /// 本地的引用名或版本；None 表示删除远程引用
+    pub src: Option<String>,
+    /// 远程的引用名（完整形式，如 `refs/heads/main`）
+    pub dst: String,
+    /// 允许非快进更新
+    pub force: bool,
+    /// `--force-with-lease`：远程引用必须等于期望值才允许更新；
+    /// 内层为 None 时期望值取本地的远程跟踪分支
+    pub lease: Option<Option<String>>,
+}
+
+/// 远程跟踪分支名，如 (`origin`, `refs/heads/main`) -> `refs/remotes/origin/main`
+pub fn tracking_ref(remote: &str, dst: &str) -> Option<String> {
+    dst.strip_prefix("refs/heads/").map(|branch| format!("refs/remotes/{}/{}", remote, branch))
+}
+
+/// 把本地对象推送到远程并更新远程引用
+///
+/// 本地先检查快进与 `--force-with-lease`，通过检查的更新再交给远程端执行，
+/// 远程端拒绝的更新标记为 [`RefStatus::RemoteRejected`]。
+/// `verify` 为 true 时，发送前先运行 pre-push 钩子，钩子失败则整个推送失败。
+pub fn push(remote: &Remote, specs: &[PushSpec], verify: bool, repo_path: &Path) -> io::Result<Vec<RefUpdate>> {
+    let mut transport = open_transport(&remote.url, Service::ReceivePack)?;
+    let advertised = transport.list_refs(repo_path)?;
+
+    let mut updates = Vec::new();
+    for spec in specs {
+        let old = advertised.get(&spec.dst);
+        let tracking = remote.name.as_deref().and_then(|name| tracking_ref(name, &spec.dst));
+        let src_name = spec.src.clone().unwrap_or_default();
+        let mut update = RefUpdate { src: src_name, dst: spec.dst.clone(), old: old.clone(), new: None, status: RefStatus::UpToDate };
+
+        let new = match &spec.src {
+            Some(src) => match crate::core::reference::resolve_revision(src, repo_path) {
+                Some(hash) => Some(hash),
+                None => {
+                    update.status = RefStatus::Rejected(format!("src refspec {} does not match any", src));
+                    updates.push(update);
+                    continue;
+                }
+            },
+            None => None,
+        };
+        update.new = new.clone();
+
+        if let Some(expected) = &spec.lease {
+            let expected = expected.clone().or_else(|| tracking.as_ref().and_then(|t| read_ref(repo_path, t)));
+            if expected != old {
+                update.status = RefStatus::Rejected("stale info".to_string());
+                updates.push(update);
+                continue;
+            }
+        }
+
+        update.status = match &new {
+            None if old.is_none() => RefStatus::Rejected("remote ref does not exist".to_string()),
+            None => RefStatus::Deleted,
+            Some(new) => classify(old.as_deref(), new, spec.force || spec.lease.is_some(), repo_path),
+        };
+        updates.push(update);
+    }
+
+    let commands: Vec<RefCommand> = updates
+        .iter()
+        .filter(|u| !matches!(u.status, RefStatus::Rejected(_) | RefStatus::UpToDate))
+        .map(|u| RefCommand { name: u.dst.clone(), old: u.old.clone(), new: u.new.clone() })
+        .collect();
+    if commands.is_empty() {
+        return Ok(updates);
+    }
+
+    if verify {
+        // 每行：<本地引用> <本地 hash> <远程引用> <远程 hash>
+        let mut input = String::new();
+        for update in updates.iter().filter(|u| !matches!(u.status, RefStatus::Rejected(_) | RefStatus::UpToDate)) {
+            let src = if update.new.is_some() { update.src.as_str() } else { "(delete)" };
+            let new = update.new.as_deref().unwrap_or(ZERO_ID);
+            let old = update.old.as_deref().unwrap_or(ZERO_ID);
+            input.push_str(&format!("{} {} {} {}\n", src, new, update.dst, old));
+        }
+        let name = remote.name.as_deref().unwrap_or(&remote.url);
+        if !run_hook(repo_path, "pre-push", &[name, &remote.url], Some(&input))? {
+            return Err(io::Error::other("pre-push 钩子返回了非零状态"));
+        }
+    }
+
+    for (name, result) in transport.push_refs(&commands, repo_path)? {
+        let Some(update) = updates.iter_mut().find(|u| u.dst == name && !matches!(u.status, RefStatus::Rejected(_))) else {
+            continue;
+        };
+        if let Err(reason) = result {
+            update.status = RefStatus::RemoteRejected(reason);
+            continue;
+        }
+        // 远程更新成功后同步本地的远程跟踪分支
+        if let Some(t) = remote.name.as_deref().and_then(|n| tracking_ref(n, &name)) {
+            match &update.new {
+                Some(new) => write_ref(repo_path, &t, new)?,
+                None => {
+                    let _ = delete_ref(repo_path, &t);
+                }
+            }
+        }
+    }
+
+    Ok(updates)
+}
+
+/// 格式化一条引用更新，形如 git 的输出：` * [new branch]      main -> origin/main`
+pub fn format_update(update: &RefUpdate, src: &str, dst: &str) -> String {
+    let short = |h: &Option<String>| h.as_deref().map(|h| h[..7.min(h.len())].to_string()).unwrap_or_default();
+    let kind = if update.dst.starts_with("refs/tags/") { "tag" } else { "branch" };
+    match &update.status {
+        RefStatus::New => format!(" * {:<17} {} -> {}", format!("[new {}]", kind), src, dst),
+        RefStatus::FastForward => format!("   {:<17} {} -> {}", format!("{}..{}", short(&update.old), short(&update.new)), src, dst),
+        RefStatus::Forced => format!(
+            " + {:<17} {} -> {}  (forced update)",
+            format!("{}...{}", short(&update.old), short(&update.new)),
+            src,
+            dst
+        ),
+        RefStatus::Deleted => format!(" - {:<17} {}", "[deleted]", dst),
+        RefStatus::UpToDate => format!(" = {:<17} {} -> {}", "[up to date]", src, dst),
+        RefStatus::Rejected(reason) => format!(" ! {:<17} {} -> {} ({})", "[rejected]", src, dst, reason),
+        RefStatus::RemoteRejected(reason) => format!(" ! {:<17} {} -> {} ({})", "[remote rejected]", src, dst, reason),
+    }
+}
+
+/// 去掉常见的引用前缀，用于显示
+pub fn shorten_ref(name: &str) -> &str {
+    ["refs/heads/", "refs/tags/", "refs/remotes/"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+}
@@ -0,0 +1,356 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::repository::common_dir;
+use crate::utils::fs::LockFile;
+
+/// 配置文件中的一个节，如 `[remote "origin"]`
+#[derive(Debug, Clone)]
+struct Section {
+    /// 节名（小写）
+    name: String,
+    /// 子节名（区分大小写）
+    subsection: Option<String>,
+    /// 键（小写）与值，按文件中的顺序保存，同一个键可以出现多次
+    entries: Vec<(String, String)>,
+}
+
+/// git 风格的仓库配置（`<git 目录>/config`）
+///
+/// 键使用 `节.键` 或 `节.子节.键` 的形式，如 `remote.origin.url`。
+#[derive(Debug, Clone, Default)]
+pub struct RepoConfig {
+    sections: Vec<Section>,
+}
+
+/// 仓库配置文件路径（在所有工作区共享的 git 目录中）
+pub fn repo_config_path(repo_path: &Path) -> PathBuf {
+    common_dir(repo_path).join("config")
+}
+
+/// 把 `a.b.c` 拆成 (节, 子节, 键)
+fn split_key(key: &str) -> Option<(String, Option<String>, String)> {
+    let (section, rest) = key.split_once('.')?;
+    let (subsection, name) = match rest.rsplit_once('.') {
+        Some((sub, name)) => (Some(sub.to_string()), name),
+        None => (None, rest),
+    };
+    if section.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some((section.to_lowercase(), subsection, name.to_lowercase()))
+}
+
+/// 键名是否合法：至少有节与键两部分，节名只含字母、数字、`-` 与 `.`，键名以字母开头且只含字母、数字与 `-`
+pub fn is_valid_key(key: &str) -> bool {
+    let Some((section, _, name)) = split_key(key) else {
+        return false;
+    };
+    section.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+        && name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// 拆分要写入的键，不合法时返回 InvalidInput 错误
+fn checked_key(key: &str) -> io::Result<(String, Option<String>, String)> {
+    match split_key(key) {
+        Some(parts) if is_valid_key(key) => Ok(parts),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("无效的配置键 '{}'", key))),
+    }
+}
+
+/// 把 `a.b` 拆成 (节, 子节)
+fn split_section(name: &str) -> (String, Option<String>) {
+    match name.split_once('.') {
+        Some((section, sub)) => (section.to_lowercase(), Some(sub.to_string())),
+        None => (name.to_lowercase(), None),
+    }
+}
+
+/// 解析值：去掉行尾注释与引号，处理转义；引号内与转义得到的空白会保留
+fn parse_value(raw: &str) -> String {
+    let mut value = String::new();
+    let mut in_quotes = false;
+    // 末尾需要保留的长度，其后只有引号外的空白
+    let mut keep = 0;
+    let mut chars = raw.trim().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(other) => value.push(other),
+                None => {}
+            },
+            '#' | ';' if !in_quotes => break,
+            _ => value.push(c),
+        }
+        if in_quotes || c == '"' || c == '\\' || !c.is_whitespace() {
+            keep = value.len();
+        }
+    }
+    value.truncate(keep);
+    value
+}
+
+/// 写出值时按需加引号与转义
+fn format_value(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t");
+    let needs_quotes = value.starts_with(' ') || value.ends_with(' ') || value.contains(['#', ';']);
+    if needs_quotes {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+impl RepoConfig {
+    /// 解析配置文本
+    pub fn parse(content: &str) -> io::Result<RepoConfig> {
+        let mut config = RepoConfig::default();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.split_once(']').map(|(h, _)| h).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("配置文件第 {} 行格式错误", number + 1))
+                })?;
+                let (name, subsection) = match header.split_once(' ') {
+                    Some((name, sub)) => (name, Some(sub.trim().trim_matches('"').to_string())),
+                    // 旧式写法 [section.sub]
+                    None => match header.split_once('.') {
+                        Some((name, sub)) => (name, Some(sub.to_string())),
+                        None => (header, None),
+                    },
+                };
+                config.sections.push(Section { name: name.trim().to_lowercase(), subsection, entries: Vec::new() });
+                continue;
+            }
+            let section = config.sections.last_mut().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("配置文件第 {} 行不在任何节中", number + 1))
+            })?;
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), parse_value(value)),
+                // 只有键名表示布尔值 true
+                None => (line, "true".to_string()),
+            };
+            section.entries.push((key.to_lowercase(), value));
+        }
+        Ok(config)
+    }
+
+    /// 读取配置文件，文件不存在时为空配置
+    pub fn load(path: &Path) -> io::Result<RepoConfig> {
+        match fs::read_to_string(path) {
+            Ok(content) => RepoConfig::parse(&content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(RepoConfig::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 通过已持有的锁文件写回配置文件
+    pub fn save(&self, mut lock: LockFile) -> io::Result<()> {
+        lock.write_all(self.to_string().as_bytes())?;
+        lock.commit()
+    }
+
+    /// 读取键的值（同一个键出现多次时以最后一个为准）
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_all(key).pop()
+    }
+
+    /// 读取键的所有值
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        let Some((section, subsection, name)) = split_key(key) else {
+            return Vec::new();
+        };
+        self.sections
+            .iter()
+            .filter(|s| s.name == section && s.subsection == subsection)
+            .flat_map(|s| s.entries.iter())
+            .filter(|(k, _)| *k == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// 设置键的值：已存在时替换最后一个，否则追加
+    pub fn set(&mut self, key: &str, value: &str) -> io::Result<()> {
+        let (section, subsection, name) = checked_key(key)?;
+        let existing = self
+            .sections
+            .iter_mut()
+            .rev()
+            .filter(|s| s.name == section && s.subsection == subsection)
+            .flat_map(|s| s.entries.iter_mut().rev())
+            .find(|(k, _)| *k == name);
+        match existing {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.push_entry(section, subsection, name, value),
+        }
+        Ok(())
+    }
+
+    /// 追加一个值（不替换已有的值）
+    pub fn add(&mut self, key: &str, value: &str) -> io::Result<()> {
+        let (section, subsection, name) = checked_key(key)?;
+        self.push_entry(section, subsection, name, value);
+        Ok(())
+    }
+
+    fn push_entry(&mut self, section: String, subsection: Option<String>, name: String, value: &str) {
+        let position = self.sections.iter().rposition(|s| s.name == section && s.subsection == subsection);
+        match position {
+            Some(i) => self.sections[i].entries.push((name, value.to_string())),
+            None => self.sections.push(Section { name: section, subsection, entries: vec![(name, value.to_string())] }),
+        }
+    }
+
+    /// 删除键的所有值，返回删除的个数
+    pub fn unset_all(&mut self, key: &str) -> usize {
+        let Some((section, subsection, name)) = split_key(key) else {
+            return 0;
+        };
+        let mut removed = 0;
+        for s in self.sections.iter_mut().filter(|s| s.name == section && s.subsection == subsection) {
+            let before = s.entries.len();
+            s.entries.retain(|(k, _)| *k != name);
+            removed += before - s.entries.len();
+        }
+        self.sections.retain(|s| !s.entries.is_empty() || s.name != section || s.subsection != subsection);
+        removed
+    }
+
+    /// 删除整个节（如 `remote.origin`），返回是否存在
+    pub fn remove_section(&mut self, name: &str) -> bool {
+        let (section, subsection) = split_section(name);
+        let before = self.sections.len();
+        self.sections.retain(|s| s.name != section || s.subsection != subsection);
+        before != self.sections.len()
+    }
+
+    /// 把整个节复制为另一个节（如 `branch.a` 复制为 `branch.b`），目标节原有的内容会被替换，返回源节是否存在
+    pub fn copy_section(&mut self, from: &str, to: &str) -> bool {
+        let (section, subsection) = split_section(from);
+        let copies: Vec<Section> = self
+            .sections
+            .iter()
+            .filter(|s| s.name == section && s.subsection == subsection)
+            .cloned()
+            .collect();
+        if copies.is_empty() {
+            return false;
+        }
+        self.remove_section(to);
+        let (name, subsection) = split_section(to);
+        for mut copy in copies {
+            copy.name = name.clone();
+            copy.subsection = subsection.clone();
+            self.sections.push(copy);
+        }
+        true
+    }
+
+    /// 重命名整个节，目标节原有的内容会被替换，返回源节是否存在
+    pub fn rename_section(&mut self, from: &str, to: &str) -> bool {
+        let (section, subsection) = split_section(from);
+        let (name, new_subsection) = split_section(to);
+        if (&section, &subsection) != (&name, &new_subsection) {
+            self.remove_section(to);
+        }
+        let mut found = false;
+        for s in self.sections.iter_mut().filter(|s| s.name == section && s.subsection == subsection) {
+            s.name = name.clone();
+            s.subsection = new_subsection.clone();
+            found = true;
+        }
+        found
+    }
+
+    /// 把另一份配置追加在后面，同一个键以后出现的为准
+    pub fn extend(&mut self, other: RepoConfig) {
+        self.sections.extend(other.sections);
+    }
+
+    /// 按文件中的顺序列出所有的 (完整键名, 值)
+    pub fn entries(&self) -> Vec<(String, &str)> {
+        let mut entries = Vec::new();
+        for s in &self.sections {
+            let prefix = match &s.subsection {
+                Some(sub) => format!("{}.{}", s.name, sub),
+                None => s.name.clone(),
+            };
+            for (key, value) in &s.entries {
+                entries.push((format!("{}.{}", prefix, key), value.as_str()));
+            }
+        }
+        entries
+    }
+
+    /// 列出某个节下所有的子节名（如 `remote` 下的各个远程名）
+    pub fn subsections(&self, section: &str) -> Vec<String> {
+        let section = section.to_lowercase();
+        let mut names: Vec<String> = Vec::new();
+        for s in self.sections.iter().filter(|s| s.name == section) {
+            if let Some(sub) = &s.subsection {
+                if !names.contains(sub) {
+                    names.push(sub.clone());
+                }
+            }
+        }
+        names
+    }
+}
+
+impl std::fmt::Display for RepoConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for s in &self.sections {
+            match &s.subsection {
+                Some(sub) => writeln!(f, "[{} \"{}\"]", s.name, sub.replace('\\', "\\\\").replace('"', "\\\""))?,
+                None => writeln!(f, "[{}]", s.name)?,
+            }
+            for (key, value) in &s.entries {
+                writeln!(f, "\t{} = {}", key, format_value(value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 读取当前仓库的配置
+pub fn load_repo_config(repo_path: &Path) -> io::Result<RepoConfig> {
+    RepoConfig::load(&repo_config_path(repo_path))
+}
+
+/// 按 git 的规则解析布尔值：`true`/`yes`/`on`/`1` 与 `false`/`no`/`off`/`0`/空值
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// 读取、修改并写回当前仓库的配置，整个过程持有 `config.lock`
+pub fn update_repo_config<F>(repo_path: &Path, edit: F) -> io::Result<()>
+where
+    F: FnOnce(&mut RepoConfig) -> io::Result<()>,
+{
+    update_config_file(&repo_config_path(repo_path), edit)
+}
+
+/// 读取、修改并写回任意一个配置文件，整个过程持有 `<文件>.lock`，所在目录不存在时会被创建
+pub fn update_config_file<F>(path: &Path, edit: F) -> io::Result<()>
+where
+    F: FnOnce(&mut RepoConfig) -> io::Result<()>,
+{
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let lock = LockFile::acquire(path)?;
+    let mut config = RepoConfig::load(path)?;
+    edit(&mut config)?;
+    config.save(lock)
+}
@@ -1,17 +1,126 @@
 use std::fs;
 use std::io;
-use std::path::Path;
-// use crate::config::{GIT_DIR, DEFAULT_BRANCH};
-use crate::core::config::{GIT_DIR,DEFAULT_BRANCH};
-pub fn init_repository(path: &Path) -> io::Result<()> {
-    // let git_dir = path.join(".mygit");
-    let git_dir = path.join(&*GIT_DIR);
-    if git_dir.exists() {
-        return Ok(()); // 已初始化直接返回成功
+use std::path::{Path, PathBuf};
+use crate::core::config::{load_config, DEFAULT_BRANCH, GIT_DIR_NAME};
+use crate::core::reference::{set_head, validate_branch_name};
+use crate::core::repo_config::{parse_bool, update_repo_config, RepoConfig};
+
+/// 初始化仓库的选项
+#[derive(Debug, Default)]
+pub struct InitOptions {
+    /// 创建裸仓库：`path` 本身就是 git 目录，没有工作区
+    pub bare: bool,
+    /// 初始分支名，默认为 `init.defaultBranch`
+    pub initial_branch: Option<String>,
+    /// 模板目录，其中的文件（如 hooks/、info/）会被复制到 git 目录，默认为 `init.templateDir`
+    pub template: Option<PathBuf>,
+    /// 把 git 目录放在别处，工作区中只留一个写有 `gitdir: <路径>` 的文件
+    pub separate_git_dir: Option<PathBuf>,
+}
+
+/// `dir` 下的路径，`dir` 为 `.` 时不加前缀，保持输出中的路径简短
+fn join(dir: &Path, path: impl AsRef<Path>) -> PathBuf {
+    if dir == Path::new(".") {
+        path.as_ref().to_path_buf()
+    } else {
+        dir.join(path)
+    }
+}
+
+/// 所有工作区共享的 git 目录
+///
+/// 关联工作区的 git 目录（`<共享目录>/worktrees/<名>`）中有一个 `commondir` 文件指向共享目录，
+/// 其他情况下就是 `git_dir` 本身。对象、`refs/` 下的引用、`packed-refs`、配置与钩子放在共享目录中，
+/// HEAD、index 以及合并、变基等进行中的状态每个工作区各有一份。
+pub fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(content) => {
+            let path = git_dir.join(content.trim_end());
+            fs::canonicalize(&path).unwrap_or(path)
+        }
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+/// 目录是否像一个 git 目录（有 HEAD，共享目录中有 objects 与 refs）
+pub fn is_git_dir(path: &Path) -> bool {
+    let common = common_dir(path);
+    path.join("HEAD").is_file() && common.join("objects").is_dir() && common.join("refs").is_dir()
+}
+
+/// 读取工作区中的 `gitdir: <路径>` 文件（`--separate-git-dir` 创建），相对路径相对于工作区
+fn read_gitdir_file(dir: &Path, file: &Path) -> Option<PathBuf> {
+    let content = fs::read_to_string(file).ok()?;
+    let target = content.trim_end().strip_prefix("gitdir: ")?;
+    Some(join(dir, target))
+}
+
+/// 查找 `dir` 对应的仓库，返回 (git 目录, 是否为裸仓库)
+///
+/// 依次尝试 `dir/.mygit` 目录、`dir/.mygit` 中的 `gitdir:` 文件，以及 `dir` 本身是 git 目录的情况。
+/// 直接位于 git 目录中时没有工作区，与 `core.bare = true` 一样视为裸仓库；
+/// 关联工作区（`worktree add` 创建）即使共享的是裸仓库也总有工作区。
+pub fn find_git_dir(dir: &Path) -> Option<(PathBuf, bool)> {
+    let candidate = join(dir, &*GIT_DIR_NAME);
+    let git_dir = if candidate.is_dir() {
+        candidate
+    } else if candidate.is_file() {
+        read_gitdir_file(dir, &candidate).filter(|path| is_git_dir(path))?
+    } else if is_git_dir(dir) {
+        return Some((dir.to_path_buf(), true));
+    } else {
+        return None;
+    };
+    if git_dir.join("commondir").is_file() {
+        return Some((git_dir, false));
     }
+    let bare = RepoConfig::load(&git_dir.join("config"))
+        .ok()
+        .and_then(|config| config.get("core.bare").and_then(parse_bool))
+        .unwrap_or(false);
+    Some((git_dir, bare))
+}
+
+/// 把模板目录中的文件复制到 git 目录，不覆盖已存在的文件
+fn copy_template(from: &Path, to: &Path) -> io::Result<()> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_template(&entry.path(), &target)?;
+        } else if !target.exists() {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// 初始化仓库，返回 git 目录；仓库已存在时直接返回
+pub fn init_repository(path: &Path, opts: &InitOptions) -> io::Result<PathBuf> {
+    let git_dir = if opts.bare {
+        path.to_path_buf()
+    } else {
+        opts.separate_git_dir.clone().unwrap_or_else(|| join(path, &*GIT_DIR_NAME))
+    };
+    if is_git_dir(&git_dir) {
+        return Ok(git_dir); // 已初始化直接返回成功
+    }
+    let branch = opts.initial_branch.clone().unwrap_or_else(|| DEFAULT_BRANCH.clone());
+    validate_branch_name(&branch)
+        .map_err(|reason| io::Error::new(io::ErrorKind::InvalidInput, format!("无效的初始分支名 '{}': {}", branch, reason)))?;
 
     fs::create_dir_all(&git_dir)?;
 
+    let template = match &opts.template {
+        Some(template) => Some(template.clone()),
+        None => load_config(&git_dir)?.get("init.templatedir").filter(|t| !t.is_empty()).map(PathBuf::from),
+    };
+    if let Some(template) = template {
+        copy_template(&template, &git_dir)
+            .map_err(|e| io::Error::new(e.kind(), format!("无法复制模板 '{}': {}", template.display(), e)))?;
+    }
+
     // 创建 objects 目录
     let objects = git_dir.join("objects");
     fs::create_dir_all(objects.join("info"))?;
@@ -23,14 +132,16 @@ pub fn init_repository(path: &Path) -> io::Result<()> {
     fs::create_dir_all(refs.join("tags"))?;
     fs::create_dir_all(refs.join("remotes"))?;
 
-    // 创建 HEAD 文件
-    let head_path = git_dir.join("HEAD");
-    // fs::write(&head_path, "ref: refs/heads/main\n")?;
-    fs::write(&head_path, format!("ref: refs/heads/{}\n", *DEFAULT_BRANCH))?;
-    // ✅ 创建空的 main 分支指针文件，防止后续找不到
-    // let main_ref = git_dir.join("refs/heads/main");
-    let main_ref = git_dir.join("refs/heads").join(&*DEFAULT_BRANCH);
-    fs::write(&main_ref, "")?;
+    update_repo_config(&git_dir, |config| config.set("core.bare", if opts.bare { "true" } else { "false" }))?;
 
-    Ok(())
+    // 创建 HEAD 文件：分支在第一次提交时才会创建（unborn）
+    set_head(&format!("refs/heads/{}", branch), &git_dir)?;
+
+    if !opts.bare && opts.separate_git_dir.is_some() {
+        fs::create_dir_all(path)?;
+        let absolute = fs::canonicalize(&git_dir)?;
+        fs::write(path.join(&*GIT_DIR_NAME), format!("gitdir: {}\n", absolute.display()))?;
+    }
+
+    Ok(git_dir)
 }
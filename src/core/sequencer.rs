@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::core::commit::read_commit;
+use crate::core::index::{commit_index_map, index_file, read_index_map, read_skip_worktree};
+use crate::core::merge::{merge_trees, Conflict};
+use crate::core::object::read_object;
+use crate::core::sparse::load_sparse;
+use crate::core::tree::{checkout_file, read_tree_entries, remove_worktree_file, write_tree_from_map, write_worktree_file};
+use crate::utils::fs::{write_atomic, LockFile};
+use crate::utils::hash::sha1_hash;
+
+/// 待办列表中的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Pick,
+    Revert,
+    Reword,
+    Edit,
+    Squash,
+    Fixup,
+    Drop,
+    Exec,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Pick => "pick",
+            Action::Revert => "revert",
+            Action::Reword => "reword",
+            Action::Edit => "edit",
+            Action::Squash => "squash",
+            Action::Fixup => "fixup",
+            Action::Drop => "drop",
+            Action::Exec => "exec",
+        }
+    }
+
+    /// 解析操作名，支持 git 的单字母缩写
+    pub fn parse(word: &str) -> Option<Action> {
+        match word {
+            "pick" | "p" => Some(Action::Pick),
+            "revert" => Some(Action::Revert),
+            "reword" | "r" => Some(Action::Reword),
+            "edit" | "e" => Some(Action::Edit),
+            "squash" | "s" => Some(Action::Squash),
+            "fixup" | "f" => Some(Action::Fixup),
+            "drop" | "d" => Some(Action::Drop),
+            "exec" | "x" => Some(Action::Exec),
+            _ => None,
+        }
+    }
+}
+
+/// 待办列表中的一行：`<操作> <提交> <标题>`
+///
+/// `exec` 没有提交，`subject` 中保存要执行的命令。
+#[derive(Debug, Clone)]
+pub struct TodoItem {
+    pub action: Action,
+    pub commit: String,
+    pub subject: String,
+}
+
+/// 读取待办列表（忽略空行与 `#` 注释）
+pub fn read_todo(path: &Path) -> io::Result<Vec<TodoItem>> {
+    let content = fs::read_to_string(path)?;
+    let mut items = Vec::new();
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (word, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let action = Action::parse(word).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("无法识别的待办操作: '{}'", word))
+        })?;
+        let rest = rest.trim();
+        let item = if action == Action::Exec {
+            TodoItem { action, commit: String::new(), subject: rest.to_string() }
+        } else {
+            let (commit, subject) = rest.split_once(' ').unwrap_or((rest, ""));
+            TodoItem { action, commit: commit.to_string(), subject: subject.to_string() }
+        };
+        items.push(item);
+    }
+    Ok(items)
+}
+
+/// 写回待办列表（先写临时文件再改名，中途崩溃不会留下被截断的列表）
+pub fn write_todo(path: &Path, items: &[TodoItem]) -> io::Result<()> {
+    write_atomic(path, format_todo(items).as_bytes())
+}
+
+/// 把待办列表格式化为文本
+pub fn format_todo(items: &[TodoItem]) -> String {
+    let mut content = String::new();
+    for item in items {
+        if item.action == Action::Exec {
+            content.push_str(&format!("exec {}\n", item.subject));
+        } else {
+            content.push_str(&format!("{} {} {}\n", item.action.as_str(), item.commit, item.subject));
+        }
+    }
+    content
+}
+
+/// 检查冲突文件是否都已解决（去掉冲突标记并重新 add，或已从 index 删除），返回第一个未解决的文件
+pub fn first_unresolved(conflicts: &[String], repo_path: &Path) -> Option<String> {
+    let index = read_index_map(&index_file(repo_path));
+    conflicts
+        .iter()
+        .find(|path| match fs::read(path) {
+            Ok(content) => {
+                let text = String::from_utf8_lossy(&content);
+                text.lines().any(|l| l.starts_with("<<<<<<< ") || l.starts_with(">>>>>>> "))
+                    || index.get(*path) != Some(&sha1_hash(&content))
+            }
+            Err(_) => index.contains_key(*path),
+        })
+        .cloned()
+}
+
+/// 应用提交后的结果
+#[derive(Debug)]
+pub enum ApplyOutcome {
+    /// 无冲突，index 与工作区已更新，附带合并后的 tree 哈希
+    Clean(String),
+    /// 有冲突的文件（已在工作区写入冲突标记，index 中保留当前版本）
+    Conflicted(Vec<String>),
+}
+
+/// 把提交相对其父提交的改动三方合并到当前 index 与工作区
+///
+/// - `revert` 为 true 时应用反向改动（以提交本身为 base，父提交为目标）
+/// - `mainline` 为合并提交指定作为基准的父提交（从 1 开始）
+pub fn apply_commit(
+    commit_hash: &str,
+    revert: bool,
+    mainline: Option<usize>,
+    repo_path: &Path,
+) -> Result<ApplyOutcome, String> {
+    let commit = read_commit(commit_hash, repo_path)
+        .map_err(|e| format!("无法读取提交 {}: {}", commit_hash, e))?;
+
+    let parent = match (commit.parents.len(), mainline) {
+        (n, None) if n > 1 => {
+            return Err(format!("提交 {} 是合并提交，但没有指定 -m 选项", commit_hash));
+        }
+        (n, Some(_)) if n <= 1 => {
+            return Err(format!("指定了 -m 选项，但提交 {} 不是合并提交", commit_hash));
+        }
+        (_, Some(m)) => Some(
+            commit
+                .parents
+                .get(m.wrapping_sub(1))
+                .cloned()
+                .ok_or_else(|| format!("提交 {} 没有第 {} 个父提交", commit_hash, m))?,
+        ),
+        (_, None) => commit.parents.first().cloned(),
+    };
+
+    let parent_tree = match parent {
+        Some(p) => {
+            let info = read_commit(&p, repo_path).map_err(|e| format!("无法读取提交 {}: {}", p, e))?;
+            read_tree_entries(&info.tree, repo_path).map_err(|e| e.to_string())?
+        }
+        None => HashMap::new(),
+    };
+    let commit_tree = read_tree_entries(&commit.tree, repo_path).map_err(|e| e.to_string())?;
+    let (base, theirs) = if revert { (commit_tree, parent_tree) } else { (parent_tree, commit_tree) };
+
+    let index_path = index_file(repo_path);
+    // 从读取 index 到写回期间一直持有锁，其间其他进程对 index 的修改不会被覆盖
+    let lock = LockFile::acquire(&index_path).map_err(|e| format!("无法锁定 index: {}", e))?;
+    let ours = read_index_map(&index_path);
+    let outcome = merge_trees(&base, &ours, &theirs, repo_path);
+
+    let short = &commit_hash[..7.min(commit_hash.len())];
+    let label = if revert {
+        format!("parent of {} ({})", short, commit.subject())
+    } else {
+        format!("{} ({})", short, commit.subject())
+    };
+
+    ensure_worktree_matches(&ours, &outcome.tree, &outcome.conflicts, repo_path)?;
+    apply_to_worktree(&ours, &outcome.tree, repo_path).map_err(|e| format!("更新工作区失败: {}", e))?;
+
+    let mut new_index = outcome.tree.clone();
+    let mut conflicted = Vec::new();
+    for conflict in &outcome.conflicts {
+        match conflict {
+            Conflict::Content { path, .. } => {
+                match ours.get(path) {
+                    Some(hash) => new_index.insert(path.clone(), hash.clone()),
+                    None => new_index.remove(path),
+                };
+                let markers = conflict_markers(ours.get(path), theirs.get(path), &label, repo_path);
+                write_worktree_file(path, &markers).map_err(|e| format!("写入冲突文件 {} 失败: {}", path, e))?;
+                conflicted.push(path.clone());
+            }
+            Conflict::RenameRename { source, .. } => conflicted.push(source.clone()),
+        }
+    }
+    commit_index_map(lock, &index_path, &new_index).map_err(|e| format!("更新 index 失败: {}", e))?;
+
+    if conflicted.is_empty() {
+        let tree = write_tree_from_map(&outcome.tree, repo_path).map_err(|e| e.to_string())?;
+        Ok(ApplyOutcome::Clean(tree))
+    } else {
+        Ok(ApplyOutcome::Conflicted(conflicted))
+    }
+}
+
+/// 应用提交会改写的文件在工作区中必须与 index（`ours`）一致，否则拒绝应用，避免覆盖未暂存的修改
+///
+/// 已跟踪的文件不能被修改或删除；要新建的文件不能与工作区中已有的未跟踪文件冲突。
+fn ensure_worktree_matches(
+    ours: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+    conflicts: &[Conflict],
+    repo_path: &Path,
+) -> Result<(), String> {
+    let skipped = read_skip_worktree(&index_file(repo_path));
+    let mut touched: Vec<&String> = ours
+        .keys()
+        .chain(new.keys())
+        .filter(|path| ours.get(*path) != new.get(*path))
+        .chain(conflicts.iter().filter_map(|c| match c {
+            Conflict::Content { path, .. } => Some(path),
+            Conflict::RenameRename { .. } => None,
+        }))
+        .filter(|path| !skipped.contains(*path))
+        .collect();
+    touched.sort();
+    touched.dedup();
+
+    for path in touched {
+        let on_disk = fs::read(path).ok().map(|content| sha1_hash(&content));
+        let dirty = match ours.get(path) {
+            Some(hash) => on_disk.as_ref() != Some(hash),
+            None => on_disk.is_some_and(|hash| new.get(path) != Some(&hash)),
+        };
+        if dirty {
+            return Err(format!("工作区中的 {} 有未提交的修改，应用提交会覆盖它，请先提交或丢弃这些修改", path));
+        }
+    }
+    Ok(())
+}
+
+/// 把 index 与工作区强制恢复到指定提交（`None` 表示空树），`extra` 中的文件若不在目标中也一并删除
+pub fn reset_hard(commit: Option<&str>, extra: &[String], repo_path: &Path) -> io::Result<()> {
+    let target = match commit {
+        Some(hash) => read_tree_entries(&read_commit(hash, repo_path)?.tree, repo_path)?,
+        None => HashMap::new(),
+    };
+    let index_path = index_file(repo_path);
+    let lock = LockFile::acquire(&index_path)?;
+    let current = read_index_map(&index_path);
+    let cone = load_sparse(repo_path)?;
+
+    for path in current.keys().chain(extra.iter()) {
+        if !target.contains_key(path) {
+            remove_worktree_file(path)?;
+        }
+    }
+    for (path, hash) in &target {
+        if cone.as_ref().is_none_or(|cone| cone.includes(path)) {
+            checkout_file(path, hash, repo_path)?;
+        }
+    }
+    commit_index_map(lock, &index_path, &target)
+}
+
+/// 把工作区从 `old` 快照更新为 `new` 快照，只改动有差异的文件（未跟踪文件保持不动）
+///
+/// 稀疏检出范围之外的文件不写入工作区。
+fn apply_to_worktree(
+    old: &HashMap<String, String>,
+    new: &HashMap<String, String>,
+    repo_path: &Path,
+) -> io::Result<()> {
+    let cone = load_sparse(repo_path)?;
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            remove_worktree_file(path)?;
+        }
+    }
+    for (path, hash) in new.iter().filter(|(path, _)| cone.as_ref().is_none_or(|cone| cone.includes(path))) {
+        if old.get(path) != Some(hash) || !Path::new(path).exists() {
+            checkout_file(path, hash, repo_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 生成带冲突标记的文件内容
+fn conflict_markers(ours: Option<&String>, theirs: Option<&String>, label: &str, repo_path: &Path) -> Vec<u8> {
+    let mut out = b"<<<<<<< HEAD\n".to_vec();
+    for (side, separator) in [(ours, "=======\n".to_string()), (theirs, format!(">>>>>>> {}\n", label))] {
+        let content = side.and_then(|h| read_object(h, repo_path).ok()).unwrap_or_default();
+        out.extend_from_slice(&content);
+        if !content.is_empty() && !content.ends_with(b"\n") {
+            out.push(b'\n');
+        }
+        out.extend_from_slice(separator.as_bytes());
+    }
+    out
+}
@@ -0,0 +1,350 @@
+//! git pack 协议的服务端部分：`upload-pack`（协议 v2）与 `receive-pack`（协议 v0），
+//! 以及把它们暴露为 smart HTTP 的路由
+//!
+//! 协议中的对象 id 与 packfile 都是 git 格式的，与本地对象的转换见 [`crate::core::object_map`]。
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use crate::core::http::{Request, Response};
+use crate::core::object::object_exists;
+use crate::core::object_map::ObjectMap;
+use crate::core::pack::{read_pack, write_pack};
+use crate::core::pktline::{read_lines, read_packet, write_delim, write_flush, write_line, write_sideband, Packet};
+use crate::core::protocol::{AGENT, ZERO_ID};
+use crate::core::reference::{check_ref_format, delete_ref, list_refs, read_ref, RefFormatOptions};
+use crate::core::refs::{RefStore, RefValue};
+use crate::core::remote::{open_remote, RemoteRepo};
+use crate::core::config::load_config;
+use crate::core::transport::{RefCommand, RefResult, Service};
+use crate::core::worktree::find_branch_checkout;
+
+/// 读取 HEAD：返回 (指向的引用, 对应的提交)；分离 HEAD 时第一项为 None
+pub fn read_head(git_dir: &Path) -> (Option<String>, Option<String>) {
+    match RefStore::new(git_dir).read("HEAD") {
+        Some(RefValue::Symbolic(target)) => {
+            let hash = read_ref(git_dir, &target);
+            (Some(target), hash)
+        }
+        Some(RefValue::Direct(hash)) => (None, Some(hash)),
+        None => (None, None),
+    }
+}
+
+/// 分支与标签及其 git 提交 id，同时让这些提交的对应关系进入缓存
+///
+/// 不指向提交的引用无法用 git 的 id 表示，不公布。
+fn git_refs(repo: &RemoteRepo, map: &mut ObjectMap) -> Vec<(String, String)> {
+    let mut refs = list_refs(&repo.git_dir, "refs/heads/");
+    refs.extend(list_refs(&repo.git_dir, "refs/tags/"));
+    refs.into_iter().filter_map(|(name, hash)| map.git_commit(&hash).ok().map(|git| (name, git))).collect()
+}
+
+/// 向客户端报告错误并中止会话
+fn reject<W: Write>(writer: &mut W, message: &str) -> io::Error {
+    let _ = write_line(writer, &format!("ERR {}", message));
+    let _ = writer.flush();
+    io::Error::other(message.to_string())
+}
+
+/// 完整的 upload-pack 会话：公布能力后依次处理命令，直到客户端关闭连接
+pub fn serve_upload_pack<R: Read, W: Write>(repo: &RemoteRepo, reader: &mut R, writer: &mut W) -> io::Result<()> {
+    advertise_upload_pack(writer)?;
+    serve_commands(repo, reader, writer)
+}
+
+/// 公布协议 v2 的能力
+pub fn advertise_upload_pack<W: Write>(writer: &mut W) -> io::Result<()> {
+    write_line(writer, "version 2")?;
+    write_line(writer, &format!("agent={}", AGENT))?;
+    write_line(writer, "ls-refs=unborn")?;
+    write_line(writer, "fetch")?;
+    write_line(writer, "object-format=sha1")?;
+    write_flush(writer)
+}
+
+/// 处理一条协议 v2 命令；客户端已关闭连接时返回 false
+pub fn handle_upload_pack_command<R: Read, W: Write>(repo: &RemoteRepo, reader: &mut R, writer: &mut W) -> io::Result<bool> {
+    let command = match read_packet(reader)? {
+        None => return Ok(false),
+        Some(Packet::Flush) => return Ok(true),
+        Some(packet) => packet.text().unwrap_or_default(),
+    };
+    let Some(command) = command.strip_prefix("command=").map(str::to_string) else {
+        return Err(reject(writer, &format!("只支持协议 v2，收到 '{}'", command)));
+    };
+    // 命令的能力行（agent、object-format 等）目前不需要处理
+    let (_, end) = read_lines(reader)?;
+    let args = if end == Packet::Delim { read_lines(reader)?.0 } else { Vec::new() };
+
+    match command.as_str() {
+        "ls-refs" => ls_refs(repo, &args, writer)?,
+        "fetch" => fetch(repo, &args, writer)?,
+        _ => return Err(reject(writer, &format!("未知的命令 '{}'", command))),
+    }
+    Ok(true)
+}
+
+fn ls_refs<W: Write>(repo: &RemoteRepo, args: &[String], writer: &mut W) -> io::Result<()> {
+    let mut map = ObjectMap::load(&repo.git_dir)?;
+    let prefixes: Vec<&str> = args.iter().filter_map(|a| a.strip_prefix("ref-prefix ")).collect();
+    let wanted = |name: &str| prefixes.is_empty() || prefixes.iter().any(|p| name.starts_with(p));
+    let symrefs = args.iter().any(|a| a == "symrefs");
+    let unborn = args.iter().any(|a| a == "unborn");
+
+    if wanted("HEAD") {
+        let (target, hash) = read_head(&repo.git_dir);
+        let hash = hash.and_then(|hash| map.git_commit(&hash).ok());
+        let symref = target.filter(|_| symrefs || unborn).map(|t| format!(" symref-target:{}", t)).unwrap_or_default();
+        match hash {
+            Some(hash) => write_line(writer, &format!("{} HEAD{}", hash, symref))?,
+            None if unborn && !symref.is_empty() => write_line(writer, &format!("unborn HEAD{}", symref))?,
+            None => {}
+        }
+    }
+    for (name, hash) in git_refs(repo, &mut map) {
+        if wanted(&name) {
+            write_line(writer, &format!("{} {}", hash, name))?;
+        }
+    }
+    // 对应关系只是缓存，只读的仓库写不进去也不影响服务
+    let _ = map.save();
+    write_flush(writer)
+}
+
+fn fetch<W: Write>(repo: &RemoteRepo, args: &[String], writer: &mut W) -> io::Result<()> {
+    let wants: Vec<String> = args.iter().filter_map(|a| a.strip_prefix("want ")).map(str::to_string).collect();
+    let haves: Vec<String> = args.iter().filter_map(|a| a.strip_prefix("have ")).map(str::to_string).collect();
+    let done = args.iter().any(|a| a == "done");
+
+    let mut map = ObjectMap::load(&repo.git_dir)?;
+    git_refs(repo, &mut map);
+    let mut local_wants = Vec::new();
+    for want in &wants {
+        match map.local_commit(want) {
+            Some(local) => local_wants.push(local),
+            None => return Err(reject(writer, &format!("upload-pack: not our ref {}", want))),
+        }
+    }
+    let common: Vec<(&String, String)> = haves.iter().filter_map(|h| map.local_commit(h).map(|local| (h, local))).collect();
+
+    if !done {
+        // 协商只需一轮：确认双方共有的提交，然后直接发送 packfile
+        write_line(writer, "acknowledgments")?;
+        if common.is_empty() {
+            write_line(writer, "NAK")?;
+        }
+        for (have, _) in &common {
+            write_line(writer, &format!("ACK {}", have))?;
+        }
+        write_line(writer, "ready")?;
+        write_delim(writer)?;
+    }
+
+    let local_haves: Vec<String> = common.into_iter().map(|(_, local)| local).collect();
+    let objects = map.git_objects(&local_wants, &local_haves)?;
+    let _ = map.save();
+    let pack = write_pack(&objects)?;
+    write_line(writer, "packfile")?;
+    write_sideband(writer, 1, &pack)?;
+    write_flush(writer)
+}
+
+/// 完整的 receive-pack 会话：公布引用，接收命令与 packfile，更新引用并报告结果
+pub fn serve_receive_pack<R: BufRead, W: Write>(repo: &RemoteRepo, reader: &mut R, writer: &mut W) -> io::Result<()> {
+    advertise_receive_pack(repo, writer)?;
+    handle_receive_pack(repo, reader, writer)
+}
+
+/// 公布分支与标签（协议 v0），第一行附带能力列表
+pub fn advertise_receive_pack<W: Write>(repo: &RemoteRepo, writer: &mut W) -> io::Result<()> {
+    // 只能还原同一个 packfile 中的 delta，要求对方不要发送 thin pack
+    let capabilities = format!("report-status delete-refs ofs-delta no-thin agent={}", AGENT);
+    let mut map = ObjectMap::load(&repo.git_dir)?;
+    let refs = git_refs(repo, &mut map);
+    let _ = map.save();
+    if refs.is_empty() {
+        write_line(writer, &format!("{} capabilities^{{}}\0{}", ZERO_ID, capabilities))?;
+    }
+    for (i, (name, hash)) in refs.iter().enumerate() {
+        if i == 0 {
+            write_line(writer, &format!("{} {}\0{}", hash, name, capabilities))?;
+        } else {
+            write_line(writer, &format!("{} {}", hash, name))?;
+        }
+    }
+    write_flush(writer)
+}
+
+/// 接收引用更新命令与 packfile，应用后写出 report-status
+pub fn handle_receive_pack<R: BufRead, W: Write>(repo: &RemoteRepo, reader: &mut R, writer: &mut W) -> io::Result<()> {
+    let mut commands = Vec::new();
+    loop {
+        let packet = match read_packet(reader)? {
+            // 客户端没有要更新的引用时直接断开
+            None if commands.is_empty() => return Ok(()),
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "命令列表不完整")),
+            Some(packet) => packet,
+        };
+        let Some(line) = packet.text() else { break };
+        let line = line.split('\0').next().unwrap_or_default();
+        let mut parts = line.splitn(3, ' ');
+        let (Some(old), Some(new), Some(name)) = (parts.next(), parts.next(), parts.next()) else {
+            return Err(reject(writer, &format!("无法解析的命令 '{}'", line)));
+        };
+        let id = |hash: &str| Some(hash.to_string()).filter(|h| h != ZERO_ID);
+        commands.push(RefCommand { name: name.to_string(), old: id(old), new: id(new) });
+    }
+    if commands.is_empty() {
+        return Ok(());
+    }
+
+    let mut map = ObjectMap::load(&repo.git_dir)?;
+    let unpacked = if commands.iter().any(|c| c.new.is_some()) {
+        read_pack(&mut *reader).and_then(|objects| map.import(&objects)).and_then(|_| map.save())
+    } else {
+        Ok(())
+    };
+    // 命令中的 git id 换成本地 id；本地没有的对象保持原样，按旧值不符或缺少对象拒绝
+    git_refs(repo, &mut map);
+    let local = |hash: &String| map.local_commit(hash).unwrap_or_else(|| hash.clone());
+    let commands: Vec<RefCommand> = commands
+        .iter()
+        .map(|c| RefCommand { name: c.name.clone(), old: c.old.as_ref().map(local), new: c.new.as_ref().map(local) })
+        .collect();
+    let results = match &unpacked {
+        Ok(()) => apply_ref_commands(repo, &commands),
+        Err(_) => commands.iter().map(|c| (c.name.clone(), Err("unpacker error".to_string()))).collect(),
+    };
+
+    match &unpacked {
+        Ok(()) => write_line(writer, "unpack ok")?,
+        Err(e) => write_line(writer, &format!("unpack {}", e))?,
+    }
+    for (name, result) in results {
+        match result {
+            Ok(()) => write_line(writer, &format!("ok {}", name))?,
+            Err(reason) => write_line(writer, &format!("ng {} {}", name, reason))?,
+        }
+    }
+    write_flush(writer)
+}
+
+/// 在仓库中执行引用更新命令
+///
+/// 引用的当前值必须与命令中的旧值一致；在工作区中检出的分支（包括关联工作区）默认拒绝更新
+/// （可用 `receive.denyCurrentBranch = ignore` 关闭）。
+pub fn apply_ref_commands(repo: &RemoteRepo, commands: &[RefCommand]) -> Vec<RefResult> {
+    let config = load_config(&repo.git_dir).unwrap_or_default();
+    let deny_current = !matches!(
+        config.get("receive.denycurrentbranch").map(str::to_lowercase).as_deref(),
+        Some("ignore") | Some("warn") | Some("false")
+    );
+    let checked_out = |name: &str| find_branch_checkout(&repo.git_dir, name, None).is_some_and(|wt| wt.name.is_some() || !repo.bare);
+
+    let mut seen = HashSet::new();
+    commands
+        .iter()
+        .map(|command| {
+            let name = &command.name;
+            let funny = command.new.is_some() && check_ref_format(name, RefFormatOptions::default()).is_err();
+            let result = if !name.starts_with("refs/") || funny || !seen.insert(name.clone()) {
+                Err("funny refname".to_string())
+            } else if read_ref(&repo.git_dir, name) != command.old {
+                Err("stale info".to_string())
+            } else if deny_current && checked_out(name) {
+                Err("branch is currently checked out".to_string())
+            } else {
+                // 在锁内再次核对旧值，防止与并发的推送互相覆盖
+                let store = RefStore::new(&repo.git_dir);
+                let mut transaction = store.transaction();
+                match (&command.new, &command.old) {
+                    (Some(new), _) if !object_exists(new, &repo.git_dir) => Err("missing necessary objects".to_string()),
+                    (Some(new), old) => {
+                        transaction.update_if(name, new, old.as_deref());
+                        transaction.commit().map_err(|_| "failed to update ref".to_string())
+                    }
+                    (None, Some(old)) => {
+                        transaction.delete_if(name, old);
+                        transaction.commit().map_err(|_| "failed to delete".to_string())
+                    }
+                    (None, None) => delete_ref(&repo.git_dir, name).map_err(|_| "failed to delete".to_string()),
+                }
+            };
+            (name.clone(), result)
+        })
+        .collect()
+}
+
+/// smart HTTP 服务：把请求路由到 `base` 目录下对应仓库的 upload-pack / receive-pack
+///
+/// - `GET <仓库>/info/refs?service=git-upload-pack|git-receive-pack`：能力或引用公布
+/// - `POST <仓库>/git-upload-pack`、`POST <仓库>/git-receive-pack`：执行命令
+///
+/// 与 git 一样，只有仓库配置了 `http.receivepack = true` 才允许推送。
+pub fn http_backend(base: &Path, request: &Request) -> Response {
+    let path = request.path.trim_start_matches('/');
+    let (repo_part, service, advertisement) = if let Some(repo) = path.strip_suffix("info/refs") {
+        match request.param("service") {
+            Some("git-upload-pack") => (repo, Service::UploadPack, true),
+            Some("git-receive-pack") => (repo, Service::ReceivePack, true),
+            _ => return Response::error(403, "只支持 smart HTTP（需要 service 参数）"),
+        }
+    } else if let Some(repo) = path.strip_suffix("git-upload-pack") {
+        (repo, Service::UploadPack, false)
+    } else if let Some(repo) = path.strip_suffix("git-receive-pack") {
+        (repo, Service::ReceivePack, false)
+    } else {
+        return Response::error(404, "Not Found");
+    };
+
+    let expected_method = if advertisement { "GET" } else { "POST" };
+    if request.method != expected_method {
+        return Response::error(405, &format!("{} 只接受 {} 请求", request.path, expected_method));
+    }
+    let request_type = format!("application/x-git-{}-request", service.name());
+    if !advertisement && request.header("Content-Type") != Some(request_type.as_str()) {
+        return Response::error(415, &format!("请求的 Content-Type 应为 {}", request_type));
+    }
+
+    // 不允许通过 `..` 访问服务目录之外的路径
+    let repo_part = repo_part.trim_end_matches('/');
+    if repo_part.split('/').any(|c| c == "..") {
+        return Response::error(404, "仓库不存在");
+    }
+    let Ok(repo) = open_remote(&base.join(repo_part).to_string_lossy()) else {
+        return Response::error(404, "仓库不存在");
+    };
+
+    if service == Service::ReceivePack {
+        let config = load_config(&repo.git_dir).unwrap_or_default();
+        if config.get("http.receivepack").map(str::to_lowercase).as_deref() != Some("true") {
+            return Response::error(403, "Service not enabled: 'receive-pack'");
+        }
+    }
+
+    let mut body = Vec::new();
+    let mut reader = request.body.as_slice();
+    // 协议错误已经以 ERR 数据包写入响应，由客户端报告
+    let _ = match (service, advertisement) {
+        (Service::UploadPack, true) => advertise_upload_pack(&mut body),
+        (Service::UploadPack, false) => serve_commands(&repo, &mut reader, &mut body),
+        (Service::ReceivePack, true) => write_line(&mut body, "# service=git-receive-pack")
+            .and_then(|_| write_flush(&mut body))
+            .and_then(|_| advertise_receive_pack(&repo, &mut body)),
+        (Service::ReceivePack, false) => handle_receive_pack(&repo, &mut reader, &mut body),
+    };
+
+    let content_type = if advertisement {
+        format!("application/x-git-{}-advertisement", service.name())
+    } else {
+        format!("application/x-git-{}-result", service.name())
+    };
+    Response::new(200, &content_type, body)
+}
+
+/// 依次处理请求体中的所有 upload-pack 命令
+fn serve_commands<R: Read, W: Write>(repo: &RemoteRepo, reader: &mut R, writer: &mut W) -> io::Result<()> {
+    while handle_upload_pack_command(repo, reader, writer)? {}
+    Ok(())
+}
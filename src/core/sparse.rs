@@ -0,0 +1,177 @@
+//! 稀疏检出（cone 模式）
+//!
+//! 每个工作区的 `info/sparse-checkout` 以 git 的 cone 格式记录要检出的目录：顶层文件总是检出，
+//! 列出的目录递归检出，这些目录的各级上级目录只检出直接位于其中的文件。
+//! index 仍然记录完整的 tree，范围之外且工作区中不存在的条目带有 skip-worktree 标记，
+//! 比较工作区与 index、提交时都按未修改处理。
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::index::{index_file, read_index_map, read_skip_worktree, write_index_map};
+use crate::core::tree::{checkout_file, remove_worktree_file, verify_tree_path};
+use crate::utils::fs::LockFile;
+use crate::utils::hash::sha1_hash;
+
+/// cone 模式的稀疏检出范围：递归检出的目录集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseCone {
+    dirs: BTreeSet<String>,
+}
+
+impl SparseCone {
+    /// 由目录列表创建；目录两端的 `/` 与开头的 `./` 会被去掉，已被其他目录包含的目录会被合并
+    pub fn new<S: AsRef<str>>(dirs: impl IntoIterator<Item = S>) -> Result<SparseCone, String> {
+        let mut normalized = BTreeSet::new();
+        for dir in dirs {
+            let dir = dir.as_ref();
+            let trimmed = dir.trim_start_matches("./").trim_matches('/');
+            verify_tree_path(trimmed).map_err(|reason| format!("无效的目录 '{}': {}", dir, reason))?;
+            normalized.insert(trimmed.to_string());
+        }
+        let dirs = normalized
+            .iter()
+            .filter(|dir| !normalized.iter().any(|other| dir.starts_with(&format!("{}/", other))))
+            .cloned()
+            .collect();
+        Ok(SparseCone { dirs })
+    }
+
+    /// 递归检出的目录，按名字排序
+    pub fn dirs(&self) -> impl Iterator<Item = &str> {
+        self.dirs.iter().map(String::as_str)
+    }
+
+    /// 路径是否在检出范围之内
+    pub fn includes(&self, path: &str) -> bool {
+        let Some((parent, _)) = path.rsplit_once('/') else {
+            return true;
+        };
+        self.dirs.iter().any(|dir| {
+            path.starts_with(&format!("{}/", dir)) || *dir == parent || dir.starts_with(&format!("{}/", parent))
+        })
+    }
+
+    /// 生成 `info/sparse-checkout` 的内容（git 的 cone 格式）
+    pub fn to_patterns(&self) -> String {
+        // 目录 -> 是否递归检出；上级目录只检出直接包含的文件
+        let mut entries: BTreeMap<String, bool> = BTreeMap::new();
+        for dir in &self.dirs {
+            let mut prefix = String::new();
+            for part in dir.split('/') {
+                if !prefix.is_empty() {
+                    entries.entry(prefix.clone()).or_insert(false);
+                    prefix.push('/');
+                }
+                prefix.push_str(part);
+            }
+            entries.insert(dir.clone(), true);
+        }
+        let mut content = String::from("/*\n!/*/\n");
+        for (dir, recursive) in entries {
+            content.push_str(&format!("/{}/\n", dir));
+            if !recursive {
+                content.push_str(&format!("!/{}/*/\n", dir));
+            }
+        }
+        content
+    }
+
+    /// 解析 cone 格式的 `info/sparse-checkout`
+    pub fn parse(content: &str) -> Result<SparseCone, String> {
+        let mut dirs = BTreeSet::new();
+        let mut parents = BTreeSet::new();
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            if line == "/*" || line == "!/*/" {
+                continue;
+            }
+            if let Some(parent) = line.strip_prefix("!/").and_then(|l| l.strip_suffix("/*/")) {
+                parents.insert(parent.to_string());
+            } else if let Some(dir) = line.strip_prefix('/').and_then(|l| l.strip_suffix('/')).filter(|d| !d.contains('*')) {
+                dirs.insert(dir.to_string());
+            } else {
+                return Err(format!("不是 cone 模式的模式: '{}'", line));
+            }
+        }
+        SparseCone::new(dirs.difference(&parents))
+    }
+}
+
+/// 稀疏检出的模式文件（每个工作区一份）
+pub fn sparse_checkout_file(repo_path: &Path) -> PathBuf {
+    repo_path.join("info").join("sparse-checkout")
+}
+
+/// 读取稀疏检出范围，未启用稀疏检出时为 None
+pub fn load_sparse(repo_path: &Path) -> io::Result<Option<SparseCone>> {
+    let path = sparse_checkout_file(repo_path);
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    SparseCone::parse(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+/// 保存稀疏检出范围（同时启用稀疏检出）
+pub fn save_sparse(repo_path: &Path, cone: &SparseCone) -> io::Result<()> {
+    let path = sparse_checkout_file(repo_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut lock = LockFile::acquire(&path)?;
+    lock.write_all(cone.to_patterns().as_bytes())?;
+    lock.commit()
+}
+
+/// 关闭稀疏检出：删除模式文件
+pub fn remove_sparse(repo_path: &Path) -> io::Result<()> {
+    match fs::remove_file(sparse_checkout_file(repo_path)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// 删除文件后清理变空的上级目录
+fn remove_empty_parents(path: &str) {
+    let mut dir = Path::new(path).parent();
+    while let Some(d) = dir.filter(|d| !d.as_os_str().is_empty()) {
+        if fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+/// 按当前的稀疏检出范围更新工作区，并重新计算 index 中的 skip-worktree 标记
+///
+/// 范围内带 skip-worktree 标记的文件被检出；范围外未修改的文件被删除，有修改的文件保留在工作区中并返回。
+pub fn reapply_sparse(repo_path: &Path) -> io::Result<Vec<String>> {
+    let cone = load_sparse(repo_path)?;
+    let index_path = index_file(repo_path);
+    let index = read_index_map(&index_path);
+    let skipped = read_skip_worktree(&index_path);
+    let mut kept = Vec::new();
+
+    let mut paths: Vec<&String> = index.keys().collect();
+    paths.sort();
+    for path in paths {
+        let hash = &index[path];
+        let included = cone.as_ref().is_none_or(|cone| cone.includes(path));
+        let present = fs::symlink_metadata(path).is_ok();
+        if included && !present && skipped.contains(path) {
+            checkout_file(path, hash, repo_path)?;
+        } else if !included && present {
+            if fs::read(path).is_ok_and(|content| sha1_hash(&content) == *hash) {
+                remove_worktree_file(path)?;
+                remove_empty_parents(path);
+            } else {
+                kept.push(path.clone());
+            }
+        }
+    }
+    write_index_map(&index_path, &index)?;
+    Ok(kept)
+}
@@ -0,0 +1,364 @@
+//! 与远程仓库交换引用与对象的传输层
+//!
+//! - 本机路径：直接读写对方的对象库与引用（[`LocalTransport`]）
+//! - `file://` 地址：启动 `rust-git upload-pack` / `rust-git receive-pack` 子进程，
+//!   通过管道使用 git 的 pack 协议通信（[`StdioTransport`]）；对方是 C git 的仓库时
+//!   （本机路径也一样）改为启动 `git upload-pack` / `git receive-pack`
+//! - `http://` 地址：smart HTTP，每个请求携带一条协议消息（[`HttpTransport`]）
+//!
+//! pack 协议中的对象 id 是 git 的 id，在这里与本地 id 互相转换（见 [`crate::core::object_map`]），
+//! [`Transport`] 的调用者只看到本地 id；本地还没有的对象保留远程公布的 id。
+
+use std::collections::HashMap;
+use std::io::{self, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use crate::core::config::GIT_DIR_NAME;
+use crate::core::http::{send_request, Response};
+use crate::core::object::{copy_missing_objects, object_exists};
+use crate::core::object_map::ObjectMap;
+use crate::core::pack::{write_pack, PackObjects};
+use crate::core::pktline::{read_packet, Packet};
+use crate::core::protocol;
+use crate::core::reference::list_refs;
+use crate::core::remote::{open_remote, RemoteRepo};
+use crate::core::repo_config::RepoConfig;
+use crate::core::repository::is_git_dir;
+use crate::core::serve::{apply_ref_commands, read_head};
+
+/// 远程端提供的服务
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    /// 供 fetch / clone 读取
+    UploadPack,
+    /// 供 push 写入
+    ReceivePack,
+}
+
+impl Service {
+    /// 服务名，同时也是对应的子命令名
+    pub fn name(&self) -> &'static str {
+        match self {
+            Service::UploadPack => "upload-pack",
+            Service::ReceivePack => "receive-pack",
+        }
+    }
+}
+
+/// 远程端公布的引用
+#[derive(Debug, Clone, Default)]
+pub struct AdvertisedRefs {
+    /// 分支与标签：(完整引用名, hash)
+    pub refs: Vec<(String, String)>,
+    /// 远程 HEAD 指向的引用（如 `refs/heads/master`），未知或分离时为 None
+    pub head: Option<String>,
+}
+
+impl AdvertisedRefs {
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.refs.iter().find(|(n, _)| n == name).map(|(_, hash)| hash.clone())
+    }
+}
+
+/// 一条远程引用更新命令；old 为 None 表示新建，new 为 None 表示删除
+#[derive(Debug, Clone)]
+pub struct RefCommand {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// 远程端对一条引用更新的处理结果：成功，或被拒绝及原因
+pub type RefResult = (String, Result<(), String>);
+
+pub trait Transport {
+    /// 列出远程的分支、标签以及 HEAD
+    fn list_refs(&mut self, repo_path: &Path) -> io::Result<AdvertisedRefs>;
+
+    /// 取回 `wants` 可达的对象；`haves` 是本地已有的提交，对方据此省去共同的历史
+    ///
+    /// 返回每个 want 取回后在本地的 id；没能取回的（如指向 tree 的标签）不在其中。
+    fn fetch_objects(&mut self, wants: &[String], haves: &[String], repo_path: &Path) -> io::Result<HashMap<String, String>>;
+
+    /// 发送所需的对象并请求远程更新引用
+    fn push_refs(&mut self, commands: &[RefCommand], repo_path: &Path) -> io::Result<Vec<RefResult>>;
+}
+
+/// 根据地址选择传输方式
+pub fn open_transport(url: &str, service: Service) -> io::Result<Box<dyn Transport>> {
+    if let Some(path) = url.strip_prefix("file://") {
+        return Ok(Box::new(StdioTransport::spawn(service, path)?));
+    }
+    if url.starts_with("http://") {
+        return Ok(Box::new(HttpTransport::connect(url, service)?));
+    }
+    if url.contains("://") {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, format!("不支持的协议: '{}'", url)));
+    }
+    if is_c_git_repository(Path::new(url)) {
+        return Ok(Box::new(StdioTransport::spawn(service, url)?));
+    }
+    Ok(Box::new(LocalTransport { remote: open_remote(url)? }))
+}
+
+/// 路径是否是 C git 的仓库：有 `.git`，或者是配置中有 `core.repositoryformatversion` 的 git 目录
+/// （rust-git 不写这一项）；同时有 rust-git 仓库目录时按 rust-git 仓库处理
+pub fn is_c_git_repository(path: &Path) -> bool {
+    if path.join(&*GIT_DIR_NAME).exists() {
+        return false;
+    }
+    path.join(".git").exists()
+        || (is_git_dir(path)
+            && RepoConfig::load(&path.join("config")).is_ok_and(|config| config.get("core.repositoryformatversion").is_some()))
+}
+
+/// 远程地址是否为 URL（而非本机路径）
+pub fn is_url(url: &str) -> bool {
+    url.contains("://")
+}
+
+/// 远程公布的 git id 换成本地 id，本地还没有的对象保持原样
+fn localize(advertised: &AdvertisedRefs, repo_path: &Path) -> io::Result<AdvertisedRefs> {
+    let map = ObjectMap::load(repo_path)?;
+    let refs = advertised.refs.iter().map(|(name, hash)| (name.clone(), map.local_commit(hash).unwrap_or_else(|| hash.clone()))).collect();
+    Ok(AdvertisedRefs { refs, head: advertised.head.clone() })
+}
+
+/// 本地 id 换成 git 的 id；本地没有的对象（即远程公布的 id）原样返回
+fn to_wire(hash: &str, map: &mut ObjectMap, repo_path: &Path) -> io::Result<String> {
+    if object_exists(hash, repo_path) {
+        map.git_commit(hash)
+    } else {
+        Ok(hash.to_string())
+    }
+}
+
+/// 本地已有的提交换成 git 的 id；无法转换的（不是提交）不作为 have 发送
+fn wire_haves(haves: &[String], map: &mut ObjectMap) -> Vec<String> {
+    haves.iter().filter_map(|have| map.git_commit(have).ok()).collect()
+}
+
+/// 导入取回的对象，返回每个 want 在本地的 id
+fn import_fetched(objects: &PackObjects, wants: &[String], map: &mut ObjectMap) -> io::Result<HashMap<String, String>> {
+    map.import(objects)?;
+    map.save()?;
+    Ok(wants.iter().filter_map(|want| map.local_commit(want).map(|local| (want.clone(), local))).collect())
+}
+
+/// 把更新命令中的本地 id 换成 git 的 id，并生成需要发送的 packfile（只有删除时为 None）
+///
+/// `advertised` 是远程公布的引用（git id），其中本地也有的提交不必再发送。
+fn prepare_push(
+    commands: &[RefCommand],
+    advertised: &AdvertisedRefs,
+    repo_path: &Path,
+) -> io::Result<(Vec<RefCommand>, Option<Vec<u8>>)> {
+    let mut map = ObjectMap::load(repo_path)?;
+    let mut wire = Vec::new();
+    for command in commands {
+        let old = command.old.as_deref().map(|old| to_wire(old, &mut map, repo_path)).transpose()?;
+        let new = command.new.as_deref().map(|new| to_wire(new, &mut map, repo_path)).transpose()?;
+        wire.push(RefCommand { name: command.name.clone(), old, new });
+    }
+
+    let tips: Vec<String> = commands.iter().filter_map(|c| c.new.clone()).collect();
+    if tips.is_empty() {
+        return Ok((wire, None));
+    }
+    let haves: Vec<String> = advertised.refs.iter().filter_map(|(_, hash)| map.local_commit(hash)).collect();
+    let objects = map.git_objects(&tips, &haves)?;
+    map.save()?;
+    Ok((wire, Some(write_pack(&objects)?)))
+}
+
+/// 直接访问本机上的仓库
+pub struct LocalTransport {
+    remote: RemoteRepo,
+}
+
+impl Transport for LocalTransport {
+    fn list_refs(&mut self, _repo_path: &Path) -> io::Result<AdvertisedRefs> {
+        let git_dir = &self.remote.git_dir;
+        let mut refs = list_refs(git_dir, "refs/heads/");
+        refs.extend(list_refs(git_dir, "refs/tags/"));
+        Ok(AdvertisedRefs { refs, head: read_head(git_dir).0 })
+    }
+
+    fn fetch_objects(&mut self, wants: &[String], _haves: &[String], repo_path: &Path) -> io::Result<HashMap<String, String>> {
+        copy_missing_objects(wants, &self.remote.git_dir, repo_path)?;
+        Ok(wants.iter().map(|want| (want.clone(), want.clone())).collect())
+    }
+
+    fn push_refs(&mut self, commands: &[RefCommand], repo_path: &Path) -> io::Result<Vec<RefResult>> {
+        let tips: Vec<String> = commands.iter().filter_map(|c| c.new.clone()).collect();
+        copy_missing_objects(&tips, repo_path, &self.remote.git_dir)?;
+        Ok(apply_ref_commands(&self.remote, commands))
+    }
+}
+
+/// 通过子进程的标准输入输出与 `upload-pack` / `receive-pack` 通信
+///
+/// `upload-pack` 使用协议 v2（能力公布后可以连续发送 `ls-refs`、`fetch` 等命令）；
+/// `receive-pack` 使用协议 v0（先公布引用，再接收命令与 packfile）。
+/// 对方是 C git 的仓库时启动 `git`，否则启动 rust-git 自身。
+pub struct StdioTransport {
+    service: Service,
+    child: Child,
+    input: Option<ChildStdin>,
+    output: BufReader<ChildStdout>,
+    /// receive-pack 连接时公布的引用（git id）
+    advertised: AdvertisedRefs,
+}
+
+impl StdioTransport {
+    pub fn spawn(service: Service, path: &str) -> io::Result<StdioTransport> {
+        let program = if is_c_git_repository(Path::new(path)) { "git".into() } else { std::env::current_exe()? };
+        let mut child = Command::new(program)
+            .arg(service.name())
+            .arg(path)
+            .env("GIT_PROTOCOL", "version=2")
+            // 服务端的标准输出是协议数据，不能混入详细输出
+            .env_remove("RUST_GIT_VERBOSE")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let input = child.stdin.take();
+        let output = BufReader::new(child.stdout.take().ok_or_else(|| io::Error::other("无法读取子进程输出"))?);
+        let mut transport = StdioTransport { service, child, input, output, advertised: AdvertisedRefs::default() };
+
+        match service {
+            Service::UploadPack => {
+                protocol::read_capabilities(&mut transport.output)?;
+            }
+            Service::ReceivePack => {
+                transport.advertised = protocol::read_receive_advertisement(&mut transport.output)?;
+            }
+        }
+        Ok(transport)
+    }
+
+    fn input(&mut self) -> io::Result<&mut ChildStdin> {
+        self.input.as_mut().ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "连接已关闭"))
+    }
+}
+
+impl Transport for StdioTransport {
+    fn list_refs(&mut self, repo_path: &Path) -> io::Result<AdvertisedRefs> {
+        if self.service == Service::ReceivePack {
+            return localize(&self.advertised, repo_path);
+        }
+        protocol::write_ls_refs_request(self.input()?)?;
+        localize(&protocol::read_ls_refs_response(&mut self.output)?, repo_path)
+    }
+
+    fn fetch_objects(&mut self, wants: &[String], haves: &[String], repo_path: &Path) -> io::Result<HashMap<String, String>> {
+        let mut map = ObjectMap::load(repo_path)?;
+        let haves = wire_haves(haves, &mut map);
+        protocol::write_fetch_request(self.input()?, wants, &haves)?;
+        let objects = protocol::read_fetch_response(&mut self.output)?;
+        import_fetched(&objects, wants, &mut map)
+    }
+
+    fn push_refs(&mut self, commands: &[RefCommand], repo_path: &Path) -> io::Result<Vec<RefResult>> {
+        let (commands, pack) = prepare_push(commands, &self.advertised, repo_path)?;
+        let input = self.input()?;
+        protocol::write_push_request(input, &commands, pack.as_deref())?;
+        input.flush()?;
+        protocol::read_push_report(&mut self.output)
+    }
+}
+
+impl Drop for StdioTransport {
+    fn drop(&mut self) {
+        // 关闭标准输入后服务端读到 EOF 自行退出
+        drop(self.input.take());
+        let _ = self.child.wait();
+    }
+}
+
+/// 通过 smart HTTP 访问远程仓库
+///
+/// 连接时先 `GET <地址>/info/refs?service=git-<服务>` 取得能力或引用公布，
+/// 之后每条命令 `POST <地址>/git-<服务>`，请求体与响应体都是 pkt-line 数据。
+pub struct HttpTransport {
+    url: String,
+    service: Service,
+    /// receive-pack 公布的引用（git id）
+    advertised: AdvertisedRefs,
+}
+
+/// 非 200 的响应转为错误，附带服务端返回的说明
+fn check_status(response: Response) -> io::Result<Vec<u8>> {
+    if response.status == 200 {
+        return Ok(response.body);
+    }
+    let message = String::from_utf8_lossy(&response.body).trim().to_string();
+    Err(io::Error::other(format!("HTTP {}: {}", response.status, message)))
+}
+
+impl HttpTransport {
+    pub fn connect(url: &str, service: Service) -> io::Result<HttpTransport> {
+        let url = url.trim_end_matches('/').to_string();
+        let info_refs = format!("{}/info/refs?service=git-{}", url, service.name());
+        let response = send_request(&info_refs, "GET", &[("Git-Protocol", "version=2")], &[])?;
+        let expected = format!("application/x-git-{}-advertisement", service.name());
+        if response.status == 200 && response.header("Content-Type") != Some(expected.as_str()) {
+            return Err(io::Error::other(format!("'{}' 不是 smart HTTP 服务", url)));
+        }
+        let body = check_status(response)?;
+        let mut reader = body.as_slice();
+
+        // v0 的公布以 `# service=...` 与一个 flush 开头，v2 没有
+        if body.get(4..).is_some_and(|rest| rest.starts_with(b"# service=")) {
+            read_packet(&mut reader)?;
+            if read_packet(&mut reader)? != Some(Packet::Flush) {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "service 行之后缺少 flush"));
+            }
+        }
+
+        let mut transport = HttpTransport { url, service, advertised: AdvertisedRefs::default() };
+        match service {
+            Service::UploadPack => {
+                protocol::read_capabilities(&mut reader)?;
+            }
+            Service::ReceivePack => {
+                transport.advertised = protocol::read_receive_advertisement(&mut reader)?;
+            }
+        }
+        Ok(transport)
+    }
+
+    fn post(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        let endpoint = format!("{}/git-{}", self.url, self.service.name());
+        let content_type = format!("application/x-git-{}-request", self.service.name());
+        let headers = [("Content-Type", content_type.as_str()), ("Git-Protocol", "version=2")];
+        check_status(send_request(&endpoint, "POST", &headers, body)?)
+    }
+}
+
+impl Transport for HttpTransport {
+    fn list_refs(&mut self, repo_path: &Path) -> io::Result<AdvertisedRefs> {
+        if self.service == Service::ReceivePack {
+            return localize(&self.advertised, repo_path);
+        }
+        let mut request = Vec::new();
+        protocol::write_ls_refs_request(&mut request)?;
+        localize(&protocol::read_ls_refs_response(&mut self.post(&request)?.as_slice())?, repo_path)
+    }
+
+    fn fetch_objects(&mut self, wants: &[String], haves: &[String], repo_path: &Path) -> io::Result<HashMap<String, String>> {
+        let mut map = ObjectMap::load(repo_path)?;
+        let haves = wire_haves(haves, &mut map);
+        let mut request = Vec::new();
+        protocol::write_fetch_request(&mut request, wants, &haves)?;
+        let objects = protocol::read_fetch_response(&mut self.post(&request)?.as_slice())?;
+        import_fetched(&objects, wants, &mut map)
+    }
+
+    fn push_refs(&mut self, commands: &[RefCommand], repo_path: &Path) -> io::Result<Vec<RefResult>> {
+        let (commands, pack) = prepare_push(commands, &self.advertised, repo_path)?;
+        let mut request = Vec::new();
+        protocol::write_push_request(&mut request, &commands, pack.as_deref())?;
+        protocol::read_push_report(&mut self.post(&request)?.as_slice())
+    }
+}
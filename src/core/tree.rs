@@ -1,9 +1,12 @@
 use std::fs::{self};
 use std::io::{self};
-use std::path::{Path};
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::core::object::{is_object_id, object_path, write_object, ObjectKind};
 use crate::utils::hash::sha1_hash;
-use std::collections::HashMap;
-use crate::core::config::{GIT_DIR, IS_VERBOSE};
+use crate::core::config::{GIT_DIR, GIT_DIR_NAME, IS_VERBOSE};
+use crate::core::index::{index_file, write_index_map};
+use crate::core::sparse::load_sparse;
 
 /// 安全清理工作区，只保留 `Git 文件夹` 和执行文件本体
 pub fn clean_working_directory() -> io::Result<()> {
@@ -15,7 +18,7 @@ pub fn clean_working_directory() -> io::Result<()> {
         let path = entry.path();
         let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
 
-        if canonical.starts_with(&mygit_path) {
+        if canonical.starts_with(&mygit_path) || path.file_name().is_some_and(|n| n == GIT_DIR_NAME.as_str()) {
             if *IS_VERBOSE {
                 println!("🔒 跳过 Git 文件夹内部文件或目录: {}", path.display());
             }
@@ -33,10 +36,8 @@ pub fn clean_working_directory() -> io::Result<()> {
         if *IS_VERBOSE {
             println!("检查路径: {}", path.display());
         }
-        if path == *GIT_DIR {
-            if *IS_VERBOSE {
-                println!("🚨 警告: 竟然试图删除 Git 目录 {}!!!", GIT_DIR.display());
-            }
+        if path == *GIT_DIR && *IS_VERBOSE {
+            println!("🚨 警告: 竟然试图删除 Git 目录 {}!!!", GIT_DIR.display());
         }
         if path.is_file() {
             if *IS_VERBOSE {
@@ -56,12 +57,104 @@ pub fn clean_working_directory() -> io::Result<()> {
 
 
 
-pub fn create_tree(entries: &[(String, String)], repo_path: &Path) -> io::Result<String> {
+/// 仓库目录名（比较时忽略大小写与 Windows 会忽略的结尾点号、空格）
+fn is_git_dir_name(part: &str) -> bool {
+    let part = part.trim_end_matches(['.', ' ']).to_lowercase();
+    let git_dir = GIT_DIR_NAME.to_lowercase();
+    // `.git` 同样拒绝：工作区里出现的 `.git` 目录会被真正的 git 当作仓库
+    part == git_dir || part == ".git"
+}
+
+/// 检查 tree 中的路径能否安全地写入工作区，返回不安全的原因
+///
+/// 拒绝空路径、绝对路径、NUL 与换行、反斜杠、空的路径部分、`.` 与 `..`，以及指向仓库目录的路径，
+/// 防止构造的 tree 把文件写到工作区之外或仓库目录之中。
+pub fn verify_tree_path(path: &str) -> Result<(), String> {
+    if path.is_empty() {
+        return Err("路径为空".to_string());
+    }
+    if path.contains('\0') {
+        return Err("含有 NUL 字符".to_string());
+    }
+    if path.contains(['\n', '\r']) {
+        return Err("含有换行符".to_string());
+    }
+    if path.contains('\\') {
+        return Err("含有反斜杠".to_string());
+    }
+    let bytes = path.as_bytes();
+    if path.starts_with('/') || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':') {
+        return Err("是绝对路径".to_string());
+    }
+    for part in path.split('/') {
+        match part {
+            "" => return Err("含有空的路径部分".to_string()),
+            "." | ".." => return Err(format!("含有 '{}'", part)),
+            _ if is_git_dir_name(part) => return Err("指向仓库目录".to_string()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn unsafe_path(path: &str, reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("不安全的路径 '{}': {}", path, reason))
+}
+
+/// 确认路径的上级目录中没有符号链接，否则写入会穿过链接落到工作区之外
+fn verify_no_symlink_prefix(path: &str) -> io::Result<()> {
+    let mut prefix = PathBuf::new();
+    let parts: Vec<&str> = path.split('/').collect();
+    for part in &parts[..parts.len().saturating_sub(1)] {
+        prefix.push(part);
+        match fs::symlink_metadata(&prefix) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                return Err(unsafe_path(path, &format!("上级目录 '{}' 是符号链接", prefix.display())));
+            }
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => break,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// 把内容写入工作区中的文件（必要时创建上级目录）
+///
+/// 路径必须通过 [`verify_tree_path`] 且上级目录不能是符号链接；目标本身是符号链接时先删除链接，
+/// 而不是写入链接指向的文件。
+pub fn write_worktree_file(path: &str, content: &[u8]) -> io::Result<()> {
+    verify_tree_path(path).map_err(|reason| unsafe_path(path, &reason))?;
+    verify_no_symlink_prefix(path)?;
+    if let Some(parent) = Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink()) {
+        fs::remove_file(path)?;
+    }
+    fs::write(path, content)
+}
+
+/// 删除工作区中的文件，文件不存在时什么也不做；同样拒绝不安全的路径与穿过符号链接的删除
+pub fn remove_worktree_file(path: &str) -> io::Result<()> {
+    verify_tree_path(path).map_err(|reason| unsafe_path(path, &reason))?;
+    verify_no_symlink_prefix(path)?;
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// 用 index 条目生成 tree；工作区中已不存在的文件被跳过，但带 skip-worktree 标记的条目（稀疏检出范围之外）保留
+pub fn create_tree(entries: &[(String, String)], skip_worktree: &HashSet<String>, repo_path: &Path) -> io::Result<String> {
     let mut content = String::new();
 
     for (hash, path) in entries {
+        verify_tree_path(path).map_err(|reason| unsafe_path(path, &reason))?;
         let file_path = Path::new(path);
-        if file_path.exists() {
+        if file_path.exists() || skip_worktree.contains(path) {
             content.push_str(&format!("blob {} {}\n", hash, path));
         } else {
             if *IS_VERBOSE {
@@ -70,55 +163,58 @@ pub fn create_tree(entries: &[(String, String)], repo_path: &Path) -> io::Result
         }
     }
 
-    let tree_hash = sha1_hash(content.as_bytes());
-    let (dir, file) = tree_hash.split_at(2);
-    let obj_dir = repo_path.join("objects").join(dir);
-    fs::create_dir_all(&obj_dir)?;
-    let tree_path = obj_dir.join(file);
     // println!("🌲 最终写入 tree 对象内容：");
     // println!("{}", content);
-    fs::write(tree_path, content)?;
-
-    Ok(tree_hash)
+    write_object(content.as_bytes(), repo_path)
 }
 
-/// 还原 tree 中记录的文件
+/// 还原 tree 中记录的文件，并让 index 与该 tree 保持一致
+///
+/// 先读取并检查 tree，其中有不安全的路径时直接返回错误，工作区保持不动。
+/// 启用稀疏检出时只写出范围之内的文件，index 仍然记录完整的 tree。
 pub fn restore_tree(tree_hash: &str, repo_path: &Path) -> io::Result<()> {
+    let entries = read_tree_entries(tree_hash, repo_path)?;
+    let cone = load_sparse(repo_path)?;
     clean_working_directory()?;
-    let (dir, file) = tree_hash.split_at(2);
-    let tree_path = repo_path.join("objects").join(dir).join(file);
-    let content = fs::read_to_string(&tree_path)?;
 
-    for line in content.lines() {
-        if let Some((_, rest)) = line.split_once("blob ") {
-            if let Some((hash, filename)) = rest.split_once(' ') {
-                let (obj_dir, obj_file) = hash.split_at(2);
-                let blob_path = repo_path.join("objects").join(obj_dir).join(obj_file);
-
-                let blob_content = fs::read(&blob_path)?;
-
-                // 💡 强制覆盖文件（即使文件存在）
-                fs::write(filename, blob_content)?;
-                if *IS_VERBOSE {
-                    println!("✔ 恢复文件 {} -> {}", filename, hash);
-                }
-            }
+    for (filename, hash) in entries.iter().filter(|(path, _)| cone.as_ref().is_none_or(|cone| cone.includes(path))) {
+        // 💡 强制覆盖文件（即使文件存在）
+        checkout_file(filename, hash, repo_path)?;
+        if *IS_VERBOSE {
+            println!("✔ 恢复文件 {} -> {}", filename, hash);
         }
     }
 
-    Ok(())
+    write_index_map(&index_file(repo_path), &entries)
+}
+
+/// 把 blob 内容写到工作区中的指定路径（必要时创建上级目录），路径的检查见 [`write_worktree_file`]
+pub fn checkout_file(path: &str, hash: &str, repo_path: &Path) -> io::Result<()> {
+    let blob_content = fs::read(object_path(hash, repo_path)?)?;
+    write_worktree_file(path, &blob_content)
 }
 
 /// 返回 tree 中所有文件及其 blob 哈希
+///
+/// tree 中有不安全的路径（见 [`verify_tree_path`]）时返回错误，这样的 tree 不会被检出或合并。
 pub fn read_tree_entries(tree_hash: &str, repo_path: &Path) -> io::Result<HashMap<String, String>> {
-    let (dir, file) = tree_hash.split_at(2);
-    let tree_path = repo_path.join("objects").join(dir).join(file);
+    let tree_path = object_path(tree_hash, repo_path)?;
     let content = fs::read_to_string(tree_path)?;
+    parse_tree_entries(tree_hash, &content)
+}
 
+/// 解析 tree 对象的内容，`tree_hash` 只用于错误信息
+pub fn parse_tree_entries(tree_hash: &str, content: &str) -> io::Result<HashMap<String, String>> {
     let mut map = HashMap::new();
     for line in content.lines() {
         if let Some((_, rest)) = line.split_once("blob ") {
             if let Some((hash, path)) = rest.split_once(' ') {
+                if !is_object_id(hash) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("tree {} 中 '{}' 的 hash 无效: '{}'", tree_hash, path, hash)));
+                }
+                verify_tree_path(path).map_err(|reason| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("tree {} 中有不安全的路径 '{}': {}", tree_hash, path, reason))
+                })?;
                 map.insert(path.to_string(), hash.to_string());
             }
         }
@@ -128,8 +224,7 @@ pub fn read_tree_entries(tree_hash: &str, repo_path: &Path) -> io::Result<HashMa
 
 
 pub fn load_blob(hash: &str, repo_path: &Path) -> io::Result<Vec<String>> {
-    let (dir, file) = hash.split_at(2);
-    let blob_path = repo_path.join("objects").join(dir).join(file);
+    let blob_path = object_path(hash, repo_path)?;
     let content = fs::read_to_string(blob_path)?;
     Ok(content.lines().map(|s| s.to_string()).collect())
 }
@@ -189,6 +284,9 @@ pub fn merge_tree_simple(
                 merged.insert(file.clone(), cur.clone());
             }
 
+            // 目标没改，当前删除 => 删除
+            (Some(base), None, Some(tgt)) if tgt == base => {}
+
             // 目标改了，当前删除 => 保留目标
             (Some(_base), None, Some(tgt)) => {
                 merged.insert(file.clone(), tgt.clone());
@@ -204,6 +302,11 @@ pub fn merge_tree_simple(
                 merged.insert(file.clone(), tgt.clone());
             }
 
+            // 双方都有，只有当前改了 => 保留当前
+            (Some(base), Some(cur), Some(tgt)) if tgt == base => {
+                merged.insert(file.clone(), cur.clone());
+            }
+
             // 双方都有（内容一致或前面已判断无冲突）
             (_, Some(_), Some(tgt)) => {
                 merged.insert(file.clone(), tgt.clone());
@@ -219,24 +322,57 @@ pub fn merge_tree_simple(
 
 
 
+/// 列出 tree 的内容，返回 (类型, hash, 路径)，按路径排序
+///
+/// tree 对象本身是扁平的，`recursive` 为 false 时把子目录合并为一项 tree，
+/// 子目录的 hash 只按内容计算，不写入对象库（查看命令不应产生新对象）。
+pub fn list_tree(entries: HashMap<String, String>, recursive: bool) -> io::Result<Vec<(ObjectKind, String, String)>> {
+    let mut entries: Vec<(String, String)> = entries.into_iter().collect();
+    entries.sort();
+    if recursive {
+        return Ok(entries.into_iter().map(|(path, hash)| (ObjectKind::Blob, hash, path)).collect());
+    }
+
+    let mut result = Vec::new();
+    let mut dirs: BTreeMap<String, HashMap<String, String>> = BTreeMap::new();
+    for (path, hash) in entries {
+        match path.split_once('/') {
+            Some((dir, rest)) => {
+                dirs.entry(dir.to_string()).or_default().insert(rest.to_string(), hash);
+            }
+            None => result.push((ObjectKind::Blob, hash, path)),
+        }
+    }
+    for (dir, sub) in dirs {
+        result.push((ObjectKind::Tree, hash_tree_from_map(&sub)?, dir));
+    }
+    result.sort_by(|a, b| a.2.cmp(&b.2));
+    Ok(result)
+}
+
 /// 将 tree 的 HashMap 写入对象存储，返回 tree 哈希
 pub fn write_tree_from_map(
     entries: &HashMap<String, String>,
     repo_path: &Path,
 ) -> io::Result<String> {
-    use crate::utils::hash::sha1_hash;
+    write_object(tree_content_from_map(entries)?.as_bytes(), repo_path)
+}
+
+/// 计算 tree 的哈希但不写入对象存储
+pub fn hash_tree_from_map(entries: &HashMap<String, String>) -> io::Result<String> {
+    Ok(sha1_hash(tree_content_from_map(entries)?.as_bytes()))
+}
+
+/// 生成 tree 对象的内容
+pub fn tree_content_from_map(entries: &HashMap<String, String>) -> io::Result<String> {
+    // 按路径排序，保证相同内容总是得到相同的 tree 哈希
+    let mut sorted: Vec<_> = entries.iter().collect();
+    sorted.sort();
 
     let mut content = String::new();
-    for (filename, blob_hash) in entries {
+    for (filename, blob_hash) in sorted {
+        verify_tree_path(filename).map_err(|reason| unsafe_path(filename, &reason))?;
         content.push_str(&format!("blob {} {}\n", blob_hash, filename));
     }
-
-    let tree_hash = sha1_hash(content.as_bytes());
-    let (dir, file) = tree_hash.split_at(2);
-    let obj_dir = repo_path.join("objects").join(dir);
-    fs::create_dir_all(&obj_dir)?;
-    let tree_path = obj_dir.join(file);
-    fs::write(tree_path, content)?;
-
-    Ok(tree_hash)
+    Ok(content)
 }
@@ -0,0 +1,234 @@
+//! 多个工作区
+//!
+//! 除了主工作区，`worktree add` 创建的关联工作区在共享 git 目录的 `worktrees/<名>` 下有自己的 git 目录，
+//! 其中保存这个工作区的 HEAD、index 与进行中的操作状态，`commondir` 指回共享目录，
+//! `gitdir` 记录工作区中 `.mygit` 文件的位置；工作区中的 `.mygit` 文件则用 `gitdir:` 指向这个目录。
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::core::commit::read_commit_tree;
+use crate::core::config::{load_config, GIT_DIR_NAME};
+use crate::core::index::{index_file, read_index_map, read_skip_worktree, write_index_map};
+use crate::core::refs::{RefStore, RefValue};
+use crate::core::reference::{set_head, write_ref};
+use crate::core::repo_config::parse_bool;
+use crate::core::repository::common_dir;
+use crate::core::tree::{checkout_file, read_tree_entries};
+use crate::utils::fs::list_files;
+use crate::utils::hash::sha1_hash;
+
+/// 一个工作区
+#[derive(Debug, Clone)]
+pub struct Worktree {
+    /// 关联工作区的名字（`worktrees/<名>`），主工作区为 None
+    pub name: Option<String>,
+    /// 工作区目录；裸仓库的主工作区没有工作区，为共享 git 目录本身
+    pub path: PathBuf,
+    /// 这个工作区的 git 目录（HEAD 与 index 所在的目录）
+    pub git_dir: PathBuf,
+    pub bare: bool,
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+impl Worktree {
+    /// HEAD 的值（不跟随符号引用）
+    pub fn head(&self) -> Option<RefValue> {
+        RefStore::new(&self.git_dir).read("HEAD")
+    }
+
+    /// HEAD 指向的提交，分支尚无提交时为 None
+    pub fn head_commit(&self) -> Option<String> {
+        RefStore::new(&self.git_dir).resolve("HEAD")
+    }
+
+    /// 检出的分支：HEAD 指向的分支，或正在变基的分支
+    pub fn branch(&self) -> Option<String> {
+        match self.head() {
+            Some(RefValue::Symbolic(target)) => Some(target),
+            _ => fs::read_to_string(self.git_dir.join("rebase-merge").join("head-name"))
+                .ok()
+                .map(|name| name.trim_end().to_string())
+                .filter(|name| name.starts_with("refs/heads/")),
+        }
+    }
+
+    /// 关联工作区可以被 `worktree prune` 清理的原因：工作区已被删除或移走
+    pub fn prunable(&self) -> Option<String> {
+        self.name.as_ref()?;
+        match fs::read_to_string(self.git_dir.join("gitdir")) {
+            Err(_) => Some("gitdir 文件不存在".to_string()),
+            Ok(gitdir) if !Path::new(gitdir.trim_end()).is_file() => Some("gitdir 文件指向不存在的位置".to_string()),
+            Ok(_) => None,
+        }
+    }
+
+    /// 工作区中是否有未提交的修改或未跟踪的文件
+    pub fn has_changes(&self) -> io::Result<bool> {
+        let index = read_index_map(&index_file(&self.git_dir));
+        let head = match self.head_commit() {
+            Some(commit) => read_tree_entries(&read_commit_tree(&commit, &self.git_dir)?, &self.git_dir)?,
+            None => HashMap::new(),
+        };
+        if index != head {
+            return Ok(true);
+        }
+        // 稀疏检出范围之外的文件不在工作区中
+        let mut files = read_skip_worktree(&index_file(&self.git_dir)).len();
+        for file in list_files(&self.path) {
+            let Ok(relative) = file.strip_prefix(&self.path) else { continue };
+            if relative.starts_with(&*GIT_DIR_NAME) {
+                continue;
+            }
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            match index.get(&relative) {
+                Some(hash) if *hash == sha1_hash(&fs::read(&file)?) => files += 1,
+                _ => return Ok(true),
+            }
+        }
+        Ok(files != index.len())
+    }
+}
+
+/// 列出仓库的所有工作区，主工作区在前，关联工作区按名字排序
+pub fn list_worktrees(repo_path: &Path) -> io::Result<Vec<Worktree>> {
+    let common = canonical(&common_dir(repo_path));
+    let bare = load_config(&common)?.get("core.bare").and_then(parse_bool).unwrap_or(false);
+    let main_path = match common.parent() {
+        Some(parent) if !bare => parent.to_path_buf(),
+        _ => common.clone(),
+    };
+    let mut worktrees = vec![Worktree { name: None, path: main_path, git_dir: common.clone(), bare }];
+
+    let mut names: Vec<String> = match fs::read_dir(common.join("worktrees")) {
+        Ok(entries) => entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    names.sort();
+    for name in names {
+        let git_dir = common.join("worktrees").join(&name);
+        let path = fs::read_to_string(git_dir.join("gitdir"))
+            .ok()
+            .and_then(|gitdir| Path::new(gitdir.trim_end()).parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| git_dir.clone());
+        worktrees.push(Worktree { name: Some(name), path, git_dir, bare: false });
+    }
+    Ok(worktrees)
+}
+
+/// 检出了分支 `refname` 的工作区，`exclude` 为 git 目录不在查找范围内的工作区（通常是当前工作区）
+pub fn find_branch_checkout(repo_path: &Path, refname: &str, exclude: Option<&Path>) -> Option<Worktree> {
+    let exclude = exclude.map(canonical);
+    list_worktrees(repo_path)
+        .ok()?
+        .into_iter()
+        .filter(|wt| !wt.bare && Some(&wt.git_dir) != exclude.as_ref())
+        .find(|wt| wt.branch().as_deref() == Some(refname))
+}
+
+/// 关联工作区的名字：取目录名，已被占用时加上数字后缀
+fn unique_name(common: &Path, path: &Path) -> String {
+    let base = path
+        .file_name()
+        .map(|n| n.to_string_lossy().replace(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_', "-"))
+        .filter(|n| !n.is_empty() && !n.starts_with('.'))
+        .unwrap_or_else(|| "worktree".to_string());
+    let mut name = base.clone();
+    let mut n = 1;
+    while common.join("worktrees").join(&name).exists() {
+        name = format!("{}{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+/// 把提交中的文件检出到 `dir`，并写入 `git_dir` 中的 index
+fn populate(dir: &Path, git_dir: &Path, commit: &str) -> io::Result<()> {
+    let entries = read_tree_entries(&read_commit_tree(commit, git_dir)?, git_dir)?;
+    // 工作区文件的写入以当前目录为基准（见 write_worktree_file），检出期间临时切换到新工作区
+    let previous = env::current_dir()?;
+    env::set_current_dir(dir)?;
+    let result = entries.iter().try_for_each(|(path, hash)| checkout_file(path, hash, git_dir));
+    env::set_current_dir(previous)?;
+    result?;
+    write_index_map(&index_file(git_dir), &entries)
+}
+
+/// 在 `path` 创建关联工作区，HEAD 设为 `head`（分支的符号引用或分离的提交），并检出 `commit`
+///
+/// `path` 必须不存在或是空目录；中途失败时已创建的目录会被删除。
+pub fn add_worktree(repo_path: &Path, path: &Path, head: &RefValue, commit: &str) -> io::Result<Worktree> {
+    if path.exists() && !fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none()) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("'{}' 已经存在", path.display())));
+    }
+    let common = canonical(&common_dir(repo_path));
+    let name = unique_name(&common, path);
+    let git_dir = common.join("worktrees").join(&name);
+    let created_path = !path.exists();
+
+    let result = (|| {
+        fs::create_dir_all(path)?;
+        let path = canonical(path);
+        fs::create_dir_all(&git_dir)?;
+        fs::write(git_dir.join("commondir"), "../..\n")?;
+        fs::write(git_dir.join("gitdir"), format!("{}\n", path.join(&*GIT_DIR_NAME).display()))?;
+        match head {
+            RefValue::Symbolic(target) => set_head(target, &git_dir)?,
+            RefValue::Direct(hash) => write_ref(&git_dir, "HEAD", hash)?,
+        }
+        fs::write(path.join(&*GIT_DIR_NAME), format!("gitdir: {}\n", git_dir.display()))?;
+        populate(&path, &git_dir, commit)?;
+        Ok(Worktree { name: Some(name.clone()), path, git_dir: git_dir.clone(), bare: false })
+    })();
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&git_dir);
+        if created_path {
+            let _ = fs::remove_dir_all(path);
+        } else {
+            let _ = fs::remove_file(path.join(&*GIT_DIR_NAME));
+        }
+    }
+    result
+}
+
+/// 删除关联工作区：工作区目录与它在 `worktrees/` 下的 git 目录
+pub fn remove_worktree(worktree: &Worktree) -> io::Result<()> {
+    if worktree.name.is_none() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "不能删除主工作区"));
+    }
+    if worktree.path.is_dir() {
+        fs::remove_dir_all(&worktree.path)?;
+    }
+    remove_admin_dir(worktree)
+}
+
+/// 删除 `worktrees/<名>`，`worktrees/` 因此变空时一并删除
+fn remove_admin_dir(worktree: &Worktree) -> io::Result<()> {
+    fs::remove_dir_all(&worktree.git_dir)?;
+    if let Some(parent) = worktree.git_dir.parent() {
+        let _ = fs::remove_dir(parent);
+    }
+    Ok(())
+}
+
+/// 清理工作区已不存在的关联工作区，返回 (名字, 原因)；`dry_run` 时只列出不删除
+pub fn prune_worktrees(repo_path: &Path, dry_run: bool) -> io::Result<Vec<(String, String)>> {
+    let mut pruned = Vec::new();
+    for worktree in list_worktrees(repo_path)? {
+        let (Some(name), Some(reason)) = (worktree.name.clone(), worktree.prunable()) else { continue };
+        if !dry_run {
+            remove_admin_dir(&worktree)?;
+        }
+        pruned.push((name, reason));
+    }
+    Ok(pruned)
+}
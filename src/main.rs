@@ -2,10 +2,11 @@ mod cli;
 mod commands;
 mod core;
 mod utils;
+use cli::alias::expand_aliases;
 use cli::args::Cli;
 use clap::Parser;
 
 fn main() {
-    let cli = Cli::parse();
+    let cli = Cli::parse_from(expand_aliases(std::env::args_os().collect()));
     cli.execute();
 }
@@ -0,0 +1,80 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+
+/// 按顺序查找环境变量中配置的编辑器，都没有时使用 `vi`
+fn editor_from_env(vars: &[&str]) -> String {
+    vars.iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string())
+}
+
+/// 编辑提交说明用的编辑器：`$GIT_EDITOR` > `$VISUAL` > `$EDITOR` > `vi`
+pub fn commit_editor() -> String {
+    editor_from_env(&["GIT_EDITOR", "VISUAL", "EDITOR"])
+}
+
+/// 编辑 rebase 待办列表用的编辑器：`$GIT_SEQUENCE_EDITOR` 优先，其余同 [`commit_editor`]
+pub fn sequence_editor() -> String {
+    editor_from_env(&["GIT_SEQUENCE_EDITOR", "GIT_EDITOR", "VISUAL", "EDITOR"])
+}
+
+/// 用编辑器打开文件并等待其退出
+///
+/// 与 git 一样通过 `sh -c` 执行，编辑器设置中可以带参数（如 `sed -i -e ...`）。
+pub fn launch_editor(editor: &str, path: &Path) -> io::Result<()> {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$@\"", editor))
+        .arg(editor)
+        .arg(path)
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("编辑器 '{}' 异常退出", editor)))
+    }
+}
+
+/// 去掉 `#` 开头的注释行以及首尾空行
+pub fn strip_comments(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(str::trim_end)
+        .collect();
+    let mut message = lines.join("\n").trim().to_string();
+    if !message.is_empty() {
+        message.push('\n');
+    }
+    message
+}
+
+/// 整理提交说明的空白：去掉行尾空白与首尾空行，连续的空行合并为一行，结尾保留一个换行
+pub fn cleanup_whitespace(text: &str) -> String {
+    let mut message = String::new();
+    let mut blank = false;
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() {
+            blank = !message.is_empty();
+            continue;
+        }
+        if blank {
+            message.push('\n');
+            blank = false;
+        }
+        message.push_str(line);
+        message.push('\n');
+    }
+    message
+}
+
+/// 把初始内容写入文件，用编辑器编辑后读回，并去掉注释
+pub fn edit_text(editor: &str, path: &Path, initial: &str) -> io::Result<String> {
+    fs::write(path, initial)?;
+    launch_editor(editor, path)?;
+    Ok(strip_comments(&fs::read_to_string(path)?))
+}
@@ -1,5 +1,7 @@
-use std::fs;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// 递归列出所有文件
 pub fn list_files(dir: &Path) -> Vec<PathBuf> {
@@ -17,3 +19,105 @@ pub fn list_files(dir: &Path) -> Vec<PathBuf> {
     }
     result
 }
+
+/// `<文件>.lock` 形式的锁文件路径
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// 尽量把目录项的变化（改名、创建）落盘；不支持打开目录的平台上忽略
+fn sync_dir(dir: &Path) {
+    if let Ok(dir) = File::open(dir) {
+        let _ = dir.sync_all();
+    }
+}
+
+/// 锁文件：新内容先写入 `<文件>.lock`，`commit` 时 fsync 并改名覆盖目标文件
+///
+/// 锁文件用 `create_new` 创建，已存在说明另一个进程正在修改同一个文件，此时立即报错而不是等待或覆盖。
+/// 没有提交就被丢弃（包括出错提前返回）时自动删除锁文件，目标文件保持原样。
+#[derive(Debug)]
+pub struct LockFile {
+    path: PathBuf,
+    lock: PathBuf,
+    file: Option<File>,
+    committed: bool,
+}
+
+impl LockFile {
+    /// 锁定 `path`，必要时创建其所在目录
+    pub fn acquire(path: &Path) -> io::Result<LockFile> {
+        let lock = lock_path(path);
+        if let Some(parent) = lock.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match OpenOptions::new().write(true).create_new(true).open(&lock) {
+            Ok(file) => Ok(LockFile { path: path.to_path_buf(), lock, file: Some(file), committed: false }),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(io::Error::new(
+                e.kind(),
+                format!(
+                    "无法锁定 '{}'：'{}' 已存在，可能有另一个 rust-git 进程正在运行；如果没有，请删除该文件后重试",
+                    path.display(),
+                    lock.display()
+                ),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 向锁文件追加内容
+    pub fn write_all(&mut self, content: &[u8]) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.write_all(content),
+            None => Err(io::Error::other("锁文件已关闭")),
+        }
+    }
+
+    /// fsync 后把锁文件改名为目标文件，新内容原子地生效
+    pub fn commit(mut self) -> io::Result<()> {
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        fs::rename(&self.lock, &self.path)?;
+        self.committed = true;
+        if let Some(parent) = self.path.parent() {
+            sync_dir(parent);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        self.file.take();
+        if !self.committed {
+            let _ = fs::remove_file(&self.lock);
+        }
+    }
+}
+
+/// 原子地写入整个文件：先写入同目录下的临时文件，fsync 后改名覆盖目标
+///
+/// 不加锁，适用于内容由文件名决定的对象文件：并发写入同一个对象时，最后生效的内容也相同。
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let tmp = dir.join(format!(".{}.{}-{}.tmp", name, std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed)));
+
+    let result = (|| {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(&tmp)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    } else {
+        sync_dir(dir);
+    }
+    result
+}
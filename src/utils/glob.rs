@@ -0,0 +1,54 @@
+/// 通配符匹配：`*` 匹配任意字符串，`?` 匹配单个字符，`[...]` 匹配字符集合（`[!...]` 或 `[^...]` 取反），
+/// `\` 转义下一个字符
+///
+/// 与 git 匹配分支名时一样，`*` 也可以匹配 `/`。
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    let Some((&first, rest)) = pattern.split_first() else {
+        return text.is_empty();
+    };
+    match first {
+        '*' => (0..=text.len()).any(|skip| match_from(rest, &text[skip..])),
+        '?' => !text.is_empty() && match_from(rest, &text[1..]),
+        '[' => match (text.first(), match_class(rest)) {
+            (Some(&c), Some((matches, after))) => matches(c) && match_from(after, &text[1..]),
+            // 没有闭合的 `[` 按普通字符处理
+            (Some(&c), None) => c == '[' && match_from(rest, &text[1..]),
+            (None, _) => false,
+        },
+        '\\' if !rest.is_empty() => text.first() == Some(&rest[0]) && match_from(&rest[1..], &text[1..]),
+        c => text.first() == Some(&c) && match_from(rest, &text[1..]),
+    }
+}
+
+/// 解析 `[` 之后的字符集合，返回匹配函数与集合之后的模式
+#[allow(clippy::type_complexity)]
+fn match_class(pattern: &[char]) -> Option<(Box<dyn Fn(char) -> bool + '_>, &[char])> {
+    let (negated, body) = match pattern.first() {
+        Some('!') | Some('^') => (true, &pattern[1..]),
+        _ => (false, pattern),
+    };
+    // 紧跟在开头的 `]` 是普通字符
+    let end = body.iter().skip(1).position(|&c| c == ']')? + 1;
+    let set = &body[..end];
+    let matches = move |c: char| {
+        let mut i = 0;
+        let mut found = false;
+        while i < set.len() {
+            if i + 2 < set.len() && set[i + 1] == '-' {
+                found |= set[i] <= c && c <= set[i + 2];
+                i += 3;
+            } else {
+                found |= set[i] == c;
+                i += 1;
+            }
+        }
+        found != negated
+    };
+    Some((Box::new(matches), &body[end + 1..]))
+}
@@ -1,2 +1,5 @@
 pub mod hash;
 pub mod fs;
+pub mod time;
+pub mod editor;
+pub mod glob;
\ No newline at end of file
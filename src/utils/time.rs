@@ -0,0 +1,96 @@
+/// 把时区字符串（如 `+0800`、`-0130`）解析为相对 UTC 的秒数
+fn tz_offset_seconds(tz: &str) -> i64 {
+    let (sign, digits) = match tz.as_bytes().first() {
+        Some(b'-') => (-1, &tz[1..]),
+        Some(b'+') => (1, &tz[1..]),
+        _ => (1, tz),
+    };
+    if digits.len() != 4 {
+        return 0;
+    }
+    let hours: i64 = digits[..2].parse().unwrap_or(0);
+    let minutes: i64 = digits[2..].parse().unwrap_or(0);
+    sign * (hours * 3600 + minutes * 60)
+}
+
+/// 由 1970-01-01 起的天数计算公历日期（Howard Hinnant 的 civil_from_days 算法）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// 把 unix 时间戳按给定时区格式化为 `YYYY-MM-DD HH:MM:SS +zzzz`
+pub fn format_timestamp(secs: i64, tz: &str) -> String {
+    let local = secs + tz_offset_seconds(tz);
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    let rem = local.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        tz
+    )
+}
+
+/// 由公历日期计算自 1970-01-01 起的天数（civil_from_days 的逆运算）
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// 解析过期时间（如 `--prune=<date>`），返回对应的 unix 时间戳
+///
+/// 支持 `now`（包括将来的时间，返回 `i64::MAX`）、`never`（返回 `i64::MIN`）、
+/// `2.weeks.ago` / `3 days ago` 这样的相对时间、`@<时间戳>` 与 `YYYY-MM-DD`。
+pub fn parse_expiry(spec: &str, now: i64) -> Option<i64> {
+    let spec = spec.trim().to_lowercase();
+    match spec.as_str() {
+        "now" | "all" => return Some(i64::MAX),
+        "never" => return Some(i64::MIN),
+        _ => {}
+    }
+    if let Some(ts) = spec.strip_prefix('@') {
+        return ts.parse().ok();
+    }
+    if let [year, month, day] = spec.split('-').collect::<Vec<_>>()[..] {
+        let (year, month, day) = (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        return Some(days_from_civil(year, month, day) * 86_400);
+    }
+
+    let words: Vec<&str> = spec.split(['.', ' ']).filter(|w| !w.is_empty()).collect();
+    let [count, unit, "ago"] = words[..] else {
+        return None;
+    };
+    let count: i64 = count.parse().ok()?;
+    let seconds = match unit.trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 3600,
+        "day" => 86_400,
+        "week" => 7 * 86_400,
+        "month" => 30 * 86_400,
+        "year" => 365 * 86_400,
+        _ => return None,
+    };
+    Some(now - count * seconds)
+}
@@ -0,0 +1,70 @@
+// tests/alias.rs
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 仓库位于临时目录下的 repo 子目录，用户与系统配置文件放在仓库之外
+fn bin(repo: &Path) -> Command {
+    let home = repo.parent().unwrap();
+    let mut cmd = Command::cargo_bin("rust-git").expect("binary build failed");
+    cmd.current_dir(repo);
+    cmd.env("RUST_GIT_CONFIG_GLOBAL", home.join("global-config"));
+    cmd.env("RUST_GIT_CONFIG_SYSTEM", home.join("system-config"));
+    cmd
+}
+
+fn setup(tmp: &Path) -> PathBuf {
+    let repo = tmp.join("repo");
+    fs::create_dir(&repo).unwrap();
+    run(&["init"], &repo);
+    repo
+}
+
+fn run(args: &[&str], repo: &Path) -> String {
+    let output = bin(repo).args(args).output().unwrap();
+    assert!(output.status.success(), "命令 {:?} 执行失败: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_alias_expansion() {
+    let tmp = tempdir().unwrap();
+    let repo = &setup(tmp.path());
+    run(&["config", "alias.ci", "commit -m 'from alias'"], repo);
+    run(&["config", "--global", "alias.co", "checkout"], repo);
+    run(&["config", "alias.nb", "co -b"], repo);
+    // 内置命令不能被别名覆盖
+    run(&["config", "alias.status", "log"], repo);
+
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["ci"], repo);
+    assert!(run(&["cat-file", "-p", "HEAD"], repo).ends_with("\n\nfrom alias\n"), "ci 应展开为带引号参数的 commit");
+
+    run(&["nb", "topic"], repo);
+    assert_eq!(run(&["symbolic-ref", "HEAD"], repo), "refs/heads/topic\n", "别名可以展开为另一个别名");
+    assert!(!run(&["status"], repo).contains("from alias"), "status 不应被别名覆盖");
+
+    assert_eq!(run(&["-c", "alias.cur=symbolic-ref --short HEAD", "cur"], repo), "topic\n");
+    assert_eq!(run(&["help", "nb"], repo), "'nb' 是 'co -b' 的别名\n");
+    assert!(run(&["co", "--help"], repo).starts_with("'co' 是 'checkout' 的别名\n"));
+}
+
+#[test]
+fn test_shell_alias_and_loops() {
+    let tmp = tempdir().unwrap();
+    let repo = &setup(tmp.path());
+    run(&["config", "alias.hello", "!echo hello"], repo);
+    run(&["config", "alias.fail", "!exit 3"], repo);
+    run(&["config", "alias.a", "b"], repo);
+    run(&["config", "alias.b", "a --x"], repo);
+
+    assert_eq!(run(&["hello", "one two", "three"], repo), "hello one two three\n");
+    bin(repo).arg("fail").assert().code(3);
+
+    let output = bin(repo).arg("a").output().unwrap();
+    assert_eq!(output.status.code(), Some(128), "别名循环应报错");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("a -> b -> a"));
+}
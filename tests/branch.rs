@@ -1,27 +1,32 @@
 // tests/branch.rs
 
-use assert_cmd::Command;
 // use predicates::str::contains;
 use tempfile::tempdir;
 use std::fs;
 use std::path::Path;
 
-fn bin() -> Command {
-    Command::cargo_bin("rust-git").expect("binary build failed")
-}
+mod common;
+use common::{bin, run, commit_file};
 
 fn assert_branch_exists(repo: &Path, name: &str) {
     let path = repo.join(".mygit/refs/heads").join(name);
     assert!(path.exists(), "分支 {} 不存在", name);
 }
 
+/// 初始化仓库并做一次提交：分支要在第一次提交之后才能创建
+fn init_with_commit(repo: &Path) {
+    bin().arg("init").current_dir(repo).assert().success();
+    fs::write(repo.join("base.txt"), "base").unwrap();
+    bin().args(["add", "base.txt"]).current_dir(repo).assert().success();
+    bin().args(["commit", "-m", "base"]).current_dir(repo).assert().success();
+}
+
 fn assert_head_points_to(repo: &Path, branch: &str) {
     let head = std::fs::read_to_string(repo.join(".mygit/HEAD")).unwrap();
     let expected = format!("ref: refs/heads/{}", branch);
     assert_eq!(head.trim(), expected, "HEAD 没有指向 {}", branch);
 }
 
-
 /// 获取当前 HEAD 指向的 commit hash，无论是分支还是 detached 状态。
 pub fn get_current_commit_hash(repo: &Path) -> String {
     let head_path = repo.join(".mygit/HEAD");
@@ -101,7 +106,7 @@ fn test_branch_after_add() {
     let tmp = tempdir().unwrap();
     let repo = tmp.path();
 
-    bin().arg("init").current_dir(repo).assert().success();
+    init_with_commit(repo);
     fs::write(repo.join("a.txt"), "1").unwrap();
     bin().args(["add", "a.txt"]).current_dir(repo).assert().success();
     bin().args(["branch", "dev"]).current_dir(repo).assert().success();
@@ -114,7 +119,7 @@ fn test_branch_after_rm() {
     let tmp = tempdir().unwrap();
     let repo = tmp.path();
 
-    bin().arg("init").current_dir(repo).assert().success();
+    init_with_commit(repo);
     fs::write(repo.join("b.txt"), "b").unwrap();
     bin().args(["add", "b.txt"]).current_dir(repo).assert().success();
     bin().args(["rm", "b.txt"]).current_dir(repo).assert().success();
@@ -129,7 +134,7 @@ fn test_branch_after_add_then_remove_file_manually() {
     let repo = tmp.path();
     let file = repo.join("foo.txt");
 
-    bin().arg("init").current_dir(repo).assert().success();
+    init_with_commit(repo);
     fs::write(&file, "data").unwrap();
     bin().args(["add", "foo.txt"]).current_dir(repo).assert().success();
     std::fs::remove_file(&file).unwrap();
@@ -144,7 +149,7 @@ fn test_branch_after_rm_add_same_file() {
     let repo = tmp.path();
     let file = repo.join("x.txt");
 
-    bin().arg("init").current_dir(repo).assert().success();
+    init_with_commit(repo);
     fs::write(&file, "aaa").unwrap();
     bin().args(["add", "x.txt"]).current_dir(repo).assert().success();
     bin().args(["rm", "x.txt"]).current_dir(repo).assert().success();
@@ -234,4 +239,148 @@ fn test_create_invalid_branch_in_detached_should_fail() {
     );
 }
 
+#[test]
+fn test_branch_list_marks_current_and_filters() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "a", "first");
+    run(&["branch", "dev"], repo);
+    run(&["branch", "fix"], repo);
+    commit_file(repo, "b.txt", "b", "second");
+
+    assert_eq!(run(&["branch"], repo), "  dev\n  fix\n* master\n");
+    let verbose = run(&["branch", "-v"], repo);
+    assert!(verbose.lines().any(|l| l.starts_with("* master") && l.ends_with(" second")), "实际输出: {}", verbose);
+    assert!(verbose.lines().any(|l| l.starts_with("  dev ") && l.ends_with(" first")), "实际输出: {}", verbose);
+
+    assert_eq!(run(&["branch", "--list", "f*"], repo), "  fix\n");
+    assert_eq!(run(&["branch", "--merged", "dev"], repo), "  dev\n  fix\n");
+    assert_eq!(run(&["branch", "--no-merged", "dev"], repo), "* master\n");
+    assert_eq!(run(&["branch", "--contains", "master"], repo), "* master\n");
+    assert_eq!(run(&["branch", "--sort=-refname"], repo), "* master\n  fix\n  dev\n");
+
+    // 游离 HEAD 时单独列出
+    let hash = get_current_commit_hash(repo);
+    run(&["checkout", &hash], repo);
+    let out = run(&["branch"], repo);
+    assert!(out.starts_with(&format!("* (HEAD detached at {})", &hash[..7])), "实际输出: {}", out);
+}
 
+#[test]
+fn test_branch_list_remotes_and_tracking() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(&server).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a", "first");
+    run(&["clone", "server", "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+
+    let remotes = run(&["branch", "-r"], &copy);
+    assert!(remotes.contains("  origin/master\n") && remotes.contains("origin/HEAD -> origin/master"), "实际输出: {}", remotes);
+    let all = run(&["branch", "-a"], &copy);
+    assert!(all.starts_with("* master\n") && all.contains("  remotes/origin/master\n"), "实际输出: {}", all);
+
+    commit_file(&copy, "b.txt", "b", "local");
+    let out = run(&["branch", "-v"], &copy);
+    assert!(out.contains("[ahead 1] local"), "实际输出: {}", out);
+    let out = run(&["branch", "-vv"], &copy);
+    assert!(out.contains("[origin/master: ahead 1] local"), "实际输出: {}", out);
+}
+
+#[test]
+fn test_branch_hierarchical_names_and_rename_copy() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "a", "first");
+
+    run(&["branch", "feature/login"], repo);
+    assert_branch_exists(repo, "feature/login");
+    bin().args(["branch", "feature"]).current_dir(repo).assert().failure();
+    run(&["checkout", "feature/login"], repo);
+    assert_head_points_to(repo, "feature/login");
+    commit_file(repo, "b.txt", "b", "login");
+    run(&["checkout", "master"], repo);
+    assert_eq!(run(&["branch"], repo), "  feature/login\n* master\n");
+
+    // 重命名时上游配置与 reflog 一起移动，空目录被清理
+    fs::write(repo.join(".mygit/config"), "[branch \"feature/login\"]\n\tremote = .\n\tmerge = refs/heads/master\n").unwrap();
+    fs::create_dir_all(repo.join(".mygit/logs/refs/heads/feature")).unwrap();
+    fs::write(repo.join(".mygit/logs/refs/heads/feature/login"), "log\n").unwrap();
+    run(&["branch", "-m", "feature/login", "login"], repo);
+    assert_branch_exists(repo, "login");
+    assert!(!repo.join(".mygit/refs/heads/feature").exists(), "空目录应被清理");
+    assert!(repo.join(".mygit/logs/refs/heads/login").exists());
+    let config = fs::read_to_string(repo.join(".mygit/config")).unwrap();
+    assert!(config.contains("[branch \"login\"]") && !config.contains("feature/login"), "实际配置: {}", config);
+
+    // 复制保留原分支；目标已存在时需要 -C
+    run(&["branch", "-c", "login", "login2"], repo);
+    assert_branch_exists(repo, "login");
+    assert!(fs::read_to_string(repo.join(".mygit/config")).unwrap().contains("[branch \"login2\"]"));
+    bin().args(["branch", "-c", "login", "login2"]).current_dir(repo).assert().failure();
+    run(&["branch", "-C", "login", "login2"], repo);
+
+    // 新引用无法写入时重命名整体失败，原分支保持不变
+    let lock = repo.join(".mygit/refs/heads/renamed.lock");
+    fs::write(&lock, "").unwrap();
+    bin().args(["branch", "-m", "login", "renamed"]).current_dir(repo).assert().failure();
+    assert_branch_exists(repo, "login");
+    fs::remove_file(&lock).unwrap();
+
+    // 加锁之后、最后改名时才失败（新引用的位置被目录占着），原分支同样保持不变
+    let blocker = repo.join(".mygit/refs/heads/renamed/sub");
+    fs::create_dir_all(&blocker).unwrap();
+    bin().args(["branch", "-m", "login", "renamed"]).current_dir(repo).assert().failure();
+    assert_branch_exists(repo, "login");
+    assert!(repo.join(".mygit/logs/refs/heads/login").is_file(), "重命名失败时 reflog 不应移动");
+    fs::remove_dir_all(repo.join(".mygit/refs/heads/renamed")).unwrap();
+
+    // `a` 与 `a/b` 之间可以互相重命名
+    run(&["branch", "-m", "login", "login/x"], repo);
+    assert_branch_exists(repo, "login/x");
+    run(&["branch", "-m", "login/x", "login"], repo);
+    assert_branch_exists(repo, "login");
+    assert!(!repo.join(".mygit/refs/heads/login").is_dir());
+
+    // 重命名当前分支会同时更新 HEAD
+    run(&["branch", "-m", "main"], repo);
+    assert_head_points_to(repo, "main");
+    run(&["branch", "-D", "login2"], repo);
+    assert_eq!(run(&["branch"], repo), "  login\n* main\n");
+}
+
+#[test]
+fn test_branch_delete_requires_merge_unless_forced() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "a", "first");
+    run(&["branch", "merged"], repo);
+    run(&["checkout", "-b", "topic"], repo);
+    commit_file(repo, "b.txt", "b", "topic work");
+    let tip = get_current_commit_hash(repo);
+    run(&["checkout", "master"], repo);
+
+    let out = run(&["branch", "-d", "merged"], repo);
+    assert!(out.starts_with("Deleted branch merged"), "实际输出: {}", out);
+
+    let output = bin().args(["branch", "-d", "topic"]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success(), "未合并的分支不应被 -d 删除");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("branch -D topic"));
+    assert_branch_exists(repo, "topic");
+
+    // 上游已包含该分支时也视为已合并
+    fs::write(repo.join(".mygit/config"), "[branch \"topic\"]\n\tremote = .\n\tmerge = refs/heads/keep\n").unwrap();
+    run(&["branch", "keep", &tip], repo);
+    run(&["branch", "-d", "topic"], repo);
+    assert!(!fs::read_to_string(repo.join(".mygit/config")).unwrap().contains("topic"), "分支配置应被删除");
+
+    // -D 强制删除，并给出可以恢复的完整 hash
+    let out = run(&["branch", "-D", "keep"], repo);
+    assert!(out.contains(&tip), "实际输出: {}", out);
+    run(&["branch", "keep", &tip], repo);
+    assert_eq!(fs::read_to_string(repo.join(".mygit/refs/heads/keep")).unwrap().trim(), tip);
+}
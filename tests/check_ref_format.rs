@@ -0,0 +1,69 @@
+// tests/check_ref_format.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run};
+
+fn valid(name: &str, extra: &[&str], repo: &Path) -> bool {
+    let mut args = vec!["check-ref-format"];
+    args.extend_from_slice(extra);
+    args.push(name);
+    bin().args(&args).current_dir(repo).output().unwrap().status.success()
+}
+
+#[test]
+fn test_check_ref_format_rules() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    for name in ["refs/heads/master", "refs/heads/feature/x-1", "refs/tags/v1.0", "refs/heads/a@b"] {
+        assert!(valid(name, &[], repo), "{} 应该合法", name);
+    }
+    for name in [
+        "master", "refs/heads/foo.lock", "refs/heads/.hidden", "refs/heads/a..b", "refs/heads/a@{b",
+        "refs/heads/x.", "refs/heads/a b", "refs/heads/a~1", "refs/heads/a^", "refs/heads/a:b",
+        "refs/heads/a?", "refs/heads/a[", "refs/heads/a\\b", "refs/heads/*", "refs/heads/", "@",
+        "refs//heads/x", "/refs/heads/x",
+    ] {
+        assert!(!valid(name, &[], repo), "{} 应该不合法", name);
+    }
+
+    assert!(valid("master", &["--allow-onelevel"], repo), "--allow-onelevel 应允许单级名字");
+    assert!(valid("refs/heads/*", &["--refspec-pattern"], repo), "--refspec-pattern 应允许一个 *");
+    assert!(!valid("refs/*/*", &["--refspec-pattern"], repo), "只允许一个 *");
+
+    assert_eq!(run(&["check-ref-format", "--normalize", "/refs//heads///x"], repo), "refs/heads/x\n");
+    assert!(!valid("refs/heads/x/", &["--normalize"], repo), "规范化不应去掉结尾的 /");
+    assert!(!valid("refs/heads/x//", &["--normalize"], repo), "规范化不应去掉结尾的 /");
+    assert_eq!(run(&["check-ref-format", "--branch", "feature/x"], repo), "feature/x\n");
+    bin().args(["check-ref-format", "--branch", "-bad"]).current_dir(repo).assert().code(128);
+    bin().args(["check-ref-format", "--branch", "HEAD"]).current_dir(repo).assert().code(128);
+}
+
+#[test]
+fn test_ref_writers_reject_bad_names() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+
+    for name in ["foo.lock", ".hidden", "a@{b", "@", "x.", "a..b"] {
+        bin().args(["branch", name]).current_dir(repo).assert().failure();
+        if name != "@" {
+            // "@" 只是不能作为分支名，refs/heads/@ 本身是合法的引用
+            bin().args(["update-ref", &format!("refs/heads/{}", name), "HEAD"]).current_dir(repo).assert().failure();
+        }
+        assert!(!repo.join(".mygit/refs/heads").join(name).exists(), "{} 不应被创建", name);
+    }
+    bin().args(["remote", "add", "bad..name", "../elsewhere"]).current_dir(repo).assert().failure();
+    bin().args(["symbolic-ref", "HEAD", "refs/heads/foo.lock"]).current_dir(repo).assert().failure();
+
+    run(&["branch", "good/name"], repo);
+    run(&["update-ref", "refs/heads/other", "HEAD"], repo);
+    assert!(repo.join(".mygit/refs/heads/good/name").exists());
+}
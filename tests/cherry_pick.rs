@@ -0,0 +1,168 @@
+// tests/cherry_pick.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run, commit_file};
+
+fn read(repo: &Path, name: &str) -> String {
+    fs::read_to_string(repo.join(name)).unwrap()
+}
+
+/// main 上有 base.txt，feature 分支上有一个修改 base.txt 的提交和一个新增文件的提交
+fn setup_feature(repo: &Path) -> (String, String) {
+    run(&["init"], repo);
+    commit_file(repo, "base.txt", "one\ntwo\nthree\n", "base");
+    run(&["branch", "feature"], repo);
+    run(&["checkout", "feature"], repo);
+    let edit = commit_file(repo, "base.txt", "one\ntwo\nTHREE\n", "edit base");
+    let add = commit_file(repo, "extra.txt", "extra\n", "add extra");
+    run(&["checkout", "master"], repo);
+    (edit, add)
+}
+
+#[test]
+fn test_cherry_pick_applies_commit_and_keeps_author() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let (_, add) = setup_feature(repo);
+
+    run(&["cherry-pick", &add], repo);
+    assert_eq!(read(repo, "extra.txt"), "extra\n");
+    assert_eq!(read(repo, "base.txt"), "one\ntwo\nthree\n", "不应带入其它提交的改动");
+
+    let log = run(&["log", "--oneline"], repo);
+    let subjects: Vec<_> = log.lines().filter_map(|l| l.split_once(' ').map(|(_, s)| s)).collect();
+    assert_eq!(subjects, vec!["add extra", "base"]);
+
+    let original = run(&["log", "-n", "1", &add], repo);
+    let picked = run(&["log", "-n", "1"], repo);
+    let author = |out: &str| out.lines().find(|l| l.starts_with("Author:")).unwrap().to_string();
+    assert_eq!(author(&original), author(&picked), "cherry-pick 应保留原作者");
+    assert!(!picked.contains(&add), "cherry-pick 应生成新的提交");
+}
+
+#[test]
+fn test_cherry_pick_no_commit_only_updates_index() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let (edit, add) = setup_feature(repo);
+
+    run(&["cherry-pick", "-n", &edit, &add], repo);
+    assert_eq!(read(repo, "base.txt"), "one\ntwo\nTHREE\n");
+    assert_eq!(read(repo, "extra.txt"), "extra\n");
+
+    let log = run(&["log", "--oneline"], repo);
+    assert_eq!(log.lines().count(), 1, "-n 不应创建提交: {}", log);
+    let status = run(&["status"], repo);
+    assert!(status.contains("extra.txt"), "改动应已暂存: {}", status);
+}
+
+#[test]
+fn test_cherry_pick_refuses_to_overwrite_unstaged_changes() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let (edit, add) = setup_feature(repo);
+    let log = run(&["log", "--oneline"], repo);
+
+    fs::write(repo.join("base.txt"), "local\n").unwrap();
+    for args in [vec!["cherry-pick", &edit], vec!["cherry-pick", "-n", &edit], vec!["revert", "HEAD"]] {
+        let output = bin().args(&args).current_dir(repo).output().unwrap();
+        assert!(!output.status.success(), "{:?} 应当失败", args);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("base.txt"));
+        assert_eq!(read(repo, "base.txt"), "local\n", "未暂存的修改不应被覆盖");
+        assert_eq!(run(&["log", "--oneline"], repo), log, "不应创建提交");
+    }
+
+    // index 被其他进程锁住时，在改动工作区之前就失败
+    let lock = repo.join(".mygit/index.lock");
+    fs::write(&lock, "").unwrap();
+    bin().args(["cherry-pick", &add]).current_dir(repo).assert().failure();
+    assert!(!repo.join("extra.txt").exists(), "index 被锁住时不应改动工作区");
+    fs::remove_file(&lock).unwrap();
+
+    // 不涉及的文件有修改时照常应用
+    run(&["cherry-pick", &add], repo);
+    assert_eq!(read(repo, "extra.txt"), "extra\n");
+    assert_eq!(read(repo, "base.txt"), "local\n");
+}
+
+#[test]
+fn test_revert_undoes_commit() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "1\n2\n3\n", "first");
+    let bad = commit_file(repo, "a.txt", "1\nbad\n3\n", "bad change");
+    commit_file(repo, "b.txt", "b\n", "unrelated");
+
+    run(&["revert", &bad], repo);
+    assert_eq!(read(repo, "a.txt"), "1\n2\n3\n");
+    assert_eq!(read(repo, "b.txt"), "b\n", "不应影响之后的提交");
+
+    let log = run(&["log", "-n", "1"], repo);
+    assert!(log.contains("Revert \"bad change\""), "实际输出: {}", log);
+    assert!(log.contains(&format!("This reverts commit {}.", bad)), "实际输出: {}", log);
+}
+
+#[test]
+fn test_cherry_pick_conflict_continue_and_abort() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let (edit, add) = setup_feature(repo);
+    let head = commit_file(repo, "base.txt", "one\ntwo\nthree!\n", "edit on master");
+
+    // 冲突：保留状态并以失败退出
+    let output = bin().args(["cherry-pick", &edit, &add]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success(), "冲突时应以失败退出");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge conflict in base.txt"));
+    let conflicted = read(repo, "base.txt");
+    assert!(conflicted.contains("<<<<<<< HEAD") && conflicted.contains(">>>>>>> "), "应写入冲突标记: {}", conflicted);
+    assert!(repo.join(".mygit/CHERRY_PICK_HEAD").exists());
+
+    // 未解决时不能继续
+    bin().args(["cherry-pick", "--continue"]).current_dir(repo).assert().failure();
+
+    // --abort 回到原来的提交
+    run(&["cherry-pick", "--abort"], repo);
+    assert_eq!(read(repo, "base.txt"), "one\ntwo\nthree!\n");
+    assert!(!repo.join("extra.txt").exists());
+    assert!(!repo.join(".mygit/sequencer").exists());
+    assert!(run(&["log", "-n", "1", "--oneline"], repo).starts_with(&head[..7]));
+
+    // 再来一次，这次解决冲突后继续，剩下的提交也会被应用
+    bin().args(["cherry-pick", &edit, &add]).current_dir(repo).assert().failure();
+    fs::write(repo.join("base.txt"), "one\ntwo\nTHREE!\n").unwrap();
+    run(&["add", "base.txt"], repo);
+    run(&["cherry-pick", "--continue"], repo);
+
+    assert_eq!(read(repo, "extra.txt"), "extra\n");
+    let log = run(&["log", "--oneline"], repo);
+    let subjects: Vec<_> = log.lines().filter_map(|l| l.split_once(' ').map(|(_, s)| s)).collect();
+    assert_eq!(subjects, vec!["add extra", "edit base", "edit on master", "base"]);
+    assert!(!repo.join(".mygit/CHERRY_PICK_HEAD").exists());
+}
+
+#[test]
+fn test_cherry_pick_merge_commit_requires_mainline() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let (_, _) = setup_feature(repo);
+    commit_file(repo, "master.txt", "m\n", "master work");
+    run(&["merge", "feature"], repo);
+    let merge = run(&["log", "-n", "1", "--oneline"], repo)[..7].to_string();
+
+    run(&["branch", "other"], repo);
+    run(&["checkout", "other"], repo);
+    run(&["revert", "-m", "1", &merge], repo);
+    assert!(!repo.join("extra.txt").exists(), "以第一父提交为基准撤销合并，应去掉 feature 的改动");
+    assert_eq!(read(repo, "base.txt"), "one\ntwo\nthree\n");
+    assert_eq!(read(repo, "master.txt"), "m\n");
+
+    let output = bin().args(["cherry-pick", &merge]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success(), "合并提交缺少 -m 时应失败");
+    assert!(!repo.join(".mygit/sequencer").exists());
+}
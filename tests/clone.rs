@@ -0,0 +1,64 @@
+// tests/clone.rs
+
+use tempfile::tempdir;
+use std::fs;
+
+mod common;
+use common::{bin, run, commit_file};
+
+#[test]
+fn test_clone_checks_out_default_branch() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(server.join("dir")).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    commit_file(&server, "dir/b.txt", "b\n", "second");
+    run(&["branch", "dev"], &server);
+
+    run(&["clone", "server", "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+    assert_eq!(fs::read_to_string(copy.join("a.txt")).unwrap(), "a\n");
+    assert_eq!(fs::read_to_string(copy.join("dir/b.txt")).unwrap(), "b\n");
+
+    let log = run(&["log", "--oneline"], &copy);
+    assert_eq!(log.lines().count(), 2, "实际输出: {}", log);
+    assert!(copy.join(".mygit/refs/remotes/origin/master").exists());
+    assert!(copy.join(".mygit/refs/remotes/origin/dev").exists());
+
+    let config = fs::read_to_string(copy.join(".mygit/config")).unwrap();
+    assert!(config.contains("[branch \"master\"]") && config.contains("merge = refs/heads/master"), "实际配置: {}", config);
+
+    // 默认目录名取源仓库目录名；目标非空时拒绝
+    let elsewhere = tmp.path().join("elsewhere");
+    fs::create_dir_all(&elsewhere).unwrap();
+    run(&["clone", "../server"], &elsewhere);
+    assert!(elsewhere.join("server/a.txt").exists());
+    bin().args(["clone", "server", "copy"]).current_dir(tmp.path()).assert().failure();
+}
+
+#[test]
+fn test_fetch_updates_remote_tracking_branches() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(&server).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    run(&["clone", "server", "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+
+    let second = commit_file(&server, "a.txt", "a2\n", "second");
+    run(&["branch", "topic"], &server);
+
+    let out = run(&["fetch"], &copy);
+    assert!(out.contains("master -> origin/master"), "实际输出: {}", out);
+    assert!(out.contains("[new branch]") && out.contains("topic -> origin/topic"), "实际输出: {}", out);
+    assert_eq!(fs::read_to_string(copy.join(".mygit/refs/remotes/origin/master")).unwrap().trim(), second);
+
+    // 工作区与本地分支不受影响，但对象已经取回
+    assert_eq!(fs::read_to_string(copy.join("a.txt")).unwrap(), "a\n");
+    let log = run(&["log", "--oneline", "origin/master"], &copy);
+    assert!(log.contains("second"), "实际输出: {}", log);
+
+    assert_eq!(run(&["fetch"], &copy), "", "没有变化时不输出");
+}
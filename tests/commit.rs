@@ -42,7 +42,10 @@ fn test_commit_empty_message() {
     bin().args(["add", "a.txt"]).current_dir(repo).assert().success();
 
     let result = bin().args(["commit", "-m", ""]).current_dir(repo).output().unwrap();
-    assert!(result.status.success());
+    assert!(!result.status.success(), "空的提交说明应被拒绝");
+    assert!(String::from_utf8_lossy(&result.stderr).contains("empty commit message"));
+
+    bin().args(["commit", "-m", "", "--allow-empty-message"]).current_dir(repo).assert().success();
 }
 
 #[test]
@@ -82,7 +85,9 @@ fn test_add_rm_then_commit() {
     fs::write(repo.join("a.txt"), "something").unwrap();
     bin().args(["add", "a.txt"]).current_dir(repo).assert().success();
     bin().args(["rm", "a.txt"]).current_dir(repo).assert().success();
-    bin().args(["commit", "-m", "add then rm commit"]).current_dir(repo).assert().success();
+    // index 为空时的根提交与空 tree 相同，需要 --allow-empty
+    bin().args(["commit", "-m", "add then rm commit"]).current_dir(repo).assert().failure();
+    bin().args(["commit", "--allow-empty", "-m", "add then rm commit"]).current_dir(repo).assert().success();
 }
 
 #[test]
@@ -98,3 +103,195 @@ fn test_add_rm_add_dot_then_commit() {
     bin().args(["add", "."]).current_dir(repo).assert().success();
     bin().args(["commit", "-m", "add rm add again commit"]).current_dir(repo).assert().success();
 }
+
+#[test]
+fn test_commit_message_paragraphs_and_file() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    bin().arg("init").current_dir(repo).assert().success();
+    fs::write(repo.join("a.txt"), "a").unwrap();
+    bin().args(["add", "a.txt"]).current_dir(repo).assert().success();
+    bin().args(["commit", "-m", "subject", "-m", "body paragraph"]).current_dir(repo).assert().success();
+    let log = bin().args(["log", "-n", "1"]).current_dir(repo).output().unwrap();
+    let log = String::from_utf8_lossy(&log.stdout).to_string();
+    assert!(log.contains("    subject\n    \n    body paragraph"), "多个 -m 应各成一段: {}", log);
+
+    fs::write(repo.join("b.txt"), "b").unwrap();
+    bin().args(["add", "b.txt"]).current_dir(repo).assert().success();
+    fs::write(repo.join("msg.txt"), "from file  \n\n\n\nlong description\n").unwrap();
+    bin().args(["commit", "-F", "msg.txt"]).current_dir(repo).assert().success();
+    let log = bin().args(["log", "-n", "1"]).current_dir(repo).output().unwrap();
+    let log = String::from_utf8_lossy(&log.stdout).to_string();
+    assert!(log.contains("    from file\n    \n    long description"), "实际输出: {}", log);
+
+    bin().args(["commit", "-m", "x", "-F", "msg.txt"]).current_dir(repo).assert().failure();
+}
+
+#[test]
+fn test_commit_editor_with_template_and_status() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    bin().arg("init").current_dir(repo).assert().success();
+    fs::write(repo.join("a.txt"), "a").unwrap();
+    bin().args(["add", "a.txt"]).current_dir(repo).assert().success();
+
+    // 编辑器看到状态摘要，注释行会被去掉
+    let editor = "sh -c 'cp \"$1\" seen.txt; printf \"from editor\\n# ignored\\n\" > \"$1\"' editor";
+    bin().arg("commit").env("GIT_EDITOR", editor).current_dir(repo).assert().success();
+    let seen = fs::read_to_string(repo.join("seen.txt")).unwrap();
+    assert!(seen.contains("# On branch master") && seen.contains("new file:   a.txt"), "编辑器内容: {}", seen);
+    let log = bin().args(["log", "-n", "1"]).current_dir(repo).output().unwrap();
+    let log = String::from_utf8_lossy(&log.stdout).to_string();
+    assert!(log.contains("    from editor") && !log.contains("ignored"), "实际输出: {}", log);
+
+    // 模板未修改时放弃提交
+    fs::write(repo.join("template.txt"), "Ticket: \n").unwrap();
+    fs::write(repo.join(".mygit/config"), "[commit]\n\ttemplate = template.txt\n").unwrap();
+    fs::write(repo.join("b.txt"), "b").unwrap();
+    bin().args(["add", "b.txt"]).current_dir(repo).assert().success();
+    let result = bin().arg("commit").env("GIT_EDITOR", "true").current_dir(repo).output().unwrap();
+    assert!(!result.status.success(), "未修改模板时应放弃提交");
+    assert!(String::from_utf8_lossy(&result.stderr).contains("did not edit"));
+
+    bin().arg("commit").env("GIT_EDITOR", "sed -i -e '1s/$/ABC-1/'").current_dir(repo).assert().success();
+    let log = bin().args(["log", "-n", "1"]).current_dir(repo).output().unwrap();
+    assert!(String::from_utf8_lossy(&log.stdout).contains("    Ticket: ABC-1"));
+
+    // 编辑器留空时放弃提交
+    fs::write(repo.join("c.txt"), "c").unwrap();
+    bin().args(["add", "c.txt"]).current_dir(repo).assert().success();
+    let result = bin().args(["commit", "-t", "/dev/null"]).env("GIT_EDITOR", "true").current_dir(repo).output().unwrap();
+    assert!(!result.status.success(), "空的提交说明应被拒绝");
+}
+
+fn last_log(repo: &std::path::Path) -> String {
+    let output = bin().args(["log", "-n", "1"]).current_dir(repo).output().unwrap();
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_commit_refuses_empty_and_amends() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    bin().arg("init").current_dir(repo).assert().success();
+    fs::write(repo.join("a.txt"), "a").unwrap();
+    bin().args(["add", "a.txt"]).current_dir(repo).assert().success();
+    bin().args(["commit", "-m", "first"]).current_dir(repo).assert().success();
+
+    // 没有修改时拒绝提交
+    let result = bin().args(["commit", "-m", "nothing"]).current_dir(repo).output().unwrap();
+    assert!(!result.status.success(), "没有修改时应拒绝提交");
+    assert!(String::from_utf8_lossy(&result.stderr).contains("nothing to commit"));
+    bin().args(["commit", "-m", "empty", "--allow-empty"]).current_dir(repo).assert().success();
+    assert!(last_log(repo).contains("    empty"));
+
+    // --amend 替换提交说明并沿用父提交
+    fs::write(repo.join("b.txt"), "b").unwrap();
+    bin().args(["add", "b.txt"]).current_dir(repo).assert().success();
+    bin().args(["commit", "--amend", "-m", "first with b"]).current_dir(repo).assert().success();
+    let log = bin().args(["log", "--oneline"]).current_dir(repo).output().unwrap();
+    let log = String::from_utf8_lossy(&log.stdout).to_string();
+    assert_eq!(log.lines().count(), 2, "amend 不应增加提交: {}", log);
+    assert!(log.contains("first with b") && !log.contains("empty"), "实际输出: {}", log);
+
+    // --no-edit 沿用原说明
+    fs::write(repo.join("c.txt"), "c").unwrap();
+    bin().args(["add", "c.txt"]).current_dir(repo).assert().success();
+    bin().args(["commit", "--amend", "--no-edit"]).current_dir(repo).assert().success();
+    assert!(last_log(repo).contains("    first with b"));
+}
+
+#[test]
+fn test_commit_all_and_paths() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    bin().arg("init").current_dir(repo).assert().success();
+    fs::create_dir_all(repo.join("dir")).unwrap();
+    for (name, content) in [("a.txt", "a"), ("b.txt", "b"), ("dir/c.txt", "c")] {
+        fs::write(repo.join(name), content).unwrap();
+        bin().args(["add", name]).current_dir(repo).assert().success();
+    }
+    bin().args(["commit", "-m", "base"]).current_dir(repo).assert().success();
+
+    // 只提交 dir 下的修改，a.txt 的修改留在工作区
+    fs::write(repo.join("a.txt"), "a2").unwrap();
+    fs::write(repo.join("dir/c.txt"), "c2").unwrap();
+    bin().args(["commit", "-m", "only dir", "dir"]).current_dir(repo).assert().success();
+    let diff = bin().args(["diff", "HEAD~1", "HEAD"]).current_dir(repo).output().unwrap();
+    let diff = String::from_utf8_lossy(&diff.stdout).to_string();
+    assert!(diff.contains("dir/c.txt") && !diff.contains("a.txt"), "实际输出: {}", diff);
+    bin().args(["commit", "-m", "x", "missing.txt"]).current_dir(repo).assert().failure();
+
+    // -a 暂存修改与删除
+    fs::remove_file(repo.join("b.txt")).unwrap();
+    bin().args(["commit", "-a", "-m", "all"]).current_dir(repo).assert().success();
+    let diff = bin().args(["diff", "HEAD~1", "HEAD"]).current_dir(repo).output().unwrap();
+    let diff = String::from_utf8_lossy(&diff.stdout).to_string();
+    assert!(diff.contains("a.txt") && diff.contains("b.txt"), "实际输出: {}", diff);
+    let result = bin().args(["commit", "-a", "-m", "again"]).current_dir(repo).output().unwrap();
+    assert!(!result.status.success(), "全部提交后不应再有修改");
+}
+
+#[test]
+fn test_failed_commit_leaves_index_untouched() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let ls_files = || {
+        let output = bin().args(["ls-files", "-s"]).current_dir(repo).output().unwrap();
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    bin().arg("init").current_dir(repo).assert().success();
+    for name in ["a.txt", "b.txt"] {
+        fs::write(repo.join(name), name).unwrap();
+        bin().args(["add", name]).current_dir(repo).assert().success();
+    }
+    bin().args(["commit", "-m", "base"]).current_dir(repo).assert().success();
+    fs::write(repo.join("a.txt"), "a2").unwrap();
+    fs::remove_file(repo.join("b.txt")).unwrap();
+    let before = ls_files();
+
+    // 提交说明为空、commit-msg 钩子拒绝时，-a 与 `commit <paths>` 都不改动 index
+    let hook = repo.join(".mygit/hooks/commit-msg");
+    fs::create_dir_all(hook.parent().unwrap()).unwrap();
+    fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    for args in [vec!["commit", "-a", "-m", ""], vec!["commit", "-a", "-m", "rejected"], vec!["commit", "-m", "rejected", "a.txt"]] {
+        bin().args(&args).current_dir(repo).assert().failure();
+        assert_eq!(ls_files(), before, "{:?} 失败后 index 不应改变", args);
+        assert!(!repo.join(".mygit/index.lock").exists(), "{:?} 失败后不应留下 index.lock", args);
+    }
+    fs::remove_file(&hook).unwrap();
+
+    // pre-commit 钩子看到的是将要提交的内容
+    let seen = tmp.path().join("seen.txt");
+    let hook = repo.join(".mygit/hooks/pre-commit");
+    let script = format!("#!/bin/sh\n'{}' ls-files > '{}'\n", assert_cmd::cargo::cargo_bin("rust-git").display(), seen.display());
+    fs::write(&hook, script).unwrap();
+    fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+    bin().args(["commit", "-a", "-m", "all"]).current_dir(repo).assert().success();
+    assert_eq!(fs::read_to_string(&seen).unwrap(), "a.txt\n");
+    assert_ne!(ls_files(), before, "提交成功后 index 应当更新");
+    assert!(!ls_files().contains("b.txt"));
+    assert!(fs::read_dir(repo.join(".mygit")).unwrap().all(|e| !e.unwrap().file_name().to_string_lossy().starts_with("next-index")));
+
+    // 提交期间 index 一直被锁住，其他进程的 add 不会在写回时被覆盖
+    fs::write(repo.join("a.txt"), "a3").unwrap();
+    fs::write(repo.join("c.txt"), "c").unwrap();
+    let status = tmp.path().join("status.txt");
+    let script = format!(
+        "#!/bin/sh\nenv -u RUST_GIT_INDEX_FILE '{}' add c.txt\necho $? > '{}'\n",
+        assert_cmd::cargo::cargo_bin("rust-git").display(),
+        status.display()
+    );
+    fs::write(&hook, script).unwrap();
+    bin().args(["commit", "-a", "-m", "locked"]).current_dir(repo).assert().success();
+    assert_ne!(fs::read_to_string(&status).unwrap().trim(), "0", "提交期间 add 应当因 index 被锁而失败");
+    assert!(!repo.join(".mygit/index.lock").exists());
+}
@@ -0,0 +1,33 @@
+// tests/common/mod.rs
+//
+// 集成测试共用的辅助函数；每个测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use std::fs;
+use std::path::Path;
+
+pub fn bin() -> Command {
+    Command::cargo_bin("rust-git").expect("binary build failed")
+}
+
+/// 在 `repo` 中执行命令，要求成功并返回 stdout
+pub fn run(args: &[&str], repo: &Path) -> String {
+    let output = bin().args(args).current_dir(repo).output().unwrap();
+    assert!(output.status.success(), "命令 {:?} 执行失败: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// 写入文件、加入 index 并提交，返回 commit 输出的最后一行（新提交的 hash）
+pub fn commit_file(repo: &Path, name: &str, content: &str, message: &str) -> String {
+    fs::write(repo.join(name), content).unwrap();
+    run(&["add", name], repo);
+    let out = run(&["commit", "-m", message], repo);
+    out.lines().last().unwrap().trim().to_string()
+}
+
+/// 把内容写入对象库，返回 blob 的 hash
+pub fn hash_object(repo: &Path, content: &str) -> String {
+    let output = bin().args(["hash-object", "-w", "--stdin"]).write_stdin(content).current_dir(repo).output().unwrap();
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
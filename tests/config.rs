@@ -0,0 +1,89 @@
+// tests/config.rs
+
+use assert_cmd::Command;
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+/// 用临时的系统与用户配置文件运行，避免读到本机的配置
+fn bin(home: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("rust-git").expect("binary build failed");
+    cmd.env("RUST_GIT_CONFIG_GLOBAL", home.join("global"));
+    cmd.env("RUST_GIT_CONFIG_SYSTEM", home.join("system"));
+    cmd
+}
+
+fn run(args: &[&str], repo: &Path, home: &Path) -> String {
+    let output = bin(home).args(args).current_dir(repo).output().unwrap();
+    assert!(output.status.success(), "命令 {:?} 执行失败: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_config_layers() {
+    let home = tempdir().unwrap();
+    let tmp = tempdir().unwrap();
+    let (home, repo) = (home.path(), tmp.path());
+
+    fs::write(home.join("system"), "[user]\n\temail = system@example.com\n[core]\n\tautocrlf = input\n").unwrap();
+    run(&["config", "--global", "user.name", "Global User"], repo, home);
+    run(&["config", "--global", "init.defaultBranch", "main"], repo, home);
+    assert!(fs::read_to_string(home.join("global")).unwrap().contains("[user]\n\tname = Global User\n"));
+
+    run(&["init"], repo, home);
+    assert_eq!(fs::read_to_string(repo.join(".mygit/HEAD")).unwrap().trim(), "ref: refs/heads/main", "init.defaultBranch 应生效");
+
+    // 仓库配置覆盖用户配置，-c 覆盖一切
+    run(&["config", "user.email", "local@example.com"], repo, home);
+    assert_eq!(run(&["config", "--get", "user.email"], repo, home), "local@example.com\n");
+    assert_eq!(run(&["config", "--system", "user.email"], repo, home), "system@example.com\n");
+    assert_eq!(run(&["config", "core.autocrlf"], repo, home), "input\n");
+    assert_eq!(run(&["-c", "user.email=cli@example.com", "config", "user.email"], repo, home), "cli@example.com\n");
+
+    let list = run(&["-c", "user.name=Cli", "config", "--list", "--show-origin"], repo, home);
+    let global = format!("file:{}", home.join("global").display());
+    let system = format!("file:{}", home.join("system").display());
+    assert_eq!(
+        list,
+        format!(
+            "{s}\tuser.email=system@example.com\n{s}\tcore.autocrlf=input\n{g}\tuser.name=Global User\n{g}\tinit.defaultbranch=main\nfile:.mygit/config\tcore.bare=false\nfile:.mygit/config\tuser.email=local@example.com\ncommand line:\tuser.name=Cli\n",
+            s = system,
+            g = global
+        )
+    );
+
+    // 提交使用配置中的身份
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], repo, home);
+    run(&["-c", "user.name=Cli User", "commit", "-m", "first"], repo, home);
+    let commit = run(&["cat-file", "-p", "HEAD"], repo, home);
+    assert!(commit.contains("\nauthor Cli User <local@example.com> "), "作者应取自配置: {}", commit);
+}
+
+#[test]
+fn test_config_edit_errors() {
+    let home = tempdir().unwrap();
+    let tmp = tempdir().unwrap();
+    let (home, repo) = (home.path(), tmp.path());
+
+    bin(home).args(["config", "user.name", "x"]).current_dir(repo).assert().code(128);
+    run(&["init"], repo, home);
+
+    run(&["config", "--add", "remote.origin.fetch", "+refs/heads/a:refs/remotes/origin/a"], repo, home);
+    run(&["config", "--add", "remote.origin.fetch", "+refs/heads/b:refs/remotes/origin/b"], repo, home);
+    assert_eq!(run(&["config", "--get-all", "remote.origin.fetch"], repo, home).lines().count(), 2);
+    bin(home).args(["config", "remote.origin.fetch", "x"]).current_dir(repo).assert().code(5);
+    bin(home).args(["config", "--unset", "remote.origin.fetch"]).current_dir(repo).assert().code(5);
+    assert!(!repo.join(".mygit/config.lock").exists(), "被拒绝的修改不应留下锁文件");
+    run(&["config", "--unset-all", "remote.origin.fetch"], repo, home);
+    bin(home).args(["config", "--get", "remote.origin.fetch"]).current_dir(repo).assert().code(1);
+
+    bin(home).args(["config", "--unset", "user.name"]).current_dir(repo).assert().code(5);
+    bin(home).args(["config", "nosection", "x"]).current_dir(repo).assert().code(1);
+    bin(home).args(["config", "user.1name", "x"]).current_dir(repo).assert().code(1);
+    bin(home).args(["-c", "nosection", "status"]).current_dir(repo).assert().code(129);
+
+    // 值中的空白与注释字符写出时加引号
+    run(&["config", "alias.weird", " a # b "], repo, home);
+    assert_eq!(run(&["config", "alias.weird"], repo, home), " a # b \n");
+}
@@ -0,0 +1,92 @@
+// tests/diff.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::run;
+
+const CONTENT: &str = "line1\nline2\nline3\nline4\nline5\n";
+
+/// 提交 a.txt，再用 rm + add 把它改名为 b.txt（可选地修改最后一行）
+fn setup_rename(repo: &Path, new_content: &str) {
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), CONTENT).unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "add a"], repo);
+
+    run(&["rm", "a.txt"], repo);
+    fs::write(repo.join("b.txt"), new_content).unwrap();
+    run(&["add", "b.txt"], repo);
+}
+
+#[test]
+fn test_diff_cached_detects_exact_rename() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    setup_rename(repo, CONTENT);
+
+    let out = run(&["diff", "--cached"], repo);
+    assert!(out.contains("R100\ta.txt\tb.txt"), "应检测到完全重命名，实际输出: {}", out);
+}
+
+#[test]
+fn test_diff_similar_rename_respects_threshold() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    setup_rename(repo, "line1\nline2\nline3\nline4\nchanged\n");
+
+    // 默认阈值 50%：视为重命名
+    let out = run(&["diff", "--cached"], repo);
+    assert!(out.contains("R075\ta.txt\tb.txt"), "应检测到相似重命名，实际输出: {}", out);
+
+    // 阈值 90%：拆成删除 + 新增
+    let out = run(&["diff", "--cached", "-M90"], repo);
+    assert!(out.contains("D\ta.txt") && out.contains("A\tb.txt"), "实际输出: {}", out);
+
+    // 阈值也可以来自 diff.renameThreshold 配置（`-c` 或 config 命令）
+    let out = run(&["-c", "diff.renameThreshold=90%", "diff", "--cached"], repo);
+    assert!(out.contains("D\ta.txt") && out.contains("A\tb.txt"), "实际输出: {}", out);
+    run(&["config", "diff.renameThreshold", "90"], repo);
+    let out = run(&["diff", "--cached"], repo);
+    assert!(out.contains("D\ta.txt") && out.contains("A\tb.txt"), "实际输出: {}", out);
+    run(&["config", "--unset", "diff.renameThreshold"], repo);
+
+    // 关闭重命名检测
+    let out = run(&["diff", "--cached", "--no-renames"], repo);
+    assert!(out.contains("D\ta.txt") && out.contains("A\tb.txt"), "实际输出: {}", out);
+}
+
+#[test]
+fn test_diff_detects_copies() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), CONTENT).unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "add a"], repo);
+    fs::write(repo.join("copy.txt"), CONTENT).unwrap();
+    run(&["add", "copy.txt"], repo);
+
+    let out = run(&["diff", "--cached"], repo);
+    assert!(out.contains("A\tcopy.txt"), "默认不检测复制，实际输出: {}", out);
+
+    let out = run(&["diff", "--cached", "-C"], repo);
+    assert!(out.contains("C100\ta.txt\tcopy.txt"), "应检测到复制，实际输出: {}", out);
+}
+
+#[test]
+fn test_diff_between_commits_and_status() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    setup_rename(repo, CONTENT);
+
+    let status = run(&["status"], repo);
+    assert!(status.contains("renamed: a.txt -> b.txt"), "status 应显示重命名，实际输出: {}", status);
+
+    run(&["commit", "-m", "rename"], repo);
+    let out = run(&["diff", "HEAD~1", "HEAD"], repo);
+    assert!(out.contains("R100\ta.txt\tb.txt"), "实际输出: {}", out);
+}
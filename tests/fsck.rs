@@ -0,0 +1,73 @@
+// tests/fsck.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod common;
+use common::{bin, run, commit_file};
+
+fn object_file(repo: &Path, hash: &str) -> PathBuf {
+    repo.join(".mygit/objects").join(&hash[..2]).join(&hash[2..])
+}
+
+#[test]
+fn test_fsck_clean_and_dangling() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    assert!(run(&["fsck"], repo).contains("notice: HEAD 指向尚未提交的分支 (master)"));
+
+    let first = commit_file(repo, "a.txt", "one\n", "first");
+    let second = commit_file(repo, "a.txt", "two\n", "second");
+    assert_eq!(run(&["fsck"], repo), "", "干净的仓库不应有任何输出");
+
+    // 分支回退后，第二个提交成为悬空提交；它的 tree 与 blob 被它引用，不单独报告
+    run(&["update-ref", "refs/heads/master", &first], repo);
+    let out = run(&["fsck", "--lost-found"], repo);
+    assert_eq!(out, format!("dangling commit {}\n", second));
+    let saved = fs::read_to_string(repo.join(".mygit/lost-found/commit").join(&second)).unwrap();
+    assert_eq!(saved.trim(), second);
+
+    // 只存在于 reflog 中的提交不算悬空
+    fs::create_dir_all(repo.join(".mygit/logs")).unwrap();
+    let entry = format!("{} {} Your Name <you@example.com> 0 +0000\treset\n", second, first);
+    fs::write(repo.join(".mygit/logs/HEAD"), entry).unwrap();
+    assert_eq!(run(&["fsck"], repo), "");
+}
+
+#[test]
+fn test_fsck_reports_corruption() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "hello\n", "first");
+    commit_file(repo, "b.txt", "world\n", "second");
+
+    let blob_a = run(&["ls-tree", "HEAD", "a.txt"], repo).split_whitespace().nth(2).unwrap().to_string();
+    let blob_b = run(&["ls-tree", "HEAD", "b.txt"], repo).split_whitespace().nth(2).unwrap().to_string();
+    let tree = run(&["cat-file", "-p", "HEAD"], repo).lines().next().unwrap()[5..].to_string();
+
+    fs::write(object_file(repo, &blob_a), "tampered\n").unwrap();
+    fs::remove_file(object_file(repo, &blob_b)).unwrap();
+    fs::write(repo.join(".mygit/refs/heads/broken"), "not-a-hash\n").unwrap();
+
+    let output = bin().arg("fsck").current_dir(repo).output().unwrap();
+    assert_eq!(output.status.code(), Some(1), "发现错误时应以状态 1 退出");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("error: 对象 {} 的 hash 不符", blob_a)), "实际输出: {}", stderr);
+    assert!(stderr.contains("error: refs/heads/broken: 无效的 sha1 指针 'not-a-hash'"), "实际输出: {}", stderr);
+    assert!(stderr.contains(&format!("error: index 中的 'b.txt' 指向不存在的 blob {}", blob_b)), "实际输出: {}", stderr);
+    assert!(stdout.contains(&format!("broken link from   tree {}\n              to   blob {}\n", tree, blob_b)), "实际输出: {}", stdout);
+    assert!(stdout.contains(&format!("missing blob {}\n", blob_b)), "实际输出: {}", stdout);
+
+    // 分支指向格式错误的提交
+    let bad = format!("tree {}\nauthor nobody\ncommitter A <a@b> 0 +0000\n\nbad\n", tree);
+    let output = bin().args(["hash-object", "-w", "--stdin"]).write_stdin(bad).current_dir(repo).output().unwrap();
+    let bad = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    run(&["update-ref", "refs/heads/broken", &bad], repo);
+    let output = bin().arg("fsck").current_dir(repo).output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&format!("error: 对象 {}: 无效的 commit: author 行的签名格式错误", bad)), "实际输出: {}", stderr);
+}
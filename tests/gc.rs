@@ -0,0 +1,115 @@
+// tests/gc.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run, hash_object};
+
+fn has_object(repo: &Path, hash: &str) -> bool {
+    repo.join(".mygit/objects").join(&hash[..2]).join(&hash[2..]).exists()
+}
+
+#[test]
+fn test_prune_keeps_everything_reachable() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "v1\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    let v1 = hash_object(repo, "v1\n");
+    fs::write(repo.join("a.txt"), "v2\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+    fs::write(repo.join("a.txt"), "v3\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    let staged = hash_object(repo, "v3\n");
+
+    let stashed = hash_object(repo, "stashed\n");
+    let stash_tree = hash_object(repo, &format!("blob {} s.txt\n", stashed));
+    let stash = run(&["commit-tree", &stash_tree, "-m", "stash"], repo).trim().to_string();
+    run(&["update-ref", "refs/stash", &stash], repo);
+    let logged = hash_object(repo, "only in reflog\n");
+    let logged_tree = hash_object(repo, &format!("blob {} r.txt\n", logged));
+    let logged_commit = run(&["commit-tree", &logged_tree, "-m", "lost"], repo).trim().to_string();
+    fs::create_dir_all(repo.join(".mygit/logs")).unwrap();
+    let zero = "0".repeat(40);
+    fs::write(repo.join(".mygit/logs/HEAD"), format!("{} {} A <a@b> 0 +0000\tcommit\n", zero, logged_commit)).unwrap();
+    let garbage = hash_object(repo, "garbage\n");
+
+    assert_eq!(run(&["count-objects"], repo), "12 objects, 12 kilobytes\n");
+    let listed = run(&["prune", "-n"], repo);
+    assert!(listed.contains(&format!("{} blob\n", v1)) && listed.contains(&format!("{} blob\n", garbage)), "实际输出: {}", listed);
+    assert!(has_object(repo, &v1), "-n 不应删除对象");
+
+    run(&["prune"], repo);
+    assert!(!has_object(repo, &v1) && !has_object(repo, &garbage), "不可达对象应被删除");
+    for kept in [&staged, &stashed, &stash, &logged, &logged_commit] {
+        assert!(has_object(repo, kept), "可达对象 {} 不应被删除", kept);
+    }
+    assert!(run(&["count-objects", "-v"], repo).starts_with("count: 10\n"));
+    assert_eq!(run(&["fsck"], repo), "");
+
+    // 引用损坏时无法判断哪些对象可达，拒绝删除
+    let orphan = hash_object(repo, "orphan\n");
+    fs::write(repo.join(".mygit/refs/heads/broken"), "oops\n").unwrap();
+    bin().arg("prune").current_dir(repo).assert().failure();
+    assert!(has_object(repo, &orphan));
+}
+
+#[test]
+fn test_gc_grace_period() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "kept\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+    let fresh = hash_object(repo, "fresh\n");
+
+    // 默认两周的宽限期内，刚写入的对象不会被删除；引用同时被打包
+    let out = run(&["gc"], repo);
+    assert!(out.contains("清理前:\ncount: 4\n") && out.contains("清理后:\ncount: 4\n"), "实际输出: {}", out);
+    assert!(has_object(repo, &fresh));
+    assert!(!repo.join(".mygit/refs/heads/master").exists(), "gc 应当打包引用");
+    assert_eq!(run(&["log", "--oneline"], repo).lines().count(), 1);
+
+    fs::write(repo.join(".mygit/config"), "[gc]\n\tpruneExpire = now\n").unwrap();
+    run(&["gc", "-q"], repo);
+    assert!(!has_object(repo, &fresh), "gc.pruneExpire = now 时应立即删除");
+
+    let fresh = hash_object(repo, "fresh again\n");
+    run(&["gc", "--prune=never", "-q"], repo);
+    assert!(has_object(repo, &fresh), "--prune 优先于配置");
+    bin().args(["gc", "--prune=sometime"]).current_dir(repo).assert().failure();
+}
+
+#[test]
+fn test_prune_keeps_commits_of_interrupted_operations() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "base\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "base"], repo);
+    let base = run(&["show-ref", "-s", "refs/heads/master"], repo).trim().to_string();
+
+    // 两个没有引用指向的提交：第一个与 master 冲突，第二个留在待办列表中
+    let x = hash_object(repo, "x\n");
+    let y = hash_object(repo, "y\n");
+    let tree_x = hash_object(repo, &format!("blob {} a.txt\n", x));
+    let tree_y = hash_object(repo, &format!("blob {} a.txt\nblob {} y.txt\n", x, y));
+    let first = run(&["commit-tree", &tree_x, "-p", &base, "-m", "x"], repo).trim().to_string();
+    let second = run(&["commit-tree", &tree_y, "-p", &first, "-m", "y"], repo).trim().to_string();
+    fs::write(repo.join("a.txt"), "mine\n").unwrap();
+    run(&["commit", "-a", "-m", "mine"], repo);
+
+    bin().args(["cherry-pick", &first, &second]).current_dir(repo).assert().failure();
+    run(&["prune"], repo);
+    for kept in [&first, &second, &tree_x, &tree_y, &y] {
+        assert!(has_object(repo, kept), "中断的 cherry-pick 还要用到 {}，不应被删除", kept);
+    }
+    assert_eq!(run(&["fsck"], repo), "");
+    run(&["cherry-pick", "--abort"], repo);
+}
@@ -0,0 +1,160 @@
+// tests/git_interop.rs
+//
+// 与 C git 的仓库互相 fetch / push；系统中没有 git 时跳过
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+mod common;
+use common::{bin, run, commit_file};
+
+fn has_git() -> bool {
+    Command::new("git").arg("--version").output().is_ok_and(|o| o.status.success())
+}
+
+/// 在 `dir` 中执行 git，要求成功并返回 stdout
+fn git(args: &[&str], dir: &Path) -> String {
+    let output = Command::new("git")
+        .args(["-c", "user.name=Tester", "-c", "user.email=tester@example.com", "-c", "init.defaultBranch=master"])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?} 执行失败: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+/// 建一个 C git 仓库：含有子目录、附注标签，以及打包成 delta 的两个相近版本
+fn git_repository(path: &Path) {
+    fs::create_dir_all(path.join("src/deep")).unwrap();
+    git(&["init", "-q"], path);
+    fs::write(path.join("a.txt"), "a\n").unwrap();
+    fs::write(path.join("src/b.rs"), "b\n").unwrap();
+    fs::write(path.join("src/deep/c.txt"), "c\n").unwrap();
+    fs::write(path.join("src-x"), "x\n").unwrap();
+    let numbers: String = (1..=2000).map(|i| format!("{}\n", i)).collect();
+    fs::write(path.join("big.txt"), &numbers).unwrap();
+    git(&["add", "."], path);
+    git(&["commit", "-q", "-m", "first"], path);
+    fs::write(path.join("big.txt"), format!("{}2001\n", numbers)).unwrap();
+    git(&["commit", "-q", "-a", "-m", "second"], path);
+    git(&["tag", "-a", "v1", "-m", "release"], path);
+    git(&["repack", "-a", "-d", "-q"], path);
+}
+
+#[test]
+fn test_clone_and_fetch_from_git_repository() {
+    if !has_git() {
+        return;
+    }
+    let tmp = tempdir().unwrap();
+    let upstream = tmp.path().join("upstream");
+    git_repository(&upstream);
+
+    run(&["clone", &upstream.to_string_lossy(), "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+    assert_eq!(fs::read_to_string(copy.join("src/deep/c.txt")).unwrap(), "c\n");
+    assert!(fs::read_to_string(copy.join("big.txt")).unwrap().ends_with("2000\n2001\n"), "delta 对象应被还原");
+    let log = run(&["log", "--oneline"], &copy);
+    assert!(log.contains("first") && log.contains("second"), "实际输出: {}", log);
+    // 附注标签取它指向的提交
+    let head = fs::read_to_string(copy.join(".mygit/refs/heads/master")).unwrap();
+    assert_eq!(fs::read_to_string(copy.join(".mygit/refs/tags/v1")).unwrap(), head);
+
+    // 增量获取：没有变化的子目录由对方省去，从本地已有的 tree 中补全
+    fs::write(upstream.join("a.txt"), "a2\n").unwrap();
+    git(&["commit", "-q", "-a", "-m", "third"], &upstream);
+    let url = format!("file://{}", upstream.display());
+    run(&["config", "remote.origin.url", &url], &copy);
+    let out = run(&["fetch"], &copy);
+    assert!(out.contains("master -> origin/master"), "实际输出: {}", out);
+    let tree = run(&["ls-tree", "-r", "origin/master"], &copy);
+    assert!(tree.contains("src/deep/c.txt") && tree.contains("src/b.rs"), "实际输出: {}", tree);
+    assert!(run(&["log", "--oneline", "origin/master"], &copy).contains("third"));
+}
+
+#[test]
+fn test_push_to_bare_git_repository() {
+    if !has_git() {
+        return;
+    }
+    let tmp = tempdir().unwrap();
+    let upstream = tmp.path().join("upstream");
+    git_repository(&upstream);
+    git(&["clone", "-q", "--bare", "upstream", "bare.git"], tmp.path());
+    let bare = tmp.path().join("bare.git");
+
+    run(&["clone", &format!("file://{}", bare.display()), "work"], tmp.path());
+    let work = tmp.path().join("work");
+    let pushed = commit_file(&work, "src/deep/new.txt", "new\n", "from rust-git");
+    let out = run(&["push", "origin", "master"], &work);
+    assert!(out.contains("master -> master"), "实际输出: {}", out);
+
+    git(&["fsck", "--strict"], &bare);
+    assert!(git(&["log", "--oneline", "master"], &bare).contains("from rust-git"));
+    assert_eq!(git(&["show", "master:src/deep/new.txt"], &bare), "new\n");
+
+    // 再取回时得到同一个本地提交
+    fs::remove_file(work.join(".mygit/refs/remotes/origin/master")).unwrap();
+    run(&["fetch"], &work);
+    assert_eq!(fs::read_to_string(work.join(".mygit/refs/remotes/origin/master")).unwrap().trim(), pushed);
+}
+
+#[test]
+fn test_git_fetches_and_pushes_through_rust_git_services() {
+    if !has_git() {
+        return;
+    }
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(server.join("dir")).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    commit_file(&server, "dir/b.txt", "b\n", "second");
+
+    let exe = assert_cmd::cargo::cargo_bin("rust-git");
+    let url = format!("file://{}", server.display());
+    git(&["clone", "-q", "--upload-pack", &format!("{} upload-pack", exe.display()), &url, "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+    git(&["fsck", "--strict"], &copy);
+    assert_eq!(fs::read_to_string(copy.join("dir/b.txt")).unwrap(), "b\n");
+
+    // git 看到的提交 id 与 rust-git 公布的相同
+    let request = "0014command=ls-refs\n00010000";
+    let output = bin().args(["upload-pack", "."]).current_dir(&server).write_stdin(request).output().unwrap();
+    let head = git(&["rev-parse", "HEAD"], &copy);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(&format!("{} refs/heads/master", head.trim())));
+
+    fs::write(copy.join("c.txt"), "c\n").unwrap();
+    git(&["add", "c.txt"], &copy);
+    git(&["commit", "-q", "-m", "from git"], &copy);
+    git(&["push", "-q", "--receive-pack", &format!("{} receive-pack", exe.display()), "origin", "HEAD:refs/heads/topic"], &copy);
+    assert!(run(&["log", "--oneline", "topic"], &server).contains("from git"));
+    assert!(run(&["ls-tree", "-r", "topic"], &server).contains("dir/b.txt"));
+}
+
+#[test]
+fn test_fetch_refuses_files_rust_git_cannot_store() {
+    if !has_git() {
+        return;
+    }
+    let tmp = tempdir().unwrap();
+    let upstream = tmp.path().join("upstream");
+    git_repository(&upstream);
+    run(&["clone", &upstream.to_string_lossy(), "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+    let before = fs::read_to_string(copy.join(".mygit/refs/remotes/origin/master")).unwrap();
+
+    fs::write(upstream.join("run.sh"), "echo hi\n").unwrap();
+    git(&["add", "run.sh"], &upstream);
+    git(&["update-index", "--chmod=+x", "run.sh"], &upstream);
+    git(&["commit", "-q", "-m", "executable"], &upstream);
+
+    let output = bin().arg("fetch").current_dir(&copy).output().unwrap();
+    assert!(!output.status.success(), "无法原样保存的 tree 应拒绝导入");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("run.sh") && stderr.contains("100755"), "实际输出: {}", stderr);
+    assert_eq!(fs::read_to_string(copy.join(".mygit/refs/remotes/origin/master")).unwrap(), before);
+}
@@ -0,0 +1,118 @@
+// tests/hooks.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+mod common;
+use common::{bin, run, commit_file};
+
+/// 写入可执行的钩子脚本
+fn write_hook(dir: &Path, name: &str, script: &str) {
+    fs::create_dir_all(dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+fn log_subjects(repo: &Path) -> String {
+    run(&["log", "--oneline"], repo)
+}
+
+#[test]
+fn test_pre_commit_and_commit_msg_hooks() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "a\n", "first");
+    let hooks = repo.join(".mygit/hooks");
+
+    // pre-commit 失败时放弃提交，--no-verify 跳过钩子
+    write_hook(&hooks, "pre-commit", "echo lint failed >&2\nexit 1\n");
+    fs::write(repo.join("b.txt"), "b\n").unwrap();
+    run(&["add", "b.txt"], repo);
+    let output = bin().args(["commit", "-m", "blocked"]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success(), "pre-commit 失败时提交应失败");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("lint failed"));
+    assert!(!log_subjects(repo).contains("blocked"));
+    run(&["commit", "-m", "skipped", "--no-verify"], repo);
+    assert!(log_subjects(repo).contains("skipped"));
+    fs::remove_file(hooks.join("pre-commit")).unwrap();
+
+    // commit-msg 可以改写提交说明，也可以拒绝
+    write_hook(&hooks, "commit-msg", "grep -q WIP \"$1\" && exit 1\necho \"$(cat \"$1\") [checked]\" > \"$1\"\n");
+    commit_file(repo, "c.txt", "c\n", "add c");
+    assert!(log_subjects(repo).contains("add c [checked]"), "实际输出: {}", log_subjects(repo));
+    fs::write(repo.join("d.txt"), "d\n").unwrap();
+    run(&["add", "d.txt"], repo);
+    bin().args(["commit", "-m", "WIP d"]).current_dir(repo).assert().failure();
+    assert!(!log_subjects(repo).contains("WIP"));
+}
+
+#[test]
+fn test_hooks_run_in_work_tree_root() {
+    let tmp = tempdir().unwrap();
+    let root = fs::canonicalize(tmp.path()).unwrap();
+    let repo = root.join("main");
+    fs::create_dir_all(&repo).unwrap();
+    run(&["init"], &repo);
+    commit_file(&repo, "a.txt", "a\n", "first");
+    let pwd_file = root.join("pwd.txt");
+    write_hook(&repo.join(".mygit/hooks"), "pre-commit", &format!("pwd > '{}'\n", pwd_file.display()));
+
+    commit_file(&repo, "b.txt", "b\n", "second");
+    assert_eq!(fs::read_to_string(&pwd_file).unwrap().trim(), repo.display().to_string());
+
+    // 关联工作区共用钩子，但钩子在关联工作区的根目录中运行
+    run(&["worktree", "add", "../linked"], &repo);
+    let linked = root.join("linked");
+    commit_file(&linked, "c.txt", "c\n", "third");
+    assert_eq!(fs::read_to_string(&pwd_file).unwrap().trim(), linked.display().to_string());
+}
+
+#[test]
+fn test_checkout_merge_and_push_hooks() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(&server).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    run(&["clone", "server", "work"], tmp.path());
+    let repo = tmp.path().join("work");
+
+    // 通过 core.hooksPath 指定钩子目录（放在工作区之外，checkout 不会清理它）
+    let hooks = tmp.path().join("shared-hooks");
+    let mut config = fs::read_to_string(repo.join(".mygit/config")).unwrap();
+    config.push_str(&format!("[core]\n\thooksPath = {}\n", hooks.display()));
+    fs::write(repo.join(".mygit/config"), config).unwrap();
+
+    let checkout_log = tmp.path().join("post-checkout.log");
+    write_hook(&hooks, "post-checkout", &format!("echo \"$1 $2 $3\" > {}\n", checkout_log.display()));
+    let base = run(&["log", "-n", "1", "--oneline"], &repo)[..7].to_string();
+    run(&["checkout", "-b", "topic"], &repo);
+    let args = fs::read_to_string(&checkout_log).unwrap();
+    assert!(args.starts_with(&base) && args.trim().ends_with(" 1"), "post-checkout 参数: {}", args);
+    commit_file(&repo, "t.txt", "t\n", "topic work");
+    run(&["checkout", "master"], &repo);
+    commit_file(&repo, "m.txt", "m\n", "master work");
+
+    write_hook(&hooks, "pre-merge-commit", "exit 1\n");
+    let output = bin().args(["merge", "topic"]).current_dir(&repo).output().unwrap();
+    assert!(!output.status.success(), "pre-merge-commit 失败时合并应失败");
+    assert!(!log_subjects(&repo).contains("Merge branch"));
+    run(&["merge", "topic", "--no-verify"], &repo);
+    assert!(log_subjects(&repo).contains("Merge branch"));
+
+    // pre-push 从标准输入读取待推送的引用
+    write_hook(&hooks, "pre-push", "echo \"$1 $2\" > pre-push.log\ncat >> pre-push.log\nexit 1\n");
+    let output = bin().args(["push", "origin", "master:feature"]).current_dir(&repo).output().unwrap();
+    assert!(!output.status.success(), "pre-push 失败时推送应失败");
+    assert!(!server.join(".mygit/refs/heads/feature").exists());
+    let log = fs::read_to_string(repo.join("pre-push.log")).unwrap();
+    assert!(log.starts_with("origin "), "pre-push 参数: {}", log);
+    assert!(log.contains("master ") && log.contains(" refs/heads/feature 0000000000000000000000000000000000000000"), "pre-push 输入: {}", log);
+
+    run(&["push", "--no-verify", "origin", "master:feature"], &repo);
+    assert!(server.join(".mygit/refs/heads/feature").exists());
+}
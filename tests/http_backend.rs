@@ -0,0 +1,106 @@
+// tests/http_backend.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, ChildStdout, Stdio};
+
+mod common;
+use common::{bin, run, commit_file};
+
+/// 后台运行的 http-backend，离开作用域时结束进程
+struct Server {
+    child: Child,
+    /// 保持输出管道打开，服务端的日志输出才不会失败
+    _output: BufReader<ChildStdout>,
+    url: String,
+}
+
+impl Server {
+    fn start(directory: &Path) -> Server {
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("rust-git"))
+            .args(["http-backend", "--port", "0"])
+            .arg(directory)
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut output = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        output.read_line(&mut line).unwrap();
+        let url = line.trim().strip_prefix("listening on ").expect("应打印监听地址").to_string();
+        Server { child, _output: output, url }
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn test_clone_fetch_and_push_over_http() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path().join("repos/project");
+    fs::create_dir_all(repo.join("dir")).unwrap();
+    run(&["init"], &repo);
+    commit_file(&repo, "a.txt", "a\n", "first");
+    commit_file(&repo, "dir/b.txt", "b\n", "second");
+
+    let server = Server::start(&tmp.path().join("repos"));
+    let url = format!("{}/project", server.url);
+
+    run(&["clone", &url], tmp.path());
+    let work = tmp.path().join("project");
+    assert_eq!(fs::read_to_string(work.join("dir/b.txt")).unwrap(), "b\n");
+    assert!(run(&["log", "--oneline"], &work).contains("second"));
+
+    let third = commit_file(&repo, "c.txt", "c\n", "third");
+    let out = run(&["fetch"], &work);
+    assert!(out.contains("master -> origin/master"), "实际输出: {}", out);
+    assert_eq!(fs::read_to_string(work.join(".mygit/refs/remotes/origin/master")).unwrap().trim(), third);
+
+    // 默认不允许通过 HTTP 推送
+    let pushed = commit_file(&work, "d.txt", "d\n", "fourth");
+    let output = bin().args(["push", "origin", "master:topic"]).current_dir(&work).output().unwrap();
+    assert!(!output.status.success(), "未开启 http.receivepack 时推送应失败");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("403"), "实际输出: {}", String::from_utf8_lossy(&output.stderr));
+
+    let mut config = fs::read_to_string(repo.join(".mygit/config")).unwrap_or_default();
+    config.push_str("[http]\n\treceivepack = true\n");
+    fs::write(repo.join(".mygit/config"), config).unwrap();
+    let out = run(&["push", "origin", "master:topic"], &work);
+    assert!(out.contains("[new branch]"), "实际输出: {}", out);
+    assert_eq!(fs::read_to_string(repo.join(".mygit/refs/heads/topic")).unwrap().trim(), pushed);
+}
+
+#[test]
+fn test_http_backend_rejects_unknown_repositories() {
+    let tmp = tempdir().unwrap();
+    let repos = tmp.path().join("repos");
+    fs::create_dir_all(&repos).unwrap();
+    let server = Server::start(&repos);
+
+    let output = bin().args(["clone", &format!("{}/missing", server.url), "copy"]).current_dir(tmp.path()).output().unwrap();
+    assert!(!output.status.success(), "克隆不存在的仓库应失败");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("404"), "实际输出: {}", String::from_utf8_lossy(&output.stderr));
+
+    // 不能通过 .. 访问服务目录之外的仓库
+    run(&["init"], tmp.path());
+    let output = bin().args(["clone", &format!("{}/..", server.url), "outside"]).current_dir(tmp.path()).output().unwrap();
+    assert!(!output.status.success(), "不应允许访问服务目录之外的路径");
+
+    // 声明的请求体过大时拒绝请求，服务进程不受影响
+    for header in ["Content-Length: 99999999999", "Transfer-Encoding: chunked\r\n\r\nfffffffffff"] {
+        let mut stream = TcpStream::connect(server.url.trim_start_matches("http://")).unwrap();
+        write!(stream, "POST /missing/git-upload-pack HTTP/1.1\r\nHost: localhost\r\n{}\r\n\r\n", header).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 413 "), "实际响应: {}", response);
+    }
+    let output = bin().args(["clone", &format!("{}/missing", server.url), "copy2"]).current_dir(tmp.path()).output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stderr).contains("404"), "服务进程应当仍在运行: {}", String::from_utf8_lossy(&output.stderr));
+}
@@ -1,12 +1,10 @@
 // tests/init.rs
 
-use assert_cmd::Command;
 use tempfile::tempdir;
-// use std::fs;
+use std::fs;
 
-fn bin() -> Command {
-    Command::cargo_bin("rust-git").expect("binary build failed")
-}
+mod common;
+use common::{bin, run};
 
 #[test]
 fn test_init_default_branch() {
@@ -23,16 +21,74 @@ fn test_init_default_branch() {
     assert!(repo.join(".mygit/HEAD").exists());
 }
 
-// #[test]
-// fn test_init_with_custom_branch() {
-//     let tmp = tempdir().unwrap();
-//     let repo = tmp.path();
-//
-//     bin().args(["init", "--initial-branch", "main"])
-//         .current_dir(repo)
-//         .assert()
-//         .success();
-//
-//     let head = fs::read_to_string(repo.join(".mygit/HEAD")).unwrap();
-//     assert!(head.contains("refs/heads/main"));
-// }
+#[test]
+fn test_init_with_custom_branch() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    bin().args(["init", "--initial-branch", "main"])
+        .current_dir(repo)
+        .assert()
+        .success();
+
+    let head = fs::read_to_string(repo.join(".mygit/HEAD")).unwrap();
+    assert!(head.contains("refs/heads/main"));
+
+    bin().args(["init", "-b", "bad..name", "other"]).current_dir(repo).assert().failure();
+    assert!(!repo.join("other/.mygit").exists(), "无效的分支名不应创建仓库");
+}
+
+#[test]
+fn test_init_bare_and_push() {
+    let tmp = tempdir().unwrap();
+    let root = tmp.path();
+    let template = root.join("template");
+    fs::create_dir_all(template.join("info")).unwrap();
+    fs::write(template.join("info/exclude"), "*.o\n").unwrap();
+    fs::write(template.join("description"), "shared repo\n").unwrap();
+
+    run(&["init", "--bare", "--template", "template", "server.git"], root);
+    let server = root.join("server.git");
+    assert!(server.join("HEAD").is_file() && server.join("objects").is_dir());
+    assert!(!server.join(".mygit").exists(), "裸仓库不应有 .mygit");
+    assert_eq!(fs::read_to_string(server.join("info/exclude")).unwrap(), "*.o\n", "应复制模板文件");
+    assert_eq!(run(&["config", "core.bare"], &server), "true\n");
+
+    // 裸仓库中拒绝工作区操作
+    for args in [&["status"][..], &["add", "x"], &["ls-files"], &["checkout", "master"]] {
+        bin().args(args).current_dir(&server).assert().code(128);
+    }
+
+    fs::create_dir(root.join("work")).unwrap();
+    let work = root.join("work");
+    run(&["init"], &work);
+    fs::write(work.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], &work);
+    run(&["commit", "-m", "first"], &work);
+    run(&["remote", "add", "origin", "../server.git"], &work);
+    run(&["push", "origin", "master"], &work);
+
+    let head = run(&["show-ref", "--hash", "refs/heads/master"], &work);
+    assert_eq!(run(&["show-ref", "--hash", "refs/heads/master"], &server), head, "推送到裸仓库后分支应更新");
+    assert!(run(&["log"], &server).contains("first"));
+}
+
+#[test]
+fn test_init_separate_git_dir() {
+    let tmp = tempdir().unwrap();
+    let root = tmp.path();
+
+    run(&["init", "--separate-git-dir", "store", "work"], root);
+    let work = root.join("work");
+    let pointer = fs::read_to_string(work.join(".mygit")).unwrap();
+    let store = fs::canonicalize(root.join("store")).unwrap();
+    assert_eq!(pointer, format!("gitdir: {}\n", store.display()));
+
+    fs::write(work.join("a.txt"), "a\n").unwrap();
+    run(&["add", "."], &work);
+    assert_eq!(run(&["ls-files"], &work), "a.txt\n", ".mygit 文件不应被加入暂存区");
+    run(&["commit", "-m", "first"], &work);
+    assert!(store.join("index").is_file());
+    assert_eq!(run(&["config", "core.bare"], &work), "false\n");
+    assert!(!run(&["status"], &work).contains(".mygit"));
+}
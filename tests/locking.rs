@@ -0,0 +1,77 @@
+// tests/locking.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod common;
+use common::{bin, run};
+
+fn leftover_files(dir: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    for entry in fs::read_dir(dir).unwrap().flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            found.extend(leftover_files(&path));
+        } else if path.extension().is_some_and(|ext| ext == "lock" || ext == "tmp") {
+            found.push(path);
+        }
+    }
+    found
+}
+
+#[test]
+fn test_held_locks_fail_cleanly() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "one\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+    let index = fs::read_to_string(repo.join(".mygit/index")).unwrap();
+
+    // 另一个进程持有 index 锁：add 失败，index 保持原样，锁文件不被删除
+    fs::write(repo.join(".mygit/index.lock"), "").unwrap();
+    fs::write(repo.join("a.txt"), "two\n").unwrap();
+    let output = bin().args(["add", "a.txt"]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success(), "index 被锁定时 add 应当失败");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("可能有另一个 rust-git 进程正在运行"));
+    assert_eq!(fs::read_to_string(repo.join(".mygit/index")).unwrap(), index);
+    assert!(repo.join(".mygit/index.lock").exists());
+    fs::remove_file(repo.join(".mygit/index.lock")).unwrap();
+
+    // 配置被锁定时修改配置失败，配置文件不被改写
+    fs::write(repo.join(".mygit/config.lock"), "").unwrap();
+    bin().args(["remote", "add", "origin", "/nowhere"]).current_dir(repo).assert().failure();
+    assert!(!fs::read_to_string(repo.join(".mygit/config")).unwrap_or_default().contains("origin"));
+    fs::remove_file(repo.join(".mygit/config.lock")).unwrap();
+
+    // 分支被锁定时提交失败，分支不动
+    run(&["add", "a.txt"], repo);
+    let head = fs::read_to_string(repo.join(".mygit/refs/heads/master")).unwrap();
+    fs::write(repo.join(".mygit/refs/heads/master.lock"), "").unwrap();
+    bin().args(["commit", "-m", "second"]).current_dir(repo).assert().failure();
+    assert_eq!(fs::read_to_string(repo.join(".mygit/refs/heads/master")).unwrap(), head);
+    fs::remove_file(repo.join(".mygit/refs/heads/master.lock")).unwrap();
+    run(&["commit", "-m", "second"], repo);
+}
+
+#[test]
+fn test_no_lock_or_temp_files_left_behind() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    for (i, content) in ["one\n", "two\n", "three\n"].iter().enumerate() {
+        fs::write(repo.join("a.txt"), content).unwrap();
+        run(&["add", "a.txt"], repo);
+        run(&["commit", "-m", &format!("c{}", i)], repo);
+    }
+    run(&["branch", "topic"], repo);
+    run(&["remote", "add", "origin", "/nowhere"], repo);
+    run(&["rm", "a.txt"], repo);
+    run(&["pack-refs", "--all"], repo);
+    run(&["branch", "-D", "topic"], repo);
+
+    assert_eq!(leftover_files(&repo.join(".mygit")), Vec::<PathBuf>::new());
+    assert_eq!(run(&["fsck"], repo), "");
+}
@@ -0,0 +1,45 @@
+// tests/log.rs
+
+use tempfile::tempdir;
+
+mod common;
+use common::{run, commit_file};
+
+#[test]
+fn test_log_lists_commits_newest_first() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "1", "first");
+    commit_file(repo, "a.txt", "2", "second");
+
+    let out = run(&["log", "--oneline"], repo);
+    let subjects: Vec<_> = out.lines().filter_map(|l| l.split_once(' ').map(|(_, s)| s)).collect();
+    assert_eq!(subjects, vec!["second", "first"]);
+
+    let out = run(&["log", "-n", "1"], repo);
+    assert!(out.contains("    second") && !out.contains("    first"), "实际输出: {}", out);
+}
+
+#[test]
+fn test_log_follow_tracks_renamed_file() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    commit_file(repo, "old.txt", "a\nb\nc\nd\n", "create old");
+    commit_file(repo, "other.txt", "x", "unrelated");
+
+    run(&["rm", "old.txt"], repo);
+    commit_file(repo, "new.txt", "a\nb\nc\nd\n", "rename to new");
+    commit_file(repo, "new.txt", "a\nb\nc\nd\ne\n", "edit new");
+
+    let plain = run(&["log", "--oneline", "--", "new.txt"], repo);
+    assert!(plain.contains("edit new") && plain.contains("rename to new"));
+    assert!(!plain.contains("create old"), "不加 --follow 时不应越过重命名: {}", plain);
+
+    let followed = run(&["log", "--oneline", "--follow", "new.txt"], repo);
+    assert!(followed.contains("create old"), "--follow 应追踪到重命名之前: {}", followed);
+    assert!(!followed.contains("unrelated"), "不应包含无关提交: {}", followed);
+}
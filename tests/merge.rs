@@ -213,3 +213,32 @@ fn test_merge_add_and_delete_different_files() {
     let out = run_and_log(&["merge", "b"], repo);
     assert!(out.contains("已合并"));
 }
+
+#[test]
+fn test_merge_edit_follows_rename() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let original = "fn main() {\n    one();\n    two();\n}\n";
+    let edited = "fn main() {\n    one();\n    two();\n    three();\n}\n";
+
+    bin().arg("init").current_dir(repo).assert().success();
+    init_commit(repo, "a.rs", original);
+    bin().args(["branch", "rename"]).current_dir(repo).assert().success();
+    bin().args(["branch", "edit"]).current_dir(repo).assert().success();
+
+    // rename 分支：a.rs -> b.rs
+    run_and_log(&["checkout", "rename"], repo);
+    run_and_log(&["rm", "a.rs"], repo);
+    init_commit(repo, "b.rs", original);
+
+    // edit 分支：修改 a.rs
+    run_and_log(&["checkout", "edit"], repo);
+    init_commit(repo, "a.rs", edited);
+
+    // 合并后修改应跟随到 b.rs，而不是留下 a.rs
+    run_and_log(&["checkout", "rename"], repo);
+    let out = run_and_log(&["merge", "edit"], repo);
+    assert!(!out.contains("Merge conflict"), "不应产生冲突: {}", out);
+    assert!(!repo.join("a.rs").exists(), "a.rs 不应被恢复");
+    assert_eq!(fs::read_to_string(repo.join("b.rs")).unwrap(), edited);
+}
@@ -0,0 +1,78 @@
+// tests/pack_refs.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run, commit_file};
+
+fn packed(repo: &Path) -> String {
+    fs::read_to_string(repo.join(".mygit/packed-refs")).unwrap_or_default()
+}
+
+#[test]
+fn test_pack_refs_moves_refs_into_packed_file() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    let first = commit_file(repo, "a.txt", "a", "first");
+    run(&["branch", "feature/x"], repo);
+    fs::write(repo.join(".mygit/refs/tags/v1"), format!("{}\n", first)).unwrap();
+
+    // 默认只打包标签
+    run(&["pack-refs"], repo);
+    assert!(packed(repo).contains(&format!("{} refs/tags/v1", first)), "实际内容: {}", packed(repo));
+    assert!(!repo.join(".mygit/refs/tags/v1").exists());
+    assert!(repo.join(".mygit/refs/heads/master").exists());
+
+    run(&["pack-refs", "--all"], repo);
+    assert!(!repo.join(".mygit/refs/heads/feature").exists(), "打包后的松散引用与空目录应被删除");
+    assert!(packed(repo).contains("refs/heads/feature/x"));
+
+    // 打包后的引用照常可用
+    assert_eq!(run(&["branch"], repo), "  feature/x\n* master\n");
+    let log = run(&["log", "--oneline", "v1"], repo);
+    assert!(log.contains("first"), "实际输出: {}", log);
+
+    // 新提交写成松散引用并覆盖打包的值
+    let second = commit_file(repo, "b.txt", "b", "second");
+    assert_eq!(fs::read_to_string(repo.join(".mygit/refs/heads/master")).unwrap().trim(), second);
+    assert!(run(&["log", "--oneline"], repo).contains("second"));
+
+    // 删除分支时同时从 packed-refs 中移除
+    run(&["branch", "-D", "feature/x"], repo);
+    assert!(!packed(repo).contains("feature/x"), "实际内容: {}", packed(repo));
+    assert_eq!(run(&["branch"], repo), "* master\n");
+}
+
+#[test]
+fn test_ref_updates_respect_lock_files_and_peeled_entries() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    let first = commit_file(repo, "a.txt", "a", "first");
+
+    // 已有的 peeled 行在重新打包时保留
+    let peeled = "1111111111111111111111111111111111111111";
+    fs::write(
+        repo.join(".mygit/packed-refs"),
+        format!("# pack-refs with: peeled fully-peeled sorted \n{} refs/tags/v1\n^{}\n", first, peeled),
+    )
+    .unwrap();
+    run(&["pack-refs", "--all"], repo);
+    assert!(packed(repo).contains(&format!("refs/tags/v1\n^{}\n", peeled)), "实际内容: {}", packed(repo));
+
+    // 其他进程持有锁时，更新失败且引用保持不变
+    fs::write(repo.join(".mygit/refs/heads/master.lock"), "").unwrap();
+    fs::write(repo.join("b.txt"), "b").unwrap();
+    run(&["add", "b.txt"], repo);
+    bin().args(["commit", "-m", "second"]).current_dir(repo).assert().failure();
+    let log = run(&["log", "--oneline"], repo);
+    assert_eq!(log.lines().count(), 1, "实际输出: {}", log);
+    assert!(repo.join(".mygit/refs/heads/master.lock").exists(), "不应删除别人的锁文件");
+
+    fs::remove_file(repo.join(".mygit/refs/heads/master.lock")).unwrap();
+    run(&["commit", "-m", "second"], repo);
+    assert_eq!(run(&["log", "--oneline"], repo).lines().count(), 2);
+}
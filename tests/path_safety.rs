@@ -0,0 +1,117 @@
+// tests/path_safety.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run, hash_object};
+
+/// 在 `repo` 中构造一个 tree 含有 `path` 的提交，父提交为 HEAD
+fn crafted_commit(repo: &Path, path: &str) -> String {
+    let blob = hash_object(repo, "pwned\n");
+    let tree = hash_object(repo, &format!("blob {} a.txt\nblob {} {}\n", blob, blob, path));
+    run(&["commit-tree", &tree, "-p", "HEAD", "-m", "crafted"], repo).trim().to_string()
+}
+
+fn setup(repo: &Path) {
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "safe\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+}
+
+#[test]
+fn test_unsafe_tree_paths_are_rejected() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    setup(&repo);
+
+    for path in ["../escape.txt", "/tmp/abs.txt", ".mygit/HEAD", "sub/.MYGIT/config", "sub/./x", ".git/config"] {
+        let commit = crafted_commit(&repo, path);
+        run(&["update-ref", "refs/heads/evil", &commit], &repo);
+
+        let output = bin().args(["checkout", "evil"]).current_dir(&repo).output().unwrap();
+        assert!(!output.status.success(), "检出含有 '{}' 的 tree 应当失败", path);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("不安全的路径"), "路径 {}", path);
+        assert_eq!(run(&["symbolic-ref", "--short", "HEAD"], &repo), "master\n", "检出失败时 HEAD 不应移动");
+        assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "safe\n", "检出失败时工作区不应改动");
+        bin().args(["checkout", &commit]).current_dir(&repo).assert().failure();
+        bin().args(["cherry-pick", &commit]).current_dir(&repo).assert().failure();
+
+        let output = bin().arg("fsck").current_dir(&repo).output().unwrap();
+        assert!(String::from_utf8_lossy(&output.stderr).contains("不安全的路径"), "fsck 应当报告 '{}'", path);
+        run(&["branch", "-D", "evil"], &repo);
+        run(&["gc", "--prune=now", "-q"], &repo);
+    }
+    assert!(!tmp.path().join("escape.txt").exists());
+    assert_eq!(fs::read_to_string(repo.join(".mygit/HEAD")).unwrap().trim(), "ref: refs/heads/master");
+
+    // 从不可信的仓库获取含有不安全路径的 tree 时直接失败
+    let commit = crafted_commit(&repo, "../escape.txt");
+    run(&["branch", "evil", &commit], &repo);
+    let output = bin().args(["clone", "repo", "copy"]).current_dir(tmp.path()).output().unwrap();
+    assert!(!output.status.success(), "克隆含有不安全路径的仓库应当失败");
+    assert!(!tmp.path().join("escape.txt").exists());
+
+    // 写入时同样拒绝：index 中的不安全路径无法生成 tree
+    let blob = hash_object(&repo, "x\n");
+    fs::write(repo.join(".mygit/index"), format!("{} ../escape.txt\n", blob)).unwrap();
+    bin().args(["write-tree"]).current_dir(&repo).assert().failure();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_checkout_does_not_follow_symlinks() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path().join("repo");
+    let outside = tmp.path().join("outside");
+    fs::create_dir_all(&repo).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    setup(&repo);
+
+    // 工作区中的符号链接作为上级目录时拒绝写入
+    std::os::unix::fs::symlink(&outside, repo.join("link")).unwrap();
+    let commit = crafted_commit(&repo, "link/x.txt");
+    let output = bin().args(["cherry-pick", &commit]).current_dir(&repo).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("是符号链接"));
+    assert!(!outside.join("x.txt").exists(), "不应穿过符号链接写入工作区之外");
+    fs::remove_file(repo.join("link")).unwrap();
+
+    // 目标文件本身是符号链接时替换链接，而不是写入链接指向的文件
+    // （链接指向的内容与 index 一致，否则 cherry-pick 会因为未提交的修改而拒绝）
+    fs::write(outside.join("target.txt"), "safe\n").unwrap();
+    fs::remove_file(repo.join("a.txt")).unwrap();
+    std::os::unix::fs::symlink(outside.join("target.txt"), repo.join("a.txt")).unwrap();
+    let commit = crafted_commit(&repo, "b.txt");
+    run(&["cherry-pick", &commit], &repo);
+    assert_eq!(fs::read_to_string(outside.join("target.txt")).unwrap(), "safe\n");
+    assert!(!fs::symlink_metadata(repo.join("a.txt")).unwrap().file_type().is_symlink());
+}
+
+#[test]
+fn test_tree_entry_hashes_must_be_object_ids() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path().join("repo");
+    fs::create_dir(&repo).unwrap();
+    setup(&repo);
+    let secret = tmp.path().join("secret.txt");
+    fs::write(&secret, "secret\n").unwrap();
+
+    // 对象路径按 hash 的前两个字符拆分，`..` 之后的绝对路径会让它指向对象库之外的文件
+    let blob = hash_object(&repo, "safe\n");
+    let bad = format!("..{}", secret.display());
+    let tree = hash_object(&repo, &format!("blob {} a.txt\nblob {} x.txt\n", blob, bad));
+    // 换掉 HEAD 的 tree 行得到指向它的提交（commit-tree 不接受这样的 tree）
+    let head = run(&["cat-file", "-p", "HEAD"], &repo);
+    let commit = hash_object(&repo, &format!("tree {}\n{}", tree, head.split_once('\n').unwrap().1));
+    run(&["update-ref", "refs/heads/evil", &commit], &repo);
+
+    let output = bin().args(["checkout", "evil"]).current_dir(&repo).output().unwrap();
+    assert!(!output.status.success(), "检出 hash 无效的 tree 应当失败");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("hash 无效"), "实际输出: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(!repo.join("x.txt").exists(), "不应把对象库之外的文件检出到工作区");
+    bin().args(["cat-file", "-p", &bad]).current_dir(&repo).assert().failure();
+}
@@ -0,0 +1,106 @@
+// tests/plumbing.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run};
+
+fn run_with_stdin(args: &[&str], input: &str, repo: &Path) -> String {
+    let output = bin().args(args).current_dir(repo).write_stdin(input).output().unwrap();
+    assert!(output.status.success(), "命令 {:?} 执行失败: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn setup(repo: &Path) -> String {
+    run(&["init"], repo);
+    fs::create_dir_all(repo.join("dir")).unwrap();
+    fs::write(repo.join("a.txt"), "hello\n").unwrap();
+    fs::write(repo.join("dir/b.txt"), "world\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["add", "dir"], repo);
+    let out = run(&["commit", "-m", "first"], repo);
+    out.lines().last().unwrap().trim().to_string()
+}
+
+#[test]
+fn test_object_plumbing() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let commit = setup(repo);
+
+    assert_eq!(run(&["cat-file", "-t", "HEAD"], repo), "commit\n");
+    assert_eq!(run(&["cat-file", "-t", "HEAD^{tree}"], repo), "tree\n");
+    assert_eq!(run(&["cat-file", "-p", "HEAD:a.txt"], repo), "hello\n");
+    assert_eq!(run(&["cat-file", "-s", "HEAD:a.txt"], repo), "6\n");
+    assert_eq!(run(&["cat-file", "blob", "HEAD:dir/b.txt"], repo), "world\n");
+    assert!(run(&["cat-file", "-p", "HEAD"], repo).contains("\n\nfirst\n"));
+    bin().args(["cat-file", "-e", "HEAD:missing"]).current_dir(repo).assert().code(1);
+    bin().args(["cat-file", "tree", "HEAD:a.txt"]).current_dir(repo).assert().failure();
+
+    // hash-object 与写入后的 blob 一致
+    let blob = run_with_stdin(&["hash-object", "-w", "--stdin"], "hello\n", repo);
+    let objects = run(&["count-objects"], repo);
+    let tree = run(&["ls-tree", "HEAD"], repo);
+    assert!(tree.starts_with(&format!("100644 blob {}\ta.txt\n040000 tree ", blob.trim())), "实际输出: {}", tree);
+    assert!(tree.ends_with("\tdir\n"), "实际输出: {}", tree);
+    let subtree = tree.lines().last().unwrap().split_whitespace().nth(2).unwrap().to_string();
+    assert!(run(&["ls-tree", "HEAD:dir"], repo).ends_with("\tb.txt\n"));
+    assert_eq!(run(&["cat-file", "-t", "HEAD:dir"], repo), "tree\n");
+    let batch = run_with_stdin(&["cat-file", "--batch-check"], "HEAD:dir\n", repo);
+    assert!(batch.starts_with(&format!("{} tree ", subtree)), "实际输出: {}", batch);
+    // 查看子目录不会把它的 tree 写入对象库
+    assert_eq!(run(&["count-objects"], repo), objects, "只读命令不应写入对象");
+    assert!(!run(&["fsck"], repo).contains("dangling"), "只读命令不应留下悬空对象");
+    assert_eq!(run(&["ls-tree", "-r", "--name-only", "HEAD"], repo), "a.txt\ndir/b.txt\n");
+    bin().args(["ls-tree", "--name-status", "HEAD"]).current_dir(repo).assert().failure();
+    assert_eq!(run(&["ls-files"], repo), "a.txt\ndir/b.txt\n");
+    assert!(run(&["ls-files", "--stage"], repo).contains(&format!("100644 {} 0\ta.txt\n", blob.trim())));
+
+    let batch = run_with_stdin(&["cat-file", "--batch-check"], "HEAD\nnope\n", repo);
+    assert_eq!(batch.lines().next().unwrap().split(' ').take(2).collect::<Vec<_>>(), [commit.as_str(), "commit"]);
+    assert_eq!(batch.lines().nth(1), Some("nope missing"));
+
+    // write-tree + commit-tree 得到与 commit 相同的 tree
+    let written = run(&["write-tree"], repo);
+    let head_tree = run(&["cat-file", "-p", "HEAD"], repo).lines().next().unwrap().trim_start_matches("tree ").to_string();
+    assert_eq!(written.trim(), head_tree);
+    let child = run_with_stdin(&["commit-tree", written.trim(), "-p", "HEAD"], "from stdin\n", repo);
+    let body = run(&["cat-file", "-p", child.trim()], repo);
+    assert!(body.contains(&format!("parent {}\n", commit)) && body.ends_with("\n\nfrom stdin\n"), "实际输出: {}", body);
+}
+
+#[test]
+fn test_ref_plumbing() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    let first = setup(repo);
+    let tree = run(&["write-tree"], repo);
+    let second = run(&["commit-tree", tree.trim(), "-p", &first, "-m", "second"], repo).trim().to_string();
+
+    // update-ref 跟随 HEAD 更新当前分支，旧值不符时失败
+    run(&["update-ref", "HEAD", &second, &first], repo);
+    assert_eq!(run(&["show-ref", "--hash", "master"], repo).trim(), second);
+    bin().args(["update-ref", "refs/heads/master", &first, &first]).current_dir(repo).assert().failure();
+    // 不记录 reflog，因此不接受 -m
+    bin().args(["update-ref", "-m", "reason", "refs/heads/master", &first]).current_dir(repo).assert().failure();
+    assert_eq!(run(&["show-ref", "--verify", "refs/heads/master"], repo), format!("{} refs/heads/master\n", second));
+
+    assert_eq!(run(&["symbolic-ref", "HEAD"], repo), "refs/heads/master\n");
+    assert_eq!(run(&["symbolic-ref", "--short", "HEAD"], repo), "master\n");
+    run(&["update-ref", "refs/heads/topic", &first], repo);
+    run(&["symbolic-ref", "HEAD", "refs/heads/topic"], repo);
+    assert_eq!(run(&["log", "--oneline"], repo).lines().count(), 1);
+    bin().args(["symbolic-ref", "refs/heads/topic"]).current_dir(repo).assert().failure();
+
+    // --stdin 中任一命令失败时整个事务不生效
+    let input = format!("create refs/tags/v1 {}\nupdate refs/heads/master {} {}\n", first, first, first);
+    bin().args(["update-ref", "--stdin"]).current_dir(repo).write_stdin(input).assert().failure();
+    bin().args(["show-ref", "--verify", "-q", "refs/tags/v1"]).current_dir(repo).assert().code(1);
+    let input = format!("create refs/tags/v1 {}\ndelete refs/heads/topic\n", first);
+    run_with_stdin(&["update-ref", "--stdin"], &input, repo);
+    assert_eq!(run(&["show-ref", "--tags"], repo), format!("{} refs/tags/v1\n", first));
+    run(&["update-ref", "-d", "refs/tags/v1"], repo);
+    bin().args(["show-ref", "--tags"]).current_dir(repo).assert().code(1);
+}
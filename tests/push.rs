@@ -0,0 +1,103 @@
+// tests/push.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod common;
+use common::{bin, run, commit_file};
+
+fn remote_ref(server: &Path, branch: &str) -> String {
+    fs::read_to_string(server.join(".mygit/refs/heads").join(branch)).unwrap().trim().to_string()
+}
+
+/// server 上有 master 一个提交，并切换到 parking 分支以便 master 可以被推送；
+/// 返回 (server, alice, bob) 三个目录，alice 与 bob 都是 server 的克隆
+fn setup(tmp: &Path) -> (PathBuf, PathBuf, PathBuf) {
+    let server = tmp.join("server");
+    fs::create_dir_all(&server).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    run(&["clone", "server", "alice"], tmp);
+    run(&["clone", "server", "bob"], tmp);
+    run(&["checkout", "-b", "parking"], &server);
+    (server, tmp.join("alice"), tmp.join("bob"))
+}
+
+#[test]
+fn test_push_fast_forward_and_reject_non_fast_forward() {
+    let tmp = tempdir().unwrap();
+    let (server, alice, bob) = setup(tmp.path());
+
+    let pushed = commit_file(&alice, "a.txt", "alice\n", "alice change");
+    let out = run(&["push"], &alice);
+    assert!(out.contains("master -> master"), "实际输出: {}", out);
+    assert_eq!(remote_ref(&server, "master"), pushed);
+    assert_eq!(fs::read_to_string(alice.join(".mygit/refs/remotes/origin/master")).unwrap().trim(), pushed);
+    assert!(run(&["push"], &alice).contains("Everything up-to-date"));
+
+    // bob 基于旧提交，普通推送被拒绝
+    commit_file(&bob, "b.txt", "bob\n", "bob change");
+    let output = bin().args(["push", "origin", "master"]).current_dir(&bob).output().unwrap();
+    assert!(!output.status.success(), "非快进推送应被拒绝");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("[rejected]"));
+    assert_eq!(remote_ref(&server, "master"), pushed);
+
+    // fetch 之后 rebase 再推送即可快进
+    run(&["fetch"], &bob);
+    run(&["rebase", "origin/master"], &bob);
+    run(&["push"], &bob);
+    let log = run(&["log", "--oneline", "master"], &server);
+    assert!(log.contains("bob change") && log.contains("alice change"), "实际输出: {}", log);
+}
+
+#[test]
+fn test_push_force_with_lease() {
+    let tmp = tempdir().unwrap();
+    let (server, alice, bob) = setup(tmp.path());
+
+    commit_file(&alice, "a.txt", "alice\n", "alice change");
+    run(&["push"], &alice);
+
+    // bob 的远程跟踪分支已过期：lease 检查失败
+    let rewritten = commit_file(&bob, "a.txt", "bob\n", "bob rewrite");
+    let output = bin().args(["push", "--force-with-lease"]).current_dir(&bob).output().unwrap();
+    assert!(!output.status.success(), "远程已被他人更新时 --force-with-lease 应失败");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("stale info"));
+
+    // fetch 后 lease 与远程一致，可以强制覆盖
+    run(&["fetch"], &bob);
+    run(&["push", "--force-with-lease"], &bob);
+    assert_eq!(remote_ref(&server, "master"), rewritten);
+
+    // 显式指定期望值
+    let newer = commit_file(&alice, "c.txt", "c\n", "alice again");
+    let wrong = format!("--force-with-lease=master:{}", newer);
+    bin().args(["push", wrong.as_str()]).current_dir(&alice).assert().failure();
+    let right = format!("--force-with-lease=master:{}", rewritten);
+    run(&["push", right.as_str()], &alice);
+    assert_eq!(remote_ref(&server, "master"), newer);
+}
+
+#[test]
+fn test_push_new_branch_with_upstream_and_refuse_checked_out_branch() {
+    let tmp = tempdir().unwrap();
+    let (server, alice, _) = setup(tmp.path());
+
+    run(&["checkout", "-b", "feature"], &alice);
+    let feature = commit_file(&alice, "f.txt", "f\n", "feature");
+    let out = run(&["push", "-u", "origin", "feature"], &alice);
+    assert!(out.contains("[new branch]"), "实际输出: {}", out);
+    assert!(out.contains("branch 'feature' set up to track 'origin/feature'."), "实际输出: {}", out);
+    assert_eq!(remote_ref(&server, "feature"), feature);
+    let config = fs::read_to_string(alice.join(".mygit/config")).unwrap();
+    assert!(config.contains("[branch \"feature\"]"), "实际配置: {}", config);
+
+    // 不能更新远程当前检出的分支
+    let output = bin().args(["push", "origin", "feature:parking"]).current_dir(&alice).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("checked out"));
+
+    run(&["push", "origin", "--delete", "feature"], &alice);
+    assert!(!server.join(".mygit/refs/heads/feature").exists());
+}
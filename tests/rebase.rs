@@ -0,0 +1,164 @@
+// tests/rebase.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run, commit_file};
+
+/// 用指定的编辑器命令执行 rebase -i
+fn run_interactive(repo: &Path, sequence_editor: &str, editor: &str, args: &[&str]) -> std::process::Output {
+    bin()
+        .arg("rebase")
+        .arg("-i")
+        .args(args)
+        .env("GIT_SEQUENCE_EDITOR", sequence_editor)
+        .env("GIT_EDITOR", editor)
+        .current_dir(repo)
+        .output()
+        .unwrap()
+}
+
+fn subjects(repo: &Path) -> Vec<String> {
+    run(&["log", "--oneline"], repo)
+        .lines()
+        .filter_map(|l| l.split_once(' ').map(|(_, s)| s.to_string()))
+        .collect()
+}
+
+/// master: base -> master work；feature: base -> f1 -> f2（当前在 feature）
+fn setup_diverged(repo: &Path) {
+    run(&["init"], repo);
+    commit_file(repo, "base.txt", "base\n", "base");
+    run(&["branch", "feature"], repo);
+    commit_file(repo, "master.txt", "m\n", "master work");
+    run(&["checkout", "feature"], repo);
+    commit_file(repo, "f1.txt", "1\n", "f1");
+    commit_file(repo, "f2.txt", "2\n", "f2");
+}
+
+#[test]
+fn test_rebase_replays_branch_onto_upstream() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    setup_diverged(repo);
+
+    let out = run(&["rebase", "master"], repo);
+    assert!(out.contains("Successfully rebased and updated refs/heads/feature."), "实际输出: {}", out);
+    assert_eq!(subjects(repo), vec!["f2", "f1", "master work", "base"]);
+    for name in ["base.txt", "master.txt", "f1.txt", "f2.txt"] {
+        assert!(repo.join(name).exists(), "{} 应存在", name);
+    }
+    assert_eq!(fs::read_to_string(repo.join(".mygit/HEAD")).unwrap().trim(), "ref: refs/heads/feature");
+    assert!(!repo.join(".mygit/rebase-merge").exists());
+
+    let out = run(&["rebase", "master"], repo);
+    assert!(out.contains("is up to date"), "再次 rebase 应无事可做: {}", out);
+}
+
+#[test]
+fn test_rebase_onto_transplants_commits() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    setup_diverged(repo);
+
+    // 只把 f2 移到 master 上，丢掉 f1
+    let f1 = run(&["log", "--oneline", "-n", "1", "HEAD~1"], repo)[..7].to_string();
+    run(&["rebase", "--onto", "master", &f1], repo);
+    assert_eq!(subjects(repo), vec!["f2", "master work", "base"]);
+    assert!(!repo.join("f1.txt").exists());
+}
+
+#[test]
+fn test_rebase_conflict_continue_and_abort() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    commit_file(repo, "a.txt", "a\n", "base");
+    run(&["branch", "feature"], repo);
+    commit_file(repo, "a.txt", "master\n", "master edit");
+    run(&["checkout", "feature"], repo);
+    let orig = commit_file(repo, "a.txt", "feature\n", "feature edit");
+    commit_file(repo, "b.txt", "b\n", "add b");
+
+    let output = bin().args(["rebase", "master"]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success(), "冲突时应以失败退出");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Merge conflict in a.txt"));
+    assert!(fs::read_to_string(repo.join("a.txt")).unwrap().contains("<<<<<<< HEAD"));
+
+    run(&["rebase", "--abort"], repo);
+    assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "feature\n");
+    assert!(repo.join("b.txt").exists());
+    assert_eq!(subjects(repo), vec!["add b", "feature edit", "base"]);
+    assert!(run(&["log", "-n", "1", "--oneline", "HEAD~1"], repo).starts_with(&orig[..7]));
+
+    bin().args(["rebase", "master"]).current_dir(repo).assert().failure();
+    bin().args(["rebase", "--continue"]).current_dir(repo).assert().failure();
+    fs::write(repo.join("a.txt"), "master\nfeature\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    let out = run(&["rebase", "--continue"], repo);
+    assert!(out.contains("Successfully rebased"), "实际输出: {}", out);
+    assert_eq!(subjects(repo), vec!["add b", "feature edit", "master edit", "base"]);
+    assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "master\nfeature\n");
+}
+
+#[test]
+fn test_rebase_interactive_squash_reword_drop_exec() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    commit_file(repo, "base.txt", "base\n", "base");
+    commit_file(repo, "a.txt", "a\n", "add a");
+    commit_file(repo, "a.txt", "a2\n", "fix a");
+    commit_file(repo, "junk.txt", "junk\n", "junk");
+    commit_file(repo, "c.txt", "c\n", "add c");
+
+    // 第 2 行改为 fixup，第 3 行丢弃，第 4 行改为 reword，最后追加 exec
+    let editor = "sed -i -e '2s/^pick/fixup/' -e '3s/^pick/drop/' -e '4s/^pick/reword/' -e '4a exec touch exec-ran'";
+    let output = run_interactive(repo, editor, "sed -i -e '1s/.*/add c (reworded)/'", &["HEAD~4"]);
+    assert!(output.status.success(), "rebase -i 失败: {}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(subjects(repo), vec!["add c (reworded)", "add a", "base"]);
+    assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "a2\n");
+    assert!(!repo.join("junk.txt").exists(), "drop 的提交不应保留");
+    assert!(repo.join("exec-ran").exists(), "exec 命令应被执行");
+
+    // squash 会合并两个提交的说明
+    let output = run_interactive(repo, "sed -i -e '2s/^pick/squash/'", "true", &["HEAD~2"]);
+    assert!(output.status.success(), "rebase -i 失败: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(subjects(repo), vec!["add a", "base"]);
+    let log = run(&["log", "-n", "1"], repo);
+    assert!(log.contains("    add c (reworded)"), "squash 后应保留两个提交的说明: {}", log);
+    let object = run(&["cat-file", "-p", "HEAD"], repo);
+    assert!(object.ends_with("\n\nadd a\n\nadd c (reworded)\n"), "去掉注释后不应留下多余的空行: {:?}", object);
+    assert!(repo.join("c.txt").exists());
+}
+
+#[test]
+fn test_rebase_interactive_edit_stops_for_amend() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+
+    run(&["init"], repo);
+    commit_file(repo, "base.txt", "base\n", "base");
+    commit_file(repo, "a.txt", "a\n", "add a");
+    commit_file(repo, "b.txt", "b\n", "add b");
+
+    let output = run_interactive(repo, "sed -i -e '1s/^pick/edit/'", "true", &["HEAD~2"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Stopped at"));
+    assert!(!repo.join("b.txt").exists(), "edit 停下时后面的提交还未应用");
+
+    fs::write(repo.join("a.txt"), "amended\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["rebase", "--continue"], repo);
+
+    assert_eq!(subjects(repo), vec!["add b", "add a", "base"]);
+    let out = run(&["diff", "HEAD~1", "HEAD"], repo);
+    assert!(out.contains("b.txt") && !out.contains("a.txt"), "修改应并入被 edit 的提交: {}", out);
+    assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "amended\n");
+    assert!(repo.join("b.txt").exists());
+}
@@ -0,0 +1,68 @@
+// tests/receive_pack.rs
+
+use tempfile::tempdir;
+use std::fs;
+
+mod common;
+use common::{bin, run, commit_file};
+
+#[test]
+fn test_receive_pack_advertises_empty_repository() {
+    let tmp = tempdir().unwrap();
+    run(&["init"], tmp.path());
+
+    let output = bin().args(["receive-pack", "."]).current_dir(tmp.path()).write_stdin("").output().unwrap();
+    assert!(output.status.success(), "receive-pack 失败: {}", String::from_utf8_lossy(&output.stderr));
+    let out = String::from_utf8_lossy(&output.stdout);
+    assert!(out.contains("0000000000000000000000000000000000000000 capabilities^{}\0"), "实际输出: {:?}", out);
+    assert!(out.contains("report-status"), "实际输出: {:?}", out);
+}
+
+#[test]
+fn test_receive_pack_rejects_pack_with_bogus_object_size() {
+    let tmp = tempdir().unwrap();
+    run(&["init"], tmp.path());
+
+    let command = format!("{} {} refs/heads/x\0report-status\n", "0".repeat(40), "1".repeat(40));
+    // 对象头部声明的长度远超实际数据，以及超出 64 位的长度
+    for size in [vec![0xbf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x0f], vec![0xbf; 12]] {
+        let mut input = format!("{:04x}{}0000", command.len() + 4, command).into_bytes();
+        input.extend_from_slice(b"PACK\0\0\0\x02\0\0\0\x01");
+        input.extend_from_slice(&size);
+        input.extend_from_slice(b"garbage");
+
+        let output = bin().args(["receive-pack", "."]).current_dir(tmp.path()).write_stdin(input).output().unwrap();
+        assert!(output.status.success(), "receive-pack 不应崩溃: {}", String::from_utf8_lossy(&output.stderr));
+        let out = String::from_utf8_lossy(&output.stdout);
+        assert!(out.contains("ng refs/heads/x unpacker error"), "实际输出: {:?}", out);
+    }
+    assert!(!tmp.path().join(".mygit/refs/heads/x").exists());
+}
+
+#[test]
+fn test_push_over_file_url_reports_remote_rejection() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(&server).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+
+    let url = format!("file://{}", server.display());
+    run(&["clone", &url, "work"], tmp.path());
+    let work = tmp.path().join("work");
+    let pushed = commit_file(&work, "b.txt", "b\n", "second");
+
+    // 远程检出的分支由服务端拒绝
+    let output = bin().args(["push", "origin", "master"]).current_dir(&work).output().unwrap();
+    assert!(!output.status.success(), "推送到检出的分支应失败");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[remote rejected]") && stderr.contains("branch is currently checked out"), "实际输出: {}", stderr);
+
+    let out = run(&["push", "origin", "master:feature"], &work);
+    assert!(out.contains("[new branch]"), "实际输出: {}", out);
+    assert_eq!(fs::read_to_string(server.join(".mygit/refs/heads/feature")).unwrap().trim(), pushed);
+    assert!(run(&["log", "--oneline", "feature"], &server).contains("second"), "对象应随 packfile 传到远程");
+
+    run(&["push", "origin", ":feature"], &work);
+    assert!(!server.join(".mygit/refs/heads/feature").exists(), "远程分支应被删除");
+}
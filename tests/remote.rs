@@ -0,0 +1,50 @@
+// tests/remote.rs
+
+use tempfile::tempdir;
+use std::fs;
+
+mod common;
+use common::{bin, run, commit_file};
+
+#[test]
+fn test_remote_add_list_remove() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+
+    run(&["remote", "add", "origin", "/srv/repo"], repo);
+    run(&["remote", "add", "backup", "../backup"], repo);
+    bin().args(["remote", "add", "origin", "/other"]).current_dir(repo).assert().failure();
+
+    assert_eq!(run(&["remote"], repo), "origin\nbackup\n");
+    let verbose = run(&["remote", "-v"], repo);
+    assert!(verbose.contains("origin\t/srv/repo (fetch)"), "实际输出: {}", verbose);
+    assert!(verbose.contains("backup\t../backup (push)"), "实际输出: {}", verbose);
+
+    let config = fs::read_to_string(repo.join(".mygit/config")).unwrap();
+    assert!(config.contains("[remote \"origin\"]"), "远程应写入配置文件: {}", config);
+    assert!(config.contains("fetch = +refs/heads/*:refs/remotes/origin/*"), "实际配置: {}", config);
+
+    run(&["remote", "remove", "origin"], repo);
+    assert_eq!(run(&["remote"], repo), "backup\n");
+    bin().args(["remote", "rm", "origin"]).current_dir(repo).assert().failure();
+}
+
+#[test]
+fn test_remote_remove_deletes_tracking_refs() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    let local = tmp.path().join("local");
+    fs::create_dir_all(&server).unwrap();
+    fs::create_dir_all(&local).unwrap();
+
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    run(&["init"], &local);
+    run(&["remote", "add", "origin", server.to_str().unwrap()], &local);
+    run(&["fetch", "origin"], &local);
+    assert!(local.join(".mygit/refs/remotes/origin/master").exists());
+
+    run(&["remote", "remove", "origin"], &local);
+    assert!(!local.join(".mygit/refs/remotes/origin").exists(), "远程跟踪分支应一并删除");
+}
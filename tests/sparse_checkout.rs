@@ -0,0 +1,78 @@
+// tests/sparse_checkout.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run};
+
+fn init_with_dirs(repo: &Path) {
+    run(&["init"], repo);
+    for (path, content) in [("top.txt", "top\n"), ("a/a.txt", "a\n"), ("a/b/b.txt", "b\n"), ("c/c.txt", "c\n")] {
+        let path = repo.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+    run(&["add", "."], repo);
+    run(&["commit", "-m", "first"], repo);
+}
+
+#[test]
+fn test_sparse_checkout_set_add_disable() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    init_with_dirs(repo);
+
+    run(&["sparse-checkout", "set", "a/b"], repo);
+    assert_eq!(run(&["sparse-checkout", "list"], repo), "a/b\n");
+    assert!(repo.join("top.txt").exists(), "顶层文件应当总是检出");
+    assert!(repo.join("a/a.txt").exists(), "上级目录中的文件应当检出");
+    assert!(repo.join("a/b/b.txt").exists());
+    assert!(!repo.join("c").exists(), "范围之外的目录应当被删除");
+
+    // index 仍记录完整的 tree，范围之外的条目带 skip-worktree 标记
+    assert_eq!(run(&["ls-files", "-t"], repo), "H a/a.txt\nH a/b/b.txt\nS c/c.txt\nH top.txt\n");
+    assert!(run(&["status"], repo).contains("75% of tracked files present"));
+
+    run(&["sparse-checkout", "add", "c"], repo);
+    assert_eq!(run(&["sparse-checkout", "list"], repo), "a/b\nc\n");
+    assert_eq!(fs::read_to_string(repo.join("c/c.txt")).unwrap(), "c\n");
+
+    run(&["sparse-checkout", "set", "c"], repo);
+    assert!(!repo.join("a/b").exists());
+    run(&["sparse-checkout", "disable"], repo);
+    assert_eq!(fs::read_to_string(repo.join("a/b/b.txt")).unwrap(), "b\n");
+    assert_eq!(run(&["ls-files", "-t"], repo), "H a/a.txt\nH a/b/b.txt\nH c/c.txt\nH top.txt\n");
+    assert_eq!(run(&["status"], repo), "");
+}
+
+#[test]
+fn test_sparse_checkout_commit_and_checkout_keep_skipped_files() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    init_with_dirs(repo);
+    run(&["sparse-checkout", "set", "a"], repo);
+
+    // 范围之外的文件不能被加入 index
+    fs::create_dir_all(repo.join("c")).unwrap();
+    fs::write(repo.join("c/new.txt"), "new\n").unwrap();
+    let output = bin().args(["add", "c/new.txt"]).current_dir(repo).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("在稀疏检出范围之外"));
+    fs::remove_dir_all(repo.join("c")).unwrap();
+
+    // 提交时不在工作区中的文件按未修改处理，仍保留在 tree 中
+    run(&["checkout", "-b", "feat"], repo);
+    fs::write(repo.join("a/a.txt"), "a2\n").unwrap();
+    run(&["commit", "-a", "-m", "second"], repo);
+    assert!(run(&["ls-tree", "-r", "HEAD"], repo).contains("c/c.txt"));
+
+    run(&["checkout", "master"], repo);
+    assert_eq!(fs::read_to_string(repo.join("a/a.txt")).unwrap(), "a\n");
+    assert!(!repo.join("c").exists(), "切换分支后仍然只检出范围之内的文件");
+    run(&["merge", "feat"], repo);
+    assert_eq!(fs::read_to_string(repo.join("a/a.txt")).unwrap(), "a2\n");
+    assert!(!repo.join("c").exists(), "合并后仍然只检出范围之内的文件");
+    assert_eq!(run(&["ls-files", "-t"], repo), "H a/a.txt\nH a/b/b.txt\nS c/c.txt\nH top.txt\n");
+}
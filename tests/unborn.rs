@@ -0,0 +1,63 @@
+// tests/unborn.rs
+
+use tempfile::tempdir;
+use std::fs;
+
+mod common;
+use common::{bin, run};
+
+#[test]
+fn test_unborn_branch_before_first_commit() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+
+    assert_eq!(fs::read_to_string(repo.join(".mygit/HEAD")).unwrap().trim(), "ref: refs/heads/master");
+    assert!(!repo.join(".mygit/refs/heads/master").exists(), "第一次提交前不应有分支文件");
+    assert!(run(&["status"], repo).starts_with("No commits yet\n"));
+    assert_eq!(run(&["branch"], repo), "");
+
+    let output = bin().arg("log").current_dir(repo).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("'master' 还没有任何提交"));
+    bin().args(["branch", "dev"]).current_dir(repo).assert().failure();
+    assert!(!repo.join(".mygit/refs/heads/dev").exists());
+
+    // checkout -b 只移动 HEAD，不动工作区与暂存区
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["checkout", "-b", "topic"], repo);
+    assert_eq!(run(&["symbolic-ref", "HEAD"], repo), "refs/heads/topic\n");
+    assert!(repo.join("a.txt").exists(), "工作区不应被清理");
+    assert_eq!(run(&["status"], repo), "No commits yet\nstaged: a.txt\n");
+
+    run(&["commit", "-m", "first"], repo);
+    assert!(repo.join(".mygit/refs/heads/topic").is_file());
+    assert!(!repo.join(".mygit/refs/heads/master").exists());
+    assert!(!run(&["status"], repo).contains("No commits yet"));
+    assert_eq!(run(&["branch"], repo), "* topic\n");
+}
+
+#[test]
+fn test_merge_into_unborn_branch_and_legacy_empty_ref() {
+    let tmp = tempdir().unwrap();
+    let repo = tmp.path();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+    let master = run(&["show-ref", "--hash", "refs/heads/master"], repo);
+
+    // 合并进尚无提交的分支：直接指向目标提交
+    run(&["symbolic-ref", "HEAD", "refs/heads/fresh"], repo);
+    run(&["merge", "master"], repo);
+    assert_eq!(run(&["show-ref", "--hash", "refs/heads/fresh"], repo), master);
+    assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "a\n");
+
+    // 旧版本留下的空分支文件视为不存在
+    fs::write(repo.join(".mygit/refs/heads/legacy"), "").unwrap();
+    run(&["symbolic-ref", "HEAD", "refs/heads/legacy"], repo);
+    assert!(run(&["status"], repo).starts_with("No commits yet\n"));
+    assert!(!run(&["branch"], repo).contains("legacy"));
+    bin().args(["update-ref", "refs/heads/empty", ""]).current_dir(repo).assert().failure();
+}
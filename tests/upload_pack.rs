@@ -0,0 +1,64 @@
+// tests/upload_pack.rs
+
+use tempfile::tempdir;
+use std::fs;
+
+mod common;
+use common::{bin, run, commit_file};
+
+/// 编码一个 pkt-line
+fn pkt(line: &str) -> String {
+    format!("{:04x}{}", line.len() + 4, line)
+}
+
+#[test]
+fn test_upload_pack_advertises_v2_and_lists_refs() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path();
+    run(&["init"], server);
+    let head = commit_file(server, "a.txt", "a\n", "first");
+    run(&["branch", "dev"], server);
+
+    let request = format!("{}0001{}{}0000", pkt("command=ls-refs\n"), pkt("symrefs\n"), pkt("ref-prefix refs/heads/\n"));
+    let output = bin().args(["upload-pack", "."]).current_dir(server).write_stdin(request).output().unwrap();
+    assert!(output.status.success(), "upload-pack 失败: {}", String::from_utf8_lossy(&output.stderr));
+    let out = String::from_utf8_lossy(&output.stdout);
+
+    assert!(out.starts_with("000eversion 2\n"), "应先公布协议 v2 能力: {}", out);
+    assert!(out.contains("ls-refs=unborn\n") && out.contains("fetch\n"), "实际输出: {}", out);
+    // 公布的是 git 格式的提交 id，而不是本地 id
+    let id = |name: &str| {
+        let end = out.find(&format!(" {}\n", name)).unwrap_or_else(|| panic!("没有公布 {}: {}", name, out));
+        out[end - 40..end].to_string()
+    };
+    assert_ne!(id("refs/heads/master"), head, "实际输出: {}", out);
+    assert_eq!(id("refs/heads/dev"), id("refs/heads/master"), "实际输出: {}", out);
+    assert!(!out.contains("HEAD symref-target"), "ref-prefix 之外的引用不应列出: {}", out);
+    assert!(out.ends_with("0000"));
+}
+
+#[test]
+fn test_clone_and_fetch_over_file_url() {
+    let tmp = tempdir().unwrap();
+    let server = tmp.path().join("server");
+    fs::create_dir_all(server.join("dir")).unwrap();
+    run(&["init"], &server);
+    commit_file(&server, "a.txt", "a\n", "first");
+    commit_file(&server, "dir/b.txt", "b\n", "second");
+
+    let url = format!("file://{}", server.display());
+    run(&["clone", &url, "copy"], tmp.path());
+    let copy = tmp.path().join("copy");
+    assert_eq!(fs::read_to_string(copy.join("dir/b.txt")).unwrap(), "b\n");
+    let log = run(&["log", "--oneline"], &copy);
+    assert!(log.contains("first") && log.contains("second"), "实际输出: {}", log);
+    let config = fs::read_to_string(copy.join(".mygit/config")).unwrap();
+    assert!(config.contains(&format!("url = {}", url)), "实际配置: {}", config);
+
+    // 增量获取：只传输新提交
+    let third = commit_file(&server, "a.txt", "a2\n", "third");
+    let out = run(&["fetch"], &copy);
+    assert!(out.contains("master -> origin/master"), "实际输出: {}", out);
+    assert_eq!(fs::read_to_string(copy.join(".mygit/refs/remotes/origin/master")).unwrap().trim(), third);
+    assert!(run(&["log", "--oneline", "origin/master"], &copy).contains("third"));
+}
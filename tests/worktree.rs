@@ -0,0 +1,93 @@
+// tests/worktree.rs
+
+use tempfile::tempdir;
+use std::fs;
+use std::path::Path;
+
+mod common;
+use common::{bin, run};
+
+fn init_with_commit(repo: &Path) {
+    fs::create_dir_all(repo).unwrap();
+    run(&["init"], repo);
+    fs::write(repo.join("a.txt"), "a\n").unwrap();
+    run(&["add", "a.txt"], repo);
+    run(&["commit", "-m", "first"], repo);
+}
+
+#[test]
+fn test_worktree_add_shares_refs_and_objects() {
+    let tmp = tempdir().unwrap();
+    let root = fs::canonicalize(tmp.path()).unwrap();
+    let main = root.join("main");
+    let linked = root.join("feat");
+    init_with_commit(&main);
+
+    run(&["worktree", "add", "../feat"], &main);
+    assert_eq!(fs::read_to_string(linked.join("a.txt")).unwrap(), "a\n");
+    assert!(linked.join(".mygit").is_file(), "关联工作区中的 .mygit 应当是 gitdir 文件");
+    assert_eq!(run(&["symbolic-ref", "HEAD"], &linked), "refs/heads/feat\n");
+    assert_eq!(run(&["status"], &linked), "");
+
+    // 关联工作区中的提交在主工作区可见，但主工作区的 HEAD 与 index 不受影响
+    fs::write(linked.join("b.txt"), "b\n").unwrap();
+    run(&["add", "b.txt"], &linked);
+    run(&["commit", "-m", "second"], &linked);
+    let feat = run(&["show-ref", "--hash", "refs/heads/feat"], &main);
+    assert_eq!(run(&["show-ref", "--hash", "refs/heads/feat"], &linked), feat);
+    assert_eq!(run(&["symbolic-ref", "HEAD"], &main), "refs/heads/master\n");
+    assert_eq!(run(&["status"], &main), "");
+    assert_eq!(run(&["branch"], &main), "+ feat\n* master\n");
+
+    let list = run(&["worktree", "list"], &main);
+    let lines: Vec<&str> = list.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!("{} ", main.display())) && lines[0].ends_with("[master]"));
+    assert!(lines[1].starts_with(&format!("{} ", linked.display())) && lines[1].ends_with(&format!("{} [feat]", &feat[..7])));
+
+    // 同一个分支不能同时在两个工作区中检出
+    let output = bin().args(["checkout", "feat"]).current_dir(&main).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("已经在工作区"));
+    assert_eq!(run(&["symbolic-ref", "HEAD"], &main), "refs/heads/master\n");
+    bin().args(["checkout", "master"]).current_dir(&linked).assert().failure();
+    bin().args(["worktree", "add", "../other", "master"]).current_dir(&main).assert().failure();
+    assert!(!root.join("other").exists());
+    bin().args(["branch", "-D", "feat"]).current_dir(&main).assert().failure();
+
+    // 分离头指针的工作区可以检出任意提交
+    run(&["worktree", "add", "--detach", "../det", "master"], &main);
+    assert!(!root.join("det/b.txt").exists());
+    assert!(run(&["worktree", "list", "--porcelain"], &main).contains(&format!("worktree {}\nHEAD ", root.join("det").display())));
+}
+
+#[test]
+fn test_worktree_remove_and_prune() {
+    let tmp = tempdir().unwrap();
+    let root = fs::canonicalize(tmp.path()).unwrap();
+    let main = root.join("main");
+    init_with_commit(&main);
+
+    run(&["worktree", "add", "-b", "topic", "../topic"], &main);
+    fs::write(root.join("topic/new.txt"), "new\n").unwrap();
+    bin().args(["worktree", "remove", "../topic"]).current_dir(&main).assert().failure();
+    assert!(root.join("topic/new.txt").exists(), "有未跟踪文件时不应删除工作区");
+    run(&["worktree", "remove", "--force", "../topic"], &main);
+    assert!(!root.join("topic").exists());
+    assert!(!main.join(".mygit/worktrees/topic").exists());
+    // 分支本身保留，并可以在主工作区检出
+    run(&["checkout", "topic"], &main);
+    run(&["checkout", "master"], &main);
+
+    bin().args(["worktree", "remove", "."]).current_dir(&main).assert().failure();
+
+    run(&["worktree", "add", "../gone", "topic"], &main);
+    fs::remove_dir_all(root.join("gone")).unwrap();
+    assert!(run(&["worktree", "list"], &main).contains("prunable"));
+    assert_eq!(run(&["worktree", "prune", "-n"], &main), "Removing worktrees/gone: gitdir 文件指向不存在的位置\n");
+    assert!(main.join(".mygit/worktrees/gone").exists(), "-n 不应删除");
+    run(&["worktree", "prune"], &main);
+    assert!(!main.join(".mygit/worktrees").exists());
+    assert_eq!(run(&["worktree", "list"], &main).lines().count(), 1);
+    run(&["checkout", "topic"], &main);
+}